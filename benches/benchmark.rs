@@ -2,7 +2,10 @@ use std::{collections::BTreeMap, iter::repeat};
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use evalexpr::{build_operator_tree, ContextWithMutableVariables, HashMapContext, Node, Value};
-use exmex::{ops_factory, prelude::*, BinOp, MakeOperators, Operator, OwnedFlatEx};
+use exmex::{
+    ops_factory, prelude::*, BinOp, CheckReport, FloatOpsFactory, MakeOperators, NumberMatcher,
+    Operator, OwnedFlatEx,
+};
 #[cfg(feature = "value")]
 use exmex::{FlatExVal, Val};
 use fasteval::{Compiler, Evaler, Instruction, Slab};
@@ -132,6 +135,21 @@ fn exmex_bench_parse_owned(c: &mut Criterion) {
     run_benchmark_parse(exmex_parse_owned, "exmex_parse_owned", c);
 }
 
+fn exmex_bench_parse_batch(c: &mut Criterion) {
+    let strings = BENCH_EXPRESSIONS_STRS
+        .iter()
+        .copied()
+        .cycle()
+        .take(10000)
+        .collect::<Vec<_>>();
+    c.bench_function("exmex_parse_owned_10000", |b| {
+        b.iter(|| exmex_parse_owned(black_box(&strings)))
+    });
+    c.bench_function("exmex_parse_batch_10000", |b| {
+        b.iter(|| exmex::parse_batch::<f64>(black_box(&strings)))
+    });
+}
+
 fn exmex_parse_uncompiled<'a>(strings: &'a [&str]) -> Vec<FlatEx<'a, f64>> {
     strings
         .iter()
@@ -154,6 +172,34 @@ fn exmex_bench_parse(c: &mut Criterion) {
     run_benchmark_parse(exmex_parse, "exmex_parse", c);
 }
 
+fn exmex_check<'a>(strings: &'a [&str]) -> Vec<CheckReport<'a>> {
+    strings
+        .iter()
+        .map(|expr_str| {
+            exmex::check::<f64, FloatOpsFactory<f64>, NumberMatcher>(expr_str).unwrap()
+        })
+        .collect::<Vec<_>>()
+}
+
+fn exmex_bench_check(c: &mut Criterion) {
+    run_benchmark_parse(exmex_check, "exmex_check", c);
+}
+
+fn exmex_bench_check_long(c: &mut Criterion) {
+    let summands = BENCH_EXPRESSIONS_STRS
+        .iter()
+        .map(|s| format!("({})", s))
+        .join("+");
+    let long_str = vec![summands; 25].join("-");
+    let long_strs = [long_str.as_str()];
+    c.bench_function("exmex_check_long", |b| {
+        b.iter(|| exmex_check(black_box(&long_strs)))
+    });
+    c.bench_function("exmex_parse_long", |b| {
+        b.iter(|| exmex_parse(black_box(&long_strs)))
+    });
+}
+
 #[cfg(feature = "value")]
 fn exmex_parse_val<'a>(strings: &'a [&str]) -> Vec<FlatExVal<'a, i32, f64>> {
     strings
@@ -261,6 +307,71 @@ fn exmex_bench_eval(c: &mut Criterion) {
     run_benchmark(funcs, "exmex_eval", c);
 }
 
+fn exmex_bench_eval_affine(c: &mut Criterion) {
+    let expr = FlatEx::<f64>::from_str("1.8*c+32").unwrap();
+    c.bench_function("exmex_eval_affine", |b| {
+        b.iter(|| {
+            for i in BENCH_X_RANGE.0..BENCH_X_RANGE.1 {
+                assert_float_eq(
+                    expr.eval(&[black_box(i as f64)]).unwrap(),
+                    1.8 * i as f64 + 32.0,
+                );
+            }
+        })
+    });
+}
+
+fn exmex_bench_eval_cse(c: &mut Criterion) {
+    let text = "sin(x+y)^2 + cos(x+y)^2 + tan(x+y)";
+    let naive = FlatEx::<f64>::from_str(text).unwrap();
+    let (cse, _) = FlatEx::<f64>::from_str(text).unwrap().with_cse().unwrap();
+    for (expr, name) in [(naive, "naive"), (cse, "cse")] {
+        c.bench_function(format!("exmex_eval_cse_{}", name).as_str(), |b| {
+            b.iter(|| {
+                for i in BENCH_X_RANGE.0..BENCH_X_RANGE.1 {
+                    expr.eval(&[black_box(i as f64), BENCH_Y]).unwrap();
+                }
+            })
+        });
+    }
+}
+
+fn exmex_bench_eval_int_pow(c: &mut Criterion) {
+    let text = "x^2 + x^3";
+    let powi = FlatEx::<f64>::from_str(text).unwrap();
+    let powf = FlatEx::<f64>::from_str_wo_compile(text).unwrap();
+    for (expr, name) in [(powi, "powi"), (powf, "powf")] {
+        c.bench_function(format!("exmex_eval_int_pow_{}", name).as_str(), |b| {
+            b.iter(|| {
+                for i in BENCH_X_RANGE.0..BENCH_X_RANGE.1 {
+                    expr.eval(&[black_box(i as f64)]).unwrap();
+                }
+            })
+        });
+    }
+}
+
+fn exmex_bench_eval_many(c: &mut Criterion) {
+    let parsed_exprs = exmex_parse(&BENCH_EXPRESSIONS_STRS);
+    let rows = (0..1000)
+        .map(|i| [i as f64 / 100.0, BENCH_Y, BENCH_Z])
+        .collect::<Vec<_>>();
+    let row_refs = rows.iter().map(|r| &r[..]).collect::<Vec<_>>();
+    for (expr, exp_name) in parsed_exprs.iter().zip(BENCH_EXPRESSIONS_NAMES.iter()) {
+        c.bench_function(format!("exmex_eval_many_{}", exp_name).as_str(), |b| {
+            b.iter(|| expr.eval_many(black_box(&row_refs)).unwrap())
+        });
+        c.bench_function(format!("exmex_eval_loop_{}", exp_name).as_str(), |b| {
+            b.iter(|| {
+                black_box(&row_refs)
+                    .iter()
+                    .map(|row| expr.eval(row).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+}
+
 fn exmex_bench_eval_owned(c: &mut Criterion) {
     let parsed_exprs = exmex_parse_owned(&BENCH_EXPRESSIONS_STRS);
     let funcs = parsed_exprs
@@ -459,6 +570,10 @@ criterion_group!(
     exmex_bench_eval_uncompiled,
     exmex_bench_eval_val,
     exmex_bench_eval_owned,
+    exmex_bench_eval_affine,
+    exmex_bench_eval_many,
+    exmex_bench_eval_cse,
+    exmex_bench_eval_int_pow,
     meval_bench_eval,
     rsc_bench_eval,
     evalexpr_bench_eval,
@@ -466,8 +581,11 @@ criterion_group!(
     exmex_bench_parse,
     exmex_bench_parse_uncompiled,
     exmex_bench_parse_owned,
+    exmex_bench_parse_batch,
     exmex_bench_parse_val,
     exmex_bench_parse_optimized,
+    exmex_bench_check,
+    exmex_bench_check_long,
     meval_bench_parse,
     rsc_bench_parse,
     evalexpr_bench_parse,
@@ -482,6 +600,10 @@ criterion_group!(
     exmex_bench_eval,
     exmex_bench_eval_uncompiled,
     exmex_bench_eval_owned,
+    exmex_bench_eval_affine,
+    exmex_bench_eval_many,
+    exmex_bench_eval_cse,
+    exmex_bench_eval_int_pow,
     meval_bench_eval,
     rsc_bench_eval,
     evalexpr_bench_eval,
@@ -489,7 +611,10 @@ criterion_group!(
     exmex_bench_parse,
     exmex_bench_parse_uncompiled,
     exmex_bench_parse_owned,
+    exmex_bench_parse_batch,
     exmex_bench_parse_optimized,
+    exmex_bench_check,
+    exmex_bench_check_long,
     meval_bench_parse,
     rsc_bench_parse,
     evalexpr_bench_parse,
@@ -0,0 +1,67 @@
+//! Derive macro for the trait `VarProvider` of [Exmex](https://docs.rs/exmex). Activate the
+//! feature `derive` of Exmex instead of depending on this crate directly.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, Type};
+
+fn is_f64(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.qself.is_none() && type_path.path.is_ident("f64"),
+        _ => false,
+    }
+}
+
+fn impl_var_provider(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "ExVars can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "ExVars can only be derived for structs",
+            ))
+        }
+    };
+    let mut arms = vec![];
+    for field in fields {
+        if !is_f64(&field.ty) {
+            return Err(Error::new(
+                field.ty.span(),
+                "ExVars supports only fields of type f64",
+            ));
+        }
+        let ident = field.ident.as_ref().expect("fields are named");
+        let name = ident.to_string();
+        let name = name.trim_start_matches("r#");
+        arms.push(quote! { #name => ::core::option::Option::Some(self.#ident), });
+    }
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::exmex::VarProvider for #struct_name #ty_generics #where_clause {
+            fn var_value(&self, var_name: &str) -> ::core::option::Option<f64> {
+                match var_name {
+                    #(#arms)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+/// Implements `exmex::VarProvider` for a struct whose fields are all of type `f64`. The
+/// name of each field is the name of the variable it provides.
+#[proc_macro_derive(ExVars)]
+pub fn derive_ex_vars(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_var_provider(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
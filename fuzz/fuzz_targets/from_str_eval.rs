@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use exmex::{prelude::*, OwnedFlatEx};
+
+// Invariant: parsing never panics but either succeeds or returns an error. Successfully
+// parsed expressions can be evaluated, unparsed, and derived without panics.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(flatex) = FlatEx::<f64>::from_str(s) {
+            let vars = vec![0.5; flatex.n_vars()];
+            let _ = flatex.eval(&vars);
+            let _ = flatex.unparse();
+            for var_idx in 0..flatex.n_vars() {
+                if let Ok(deri) = flatex.clone().partial(var_idx) {
+                    let _ = deri.eval(&vars);
+                }
+            }
+            let owned = OwnedFlatEx::from_flatex(flatex);
+            let _ = owned.eval(&vars);
+        }
+    }
+});
@@ -0,0 +1,25 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use exmex::{literal_matcher_from_pattern, prelude::*, FloatOpsFactory, MatchLiteral};
+
+// matches the empty string everywhere
+literal_matcher_from_pattern!(EmptyMatcher, "^[0-9]*");
+// matches not only at the beginning
+literal_matcher_from_pattern!(UnanchoredMatcher, "[0-9]+(\\.[0-9]+)?");
+// matches multi-byte characters
+literal_matcher_from_pattern!(UnicodeMatcher, "^[①-⑨]");
+
+fn parse_and_eval<LMF: MatchLiteral>(s: &str) {
+    if let Ok(flatex) = FlatEx::<f64, FloatOpsFactory<f64>, LMF>::from_str(s) {
+        let _ = flatex.eval(&vec![0.5; flatex.n_vars()]);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        parse_and_eval::<EmptyMatcher>(s);
+        parse_and_eval::<UnanchoredMatcher>(s);
+        parse_and_eval::<UnicodeMatcher>(s);
+    }
+});
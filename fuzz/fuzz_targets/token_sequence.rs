@@ -0,0 +1,67 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use exmex::prelude::*;
+
+/// Tokens that are likely to form valid expressions such that the fuzzer gets past the
+/// tokenizer more often than with arbitrary bytes.
+#[derive(Arbitrary, Debug)]
+enum Token {
+    Var(u8),
+    CurlyVar(String),
+    Num(u16),
+    Float(f32),
+    Constant,
+    BinOp(u8),
+    UnaryOp(u8),
+    Open,
+    Close,
+    Space,
+    Other(char),
+}
+
+impl Token {
+    fn push_to(&self, s: &mut String) {
+        match self {
+            Token::Var(i) => s.push_str(["x", "y", "z", "α", "x_1"][*i as usize % 5]),
+            Token::CurlyVar(name) => {
+                s.push('{');
+                s.push_str(name);
+                s.push('}');
+            }
+            Token::Num(n) => s.push_str(&n.to_string()),
+            Token::Float(f) => s.push_str(&f.to_string()),
+            Token::Constant => s.push_str("PI"),
+            Token::BinOp(i) => s.push_str(["+", "-", "*", "/", "^"][*i as usize % 5]),
+            Token::UnaryOp(i) => {
+                s.push_str(["sin", "cos", "log", "exp", "sqrt", "abs"][*i as usize % 6])
+            }
+            Token::Open => s.push('('),
+            Token::Close => s.push(')'),
+            Token::Space => s.push(' '),
+            Token::Other(c) => s.push(*c),
+        }
+    }
+}
+
+fn parse_and_eval(s: &str) {
+    if let Ok(flatex) = FlatEx::<f64>::from_str(s) {
+        let vars = vec![0.5; flatex.n_vars()];
+        let _ = flatex.eval(&vars);
+        let _ = flatex.unparse();
+        if flatex.n_vars() > 0 {
+            if let Ok(deri) = flatex.partial(0) {
+                let _ = deri.eval(&vars);
+            }
+        }
+    }
+}
+
+fuzz_target!(|tokens: Vec<Token>| {
+    let mut s = String::new();
+    for token in &tokens {
+        token.push_to(&mut s);
+    }
+    parse_and_eval(&s);
+});
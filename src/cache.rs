@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    marker::PhantomData,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    data_type::DataType, ExError, ExResult, FloatOpsFactory, MakeOperators, MatchLiteral,
+    NumberMatcher, OwnedFlatEx,
+};
+
+type CacheMap<T, OF, LMF> = HashMap<String, Arc<OwnedFlatEx<T, OF, LMF>>>;
+
+/// Registry of parsed expressions keyed by their source string. Applications that parse
+/// the same strings repeatedly can use the cache to parse each string only once.
+/// The cache can be shared between threads.
+///
+/// There is no eviction policy. The cache grows with each new string until
+/// [`clear`](ExprCache::clear) is called.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{Eval, ExprCache, Express};
+///
+/// let cache = ExprCache::<f64>::new();
+/// let expr = cache.get_or_parse("2*x")?;
+/// assert!((expr.eval(&[1.5])? - 3.0).abs() < 1e-12);
+/// let expr_again = cache.get_or_parse("2*x")?;
+/// assert!(std::sync::Arc::ptr_eq(&expr, &expr_again));
+/// assert_eq!(cache.len(), 1);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ExprCache<T, OF = FloatOpsFactory<T>, LMF = NumberMatcher>
+where
+    T: Clone + Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    exprs: Mutex<CacheMap<T, OF, LMF>>,
+    dummy_ops_factory: PhantomData<OF>,
+    dummy_literal_matcher_factory: PhantomData<LMF>,
+}
+
+impl<T, OF, LMF> ExprCache<T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    pub fn new() -> Self {
+        Self {
+            exprs: Mutex::new(HashMap::new()),
+            dummy_ops_factory: PhantomData,
+            dummy_literal_matcher_factory: PhantomData,
+        }
+    }
+
+    fn lock(&self) -> ExResult<MutexGuard<'_, CacheMap<T, OF, LMF>>> {
+        self.exprs
+            .lock()
+            .map_err(|_| ExError::new("expression cache is poisoned"))
+    }
+
+    /// Returns the cached expression of `text` or parses `text` and caches the result.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `text` cannot be parsed. Errors are not cached.
+    ///
+    pub fn get_or_parse(&self, text: &str) -> ExResult<Arc<OwnedFlatEx<T, OF, LMF>>> {
+        if let Some(expr) = self.lock()?.get(text) {
+            return Ok(expr.clone());
+        }
+        // we parse without holding the lock to not block other threads
+        let expr = Arc::new(OwnedFlatEx::<T, OF, LMF>::from_str(text)?);
+        Ok(self.lock()?.entry(text.to_string()).or_insert(expr).clone())
+    }
+
+    /// Removes all cached expressions.
+    pub fn clear(&self) -> ExResult<()> {
+        self.lock()?.clear();
+        Ok(())
+    }
+
+    /// Returns the number of cached expressions.
+    pub fn len(&self) -> usize {
+        self.lock().map(|exprs| exprs.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T, OF, LMF> Default for ExprCache<T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::{util::assert_float_eq_f64, Eval};
+
+#[test]
+fn test_cache() -> ExResult<()> {
+    let cache = ExprCache::<f64>::new();
+    assert!(cache.is_empty());
+    let expr_1 = cache.get_or_parse("x*y+1")?;
+    let expr_2 = cache.get_or_parse("x*y+1")?;
+    assert!(Arc::ptr_eq(&expr_1, &expr_2));
+    assert_float_eq_f64(expr_1.eval(&[2.0, 3.0])?, 7.0);
+    let expr_3 = cache.get_or_parse("sin(x)")?;
+    assert!(!Arc::ptr_eq(&expr_1, &expr_3));
+    assert_eq!(cache.len(), 2);
+
+    assert!(cache.get_or_parse("x*(").is_err());
+    assert_eq!(cache.len(), 2);
+
+    cache.clear()?;
+    assert!(cache.is_empty());
+    let expr_4 = cache.get_or_parse("x*y+1")?;
+    assert!(!Arc::ptr_eq(&expr_1, &expr_4));
+    assert_eq!(expr_1, expr_4);
+    Ok(())
+}
+
+#[test]
+fn test_cache_threads() -> ExResult<()> {
+    let cache = Arc::new(ExprCache::<f64>::new());
+    let texts = ["x+1", "x*2", "x^3", "sin(x)"];
+    let handles = (0..8)
+        .map(|i| {
+            let cache = cache.clone();
+            std::thread::spawn(move || -> ExResult<f64> {
+                let expr = cache.get_or_parse(texts[i % texts.len()])?;
+                expr.eval(&[2.0])
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .collect::<ExResult<Vec<_>>>()?;
+    let reference = [3.0, 4.0, 8.0, 2f64.sin()];
+    for (i, res) in results.iter().enumerate() {
+        assert_float_eq_f64(*res, reference[i % reference.len()]);
+    }
+    assert_eq!(cache.len(), texts.len());
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::{fmt::Debug, str::FromStr};
+
+use smallvec::SmallVec;
+
+use crate::{
+    data_type::DataType,
+    definitions::N_VARS_ON_STACK,
+    parser::{self, ParsedToken},
+    ExError, MakeOperators, MatchLiteral,
+};
+
+/// Result of a successful [`check`](check) of a string.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct CheckReport<'a> {
+    /// Variable names in alphabetical order, i.e., in the order expected by
+    /// [`eval`](crate::Eval::eval).
+    pub var_names: SmallVec<[&'a str; N_VARS_ON_STACK]>,
+    /// Representations of the operators used in the string in alphabetical order, each
+    /// listed once. Constants such as `PI` are not listed.
+    pub operators: Vec<&'a str>,
+}
+
+/// Checks the syntax of a string without building an expression. This is cheaper than
+/// parsing, e.g., to validate user input on each keystroke. The validation is the same
+/// that the parser applies before creating an expression.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{FloatOpsFactory, NumberMatcher};
+/// let report = exmex::check::<f64, FloatOpsFactory<f64>, NumberMatcher>("sin(y)*x+x")
+///     .map_err(|errs| errs[0].clone())?;
+/// assert_eq!(report.var_names.as_slice(), &["x", "y"]);
+/// assert_eq!(report.operators, vec!["*", "+", "sin"]);
+///
+/// let errors = exmex::check::<f64, FloatOpsFactory<f64>, NumberMatcher>("(x*+)-")
+///     .unwrap_err();
+/// assert_eq!(errors.len(), 2);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// All violated preconditions that have been found are returned. If the string cannot be
+/// split into tokens, only the tokenization error is returned.
+///
+pub fn check<'a, T, OF, LMF>(text: &'a str) -> Result<CheckReport<'a>, Vec<ExError>>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    let ops = OF::make();
+    let (parsed_tokens, positions) =
+        parser::tokenize_and_analyze(text, &ops, LMF::is_literal).map_err(|e| vec![e])?;
+    let errors = parser::find_precondition_errors(&parsed_tokens, &positions);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let mut operators = parsed_tokens
+        .iter()
+        .filter_map(|pt| match pt {
+            ParsedToken::Op(op) => Some(op.repr()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    operators.sort_unstable();
+    operators.dedup();
+    Ok(CheckReport {
+        var_names: parser::find_parsed_vars(&parsed_tokens),
+        operators,
+    })
+}
+
+#[cfg(test)]
+use crate::{FloatOpsFactory, NumberMatcher};
+
+#[test]
+fn test_check() {
+    let check_f64 = check::<f64, FloatOpsFactory<f64>, NumberMatcher>;
+    let report = check_f64("z*-x+{a b}^2.3").unwrap();
+    assert_eq!(report.var_names.as_slice(), &["a b", "x", "z"]);
+    assert_eq!(report.operators, vec!["*", "+", "-", "^"]);
+
+    let report = check_f64("PI").unwrap();
+    assert!(report.var_names.is_empty());
+    assert!(report.operators.is_empty());
+
+    assert_eq!(check_f64("").unwrap_err().len(), 1);
+    assert_eq!(check_f64("2 $ 3").unwrap_err().len(), 1);
+    assert_eq!(check_f64("x y").unwrap_err().len(), 1);
+    assert_eq!(check_f64("((x)").unwrap_err().len(), 1);
+    assert_eq!(check_f64("x)+(").unwrap_err().len(), 1);
+    assert_eq!(check_f64("x y)+").unwrap_err().len(), 3);
+    let errors = check_f64("x*/2+").unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].msg.contains("binary operator"));
+    assert!(errors[1].msg.contains("last element"));
+}
@@ -0,0 +1,229 @@
+use std::{fmt::Debug, marker::PhantomData, str::FromStr};
+
+use num::{Complex, Float};
+
+use crate::{
+    expression::MatchLiteral, literal_matcher_from_pattern, BinOp, ExpressionNumber, FlatEx,
+    MakeOperators, Operator, OwnedFlatEx,
+};
+
+/// Factory of default operators for complex numbers.
+///
+/// |representation|description|
+/// |--------------|-----------|
+/// |`^`| power, see [`powc`](num::Complex::powc) |
+/// |`*`| product |
+/// |`/`| division |
+/// |`+`| addition as binary or identity as unary operator|
+/// |`-`| subtraction as binary or inverting the sign as unary operator |
+/// |`sin`| sine |
+/// |`cos`| cosine |
+/// |`exp`| exponential function |
+/// |`log`| principal value of the natural logarithm |
+/// |`sqrt`| principal value of the square root |
+/// |`conj`| complex conjugate |
+/// |`i`| imaginary unit |
+/// |`PI`| constant π  |
+/// |`π`| second representations of constant π  |
+/// |`E`| Euler's number |
+///
+/// Variables called `i` need to be put into curly brackets, i.e., `{i}`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, ComplexMatcher, ComplexOpsFactory};
+/// use num::Complex;
+/// let expr =
+///     FlatEx::<Complex<f64>, ComplexOpsFactory, ComplexMatcher>::from_str("(1+2i)*x")?;
+/// assert_eq!(expr.eval(&[Complex::new(0.0, 1.0)])?, Complex::new(-2.0, 1.0));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct ComplexOpsFactory<F: Float = f64> {
+    dummy: PhantomData<F>,
+}
+
+impl<F: Float> MakeOperators<Complex<F>> for ComplexOpsFactory<F> {
+    /// Returns the default operators.
+    fn make<'a>() -> Vec<Operator<'a, Complex<F>>> {
+        vec![
+            Operator::make_bin(
+                "^",
+                BinOp {
+                    apply: |a: Complex<F>, b| a.powc(b),
+                    prio: 4,
+                    is_commutative: false,
+                },
+            )
+            .with_id("pow"),
+            Operator::make_bin(
+                "*",
+                BinOp {
+                    apply: |a, b| a * b,
+                    prio: 2,
+                    is_commutative: true,
+                },
+            )
+            .with_id("mul"),
+            Operator::make_bin(
+                "/",
+                BinOp {
+                    apply: |a, b| a / b,
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_id("div"),
+            Operator::make_bin_unary(
+                "+",
+                BinOp {
+                    apply: |a, b| a + b,
+                    prio: 0,
+                    is_commutative: true,
+                },
+                |a| a,
+            )
+            .with_id("add"),
+            Operator::make_bin_unary(
+                "-",
+                BinOp {
+                    apply: |a, b| a - b,
+                    prio: 1,
+                    is_commutative: false,
+                },
+                |a: Complex<F>| -a,
+            )
+            .with_id("sub"),
+            Operator::make_unary("sin", |a| a.sin()),
+            Operator::make_unary("cos", |a| a.cos()),
+            Operator::make_unary("exp", |a| a.exp()),
+            Operator::make_unary("log", |a: Complex<F>| a.ln()).with_id("ln"),
+            Operator::make_unary("sqrt", |a| a.sqrt()),
+            Operator::make_unary("conj", |a| a.conj()),
+            Operator::make_constant("i", Complex::i()),
+            Operator::make_constant("PI", Complex::from(F::from(std::f64::consts::PI).unwrap()))
+                .with_id("pi"),
+            Operator::make_constant("π", Complex::from(F::from(std::f64::consts::PI).unwrap()))
+                .with_id("pi"),
+            Operator::make_constant("E", Complex::from(F::from(std::f64::consts::E).unwrap()))
+                .with_id("e"),
+        ]
+    }
+}
+
+/// Factory to match literals of real or imaginary numbers such as `2.5` or `3i`. A complex
+/// number such as `2+3i` is the sum of the literals `2` and `3i`. Constant sums are
+/// evaluated during parsing.
+const PATTERN: &str = r"^([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?i?";
+literal_matcher_from_pattern!(ComplexMatcher, PATTERN);
+
+impl<F> ExpressionNumber for Complex<F>
+where
+    F: Float + FromStr + Debug,
+{
+    type OpsFactory = ComplexOpsFactory<F>;
+    type LiteralMatcher = ComplexMatcher;
+}
+
+/// Alias for [`FlatEx`](FlatEx) with [`Complex`](num::Complex) as data type and
+/// [`ComplexOpsFactory`](ComplexOpsFactory) as operator factory.
+pub type FlatExComplex<'a, F = f64> = FlatEx<'a, Complex<F>, ComplexOpsFactory<F>, ComplexMatcher>;
+/// Alias for [`OwnedFlatEx`](OwnedFlatEx) with [`Complex`](num::Complex) as data type and
+/// [`ComplexOpsFactory`](ComplexOpsFactory) as operator factory.
+pub type OwnedFlatExComplex<F = f64> =
+    OwnedFlatEx<Complex<F>, ComplexOpsFactory<F>, ComplexMatcher>;
+
+#[cfg(test)]
+mod tests {
+
+    use num::Complex;
+
+    use crate::{
+        util::assert_float_eq_f64, Eval, ExResult, Express, FlatExComplex, OwnedFlatExComplex,
+    };
+
+    fn assert_complex_eq(a: Complex<f64>, b: Complex<f64>) {
+        assert_float_eq_f64(a.re, b.re);
+        assert_float_eq_f64(a.im, b.im);
+    }
+
+    #[test]
+    fn test_complex() -> ExResult<()> {
+        fn test(text: &str, vars: &[Complex<f64>], reference: Complex<f64>) -> ExResult<()> {
+            println!("testing {}...", text);
+            let expr = FlatExComplex::<f64>::from_str(text)?;
+            assert_complex_eq(expr.eval(vars)?, reference);
+            let expr = OwnedFlatExComplex::<f64>::from_str(text)?;
+            assert_complex_eq(expr.eval(vars)?, reference);
+            println!("...ok.");
+            Ok(())
+        }
+        let i = Complex::i();
+        test("(1+2i)*x", &[i], Complex::new(-2.0, 1.0))?;
+        test("2+3i", &[], Complex::new(2.0, 3.0))?;
+        test("2-3.5i", &[], Complex::new(2.0, -3.5))?;
+        test("i*i", &[], Complex::new(-1.0, 0.0))?;
+        test("-i^2", &[], Complex::new(-1.0, 0.0))?;
+        test("x/y", &[Complex::new(1.0, 1.0), i], Complex::new(1.0, -1.0))?;
+        test("exp(i*PI)", &[], Complex::new(-1.0, 0.0))?;
+        test(
+            "sin(x)^2+cos(x)^2",
+            &[Complex::new(0.3, -1.2)],
+            Complex::new(1.0, 0.0),
+        )?;
+        test(
+            "sqrt(x)",
+            &[Complex::new(-4.0, 0.0)],
+            Complex::new(0.0, 2.0),
+        )?;
+        test(
+            "log(E)*conj(x)",
+            &[Complex::new(1.0, 2.0)],
+            Complex::new(1.0, -2.0),
+        )?;
+        test(
+            "{i}*1e1i",
+            &[Complex::new(2.0, 0.0)],
+            Complex::new(0.0, 20.0),
+        )?;
+
+        let expr = FlatExComplex::<f64>::from_str("(1+2i)*x")?;
+        assert_eq!(expr.unparse()?, "(1+2i)*x");
+        assert!(FlatExComplex::<f64>::from_str("2ii").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_level_functions() -> ExResult<()> {
+        let i = Complex::i();
+        let expr = crate::parse::<Complex<f64>>("i*x")?;
+        assert_complex_eq(
+            expr.eval(&[Complex::new(2.0, 0.0)])?,
+            Complex::new(0.0, 2.0),
+        );
+        assert_complex_eq(
+            crate::eval_str::<Complex<f64>>("exp(i*PI)+2i")?,
+            Complex::new(-1.0, 2.0),
+        );
+        assert!(crate::eval_str::<Complex<f64>>("i*x").is_err());
+        assert_complex_eq(
+            crate::eval_str_vars::<Complex<f64>>("x*y", &[("x", i), ("y", i)])?,
+            Complex::new(-1.0, 0.0),
+        );
+        let res = crate::eval_str::<Complex<f32>>("(1+i)^2")?;
+        assert!(res.re.abs() < 1e-6 && (res.im - 2.0).abs() < 1e-6);
+        assert_eq!(crate::vars_in::<Complex<f64>>("i*x+2i*y")?, vec!["x", "y"]);
+        assert_eq!(crate::vars_in::<f64>("i*x+y")?, vec!["i", "x", "y"]);
+        let exprs = crate::parse_batch::<Complex<f64>>(&["(1+2i)*x", "2ii", "x+"]);
+        assert_complex_eq(
+            exprs[0].as_ref().unwrap().eval(&[i])?,
+            Complex::new(-2.0, 1.0),
+        );
+        assert!(exprs[1].is_err() && exprs[2].is_err());
+        Ok(())
+    }
+}
@@ -1,4 +1,71 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{cmp::Ordering, fmt::Debug, str::FromStr};
+
+use crate::{FloatOpsFactory, MakeOperators, MatchLiteral, NumberMatcher};
 
 pub trait DataType: Clone + FromStr + Debug {}
 impl<T: Clone + FromStr + Debug> DataType for T {}
+
+/// Number types that can be used with the top-level functions such as
+/// [`parse`](crate::parse) and [`eval_str`](crate::eval_str). The operators and literals are
+/// taken from the default operator factory and literal matcher of the number type, e.g.,
+/// [`FloatOpsFactory`](FloatOpsFactory) for floats and
+/// [`ComplexOpsFactory`](crate::ComplexOpsFactory) for complex numbers.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// use num::Complex;
+/// let expr = exmex::parse::<Complex<f64>>("i*x")?;
+/// assert_eq!(expr.eval(&[Complex::new(2.0, 0.0)])?, Complex::new(0.0, 2.0));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub trait ExpressionNumber: DataType {
+    type OpsFactory: MakeOperators<Self>;
+    type LiteralMatcher: MatchLiteral;
+}
+
+impl ExpressionNumber for f32 {
+    type OpsFactory = FloatOpsFactory<f32>;
+    type LiteralMatcher = NumberMatcher;
+}
+
+impl ExpressionNumber for f64 {
+    type OpsFactory = FloatOpsFactory<f64>;
+    type LiteralMatcher = NumberMatcher;
+}
+
+/// Total order of numbers that is used to compare expressions. Floats are compared with
+/// [`f64::total_cmp`](f64::total_cmp) such that also expressions with `NaN` constants can be
+/// keys of, e.g., a [`BTreeMap`](std::collections::BTreeMap).
+pub trait TotalCmp {
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl TotalCmp for f32 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
+
+impl TotalCmp for f64 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+macro_rules! impl_total_cmp_for_ord {
+    ($($t:ty),*) => {
+        $(
+            impl TotalCmp for $t {
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+impl_total_cmp_for_ord!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, bool);
@@ -0,0 +1,464 @@
+use std::{
+    marker::PhantomData,
+    ops::{Add, Div, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use num::Float;
+
+use crate::{BinOp, FlatEx, MakeOperators, NumberMatcher, Operator, OwnedFlatEx};
+
+/// Dual number `real + dual*ε` with `ε^2 = 0` for forward-mode automatic differentiation.
+/// Evaluating an expression `f` with dual numbers results in `f(x) + f'(x)*ε`, i.e., the
+/// function value and the derivative are computed simultaneously without symbolic
+/// manipulation. The derivative with respect to a variable is obtained by passing it as
+/// [`variable`](DualNumber::variable) and all other variables as
+/// [`constant`](DualNumber::constant).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, DualNumber, FlatExDual};
+/// let expr = FlatExDual::<f64>::from_str("sin(x)*y^2")?;
+/// let (x, y) = (0.5f64, 3.0);
+/// let res = expr.eval(&[DualNumber::variable(x), DualNumber::constant(y)])?;
+/// assert!((res.real - x.sin() * y * y).abs() < 1e-12);
+/// assert!((res.dual - x.cos() * y * y).abs() < 1e-12);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug)]
+pub struct DualNumber<T: Float> {
+    pub real: T,
+    pub dual: T,
+}
+
+impl<T: Float> DualNumber<T> {
+    pub fn new(real: T, dual: T) -> Self {
+        DualNumber { real, dual }
+    }
+
+    /// Creates the dual number of the variable we differentiate with respect to, i.e.,
+    /// its dual part is one.
+    pub fn variable(real: T) -> Self {
+        Self::new(real, T::one())
+    }
+
+    /// Creates a dual number that is constant with respect to the differentiation, i.e.,
+    /// its dual part is zero.
+    pub fn constant(real: T) -> Self {
+        Self::new(real, T::zero())
+    }
+
+    /// Applies a function with value `f` and derivative `df` at `self.real` by the chain rule.
+    fn chain(self, f: T, df: T) -> Self {
+        Self::new(f, self.dual * df)
+    }
+
+    pub fn powf(self, exponent: Self) -> Self {
+        let real = self.real.powf(exponent.real);
+        // zero dual parts are skipped to avoid NaNs, e.g., from the logarithm of negative bases
+        let d_base = if self.dual == T::zero() {
+            T::zero()
+        } else {
+            exponent.real * self.real.powf(exponent.real - T::one()) * self.dual
+        };
+        let d_exponent = if exponent.dual == T::zero() {
+            T::zero()
+        } else {
+            real * self.real.ln() * exponent.dual
+        };
+        Self::new(real, d_base + d_exponent)
+    }
+
+    pub fn atan2(self, other: Self) -> Self {
+        let denominator = self.real * self.real + other.real * other.real;
+        Self::new(
+            self.real.atan2(other.real),
+            (other.real * self.dual - self.real * other.dual) / denominator,
+        )
+    }
+
+    pub fn abs(self) -> Self {
+        self.chain(self.real.abs(), self.real.signum())
+    }
+
+    pub fn signum(self) -> Self {
+        Self::constant(self.real.signum())
+    }
+
+    pub fn sin(self) -> Self {
+        self.chain(self.real.sin(), self.real.cos())
+    }
+
+    pub fn cos(self) -> Self {
+        self.chain(self.real.cos(), -self.real.sin())
+    }
+
+    pub fn tan(self) -> Self {
+        let tan = self.real.tan();
+        self.chain(tan, T::one() + tan * tan)
+    }
+
+    pub fn asin(self) -> Self {
+        self.chain(
+            self.real.asin(),
+            (T::one() - self.real * self.real).sqrt().recip(),
+        )
+    }
+
+    pub fn acos(self) -> Self {
+        self.chain(
+            self.real.acos(),
+            -(T::one() - self.real * self.real).sqrt().recip(),
+        )
+    }
+
+    pub fn atan(self) -> Self {
+        self.chain(self.real.atan(), (T::one() + self.real * self.real).recip())
+    }
+
+    pub fn sinh(self) -> Self {
+        self.chain(self.real.sinh(), self.real.cosh())
+    }
+
+    pub fn cosh(self) -> Self {
+        self.chain(self.real.cosh(), self.real.sinh())
+    }
+
+    pub fn tanh(self) -> Self {
+        let tanh = self.real.tanh();
+        self.chain(tanh, T::one() - tanh * tanh)
+    }
+
+    pub fn floor(self) -> Self {
+        Self::constant(self.real.floor())
+    }
+
+    pub fn ceil(self) -> Self {
+        Self::constant(self.real.ceil())
+    }
+
+    pub fn round(self) -> Self {
+        Self::constant(self.real.round())
+    }
+
+    pub fn trunc(self) -> Self {
+        Self::constant(self.real.trunc())
+    }
+
+    pub fn fract(self) -> Self {
+        self.chain(self.real.fract(), T::one())
+    }
+
+    pub fn exp(self) -> Self {
+        let exp = self.real.exp();
+        self.chain(exp, exp)
+    }
+
+    pub fn exp2(self) -> Self {
+        let exp2 = self.real.exp2();
+        self.chain(exp2, exp2 * T::from(std::f64::consts::LN_2).unwrap())
+    }
+
+    pub fn sqrt(self) -> Self {
+        let sqrt = self.real.sqrt();
+        self.chain(sqrt, (sqrt + sqrt).recip())
+    }
+
+    pub fn cbrt(self) -> Self {
+        let cbrt = self.real.cbrt();
+        self.chain(cbrt, (T::from(3.0).unwrap() * cbrt * cbrt).recip())
+    }
+
+    pub fn recip(self) -> Self {
+        self.chain(self.real.recip(), -(self.real * self.real).recip())
+    }
+
+    pub fn ln(self) -> Self {
+        self.chain(self.real.ln(), self.real.recip())
+    }
+
+    pub fn log2(self) -> Self {
+        self.chain(
+            self.real.log2(),
+            (self.real * T::from(std::f64::consts::LN_2).unwrap()).recip(),
+        )
+    }
+}
+
+impl<T: Float> Add for DualNumber<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.real + other.real, self.dual + other.dual)
+    }
+}
+
+impl<T: Float> Sub for DualNumber<T> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.real - other.real, self.dual - other.dual)
+    }
+}
+
+impl<T: Float> Mul for DualNumber<T> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.real * other.real,
+            self.dual * other.real + self.real * other.dual,
+        )
+    }
+}
+
+impl<T: Float> Div for DualNumber<T> {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        Self::new(
+            self.real / other.real,
+            (self.dual * other.real - self.real * other.dual) / (other.real * other.real),
+        )
+    }
+}
+
+impl<T: Float> Neg for DualNumber<T> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.real, -self.dual)
+    }
+}
+
+/// Literals are parsed as constants, i.e., with a dual part of zero.
+impl<T: Float + FromStr> FromStr for DualNumber<T> {
+    type Err = <T as FromStr>::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<T>().map(Self::constant)
+    }
+}
+
+/// Factory of default operators for [`DualNumber`](DualNumber)s. The operators are lifted
+/// to dual numbers, e.g., `sin(a + a'ε) = sin(a) + a'cos(a)ε`.
+///
+/// |representation|description|
+/// |--------------|-----------|
+/// |`^`| power |
+/// |`*`| product |
+/// |`/`| division |
+/// |`+`| addition as binary or identity as unary operator|
+/// |`-`| subtraction as binary or inverting the sign as unary operator |
+/// |`atan2`| four-quadrant inverse tangent called as `atan2(y, x)` |
+/// |`abs`| absolute value |
+/// |`signum`| signum with a derivative of zero |
+/// |`sin`| sine |
+/// |`cos`| cosine |
+/// |`tan`| tangent |
+/// |`asin`| inverse sine |
+/// |`acos`| inverse cosine |
+/// |`atan`| inverse tangent |
+/// |`sinh`| hyperbolic sine |
+/// |`cosh`| hyperbolic cosine |
+/// |`tanh`| hyperbolic tangent |
+/// |`floor`| largest integer less than or equal to a number with a derivative of zero |
+/// |`ceil`| smallest integer greater than or equal to a number with a derivative of zero |
+/// |`round`| nearest integer with a derivative of zero |
+/// |`trunc`| integer part of a number with a derivative of zero |
+/// |`fract`| fractional part of a number |
+/// |`exp`| exponential function |
+/// |`exp2`| exponential function with basis 2 |
+/// |`sqrt`| square root |
+/// |`cbrt`| cube root |
+/// |`recip`| reciprocal `1/x` |
+/// |`log`| natural logarithm  |
+/// |`log2`| logarithm with basis 2  |
+/// |`PI`| constant π  |
+/// |`π`| second representations of constant π  |
+/// |`E`| Euler's number |
+///
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct DualOpsFactory<F: Float = f64> {
+    dummy: PhantomData<F>,
+}
+
+impl<F: Float> MakeOperators<DualNumber<F>> for DualOpsFactory<F> {
+    /// Returns the default operators.
+    fn make<'a>() -> Vec<Operator<'a, DualNumber<F>>> {
+        vec![
+            Operator::make_bin(
+                "^",
+                BinOp {
+                    apply: |a: DualNumber<F>, b| a.powf(b),
+                    prio: 4,
+                    is_commutative: false,
+                },
+            )
+            .with_id("pow"),
+            Operator::make_bin(
+                "*",
+                BinOp {
+                    apply: |a, b| a * b,
+                    prio: 2,
+                    is_commutative: true,
+                },
+            )
+            .with_id("mul"),
+            Operator::make_bin(
+                "/",
+                BinOp {
+                    apply: |a, b| a / b,
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_id("div"),
+            Operator::make_bin_unary(
+                "+",
+                BinOp {
+                    apply: |a, b| a + b,
+                    prio: 0,
+                    is_commutative: true,
+                },
+                |a| a,
+            )
+            .with_id("add"),
+            Operator::make_bin_unary(
+                "-",
+                BinOp {
+                    apply: |a, b| a - b,
+                    prio: 1,
+                    is_commutative: false,
+                },
+                |a: DualNumber<F>| -a,
+            )
+            .with_id("sub"),
+            Operator::make_unary("abs", |a| a.abs()),
+            Operator::make_unary("signum", |a| a.signum()),
+            Operator::make_unary("sin", |a| a.sin()),
+            Operator::make_unary("cos", |a| a.cos()),
+            Operator::make_unary("tan", |a| a.tan()),
+            Operator::make_unary("asin", |a| a.asin()),
+            Operator::make_unary("acos", |a| a.acos()),
+            Operator::make_unary("atan", |a| a.atan()),
+            Operator::make_unary("sinh", |a| a.sinh()),
+            Operator::make_unary("cosh", |a| a.cosh()),
+            Operator::make_unary("tanh", |a| a.tanh()),
+            Operator::make_unary("floor", |a| a.floor()),
+            Operator::make_unary("ceil", |a| a.ceil()),
+            Operator::make_unary("round", |a| a.round()),
+            Operator::make_unary("trunc", |a| a.trunc()),
+            Operator::make_unary("fract", |a| a.fract()),
+            Operator::make_unary("exp", |a| a.exp()),
+            Operator::make_unary("exp2", |a| a.exp2()),
+            Operator::make_unary("sqrt", |a| a.sqrt()),
+            Operator::make_unary("cbrt", |a| a.cbrt()),
+            Operator::make_unary("recip", |a| a.recip()),
+            Operator::make_unary("log", |a: DualNumber<F>| a.ln()).with_id("ln"),
+            Operator::make_unary("log2", |a| a.log2()),
+            Operator::make_constant(
+                "PI",
+                DualNumber::constant(F::from(std::f64::consts::PI).unwrap()),
+            )
+            .with_id("pi"),
+            Operator::make_constant(
+                "π",
+                DualNumber::constant(F::from(std::f64::consts::PI).unwrap()),
+            )
+            .with_id("pi"),
+            Operator::make_constant(
+                "E",
+                DualNumber::constant(F::from(std::f64::consts::E).unwrap()),
+            )
+            .with_id("e"),
+            Operator::make_bin(
+                "atan2",
+                BinOp {
+                    apply: |y: DualNumber<F>, x| y.atan2(x),
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_function_style(),
+        ]
+    }
+}
+
+/// Alias for [`FlatEx`](FlatEx) with [`DualNumber`](DualNumber) as data type and
+/// [`DualOpsFactory`](DualOpsFactory) as operator factory.
+pub type FlatExDual<'a, F = f64> = FlatEx<'a, DualNumber<F>, DualOpsFactory<F>, NumberMatcher>;
+/// Alias for [`OwnedFlatEx`](OwnedFlatEx) with [`DualNumber`](DualNumber) as data type and
+/// [`DualOpsFactory`](DualOpsFactory) as operator factory.
+pub type OwnedFlatExDual<F = f64> = OwnedFlatEx<DualNumber<F>, DualOpsFactory<F>, NumberMatcher>;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        util::assert_float_eq_f64, DualNumber, Eval, ExResult, Express, FlatEx, FlatExDual,
+        OwnedFlatExDual,
+    };
+
+    #[test]
+    fn test_dual() -> ExResult<()> {
+        fn test(text: &str, vars: &[f64]) -> ExResult<()> {
+            println!("testing {}...", text);
+            let flatex = FlatEx::<f64>::from_str(text)?;
+            let dual_expr = FlatExDual::<f64>::from_str(text)?;
+            let owned_dual_expr = OwnedFlatExDual::<f64>::from_str(text)?;
+            for var_idx in 0..vars.len() {
+                let dual_vars = vars
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        if i == var_idx {
+                            DualNumber::variable(*v)
+                        } else {
+                            DualNumber::constant(*v)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let reference = flatex.clone().partial(var_idx)?.eval(vars)?;
+                let res = dual_expr.eval(&dual_vars)?;
+                assert_float_eq_f64(res.real, flatex.eval(vars)?);
+                assert_float_eq_f64(res.dual, reference);
+                assert_eq!(owned_dual_expr.eval(&dual_vars)?, res);
+            }
+            println!("...ok.");
+            Ok(())
+        }
+        test("x*y+3", &[0.5, -2.0])?;
+        test("x/y-y/x", &[1.5, -2.0])?;
+        test("x^3.5", &[1.3])?;
+        test("2^x", &[-0.7])?;
+        test("x^y", &[1.7, 2.2])?;
+        test("(-x)^2", &[0.7])?;
+        test("sin(x)*cos(y)+tan(x*y)", &[0.3, 0.9])?;
+        test("asin(x)+acos(x/2)+atan(y)", &[0.3, 0.9])?;
+        test("sinh(x)+cosh(x*y)-tanh(y)", &[0.3, 0.9])?;
+        test("exp(x)*log(y)", &[0.3, 0.9])?;
+        test("sqrt(x)+recip(x*y)", &[2.3, 0.9])?;
+        test("atan2(y, x)", &[-2.3, 0.9])?;
+        test("-PI*x+E*y", &[1.0, 2.0])?;
+
+        let expr = FlatExDual::<f64>::from_str("floor(x)+ceil(x)+round(x)+trunc(x)+fract(x)")?;
+        let res = expr.eval(&[DualNumber::variable(2.3)])?;
+        assert_float_eq_f64(res.real, 2.0 + 3.0 + 2.0 + 2.0 + 0.3);
+        assert_float_eq_f64(res.dual, 1.0);
+        let expr = FlatExDual::<f64>::from_str("exp2(x)+log2(x)+cbrt(x)")?;
+        let x = 2.3f64;
+        let res = expr.eval(&[DualNumber::variable(x)])?;
+        assert_float_eq_f64(res.real, x.exp2() + x.log2() + x.cbrt());
+        let ln_2 = std::f64::consts::LN_2;
+        let reference = x.exp2() * ln_2 + 1.0 / (x * ln_2) + 1.0 / (3.0 * x.cbrt().powi(2));
+        assert_float_eq_f64(res.dual, reference);
+        let expr = FlatExDual::<f64>::from_str("abs(x)*signum(y)")?;
+        let res = expr.eval(&[DualNumber::variable(-2.3), DualNumber::constant(0.9)])?;
+        assert_eq!(res, DualNumber::new(2.3, -1.0));
+        assert_eq!(
+            "2.5".parse::<DualNumber<f64>>(),
+            Ok(DualNumber::constant(2.5))
+        );
+        assert!(FlatExDual::<f64>::from_str("x+").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use std::{fmt::Debug, ops::Range, str::FromStr};
+
+use num::Float;
+use smallvec::SmallVec;
+
+use crate::{
+    data_type::DataType, definitions::N_VARS_ON_STACK, Eval, ExResult, Express, FlatEx,
+    FloatOpsFactory, MakeOperators, MatchLiteral, NumberMatcher,
+};
+
+/// Method that decided whether two strings are semantically equal, see
+/// [`semantically_equal_with`](semantically_equal_with).
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum EqualityMethod {
+    /// The structures of the parsed expressions have been compared.
+    Structure,
+    /// The expressions have been evaluated at sample points.
+    Samples,
+}
+
+/// Result of [`semantically_equal_with`](semantically_equal_with).
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct EqualityReport {
+    pub is_equal: bool,
+    pub method: EqualityMethod,
+}
+
+/// Configuration of [`semantically_equal_with`](semantically_equal_with).
+#[derive(Clone, PartialEq, Debug)]
+pub struct EqualityConfig<T> {
+    /// If false, expressions with different structures are not equal. If true, the
+    /// expressions are evaluated at sample points in case their structures differ.
+    pub use_samples: bool,
+    /// Number of sample points, at least one point is used.
+    pub n_samples: usize,
+    /// Range the values of each variable are sampled from.
+    pub range: Range<T>,
+    /// Two results `a` and `b` are equal if `|a-b| <= tolerance*max(1, |a|, |b|)`. Two NaNs
+    /// are equal.
+    pub tolerance: T,
+}
+
+impl<T: Float> Default for EqualityConfig<T> {
+    fn default() -> Self {
+        EqualityConfig {
+            use_samples: true,
+            n_samples: 100,
+            range: T::from(-10.0).unwrap()..T::from(10.0).unwrap(),
+            tolerance: T::from(1e-10).unwrap(),
+        }
+    }
+}
+
+/// Deterministic pseudo random numbers in `[0, 1)` created by SplitMix64.
+fn next_unit<T: Float>(state: &mut u64) -> T {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // 53 random bits are exactly representable as f64
+    T::from((z >> 11) as f64 / (1u64 << 53) as f64).unwrap()
+}
+
+fn is_close<T: Float>(a: T, b: T, tolerance: T) -> bool {
+    if a.is_nan() || b.is_nan() {
+        a.is_nan() && b.is_nan()
+    } else if a.is_infinite() || b.is_infinite() {
+        a == b
+    } else {
+        (a - b).abs() <= tolerance * T::one().max(a.abs()).max(b.abs())
+    }
+}
+
+/// Checks whether two strings are semantically equal, e.g., to decide whether an edit of a
+/// stored formula is only cosmetic. Both strings are parsed with the same operator factory.
+/// First, the structures of the expressions are compared, which neither depend on whitespace
+/// and redundant parentheses nor on the order of the two operands of commutative operators.
+/// Second, if configured and the structures differ, the expressions are evaluated at
+/// deterministic sample points of the variables of both expressions. Evaluations at
+/// sample points are a heuristic, expressions that differ only outside of the sampled range
+/// are considered to be equal.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{EqualityConfig, EqualityMethod, FloatOpsFactory, NumberMatcher};
+/// let config = EqualityConfig::default();
+/// let equal = exmex::semantically_equal_with::<f64, FloatOpsFactory<f64>, NumberMatcher>;
+/// let report = equal("2*x + (y)", "y+x*2", &config)?;
+/// assert!(report.is_equal);
+/// assert_eq!(report.method, EqualityMethod::Structure);
+/// let report = equal("sin(x)^2+cos(x)^2", "1", &config)?;
+/// assert!(report.is_equal);
+/// assert_eq!(report.method, EqualityMethod::Samples);
+/// let report = equal("x/y", "y/x", &config)?;
+/// assert!(!report.is_equal);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// * If one of the strings cannot be parsed, we return the error of the parser.
+/// * If an evaluation at a sample point fails, we return its error.
+///
+pub fn semantically_equal_with<T, OF, LMF>(
+    a: &str,
+    b: &str,
+    config: &EqualityConfig<T>,
+) -> ExResult<EqualityReport>
+where
+    T: Float + DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    let expr_a = FlatEx::<T, OF, LMF>::from_str(a)?;
+    let expr_b = FlatEx::<T, OF, LMF>::from_str(b)?;
+    let report = |is_equal, method| EqualityReport { is_equal, method };
+    if expr_a.canonical_form() == expr_b.canonical_form() {
+        return Ok(report(true, EqualityMethod::Structure));
+    }
+    if !config.use_samples {
+        return Ok(report(false, EqualityMethod::Structure));
+    }
+    let mut var_names = expr_a.var_names();
+    var_names.extend(expr_b.var_names());
+    var_names.sort_unstable();
+    var_names.dedup();
+    let eval_at = |expr: &FlatEx<T, OF, LMF>, point: &[T]| {
+        let vars = expr
+            .var_names()
+            .iter()
+            .map(|name| point[var_names.binary_search(name).unwrap()])
+            .collect::<SmallVec<[T; N_VARS_ON_STACK]>>();
+        expr.eval(&vars)
+    };
+    let width = config.range.end - config.range.start;
+    let mut state = 0u64;
+    for _ in 0..config.n_samples.max(1) {
+        let point = var_names
+            .iter()
+            .map(|_| config.range.start + next_unit::<T>(&mut state) * width)
+            .collect::<SmallVec<[T; N_VARS_ON_STACK]>>();
+        if !is_close(
+            eval_at(&expr_a, &point)?,
+            eval_at(&expr_b, &point)?,
+            config.tolerance,
+        ) {
+            return Ok(report(false, EqualityMethod::Samples));
+        }
+    }
+    Ok(report(true, EqualityMethod::Samples))
+}
+
+/// Checks with the default operators and the default [`EqualityConfig`](EqualityConfig)
+/// whether two strings are semantically equal, see
+/// [`semantically_equal_with`](semantically_equal_with).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// assert!(exmex::semantically_equal::<f64>("a*(b+c)", "(c+b) * a")?);
+/// assert!(!exmex::semantically_equal::<f64>("a*(b+c)", "a*b+c")?);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// See [`semantically_equal_with`](semantically_equal_with).
+///
+pub fn semantically_equal<T>(a: &str, b: &str) -> ExResult<bool>
+where
+    T: Float + DataType,
+    <T as FromStr>::Err: Debug,
+{
+    semantically_equal_with::<T, FloatOpsFactory<T>, NumberMatcher>(
+        a,
+        b,
+        &EqualityConfig::default(),
+    )
+    .map(|report| report.is_equal)
+}
+
+#[test]
+fn test_semantically_equal() -> ExResult<()> {
+    fn test(a: &str, b: &str, is_equal: bool, method: EqualityMethod) -> ExResult<()> {
+        println!("testing {} and {}...", a, b);
+        let equal = semantically_equal_with::<f64, FloatOpsFactory<f64>, NumberMatcher>;
+        let report = equal(a, b, &EqualityConfig::default())?;
+        assert_eq!(report, EqualityReport { is_equal, method });
+        assert_eq!(equal(b, a, &EqualityConfig::default())?, report);
+        assert_eq!(semantically_equal::<f64>(a, b)?, is_equal);
+        Ok(())
+    }
+    // cosmetic changes
+    test("2*x+y", " 2 * x +   y ", true, EqualityMethod::Structure)?;
+    test("x*y+z", "(x*y)+(z)", true, EqualityMethod::Structure)?;
+    test("sin(x)", "sin((x))", true, EqualityMethod::Structure)?;
+    test("x^2", "((x)^2)", true, EqualityMethod::Structure)?;
+    // reordering of commutative terms
+    test("x*y+z", "z+y*x", true, EqualityMethod::Structure)?;
+    test("x+y+z", "z+x+y", true, EqualityMethod::Samples)?;
+    test("x+(y+z)", "x+y+z", true, EqualityMethod::Samples)?;
+    // equal results but different formulas
+    test("sin(x)^2+cos(x)^2", "1", true, EqualityMethod::Samples)?;
+    test("x+y-y", "x", true, EqualityMethod::Samples)?;
+    test("2*(x+1)", "2*x+2", true, EqualityMethod::Samples)?;
+    // semantic changes
+    test("x-y", "y-x", false, EqualityMethod::Samples)?;
+    test("x^2", "2^x", false, EqualityMethod::Samples)?;
+    test("x+y", "x+z", false, EqualityMethod::Samples)?;
+    test("x*(y+z)", "x*y+z", false, EqualityMethod::Samples)?;
+    test("log(x)", "sqrt(x)", false, EqualityMethod::Samples)?;
+    // no fallback
+    let config = EqualityConfig {
+        use_samples: false,
+        ..EqualityConfig::default()
+    };
+    let report = semantically_equal_with::<f64, FloatOpsFactory<f64>, NumberMatcher>(
+        "x+y+z", "z+x+y", &config,
+    )?;
+    assert_eq!(
+        report,
+        EqualityReport {
+            is_equal: false,
+            method: EqualityMethod::Structure
+        }
+    );
+    assert!(semantically_equal::<f64>("x+", "x").is_err());
+    Ok(())
+}
@@ -5,12 +5,15 @@ use crate::{
         N_VARS_ON_STACK,
     },
     expression::deep_details::{self, BinOpsWithReprsBuf, UnaryOpWithReprsBuf},
-    operators::{BinOp, UnaryOp},
-    parser, ExError, ExResult, Operator,
+    format_exerr,
+    operators::{BinOp, TernaryOp, UnaryOp},
+    parser::{self, CurlyVarNames, ParsedToken},
+    ExError, ExResult, Operator,
 };
 use num::Float;
 use smallvec::{smallvec, SmallVec};
 use std::{
+    collections::HashMap,
     fmt,
     fmt::{Debug, Display, Formatter},
     iter,
@@ -26,22 +29,35 @@ pub fn parse<'a, T, F>(
     text: &'a str,
     ops: &[Operator<'a, T>],
     is_numeric: F,
+    curly_var_names: CurlyVarNames,
 ) -> ExResult<DeepEx<'a, T>>
 where
     T: DataType,
     <T as FromStr>::Err: Debug,
     F: Fn(&'a str) -> Option<&'a str>,
 {
-    let parsed_tokens = parser::tokenize_and_analyze(text, ops, is_numeric)?;
-    parser::check_parsed_token_preconditions(&parsed_tokens)?;
+    let (parsed_tokens, positions) =
+        parser::tokenize_and_analyze_with_curly_var_names(text, ops, is_numeric, curly_var_names)?;
+    parser::check_parsed_token_preconditions(&parsed_tokens, &positions)?;
+    let stochastic_op = parsed_tokens.iter().find_map(|token| match token {
+        ParsedToken::Op(op) if op.is_stochastic() => Some(op.repr()),
+        _ => None,
+    });
+    if let Some(repr) = stochastic_op {
+        return Err(format_exerr!(
+            "there is no deep expression of '{}' since it contains the stochastic operator '{}'",
+            text,
+            repr
+        ));
+    }
     let parsed_vars = parser::find_parsed_vars(&parsed_tokens);
     let (expr, _) =
         deep_details::make_expression(&parsed_tokens[0..], &parsed_vars, UnaryOpWithReprs::new())?;
     Ok(expr)
 }
 
-/// A deep node can be an expression, a number, or
-/// a variable.
+/// A deep node can be an expression, a number, a variable, or a ternary operator
+/// applied to its arguments.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum DeepNode<'a, T: Clone + Debug> {
     /// Boxing this due to <https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant>
@@ -50,6 +66,7 @@ pub enum DeepNode<'a, T: Clone + Debug> {
     /// The contained integer points to the index of the variable in the slice of
     /// variables passed to [`eval`](Expression::eval).
     Var((usize, &'a str)),
+    Ternary(Box<TernaryOpWithArgs<'a, T>>),
 }
 impl<'a, T: Debug> DeepNode<'a, T>
 where
@@ -71,6 +88,17 @@ impl<'a, T: Clone + Debug> Debug for DeepNode<'a, T> {
             DeepNode::Expr(e) => write!(f, "{}", e),
             DeepNode::Num(n) => write!(f, "{:?}", n),
             DeepNode::Var((_, var_name)) => write!(f, "{}", var_name),
+            DeepNode::Ternary(t) => {
+                let [c, a, b] = &t.args;
+                write!(
+                    f,
+                    "{}({}, {}, {})",
+                    t.repr,
+                    c.unparse_raw(),
+                    a.unparse_raw(),
+                    b.unparse_raw()
+                )
+            }
         }
     }
 }
@@ -78,12 +106,15 @@ impl<'a, T: Clone + Debug> Debug for DeepNode<'a, T> {
 pub struct BinOpsWithReprs<'a, T: Clone> {
     pub reprs: SmallVec<[&'a str; N_BINOPS_OF_DEEPEX_ON_STACK]>,
     pub ops: BinOpVec<T>,
+    /// Function-style operators are unparsed as `f(a, b)` instead of `a f b`.
+    pub is_function_style: SmallVec<[bool; N_BINOPS_OF_DEEPEX_ON_STACK]>,
 }
 impl<'a, T: Clone> BinOpsWithReprs<'a, T> {
     pub fn new() -> Self {
         BinOpsWithReprs {
             reprs: smallvec![],
             ops: BinOpVec::new(),
+            is_function_style: smallvec![],
         }
     }
 }
@@ -104,6 +135,9 @@ where
         }
     }
 
+    /// Prepends the representations of `other` such that `other` becomes the outermost
+    /// unary operator, i.e., the representations stay ordered outermost-first like the
+    /// functions of [`UnaryOp`](crate::UnaryOp).
     pub fn append_front(&mut self, other: &UnaryOpWithReprs<'a, T>) {
         self.op.append_after(&other.op);
         self.reprs = other
@@ -115,6 +149,56 @@ where
     }
 }
 
+/// Ternary operator together with its representation and its three arguments.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct TernaryOpWithArgs<'a, T: Clone + Debug> {
+    pub repr: &'a str,
+    pub op: TernaryOp<T>,
+    pub args: [DeepEx<'a, T>; 3],
+}
+impl<'a, T: Clone + Debug> TernaryOpWithArgs<'a, T> {
+    /// Applies `f` to each argument.
+    pub fn map_args<F>(&self, mut f: F) -> ExResult<Self>
+    where
+        F: FnMut(&DeepEx<'a, T>) -> ExResult<DeepEx<'a, T>>,
+    {
+        let [c, a, b] = &self.args;
+        Ok(TernaryOpWithArgs {
+            repr: self.repr,
+            op: self.op,
+            args: [f(c)?, f(a)?, f(b)?],
+        })
+    }
+
+    /// Node that replaces the ternary operator if its result is known without variables,
+    /// i.e., the selected argument of a selection with a constant condition or the result
+    /// of a function applied to three numbers.
+    fn folded(&self) -> Option<DeepNode<'a, T>> {
+        let [c, a, b] = &self.args;
+        match self.op {
+            TernaryOp::Select(is_first) => {
+                let selected = if is_first(&c.as_num()?) { a } else { b };
+                Some(DeepNode::Expr(Box::new(selected.clone())))
+            }
+            TernaryOp::Apply(apply) => {
+                Some(DeepNode::Num(apply(c.as_num()?, a.as_num()?, b.as_num()?)))
+            }
+        }
+    }
+
+    /// Unparses the operator like a function call `op(c, a, b)`.
+    pub fn unparse_with(&self, reprs: &NodeReprs<T>) -> String {
+        let [c, a, b] = &self.args;
+        format!(
+            "{}({}, {}, {})",
+            self.repr,
+            c.unparse_with(reprs),
+            a.unparse_with(reprs),
+            b.unparse_with(reprs)
+        )
+    }
+}
+
 /// A deep expression evaluates co-recursively since its nodes can contain other deep
 /// expressions.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -130,14 +214,14 @@ pub struct DeepEx<'a, T: Clone + Debug> {
 }
 
 fn lift_nodes<T: Clone + Debug>(deepex: &mut DeepEx<T>) {
-    if deepex.nodes.len() == 1 && deepex.unary_op.op.len() == 0 {
+    if deepex.nodes.len() == 1 && deepex.unary_op.op.is_empty() {
         if let DeepNode::Expr(e) = &deepex.nodes[0] {
             *deepex = (**e).clone();
         }
     } else {
         for node in &mut deepex.nodes {
             if let DeepNode::Expr(e) = node {
-                if e.nodes.len() == 1 && e.unary_op.op.len() == 0 {
+                if e.nodes.len() == 1 && e.unary_op.op.is_empty() {
                     match &mut e.nodes[0] {
                         DeepNode::Num(n) => *node = DeepNode::Num(n.clone()),
                         DeepNode::Var(v) => {
@@ -145,10 +229,11 @@ fn lift_nodes<T: Clone + Debug>(deepex: &mut DeepEx<T>) {
                         }
                         DeepNode::Expr(e_deeper) => {
                             lift_nodes(e_deeper);
-                            if e_deeper.nodes.len() == 1 && e_deeper.unary_op.op.len() == 0 {
+                            if e_deeper.nodes.len() == 1 && e_deeper.unary_op.op.is_empty() {
                                 *node = DeepNode::Expr(e_deeper.clone());
                             }
                         }
+                        DeepNode::Ternary(t) => *node = DeepNode::Ternary(t.clone()),
                     }
                 }
             }
@@ -159,6 +244,14 @@ fn lift_nodes<T: Clone + Debug>(deepex: &mut DeepEx<T>) {
 impl<'a, T: Clone + Debug> DeepEx<'a, T> {
     /// Compiles expression, needed for partial differentation.
     pub fn compile(&mut self) {
+        for node in &mut self.nodes {
+            if let Some(folded) = match node {
+                DeepNode::Ternary(t) => t.folded(),
+                _ => None,
+            } {
+                *node = folded;
+            }
+        }
         lift_nodes(self);
 
         let prio_indices = deep_details::prioritized_indices(&self.bin_ops.ops, &self.nodes);
@@ -194,6 +287,7 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         }
 
         let mut resulting_reprs = smallvec![];
+        let mut resulting_is_function_style = smallvec![];
         self.bin_ops.ops = self
             .bin_ops
             .ops
@@ -202,10 +296,12 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
             .filter(|(i, _)| !used_prio_indices.contains(i))
             .map(|(i, bin_op)| {
                 resulting_reprs.push(self.bin_ops.reprs[i]);
+                resulting_is_function_style.push(self.bin_ops.is_function_style[i]);
                 bin_op.clone()
             })
             .collect();
         self.bin_ops.reprs = resulting_reprs;
+        self.bin_ops.is_function_style = resulting_is_function_style;
 
         if self.nodes.len() == 1 {
             if let DeepNode::Num(n) = self.nodes[0].clone() {
@@ -234,6 +330,7 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
                     nodes.len(),
                     bin_ops.ops.len()
                 ),
+                position: None,
             })
         } else {
             let mut found_vars = SmallVec::<[&str; N_VARS_ON_STACK]>::new();
@@ -252,6 +349,13 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
                             }
                         }
                     }
+                    DeepNode::Ternary(t) => {
+                        for name in t.args.iter().flat_map(|arg| &arg.var_names) {
+                            if !found_vars.contains(name) {
+                                found_vars.push(name);
+                            }
+                        }
+                    }
                 }
             }
             found_vars.sort_unstable();
@@ -312,6 +416,14 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         &self.nodes
     }
 
+    /// The number if the expression consists of a number without unary operator.
+    fn as_num(&self) -> Option<T> {
+        match &self.nodes[..] {
+            [DeepNode::Num(n)] if self.unary_op.op.is_empty() => Some(n.clone()),
+            _ => None,
+        }
+    }
+
     fn is_num(&self, num: T) -> bool
     where
         T: Float,
@@ -338,26 +450,25 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         self.is_num(T::from(0.0).unwrap())
     }
 
+    pub fn var_names(&self) -> &[&'a str] {
+        &self.var_names
+    }
+
+    /// Number of nesting levels, e.g., 1 for `2*x+1` and 3 for `sin(cos(x+1))*2`.
+    pub fn depth(&self) -> usize {
+        1 + self
+            .nodes
+            .iter()
+            .map(|node| match node {
+                DeepNode::Expr(e) => e.depth(),
+                DeepNode::Ternary(t) => t.args.iter().map(|arg| arg.depth()).max().unwrap_or(0),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn var_names_union(self, other: Self) -> (Self, Self) {
-        fn reset_vars<'a, T: Clone + Debug>(
-            deepex: &mut DeepEx<'a, T>,
-            new_var_names: SmallVec<[&'a str; N_VARS_ON_STACK]>,
-        ) {
-            for node in &mut deepex.nodes {
-                match node {
-                    DeepNode::Expr(e) => reset_vars(e, new_var_names.clone()),
-                    DeepNode::Var((i, var_name)) => {
-                        for (new_idx, new_name) in new_var_names.iter().enumerate() {
-                            if var_name == new_name {
-                                *i = new_idx;
-                            }
-                        }
-                    }
-                    _ => (),
-                }
-            }
-            deepex.var_names = new_var_names;
-        }
         let mut all_var_names = self.var_names.clone();
         for name in other.var_names.clone() {
             if !all_var_names.contains(&name) {
@@ -372,6 +483,121 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         (self_vars_updated, other_vars_updated)
     }
 
+    /// Replaces each variable for which `value` returns a number by this number. The
+    /// remaining variables are re-indexed and constant sub-expressions are evaluated.
+    pub fn bind_vars<F>(&self, value: &F) -> ExResult<Self>
+    where
+        F: Fn(&str) -> Option<T>,
+    {
+        fn bind<'a, T, F>(deepex: &DeepEx<'a, T>, value: &F) -> ExResult<DeepEx<'a, T>>
+        where
+            T: Clone + Debug,
+            F: Fn(&str) -> Option<T>,
+        {
+            let nodes = deepex
+                .nodes
+                .iter()
+                .map(|node| -> ExResult<_> {
+                    Ok(match node {
+                        DeepNode::Var((i, var_name)) => match value(var_name) {
+                            Some(x) => DeepNode::Num(x),
+                            None => DeepNode::Var((*i, *var_name)),
+                        },
+                        DeepNode::Expr(e) => DeepNode::Expr(Box::new(bind(e, value)?)),
+                        DeepNode::Ternary(t) => {
+                            DeepNode::Ternary(Box::new(t.map_args(|arg| bind(arg, value))?))
+                        }
+                        DeepNode::Num(n) => DeepNode::Num(n.clone()),
+                    })
+                })
+                .collect::<ExResult<Vec<_>>>()?;
+            DeepEx::new(nodes, deepex.bin_ops.clone(), deepex.unary_op.clone())
+        }
+        let mut res = bind(self, value)?;
+        let var_names = res.var_names.clone();
+        reset_vars(&mut res, var_names);
+        Ok(res)
+    }
+
+    /// Replaces each occurrence of the variable `var_name` by `replacement`. The variables
+    /// of the result are the union of the remaining variables and the variables of
+    /// `replacement`.
+    pub fn substitute_var(&self, var_name: &str, replacement: &Self) -> ExResult<Self> {
+        fn substitute<'a, T>(
+            deepex: &DeepEx<'a, T>,
+            var_name: &str,
+            replacement: &DeepEx<'a, T>,
+        ) -> ExResult<DeepEx<'a, T>>
+        where
+            T: Clone + Debug,
+        {
+            // avoid redundant parentheses such as in `sin((x+y))`
+            if let [DeepNode::Var((_, name))] = &deepex.nodes[..] {
+                if *name == var_name {
+                    return Ok(replacement.clone().operate_unary(deepex.unary_op.clone()));
+                }
+            }
+            let nodes = deepex
+                .nodes
+                .iter()
+                .map(|node| -> ExResult<_> {
+                    Ok(match node {
+                        DeepNode::Var((_, name)) if *name == var_name => {
+                            DeepNode::Expr(Box::new(replacement.clone()))
+                        }
+                        DeepNode::Expr(e) => {
+                            DeepNode::Expr(Box::new(substitute(e, var_name, replacement)?))
+                        }
+                        DeepNode::Ternary(t) => DeepNode::Ternary(Box::new(
+                            t.map_args(|arg| substitute(arg, var_name, replacement))?,
+                        )),
+                        _ => node.clone(),
+                    })
+                })
+                .collect::<ExResult<Vec<_>>>()?;
+            DeepEx::new(nodes, deepex.bin_ops.clone(), deepex.unary_op.clone())
+        }
+        let mut res = substitute(self, var_name, replacement)?;
+        let var_names = res.var_names.clone();
+        reset_vars(&mut res, var_names);
+        Ok(res)
+    }
+
+    /// Renames each variable for which `new_name` returns a name. The variables are
+    /// re-indexed according to the sorted new names.
+    pub fn rename_vars<F>(&self, new_name: &F) -> ExResult<Self>
+    where
+        F: Fn(&str) -> Option<&'a str>,
+    {
+        fn rename<'a, T, F>(deepex: &DeepEx<'a, T>, new_name: &F) -> ExResult<DeepEx<'a, T>>
+        where
+            T: Clone + Debug,
+            F: Fn(&str) -> Option<&'a str>,
+        {
+            let nodes = deepex
+                .nodes
+                .iter()
+                .map(|node| -> ExResult<_> {
+                    Ok(match node {
+                        DeepNode::Var((i, var_name)) => {
+                            DeepNode::Var((*i, new_name(var_name).unwrap_or(var_name)))
+                        }
+                        DeepNode::Expr(e) => DeepNode::Expr(Box::new(rename(e, new_name)?)),
+                        DeepNode::Ternary(t) => {
+                            DeepNode::Ternary(Box::new(t.map_args(|arg| rename(arg, new_name))?))
+                        }
+                        DeepNode::Num(n) => DeepNode::Num(n.clone()),
+                    })
+                })
+                .collect::<ExResult<Vec<_>>>()?;
+            DeepEx::new(nodes, deepex.bin_ops.clone(), deepex.unary_op.clone())
+        }
+        let mut res = rename(self, new_name)?;
+        let var_names = res.var_names.clone();
+        reset_vars(&mut res, var_names);
+        Ok(res)
+    }
+
     pub fn var_names_like_other(mut self, other: &Self) -> Self {
         self.var_names = other.var_names.clone();
         self
@@ -400,27 +626,146 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         self
     }
 
-    pub fn unparse_raw(&self) -> String {
-        let mut node_strings = self.nodes.iter().map(|n| match n {
-            DeepNode::Num(n) => format!("{:?}", n),
-            DeepNode::Var((_, var_name)) => format!("{{{}}}", var_name),
-            DeepNode::Expr(e) => {
-                if e.unary_op.op.len() == 0 {
-                    format!("({})", e.unparse_raw())
-                } else {
-                    e.unparse_raw()
+    /// Replaces sub-expressions that occur more than once by variables with the indices
+    /// `n_vars()`, `n_vars()+1`, and so on. Sub-expressions are identified by their unparsed
+    /// strings. Sub-expressions with unary operators are also compared without them such
+    /// that, e.g., `x+y` is shared by `sin(x+y)` and `cos(x+y)`. Returns the shared
+    /// sub-expressions in the order of their evaluation, where each can refer to the previous
+    /// ones, the expression with the replacements, and the number of eliminated duplicates.
+    /// The results are meant to be flattened, the names of the variables that refer to
+    /// shared sub-expressions are meaningless.
+    pub fn eliminate_common_subexprs(&self) -> (Vec<DeepEx<'a, T>>, DeepEx<'a, T>, usize) {
+        let mut cse = CommonSubexprs {
+            counts: HashMap::new(),
+            indices: HashMap::new(),
+            shared: vec![],
+            n_vars: self.n_vars(),
+            n_eliminated: 0,
+        };
+        cse.count(self);
+        let replaced = cse.replace(self);
+        (cse.shared, replaced, cse.n_eliminated)
+    }
+
+    /// Returns the representations of all binary, unary, and ternary operators of `self` and
+    /// its sub-expressions in alphabetical order, each listed once.
+    pub fn operator_reprs(&self) -> Vec<&'a str> {
+        fn collect<'a, T: Clone + Debug>(deepex: &DeepEx<'a, T>, reprs: &mut Vec<&'a str>) {
+            reprs.extend(deepex.bin_ops.reprs.iter());
+            reprs.extend(deepex.unary_op.reprs.iter());
+            for node in &deepex.nodes {
+                match node {
+                    DeepNode::Expr(e) => collect(e, reprs),
+                    DeepNode::Ternary(t) => {
+                        reprs.push(t.repr);
+                        for arg in &t.args {
+                            collect(arg, reprs);
+                        }
+                    }
+                    _ => (),
                 }
             }
-        });
-        let mut bin_op_strings = self.bin_ops.reprs.iter();
-        // a valid expression has at least one node
-        let first_node_str = node_strings.next().unwrap();
-        let node_with_bin_ops_string = node_strings.fold(first_node_str, |mut res, node_str| {
-            let bin_op_str = bin_op_strings.next().unwrap();
-            res.push_str(bin_op_str);
-            res.push_str(node_str.as_str());
-            res
-        });
+        }
+        let mut reprs = vec![];
+        collect(self, &mut reprs);
+        reprs.sort_unstable();
+        reprs.dedup();
+        reprs
+    }
+
+    fn is_function_call(&self) -> bool {
+        self.unary_op.op.is_empty() && self.bin_ops.is_function_style.as_slice() == [true]
+    }
+
+    /// Combines the operands that correspond to the nodes with the binary operators in the
+    /// order of their evaluation. The closure receives the index of the binary operator and
+    /// its left and right operands.
+    pub(super) fn fold_in_prio_order<R, F>(&self, mut operands: Vec<R>, mut combine: F) -> R
+    where
+        F: FnMut(usize, R, R) -> R,
+    {
+        let prio_indices = deep_details::prioritized_indices(&self.bin_ops.ops, &self.nodes);
+        let mut num_inds = prio_indices.clone();
+        for (i, &bin_op_idx) in prio_indices.iter().enumerate() {
+            let num_idx = num_inds[i];
+            let operand_2 = operands.remove(num_idx + 1);
+            let operand_1 = operands.remove(num_idx);
+            operands.insert(num_idx, combine(bin_op_idx, operand_1, operand_2));
+            // reduce indices after removed position
+            for num_idx_after in num_inds.iter_mut() {
+                if *num_idx_after > num_idx {
+                    *num_idx_after -= 1;
+                }
+            }
+        }
+        operands.remove(0)
+    }
+
+    /// Unparses nodes and binary operators in the order of their evaluation, since
+    /// function-style operators cannot be placed in between their operands.
+    fn unparse_with_function_style(&self, reprs: &NodeReprs<T>) -> String {
+        // each operand is kept with parentheses for infix operators and without
+        // parentheses for function-style operators
+        let operands = self
+            .nodes
+            .iter()
+            .map(|n| (unparse_node(n, true, reprs), unparse_node(n, false, reprs)))
+            .collect::<Vec<_>>();
+        let combine = |bin_op_idx: usize, (wrapped_1, raw_1), (wrapped_2, raw_2)| {
+            let repr = self.bin_ops.reprs[bin_op_idx];
+            if self.bin_ops.is_function_style[bin_op_idx] {
+                let call = format!("{}({}, {})", repr, raw_1, raw_2);
+                (call.clone(), call)
+            } else {
+                let infix = format!("{}{}{}", wrapped_1, repr, wrapped_2);
+                (format!("({})", infix), infix)
+            }
+        };
+        self.fold_in_prio_order(operands, combine).1
+    }
+
+    /// Creates a LaTeX representation of the expression, e.g., `\frac{\sin(x)}{5}` for
+    /// `sin(x)/5`. Operators without LaTeX counterpart are written as `\operatorname{repr}`.
+    /// Fractions in fractions are written as `\dfrac`. Variable names with more than one
+    /// character or with special characters are written as `\text{name}` with special
+    /// characters escaped.
+    pub fn unparse_latex(&self) -> String {
+        latex_deepex(self).0
+    }
+
+    /// Creates a [Graphviz](https://graphviz.org/) DOT representation of the expression tree.
+    /// Binary operators point to their left and right operands in the order of evaluation,
+    /// unary operators point to their operand, and ternary operators to their arguments.
+    pub fn to_dot(&self) -> String {
+        let mut graph = DotGraph::default();
+        graph.add_deepex(self);
+        format!("digraph {{\n{}}}\n", graph.statements)
+    }
+
+    pub fn unparse_raw(&self) -> String {
+        self.unparse_with(&NodeReprs {
+            num: &|n| format!("{:?}", n),
+            var: &|_, name| format!("{{{}}}", name),
+        })
+    }
+
+    /// Unparses like [`unparse_raw`](DeepEx::unparse_raw) with custom representations of
+    /// numbers and variables.
+    pub fn unparse_with(&self, reprs: &NodeReprs<T>) -> String {
+        let node_with_bin_ops_string = if self.bin_ops.is_function_style.contains(&true) {
+            self.unparse_with_function_style(reprs)
+        } else {
+            let mut node_strings = self.nodes.iter().map(|n| unparse_node(n, true, reprs));
+            let mut bin_op_strings = self.bin_ops.reprs.iter();
+            // a valid expression has at least one node
+            let first_node_str = node_strings.next().unwrap();
+            node_strings.fold(first_node_str, |mut res, node_str| {
+                let bin_op_str = bin_op_strings.next().unwrap();
+                res.push_str(bin_op_str);
+                res.push_str(node_str.as_str());
+                res
+            })
+        };
         let unary_op_string = self
             .unary_op
             .reprs
@@ -437,7 +782,7 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
                 res
             },
         );
-        if self.unary_op.op.len() == 0 {
+        if self.unary_op.op.is_empty() {
             node_with_bin_ops_string
         } else {
             format!(
@@ -452,7 +797,363 @@ impl<'a, T: Clone + Debug> DeepEx<'a, T> {
         <T as std::str::FromStr>::Err: Debug,
         T: DataType,
     {
-        parse(text, ops, parser::is_numeric_text)
+        parse(text, ops, parser::is_numeric_text, CurlyVarNames::default())
+    }
+
+    /// Parses like [`from_ops`](DeepEx::from_ops) where names of variables in curly
+    /// brackets are treated as determined by `curly_var_names`.
+    pub fn from_ops_with_curly_var_names(
+        text: &'a str,
+        ops: &[Operator<'a, T>],
+        curly_var_names: CurlyVarNames,
+    ) -> ExResult<DeepEx<'a, T>>
+    where
+        <T as std::str::FromStr>::Err: Debug,
+        T: DataType,
+    {
+        parse(text, ops, parser::is_numeric_text, curly_var_names)
+    }
+}
+
+fn reset_vars<'a, T: Clone + Debug>(
+    deepex: &mut DeepEx<'a, T>,
+    new_var_names: SmallVec<[&'a str; N_VARS_ON_STACK]>,
+) {
+    for node in &mut deepex.nodes {
+        match node {
+            DeepNode::Expr(e) => reset_vars(e, new_var_names.clone()),
+            DeepNode::Ternary(t) => {
+                for arg in t.args.iter_mut() {
+                    reset_vars(arg, new_var_names.clone());
+                }
+            }
+            DeepNode::Var((i, var_name)) => {
+                for (new_idx, new_name) in new_var_names.iter().enumerate() {
+                    if var_name == new_name {
+                        *i = new_idx;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    deepex.var_names = new_var_names;
+}
+
+/// Determines where LaTeX sub-expressions need parentheses.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum LatexKind {
+    /// Numbers, variables, and function calls
+    Atom,
+    /// Results of unary minus or negative numbers
+    Negation,
+    Sum,
+    Product,
+    Fraction,
+    Power,
+    Infix,
+}
+
+fn latex_parens((latex, kind): (String, LatexKind), kinds_to_wrap: &[LatexKind]) -> String {
+    if kinds_to_wrap.contains(&kind) {
+        format!("({})", latex)
+    } else {
+        latex
+    }
+}
+
+fn latex_num<T: Debug>(num: &T) -> (String, LatexKind) {
+    let num_str = format!("{:?}", num);
+    // integral floats are written without decimal point
+    let is_integral = |s: &str| {
+        s.trim_start_matches('-')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    };
+    let num_str = match num_str.strip_suffix(".0") {
+        Some(int_str) if is_integral(int_str) => int_str.to_string(),
+        _ => num_str,
+    };
+    if num_str.starts_with('-') {
+        (num_str, LatexKind::Negation)
+    } else {
+        (num_str, LatexKind::Atom)
+    }
+}
+
+/// Escapes characters with a special meaning in LaTeX text mode.
+fn latex_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => format!("\\{}", c),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+fn latex_var(var_name: &str) -> (String, LatexKind) {
+    let mut chars = var_name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphanumeric() => (var_name.to_string(), LatexKind::Atom),
+        _ => (
+            format!("\\text{{{}}}", latex_escape(var_name)),
+            LatexKind::Atom,
+        ),
+    }
+}
+
+/// Fractions in fractions are written with `\dfrac` to keep their size.
+fn latex_dfrac((latex, kind): (String, LatexKind)) -> String {
+    match latex.strip_prefix("\\frac") {
+        Some(rest) if kind == LatexKind::Fraction => format!("\\dfrac{}", rest),
+        _ => latex,
+    }
+}
+
+fn latex_bin(
+    repr: &str,
+    is_function_style: bool,
+    operand_1: (String, LatexKind),
+    operand_2: (String, LatexKind),
+) -> (String, LatexKind) {
+    use LatexKind::*;
+    if is_function_style {
+        return (
+            format!(
+                "\\operatorname{{{}}}({}, {})",
+                repr, operand_1.0, operand_2.0
+            ),
+            Atom,
+        );
+    }
+    match repr {
+        "+" => (
+            format!("{}+{}", operand_1.0, latex_parens(operand_2, &[Negation])),
+            Sum,
+        ),
+        "-" => (
+            format!(
+                "{}-{}",
+                operand_1.0,
+                latex_parens(operand_2, &[Negation, Sum])
+            ),
+            Sum,
+        ),
+        "*" => (
+            format!(
+                "{} \\cdot {}",
+                latex_parens(operand_1, &[Sum, Infix]),
+                latex_parens(operand_2, &[Negation, Sum, Infix])
+            ),
+            Product,
+        ),
+        "/" => (
+            format!(
+                "\\frac{{{}}}{{{}}}",
+                latex_dfrac(operand_1),
+                latex_dfrac(operand_2)
+            ),
+            Fraction,
+        ),
+        "^" => (
+            format!(
+                "{}^{{{}}}",
+                latex_parens(operand_1, &[Negation, Sum, Product, Fraction, Power, Infix]),
+                operand_2.0
+            ),
+            Power,
+        ),
+        "%" => {
+            let wrapped = [Negation, Sum, Product, Fraction, Power, Infix];
+            (
+                format!(
+                    "{} \\bmod {}",
+                    latex_parens(operand_1, &wrapped),
+                    latex_parens(operand_2, &wrapped)
+                ),
+                Infix,
+            )
+        }
+        _ => {
+            let wrapped = [Negation, Sum, Product, Fraction, Power, Infix];
+            (
+                format!(
+                    "{} \\operatorname{{{}}} {}",
+                    latex_parens(operand_1, &wrapped),
+                    repr,
+                    latex_parens(operand_2, &wrapped)
+                ),
+                Infix,
+            )
+        }
+    }
+}
+
+fn latex_unary(repr: &str, operand: (String, LatexKind)) -> (String, LatexKind) {
+    use LatexKind::*;
+    let latex = match repr {
+        "-" => {
+            return (
+                format!("-{}", latex_parens(operand, &[Negation, Sum])),
+                Negation,
+            )
+        }
+        "+" => return operand,
+        "sqrt" => format!("\\sqrt{{{}}}", operand.0),
+        "cbrt" => format!("\\sqrt[3]{{{}}}", operand.0),
+        "abs" => format!("\\left|{}\\right|", operand.0),
+        "floor" => format!("\\lfloor {} \\rfloor", operand.0),
+        "ceil" => format!("\\lceil {} \\rceil", operand.0),
+        "exp2" => format!("2^{{{}}}", operand.0),
+        "exp10" => format!("10^{{{}}}", operand.0),
+        "log2" => format!("\\log_{{2}}({})", operand.0),
+        "log" => format!("\\ln({})", operand.0),
+        "asin" => format!("\\arcsin({})", operand.0),
+        "acos" => format!("\\arccos({})", operand.0),
+        "atan" => format!("\\arctan({})", operand.0),
+        "sin" | "cos" | "tan" | "sinh" | "cosh" | "tanh" | "exp" => {
+            format!("\\{}({})", repr, operand.0)
+        }
+        _ => format!("\\operatorname{{{}}}({})", repr, operand.0),
+    };
+    let kind = match repr {
+        "exp2" | "exp10" => Power,
+        _ => Atom,
+    };
+    (latex, kind)
+}
+
+fn latex_ternary<T: Clone + Debug>(ternary: &TernaryOpWithArgs<T>) -> (String, LatexKind) {
+    let [c, a, b] = &ternary.args;
+    (
+        format!(
+            "\\operatorname{{{}}}({}, {}, {})",
+            ternary.repr,
+            latex_deepex(c).0,
+            latex_deepex(a).0,
+            latex_deepex(b).0
+        ),
+        LatexKind::Atom,
+    )
+}
+
+fn latex_deepex<T: Clone + Debug>(deepex: &DeepEx<T>) -> (String, LatexKind) {
+    let operands = deepex
+        .nodes
+        .iter()
+        .map(|node| match node {
+            DeepNode::Num(n) => latex_num(n),
+            DeepNode::Var((_, var_name)) => latex_var(var_name),
+            DeepNode::Expr(e) => latex_deepex(e),
+            DeepNode::Ternary(t) => latex_ternary(t),
+        })
+        .collect::<Vec<_>>();
+    let result = deepex.fold_in_prio_order(operands, |bin_op_idx, operand_1, operand_2| {
+        latex_bin(
+            deepex.bin_ops.reprs[bin_op_idx],
+            deepex.bin_ops.is_function_style[bin_op_idx],
+            operand_1,
+            operand_2,
+        )
+    });
+    // the innermost unary operator is the last one
+    deepex
+        .unary_op
+        .reprs
+        .iter()
+        .rev()
+        .fold(result, |operand, repr| latex_unary(repr, operand))
+}
+
+/// Collects the statements of a DOT graph.
+#[derive(Default)]
+struct DotGraph {
+    statements: String,
+    n_nodes: usize,
+}
+
+impl DotGraph {
+    fn add_node(&mut self, label: &str) -> usize {
+        let id = self.n_nodes;
+        self.n_nodes += 1;
+        let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+        self.statements
+            .push_str(&format!("    n{} [label=\"{}\"];\n", id, label));
+        id
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        self.statements
+            .push_str(&format!("    n{} -> n{};\n", from, to));
+    }
+
+    /// Adds the nodes and edges of `deepex` and returns the id of its root.
+    fn add_deepex<T: Clone + Debug>(&mut self, deepex: &DeepEx<T>) -> usize {
+        let operands = deepex
+            .nodes
+            .iter()
+            .map(|node| match node {
+                DeepNode::Num(n) => self.add_node(&format!("{:?}", n)),
+                DeepNode::Var((_, var_name)) => self.add_node(var_name),
+                DeepNode::Expr(e) => self.add_deepex(e),
+                DeepNode::Ternary(t) => {
+                    let arg_ids = t
+                        .args
+                        .iter()
+                        .map(|arg| self.add_deepex(arg))
+                        .collect::<SmallVec<[_; 3]>>();
+                    let id = self.add_node(t.repr);
+                    for arg_id in arg_ids {
+                        self.add_edge(id, arg_id);
+                    }
+                    id
+                }
+            })
+            .collect::<Vec<_>>();
+        let root = deepex.fold_in_prio_order(operands, |bin_op_idx, id_1, id_2| {
+            let id = self.add_node(deepex.bin_ops.reprs[bin_op_idx]);
+            self.add_edge(id, id_1);
+            self.add_edge(id, id_2);
+            id
+        });
+        // the innermost unary operator is the last one
+        deepex
+            .unary_op
+            .reprs
+            .iter()
+            .rev()
+            .fold(root, |operand_id, repr| {
+                let id = self.add_node(repr);
+                self.add_edge(id, operand_id);
+                id
+            })
+    }
+}
+
+/// Unparses a node, sub-expressions without unary operators are put into parentheses
+/// if `wrap` is true.
+/// Representations of numbers and of variables given their indices and names used for
+/// unparsing, see [`unparse_with`](DeepEx::unparse_with).
+pub struct NodeReprs<'r, T> {
+    pub num: &'r dyn Fn(&T) -> String,
+    pub var: &'r dyn Fn(usize, &str) -> String,
+}
+
+fn unparse_node<T: Clone + Debug>(node: &DeepNode<T>, wrap: bool, reprs: &NodeReprs<T>) -> String {
+    match node {
+        DeepNode::Num(n) => (reprs.num)(n),
+        DeepNode::Var((idx, var_name)) => (reprs.var)(*idx, var_name),
+        DeepNode::Expr(e) => {
+            if wrap && e.unary_op.op.is_empty() && !e.is_function_call() {
+                format!("({})", e.unparse_with(reprs))
+            } else {
+                e.unparse_with(reprs)
+            }
+        }
+        DeepNode::Ternary(t) => t.unparse_with(reprs),
     }
 }
 
@@ -461,6 +1162,122 @@ impl<'a, T: Clone + Debug> Display for DeepEx<'a, T> {
         write!(f, "{}", self.unparse_raw())
     }
 }
+/// Name of the variables that refer to shared sub-expressions, see
+/// [`eliminate_common_subexprs`](DeepEx::eliminate_common_subexprs).
+const SHARED_VAR_NAME: &str = "__shared";
+
+/// State of the common sub-expression elimination.
+struct CommonSubexprs<'a, T: Clone + Debug> {
+    /// Number of occurrences of each unparsed sub-expression.
+    counts: HashMap<String, usize>,
+    /// Variable index of each shared sub-expression.
+    indices: HashMap<String, usize>,
+    shared: Vec<DeepEx<'a, T>>,
+    n_vars: usize,
+    n_eliminated: usize,
+}
+
+impl<'a, T: Clone + Debug> CommonSubexprs<'a, T> {
+    /// Copy of `deepex` without its unary operator.
+    fn core(deepex: &DeepEx<'a, T>) -> DeepEx<'a, T> {
+        DeepEx {
+            nodes: deepex.nodes.clone(),
+            bin_ops: deepex.bin_ops.clone(),
+            unary_op: UnaryOpWithReprs::new(),
+            var_names: deepex.var_names.clone(),
+        }
+    }
+
+    /// Key of the sub-expression without unary operator if it is worth sharing.
+    fn core_key(deepex: &DeepEx<'a, T>) -> Option<String> {
+        if deepex.unary_op.op.is_empty() || deepex.nodes.len() < 2 {
+            None
+        } else {
+            Some(Self::core(deepex).unparse_raw())
+        }
+    }
+
+    fn count_key(&mut self, key: String) -> usize {
+        let count = self.counts.entry(key).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    fn is_shared(&self, key: &str) -> bool {
+        self.counts.get(key).is_some_and(|count| *count > 1)
+    }
+
+    /// Counts the sub-expressions. Repeated occurrences are not descended into, since
+    /// their sub-expressions are evaluated only once.
+    fn count(&mut self, deepex: &DeepEx<'a, T>) {
+        for node in &deepex.nodes {
+            if let DeepNode::Expr(e) = node {
+                if self.count_key(e.unparse_raw()) > 1 {
+                    continue;
+                }
+                if let Some(key) = Self::core_key(e) {
+                    if self.count_key(key) > 1 {
+                        continue;
+                    }
+                }
+                self.count(e);
+            }
+        }
+    }
+
+    /// Returns the index of the variable that refers to the shared sub-expression with
+    /// key `key` and creates the shared sub-expression in case of its first occurrence.
+    fn shared_idx(&mut self, key: String, deepex: &DeepEx<'a, T>) -> usize {
+        match self.indices.get(&key) {
+            Some(idx) => {
+                self.n_eliminated += 1;
+                *idx
+            }
+            None => {
+                let replaced = self.replace(deepex);
+                self.shared.push(replaced);
+                let idx = self.n_vars + self.shared.len() - 1;
+                self.indices.insert(key, idx);
+                idx
+            }
+        }
+    }
+
+    /// Replaces shared sub-expressions of the nodes of `deepex` by variables.
+    fn replace(&mut self, deepex: &DeepEx<'a, T>) -> DeepEx<'a, T> {
+        let nodes = deepex
+            .nodes
+            .iter()
+            .map(|node| match node {
+                DeepNode::Expr(e) => {
+                    let key = e.unparse_raw();
+                    let core_key = Self::core_key(e);
+                    if self.is_shared(&key) {
+                        DeepNode::Var((self.shared_idx(key, e), SHARED_VAR_NAME))
+                    } else if let Some(core_key) = core_key.filter(|k| self.is_shared(k)) {
+                        let idx = self.shared_idx(core_key, &Self::core(e));
+                        DeepNode::Expr(Box::new(DeepEx {
+                            nodes: vec![DeepNode::Var((idx, SHARED_VAR_NAME))],
+                            bin_ops: BinOpsWithReprs::new(),
+                            unary_op: e.unary_op.clone(),
+                            var_names: e.var_names.clone(),
+                        }))
+                    } else {
+                        DeepNode::Expr(Box::new(self.replace(e)))
+                    }
+                }
+                _ => node.clone(),
+            })
+            .collect();
+        DeepEx {
+            nodes,
+            bin_ops: deepex.bin_ops.clone(),
+            unary_op: deepex.unary_op.clone(),
+            var_names: deepex.var_names.clone(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum DeepBufNode<T: Clone + Debug> {
     /// Boxing this due to <https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant>
@@ -469,6 +1286,8 @@ pub enum DeepBufNode<T: Clone + Debug> {
     /// The contained integer points to the index of the variable in the slice of
     /// variables passed to [`eval`](Expression::eval).
     Var((usize, String)),
+    /// Representation, operator, and arguments of a ternary operator
+    Ternary((String, TernaryOp<T>, Box<[DeepBuf<T>; 3]>)),
 }
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct DeepBuf<T: Clone + Debug> {
@@ -492,6 +1311,18 @@ impl<'a, T: Clone + Debug> DeepBuf<T> {
                     DeepNode::Expr(e) => DeepBufNode::Expr(Box::new(Self::from_deepex(e))),
                     DeepNode::Num(n) => DeepBufNode::Num(n.clone()),
                     DeepNode::Var(v) => DeepBufNode::Var((v.0, v.1.to_string())),
+                    DeepNode::Ternary(t) => {
+                        let [c, a, b] = &t.args;
+                        DeepBufNode::Ternary((
+                            t.repr.to_string(),
+                            t.op,
+                            Box::new([
+                                Self::from_deepex(c),
+                                Self::from_deepex(a),
+                                Self::from_deepex(b),
+                            ]),
+                        ))
+                    }
                 })
                 .collect(),
             bin_ops: BinOpsWithReprsBuf::from_deepex(deepex.bin_ops()),
@@ -509,6 +1340,14 @@ impl<'a, T: Clone + Debug> DeepBuf<T> {
                         DeepBufNode::Expr(e) => Ok(DeepNode::Expr(Box::new(e.to_deepex(ops)?))),
                         DeepBufNode::Num(n) => Ok(DeepNode::Num(n.clone())),
                         DeepBufNode::Var(v) => Ok(DeepNode::Var((v.0, v.1.as_str()))),
+                        DeepBufNode::Ternary((repr, op, args)) => {
+                            let [c, a, b] = &**args;
+                            Ok(DeepNode::Ternary(Box::new(TernaryOpWithArgs {
+                                repr: repr.as_str(),
+                                op: *op,
+                                args: [c.to_deepex(ops)?, a.to_deepex(ops)?, b.to_deepex(ops)?],
+                            })))
+                        }
                     }
                 })
                 .collect::<ExResult<_>>()?,
@@ -592,6 +1431,17 @@ where
                 DeepNode::Num(n) => Ok(n.clone()),
                 DeepNode::Var((idx, _)) => Ok(vars[*idx].clone()),
                 DeepNode::Expr(e) => eval(e, vars),
+                DeepNode::Ternary(t) => {
+                    let [c, a, b] = &t.args;
+                    match t.op {
+                        TernaryOp::Select(is_first) => {
+                            eval(if is_first(&eval(c, vars)?) { a } else { b }, vars)
+                        }
+                        TernaryOp::Apply(apply) => {
+                            Ok(apply(eval(c, vars)?, eval(a, vars)?, eval(b, vars)?))
+                        }
+                    }
+                }
             }
         })
         .collect::<ExResult<SmallVec<[T; N_NODES_ON_STACK]>>>()?;
@@ -629,6 +1479,7 @@ fn test_deep_compile() {
     let bin_ops = BinOpsWithReprs {
         reprs: smallvec![ops[1].repr(), ops[3].repr()],
         ops: smallvec![ops[1].bin().unwrap(), ops[3].bin().unwrap()],
+        is_function_style: smallvec![false, false],
     };
     let unary_op = UnaryOpWithReprs {
         reprs: smallvec![ops[6].repr()],
@@ -639,6 +1490,7 @@ fn test_deep_compile() {
     let bin_ops = BinOpsWithReprs {
         reprs: smallvec![ops[1].repr(), ops[3].repr()],
         ops: smallvec![ops[1].bin().unwrap(), ops[3].bin().unwrap()],
+        is_function_style: smallvec![false, false],
     };
     let unary_op = UnaryOpWithReprs {
         reprs: smallvec![ops[6].repr()],
@@ -720,3 +1572,255 @@ fn test_operate_unary() -> ExResult<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_unparse_function_style() -> ExResult<()> {
+    let mut ops = FloatOpsFactory::<f64>::make();
+    ops.push(
+        Operator::make_bin(
+            "atan2",
+            BinOp {
+                apply: |y: f64, x| y.atan2(x),
+                prio: 0,
+                is_commutative: false,
+            },
+        )
+        .with_function_style(),
+    );
+    fn test(text: &str, reference: &str, vars: &[f64], ops: &[Operator<f64>]) -> ExResult<()> {
+        let deepex = DeepEx::from_ops(text, ops)?;
+        let unparsed = deepex.unparse_raw();
+        assert_eq!(unparsed, reference);
+        let deepex_reparsed = DeepEx::from_ops(&unparsed, ops)?;
+        assert_eq!(deepex_reparsed.unparse_raw(), reference);
+        assert_float_eq_f64(eval(&deepex, vars)?, eval(&deepex_reparsed, vars)?);
+        Ok(())
+    }
+    test("atan2(y,x)", "atan2({y}, {x})", &[0.5, 1.5], &ops)?;
+    test("atan2(y, x)", "atan2({y}, {x})", &[0.5, 1.5], &ops)?;
+    test(
+        "1+atan2(y, 2*x)^2",
+        "1.0+atan2({y}, 2.0*{x})^2.0",
+        &[0.5, 1.5],
+        &ops,
+    )?;
+    test(
+        "sin(atan2(y, x))",
+        "sin(atan2({y}, {x}))",
+        &[0.5, 1.5],
+        &ops,
+    )?;
+    test("-atan2(y, x)", "-(atan2({y}, {x}))", &[0.5, 1.5], &ops)?;
+    test(
+        "atan2(atan2(a, b), c*(b+a))",
+        "atan2(atan2({a}, {b}), {c}*({b}+{a}))",
+        &[0.5, 1.5, -1.0],
+        &ops,
+    )?;
+    test("atan2(1, 2)*x", "0.4636476090008061*{x}", &[0.5], &ops)?;
+
+    let mut deepex = DeepEx::from_ops("atan2(y, x)", &ops)?;
+    deepex.bin_ops.is_function_style[0] = false;
+    assert_eq!(deepex.unparse_raw(), "{y}atan2{x}");
+
+    // infix operators that share an expression with a function-style operator
+    let x = DeepEx::from_ops("x", &ops)?;
+    let atan2 = DeepEx::from_ops("atan2(y, x)", &ops)?;
+    let bin_ops = BinOpsWithReprs {
+        reprs: smallvec!["*", "atan2"],
+        ops: smallvec![ops[1].bin()?, ops[ops.len() - 1].bin()?],
+        is_function_style: smallvec![false, true],
+    };
+    let nodes = vec![
+        DeepNode::Expr(Box::new(atan2)),
+        DeepNode::Expr(Box::new(x.clone())),
+        DeepNode::Expr(Box::new(x)),
+    ];
+    let deepex = DeepEx::new(nodes, bin_ops, UnaryOpWithReprs::new())?;
+    assert_eq!(deepex.unparse_raw(), "atan2(atan2({y}, {x})*{x}, {x})");
+    Ok(())
+}
+
+#[test]
+fn test_to_dot() -> ExResult<()> {
+    fn test(text: &str, reference: &str) -> ExResult<()> {
+        let deepex = DeepEx::<f64>::from_ops(text, &FloatOpsFactory::make())?;
+        assert_eq!(deepex.to_dot(), reference);
+        Ok(())
+    }
+    test("x", "digraph {\n    n0 [label=\"x\"];\n}\n")?;
+    test(
+        "sin(x)/5",
+        "digraph {\n    n0 [label=\"x\"];\n    n1 [label=\"sin\"];\n    n1 -> n0;\n    \
+         n2 [label=\"5.0\"];\n    n3 [label=\"/\"];\n    n3 -> n1;\n    n3 -> n2;\n}\n",
+    )?;
+    // multiplication is evaluated before subtraction
+    test(
+        "-(a-b*c)",
+        "digraph {\n    n0 [label=\"a\"];\n    n1 [label=\"b\"];\n    n2 [label=\"c\"];\n    \
+         n3 [label=\"*\"];\n    n3 -> n1;\n    n3 -> n2;\n    n4 [label=\"-\"];\n    \
+         n4 -> n0;\n    n4 -> n3;\n    n5 [label=\"-\"];\n    n5 -> n4;\n}\n",
+    )?;
+    test(
+        "atan2({a\"b}, 1)",
+        "digraph {\n    n0 [label=\"a\\\"b\"];\n    n1 [label=\"1.0\"];\n    \
+         n2 [label=\"atan2\"];\n    n2 -> n0;\n    n2 -> n1;\n}\n",
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_unparse_latex() -> ExResult<()> {
+    fn test(text: &str, reference: &str) -> ExResult<()> {
+        let deepex = DeepEx::<f64>::from_ops(text, &FloatOpsFactory::make())?;
+        assert_eq!(deepex.unparse_latex(), reference);
+        Ok(())
+    }
+    test("sin(x)/5", r"\frac{\sin(x)}{5}")?;
+    test("x^2", r"x^{2}")?;
+    test("x^2.5", r"x^{2.5}")?;
+    test("2*x*y", r"2 \cdot x \cdot y")?;
+    test("(x+1)*(y-1)", r"(x+1) \cdot (y-1)")?;
+    test("x-(y+z)", r"x-(y+z)")?;
+    test("x-y+z", r"x-y+z")?;
+    test("(x+y)^(2*z)", r"(x+y)^{2 \cdot z}")?;
+    test("(x/y)^2", r"(\frac{x}{y})^{2}")?;
+    test("-(x+y)", r"-(x+y)")?;
+    // unary operators have a higher priority than binary operators
+    test("-x^2", r"(-x)^{2}")?;
+    test("-(x^2)", r"-x^{2}")?;
+    test("(-x)^2", r"(-x)^{2}")?;
+    test("x*-y", r"x \cdot (-y)")?;
+    test("sqrt(x+1)", r"\sqrt{x+1}")?;
+    test("cbrt(x)", r"\sqrt[3]{x}")?;
+    test("abs(x)", r"\left|x\right|")?;
+    test("log(x)+log2(x)", r"\ln(x)+\log_{2}(x)")?;
+    test("exp(asin(x))", r"\exp(\arcsin(x))")?;
+    test("signum(x)", r"\operatorname{signum}(x)")?;
+    test("atan2(y, x+1)", r"\operatorname{atan2}(y, x+1)")?;
+    test("x % 3", r"x \bmod 3")?;
+    test("(x+1) % (2*y)", r"(x+1) \bmod (2 \cdot y)")?;
+    test("{alpha}*{x y}", r"\text{alpha} \cdot \text{x y}")?;
+    test("α+{x_1}*{a&b%}", r"α+\text{x\_1} \cdot \text{a\&b\%}")?;
+    test(
+        "{_}^{\\~}",
+        r"\text{\_}^{\text{\textbackslash{}\textasciitilde{}}}",
+    )?;
+    test("a/(b/c)", r"\frac{a}{\dfrac{b}{c}}")?;
+    test(
+        "(a/b)/(c/(d/e))",
+        r"\frac{\dfrac{a}{b}}{\dfrac{c}{\dfrac{d}{e}}}",
+    )?;
+    test("a/(b*c/d)", r"\frac{a}{b \cdot \frac{c}{d}}")?;
+    Ok(())
+}
+
+#[test]
+fn test_unparse_stacked_unaries() -> ExResult<()> {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    fn random_text(rng: &mut StdRng, depth: usize, unaries: &[&str], bins: &[&str]) -> String {
+        let leaves = ["x", "y", "0.5", "2"];
+        if depth == 0 || rng.gen_bool(0.25) {
+            return leaves[rng.gen_range(0..leaves.len())].to_string();
+        }
+        if rng.gen_bool(0.5) {
+            let n_unaries = rng.gen_range(1..4);
+            let uops = (0..n_unaries)
+                .map(|_| format!("{}(", unaries[rng.gen_range(0..unaries.len())]))
+                .collect::<String>();
+            let inner = random_text(rng, depth - 1, unaries, bins);
+            format!("{}{}{}", uops, inner, ")".repeat(n_unaries))
+        } else {
+            let left = random_text(rng, depth - 1, unaries, bins);
+            let right = random_text(rng, depth - 1, unaries, bins);
+            let bin = bins[rng.gen_range(0..bins.len())];
+            format!("({}{}{})", left, bin, right)
+        }
+    }
+    fn is_close(a: f64, b: f64) -> bool {
+        (a.is_nan() && b.is_nan())
+            || a == b
+            || (a - b).abs() <= 1e-8 * 1f64.max(a.abs()).max(b.abs())
+    }
+    fn test_roundtrip<'a>(deepex: &DeepEx<'a, f64>, ops: &[Operator<'a, f64>]) -> ExResult<()> {
+        let unparsed = deepex.unparse_raw();
+        // compiled non-finite constants cannot be parsed by the number matcher
+        if unparsed.contains("inf") || unparsed.contains("NaN") {
+            return Ok(());
+        }
+        let reparsed = DeepEx::from_ops(unparsed.as_str(), ops)?;
+        for point in [[0.3, -1.7], [2.5, 0.25], [-4.0, 3.0]] {
+            let reparsed_vars = reparsed
+                .var_names()
+                .iter()
+                .map(|name| point[deepex.var_names().iter().position(|n| n == name).unwrap()])
+                .collect::<Vec<_>>();
+            let vars = &point[..deepex.n_vars()];
+            let (res, reparsed_res) = (eval(deepex, vars)?, eval(&reparsed, &reparsed_vars)?);
+            assert!(
+                is_close(res, reparsed_res),
+                "{} evaluates to {} but its unparsed version {} to {}",
+                deepex.unparse_raw(),
+                res,
+                unparsed,
+                reparsed_res
+            );
+        }
+        Ok(())
+    }
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let float_ops = FloatOpsFactory::<f64>::make();
+    let unaries = ["-", "sin", "cos", "exp"];
+    for _ in 0..500 {
+        let text = random_text(&mut rng, 4, &unaries, &["+", "-", "*", "/"]);
+        let text = if text.contains('x') {
+            text
+        } else {
+            format!("x*{}", text)
+        };
+        let deepex = DeepEx::from_ops(text.as_str(), &float_ops)?;
+        test_roundtrip(&deepex, &float_ops)?;
+        let mut deri = partial_deepex(0, deepex, &float_ops)?;
+        deri.compile();
+        test_roundtrip(&deri, &float_ops)?;
+    }
+
+    // unary operators that do not commute
+    let custom_ops = [
+        Operator::make_bin(
+            "+",
+            BinOp {
+                apply: |a, b| a + b,
+                prio: 0,
+                is_commutative: true,
+            },
+        ),
+        Operator::make_bin(
+            "*",
+            BinOp {
+                apply: |a, b| a * b,
+                prio: 1,
+                is_commutative: true,
+            },
+        ),
+        Operator::make_unary("dbl", |a| 2.0 * a),
+        Operator::make_unary("inc", |a| a + 1.0),
+    ];
+    for _ in 0..500 {
+        let text = random_text(&mut rng, 4, &["dbl", "inc"], &["+", "*"]);
+        let mut deepex = DeepEx::from_ops(text.as_str(), &custom_ops)?;
+        test_roundtrip(&deepex, &custom_ops)?;
+        deepex.compile();
+        test_roundtrip(&deepex, &custom_ops)?;
+        let uops = (0..rng.gen_range(1..3))
+            .map(|_| &custom_ops[rng.gen_range(2..4)])
+            .collect::<Vec<_>>();
+        let unary_op = UnaryOpWithReprs {
+            reprs: uops.iter().map(|op| op.repr()).collect(),
+            op: UnaryOp::from_vec(uops.iter().map(|op| op.unary().unwrap()).collect()),
+        };
+        test_roundtrip(&deepex.operate_unary(unary_op), &custom_ops)?;
+    }
+    Ok(())
+}
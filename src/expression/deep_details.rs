@@ -1,7 +1,10 @@
 use crate::{
     definitions::{N_BINOPS_OF_DEEPEX_ON_STACK, N_UNARYOPS_OF_DEEPEX_ON_STACK},
-    expression::deep::{BinOpVec, BinOpsWithReprs, DeepEx, DeepNode, ExprIdxVec, UnaryOpWithReprs},
-    operators::{BinOp, UnaryOp, VecOfUnaryFuncs},
+    expression::deep::{
+        BinOpVec, BinOpsWithReprs, DeepEx, DeepNode, ExprIdxVec, TernaryOpWithArgs,
+        UnaryOpWithReprs,
+    },
+    operators::{BinOp, TernaryOp, UnaryOp, VecOfUnaryFuncs},
     parser::{Paren, ParsedToken, self},
     ExError, ExResult,
 };
@@ -9,6 +12,43 @@ use std::{fmt::Debug, iter, str::FromStr};
 
 use smallvec::SmallVec;
 
+/// Handles the case that a token is a ternary operator. After resolving function-style
+/// operators, the three arguments follow as parenthesized groups `op(c)(a)(b)`. Returns the
+/// ternary node and the number of tokens covered by the operator and its arguments.
+fn process_ternary<'a, T: Clone + FromStr + Debug>(
+    token_idx: usize,
+    ternary_op: TernaryOp<T>,
+    repr: &'a str,
+    parsed_tokens: &[ParsedToken<'a, T>],
+    parsed_vars: &[&'a str],
+) -> ExResult<(DeepNode<'a, T>, usize)> {
+    let mut idx_tkn = token_idx + 1;
+    let mut parse_arg = || -> ExResult<DeepEx<'a, T>> {
+        match parsed_tokens.get(idx_tkn) {
+            Some(ParsedToken::Paren(Paren::Open)) => {
+                let (arg, i_forward) = make_expression::<T>(
+                    &parsed_tokens[idx_tkn + 1..],
+                    parsed_vars,
+                    UnaryOpWithReprs::new(),
+                )?;
+                idx_tkn += i_forward + 1;
+                Ok(arg)
+            }
+            _ => Err(ExError {
+                msg: format!("ternary operator {} expects 3 arguments", repr),
+                position: None,
+            }),
+        }
+    };
+    let args = [parse_arg()?, parse_arg()?, parse_arg()?];
+    let node = DeepNode::Ternary(Box::new(TernaryOpWithArgs {
+        repr,
+        op: ternary_op,
+        args,
+    }));
+    Ok((node, idx_tkn - token_idx))
+}
+
 /// Handles the case that a token is a unary operator and returns a tuple.
 /// The first element is a node that is either an expression with a unary operator or a
 /// number where the unary operator has been applied to. the second element is the number
@@ -74,8 +114,27 @@ fn process_unary<'a, T: Clone + FromStr + Debug>(
             Ok((DeepNode::Expr(Box::new(expr)), n_uops + 1))
         }
         ParsedToken::Num(n) => Ok((DeepNode::Num(uop.apply(n.clone())), n_uops + 1)),
+        ParsedToken::Op(op) if op.ternary().is_some() => {
+            let (node, i_forward) = process_ternary(
+                token_idx + n_uops,
+                *op.ternary().unwrap(),
+                op.repr(),
+                parsed_tokens,
+                parsed_vars,
+            )?;
+            let expr = DeepEx::new(
+                vec![node],
+                BinOpsWithReprs::new(),
+                UnaryOpWithReprs {
+                    reprs: vec_of_uop_reprs,
+                    op: uop,
+                },
+            )?;
+            Ok((DeepNode::Expr(Box::new(expr)), i_forward + n_uops))
+        }
         _ => Err(ExError {
             msg: "Invalid parsed token configuration".to_string(),
+            position: None,
         }),
     }
 }
@@ -102,6 +161,7 @@ where
 {
     let mut bin_ops = BinOpVec::new();
     let mut reprs_bin_ops: SmallVec<[&'a str; N_BINOPS_OF_DEEPEX_ON_STACK]> = SmallVec::new();
+    let mut is_function_style: SmallVec<[bool; N_BINOPS_OF_DEEPEX_ON_STACK]> = SmallVec::new();
     let mut nodes = Vec::<DeepNode<T>>::new();
     nodes.reserve(parsed_tokens.len() / 2);
     // The main loop checks one token after the next whereby sub-expressions are
@@ -114,7 +174,18 @@ where
                 if idx_tkn > 0 && parser::is_operator_binary(op, &parsed_tokens[idx_tkn - 1])? {
                     bin_ops.push(op.bin()?);
                     reprs_bin_ops.push(op.repr());
+                    is_function_style.push(op.is_function_style());
                     idx_tkn += 1;
+                } else if let Some(ternary_op) = op.ternary() {
+                    let (node, idx_forward) = process_ternary(
+                        idx_tkn,
+                        *ternary_op,
+                        op.repr(),
+                        parsed_tokens,
+                        parsed_vars,
+                    )?;
+                    nodes.push(node);
+                    idx_tkn += idx_forward;
                 } else {
                     let (node, idx_forward) =
                         process_unary(idx_tkn, op.unary()?, op.repr(), parsed_tokens, parsed_vars)?;
@@ -154,6 +225,7 @@ where
             BinOpsWithReprs {
                 reprs: reprs_bin_ops,
                 ops: bin_ops,
+                is_function_style,
             },
             unary_ops,
         )?,
@@ -185,6 +257,7 @@ pub fn prioritized_indices<T: Clone + Debug>(
 pub struct BinOpsWithReprsBuf<T: Clone> {
     pub reprs: SmallVec<[String; N_BINOPS_OF_DEEPEX_ON_STACK]>,
     pub ops: BinOpVec<T>,
+    pub is_function_style: SmallVec<[bool; N_BINOPS_OF_DEEPEX_ON_STACK]>,
 }
 impl<T: Clone> BinOpsWithReprsBuf<T> {
     pub fn from_deepex(bin_ops_in: &BinOpsWithReprs<T>) -> Self {
@@ -195,12 +268,14 @@ impl<T: Clone> BinOpsWithReprsBuf<T> {
                 .map(|repr| repr.to_string())
                 .collect(),
             ops: bin_ops_in.ops.clone(),
+            is_function_style: bin_ops_in.is_function_style.clone(),
         }
     }
     pub fn to_deepex(&self) -> BinOpsWithReprs<T> {
         BinOpsWithReprs {
             reprs: self.reprs.iter().map(|repr| repr.as_str()).collect(),
             ops: self.ops.clone(),
+            is_function_style: self.is_function_style.clone(),
         }
     }
 }
@@ -0,0 +1,114 @@
+use crate::{
+    data_type::DataType,
+    expression::{
+        flat::{FlatEx, OwnedFlatEx},
+        flat_details, Eval, MatchLiteral,
+    },
+    ExResult, MakeOperators,
+};
+
+/// Expression together with default values of some of its variables. The defaults are
+/// used by [`eval_with_defaults`](WithDefaults::eval_with_defaults) for variables without a
+/// given value. The wrapper is usually created with
+/// [`FlatEx::with_defaults`](FlatEx::with_defaults) or
+/// [`OwnedFlatEx::with_defaults`](OwnedFlatEx::with_defaults). Expressions derived from the
+/// wrapped one, e.g., partial derivatives, do not have defaults.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// let expr = FlatEx::<f64>::from_str("x*scale+offset")?
+///     .with_defaults(&[("scale", 1.0), ("offset", 0.0)])?;
+/// assert_eq!(expr.eval_with_defaults(&[("x", 2.0)])?, 2.0);
+/// assert_eq!(expr.eval_with_defaults(&[("x", 2.0), ("offset", 1.0)])?, 3.0);
+/// assert!(expr.eval_with_defaults(&[]).is_err());
+/// // the wrapped expression is evaluated without defaults, variables are offset, scale, x
+/// assert_eq!(expr.expr().eval(&[1.0, 2.0, 3.0])?, 7.0);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct WithDefaults<Ex, T> {
+    expr: Ex,
+    defaults: Vec<(String, T)>,
+}
+
+impl<Ex, T> WithDefaults<Ex, T> {
+    /// Returns the default values of variables in the order they have been recorded.
+    pub fn defaults(&self) -> &[(String, T)] {
+        &self.defaults
+    }
+
+    /// Returns the wrapped expression.
+    pub fn expr(&self) -> &Ex {
+        &self.expr
+    }
+
+    /// Returns the wrapped expression and drops the defaults.
+    pub fn into_expr(self) -> Ex {
+        self.expr
+    }
+}
+
+impl<Ex, T> WithDefaults<Ex, T>
+where
+    Ex: Eval<T>,
+    T: Clone,
+{
+    /// Wraps an expression without any defaults.
+    pub fn new(expr: Ex) -> Self {
+        WithDefaults {
+            expr,
+            defaults: vec![],
+        }
+    }
+
+    /// Records default values of variables. Existing defaults of the same variables are
+    /// overridden. Defaults of names that are not variables of the expression are ignored.
+    ///
+    /// # Errors
+    ///
+    /// If a name is contained twice in `defaults`, we return an [`ExError`](crate::ExError).
+    ///
+    pub fn with_defaults(mut self, defaults: &[(&str, T)]) -> ExResult<Self> {
+        flat_details::merge_defaults(&mut self.defaults, defaults, &self.expr.var_names())?;
+        Ok(self)
+    }
+
+    /// Evaluates the expression like [`eval_named`](crate::Express::eval_named) but takes the
+    /// values of variables that are not contained in `given` from the defaults.
+    ///
+    /// # Errors
+    ///
+    /// * If a variable has neither a given value nor a default, we return an
+    ///   [`ExError`](crate::ExError).
+    /// * If a name in `given` is not a variable of the expression, we return an
+    ///   [`ExError`](crate::ExError).
+    ///
+    pub fn eval_with_defaults(&self, given: &[(&str, T)]) -> ExResult<T> {
+        self.expr.eval(&flat_details::vars_by_name_or_default(
+            given,
+            &self.defaults,
+            &self.expr.var_names(),
+        )?)
+    }
+}
+
+impl<'a, T, OF, LMF> WithDefaults<FlatEx<'a, T, OF, LMF>, T>
+where
+    T: DataType,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    /// Turns the wrapped expression into an [`OwnedFlatEx`](OwnedFlatEx) and keeps the
+    /// defaults.
+    pub fn into_owned(self) -> WithDefaults<OwnedFlatEx<T, OF, LMF>, T> {
+        WithDefaults {
+            expr: OwnedFlatEx::from_flatex(self.expr),
+            defaults: self.defaults,
+        }
+    }
+}
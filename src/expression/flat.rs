@@ -1,20 +1,26 @@
-use crate::data_type::DataType;
-use crate::definitions::{N_NODES_ON_STACK, N_UNARYOPS_OF_DEEPEX_ON_STACK};
+use crate::data_type::{DataType, TotalCmp};
+use crate::definitions::{N_UNARYOPS_OF_DEEPEX_ON_STACK, N_VARS_ON_STACK};
 use crate::expression::flat_details::{
-    self, FlatNode, FlatNodeKind, FlatNodeVec, FlatOp, FlatOpVec,
+    self, Affine, CsePlan, FlatNode, FlatNodeKind, FlatNodeVec, FlatOp, FlatOpVec, FlatPlan,
+    FlatTernary,
 };
 
 use crate::expression::{
-    deep::{DeepBuf, DeepEx, ExprIdxVec},
-    partial_derivatives, Express,
+    deep::{DeepBuf, DeepEx, DeepNode, ExprIdxVec, UnaryOpWithReprs},
+    defaults::WithDefaults,
+    partial_derivatives, polynomial, simplify, Eval, Express, ParseExpress,
 };
-use crate::operators::UnaryOp;
-use crate::parser::{Paren, ParsedToken};
-use crate::{parser, ExError, ExResult, FloatOpsFactory, MakeOperators, Operator};
+use crate::parser::{CurlyVarNames, Paren, ParsedToken};
+use crate::{format_exerr, parser, ExError, ExResult, FloatOpsFactory, MakeOperators, Operator};
 use num::Float;
 use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 use std::str::FromStr;
 
 type UnaryOpIdxDepthStack = SmallVec<[(usize, i64); N_UNARYOPS_OF_DEEPEX_ON_STACK]>;
@@ -48,14 +54,20 @@ where
 
 type ExResultOption<T> = ExResult<Option<T>>;
 
-fn unpack_unary<T>(idx: usize, parsed_tokens: &[ParsedToken<T>]) -> ExResultOption<fn(T) -> T>
+/// Unary function together with its representation.
+type UnaryFnWithRepr<'a, T> = (fn(T) -> T, &'a str);
+
+fn unpack_unary<'a, T>(
+    idx: usize,
+    parsed_tokens: &[ParsedToken<'a, T>],
+) -> ExResultOption<UnaryFnWithRepr<'a, T>>
 where
     T: DataType,
 {
     match &parsed_tokens[idx] {
         ParsedToken::Op(op) => {
             if !is_binary(op, idx, parsed_tokens)? {
-                Ok(Some(op.unary()?))
+                Ok(Some((op.unary()?, op.repr())))
             } else {
                 Ok(None)
             }
@@ -64,15 +76,62 @@ where
     }
 }
 
-pub fn make_expression<'a, T, OF, LMF>(
-    text: &'a str,
+/// Creates the ternary operator at `idx_tkn` and returns it together with the index of the
+/// closing paren of its last argument. After resolving function-style operators, the
+/// arguments are given as `op(c)(a)(b)`. The arguments are large and created in a function
+/// of their own to keep the stack frame of [`make_parts`] small.
+#[inline(never)]
+fn make_ternary<'a, T>(
+    idx_tkn: usize,
     parsed_tokens: &[ParsedToken<'a, T>],
     parsed_vars: &[&'a str],
-) -> ExResult<FlatEx<'a, T, OF, LMF>>
+    operators: &[Operator<'static, T>],
+) -> ExResult<(Box<FlatTernary<T>>, usize)>
+where
+    T: Clone + FromStr + Debug,
+{
+    let op = match &parsed_tokens[idx_tkn] {
+        ParsedToken::Op(op) => op,
+        _ => return Err(ExError::new("ternary operator expected")),
+    };
+    let ternary_op = *op
+        .ternary()
+        .ok_or_else(|| format_exerr!("{} is not a ternary operator", op.repr()))?;
+    let mut close_idx = idx_tkn;
+    let mut make_arg = || -> ExResult<FlatPlan<T>> {
+        let open_idx = close_idx + 1;
+        close_idx = match parsed_tokens.get(open_idx) {
+            Some(ParsedToken::Paren(Paren::Open)) => {
+                parser::find_closing_paren(parsed_tokens, open_idx)
+            }
+            _ => None,
+        }
+        .ok_or_else(|| format_exerr!("ternary operator {} expects 3 arguments", op.repr()))?;
+        let (nodes, ops) = make_parts(
+            &parsed_tokens[open_idx + 1..close_idx],
+            parsed_vars,
+            operators,
+        )?;
+        Ok(FlatPlan::from_parts(nodes, ops))
+    };
+    let args = [make_arg()?, make_arg()?, make_arg()?];
+    let ternary = Box::new(FlatTernary {
+        op: ternary_op,
+        repr: flat_details::factory_repr(op.repr(), operators),
+        args,
+    });
+    Ok((ternary, close_idx))
+}
+
+/// Creates the nodes and binary operators of the parsed tokens. The arguments of ternary
+/// operators are created recursively.
+fn make_parts<'a, T>(
+    parsed_tokens: &[ParsedToken<'a, T>],
+    parsed_vars: &[&'a str],
+    operators: &[Operator<'static, T>],
+) -> ExResult<(FlatNodeVec<T>, FlatOpVec<T>)>
 where
     T: Clone + FromStr + Debug,
-    OF: MakeOperators<T>,
-    LMF: MatchLiteral,
 {
     let mut flat_nodes = FlatNodeVec::<T>::new();
     let mut flat_ops = FlatOpVec::<T>::new();
@@ -108,7 +167,10 @@ where
                 if !is_binary(op, idx_op, parsed_tokens)? {
                     return Ok(FlatNode {
                         kind,
-                        unary_op: UnaryOp::from_iter(iter_subsequent_unaries(idx_op)?),
+                        unary_op: flat_details::factory_unary_op(
+                            iter_subsequent_unaries(idx_op)?,
+                            operators,
+                        ),
                     });
                 }
             }
@@ -122,9 +184,18 @@ where
                     let mut bin_op = op.bin()?;
                     bin_op.prio += depth * DEPTH_PRIO_STEP;
                     flat_ops.push(FlatOp::<T> {
-                        unary_op: UnaryOp::new(),
+                        unary_op: UnaryOpWithReprs::new(),
                         bin_op,
+                        repr: flat_details::factory_repr(op.repr(), operators),
+                        is_addition: op.id() == "add",
                     });
+                } else if op.ternary().is_some() {
+                    let (ternary, end_idx) =
+                        make_ternary(idx_tkn, parsed_tokens, parsed_vars, operators)?;
+                    let flat_node = create_node(idx_tkn, FlatNodeKind::Ternary(ternary))?;
+                    flat_nodes.push(flat_node);
+                    // continue after the closing paren of the last argument
+                    idx_tkn = end_idx;
                 } else if let ParsedToken::Paren(p) = &parsed_tokens[idx_tkn + 1] {
                     match p {
                         Paren::Close => {
@@ -170,18 +241,24 @@ where
                                 let mut closed = pop_unary_stack(&mut unary_stack, depth - 1);
                                 match &mut closed {
                                     None => (),
-                                    Some(uop_idx) => last_node
-                                        .unary_op
-                                        .append_after_iter(iter_subsequent_unaries(*uop_idx)?),
+                                    Some(uop_idx) => last_node.unary_op.append_front(
+                                        &flat_details::factory_unary_op(
+                                            iter_subsequent_unaries(*uop_idx)?,
+                                            operators,
+                                        ),
+                                    ),
                                 }
                             }
                             Some(lowpfo) => {
                                 let mut closed = pop_unary_stack(&mut unary_stack, depth - 1);
                                 match &mut closed {
                                     None => (),
-                                    Some(uop_idx) => lowpfo
-                                        .unary_op
-                                        .append_after_iter(iter_subsequent_unaries(*uop_idx)?),
+                                    Some(uop_idx) => lowpfo.unary_op.append_front(
+                                        &flat_details::factory_unary_op(
+                                            iter_subsequent_unaries(*uop_idx)?,
+                                            operators,
+                                        ),
+                                    ),
                                 }
                             }
                         }
@@ -192,34 +269,82 @@ where
             }
         }
     }
+    Ok((flat_nodes, flat_ops))
+}
+
+pub fn make_expression<'a, T, OF, LMF>(
+    text: &'a str,
+    parsed_tokens: &[ParsedToken<'a, T>],
+    parsed_vars: &[&'a str],
+    operators: &[Operator<'static, T>],
+) -> ExResult<FlatEx<'a, T, OF, LMF>>
+where
+    T: Clone + FromStr + Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    let (flat_nodes, flat_ops) = make_parts(parsed_tokens, parsed_vars, operators)?;
     let indices = flat_details::prioritized_indices_flat(&flat_ops, &flat_nodes);
     Ok(FlatEx {
         nodes: flat_nodes,
         ops: flat_ops,
         prio_indices: indices,
         n_unique_vars: parsed_vars.len(),
+        var_names: parsed_vars.iter().copied().collect(),
+        affine: None,
+        cse: None,
         deepex: None,
+        depth: None,
         text: Some(text),
         dummy_ops_factory: PhantomData,
         dummy_literal_matcher_factory: PhantomData,
     })
 }
 
-fn parse<'a, T, OF, LMF>(text: &'a str, ops: &[Operator<'a, T>]) -> ExResult<FlatEx<'a, T, OF, LMF>>
+fn parse<'a, T, OF, LMF>(
+    text: &'a str,
+    ops: &[Operator<'static, T>],
+) -> ExResult<FlatEx<'a, T, OF, LMF>>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    parse_with_curly_var_names(text, ops, CurlyVarNames::default())
+}
+
+fn parse_with_curly_var_names<'a, T, OF, LMF>(
+    text: &'a str,
+    ops: &[Operator<'static, T>],
+    curly_var_names: CurlyVarNames,
+) -> ExResult<FlatEx<'a, T, OF, LMF>>
 where
     T: DataType,
     <T as FromStr>::Err: Debug,
     OF: MakeOperators<T>,
     LMF: MatchLiteral,
 {
-    let mut expr = parse_wo_compile(text, ops)?;
-    expr.compile();
+    let mut expr = parse_wo_compile(text, ops, curly_var_names)?;
+    if curly_var_names != CurlyVarNames::default() {
+        // the deep expression cannot be re-created from the text with default settings
+        expr.deepex = Some(DeepEx::from_ops_with_curly_var_names(
+            text,
+            ops,
+            curly_var_names,
+        )?);
+    }
+    // calculations with stochastic operators need to be executed in each evaluation
+    if !ops.iter().any(|op| op.is_stochastic()) {
+        expr.compile();
+    }
     Ok(expr)
 }
 
 fn parse_wo_compile<'a, T, OF, LMF>(
     text: &'a str,
-    ops: &[Operator<'a, T>],
+    ops: &[Operator<'static, T>],
+    curly_var_names: CurlyVarNames,
 ) -> ExResult<FlatEx<'a, T, OF, LMF>>
 where
     T: DataType,
@@ -227,10 +352,57 @@ where
     OF: MakeOperators<T>,
     LMF: MatchLiteral,
 {
-    let parsed_tokens = parser::tokenize_and_analyze(text, ops, LMF::is_literal)?;
-    parser::check_parsed_token_preconditions(&parsed_tokens)?;
+    let (parsed_tokens, positions) = parser::tokenize_and_analyze_with_curly_var_names(
+        text,
+        ops,
+        LMF::is_literal,
+        curly_var_names,
+    )?;
+    parser::check_parsed_token_preconditions(&parsed_tokens, &positions)?;
     let parsed_vars = parser::find_parsed_vars(&parsed_tokens);
-    make_expression(text, &parsed_tokens[0..], &parsed_vars)
+    make_expression(text, &parsed_tokens[0..], &parsed_vars, ops)
+}
+
+/// Evaluates with [`eval_flatex_checked`](flat_details::eval_flatex_checked). Since constant
+/// sub-expressions are folded during parsing, a non-finite constant is traced back to its
+/// operator by evaluating the uncompiled expression parsed from `text`.
+fn eval_checked<T, OF, LMF, S>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    var_names: &[S],
+    text: Option<&str>,
+) -> ExResult<T>
+where
+    T: Float + DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+    S: AsRef<str>,
+{
+    let operators = OF::make();
+    let res =
+        flat_details::eval_flatex_checked(vars, nodes, ops, prio_indices, var_names, &operators);
+    match (res, text) {
+        (Err(e), Some(text)) => {
+            let uncompiled =
+                parse_wo_compile::<T, OF, LMF>(text, &operators, CurlyVarNames::default())?;
+            if uncompiled.var_names.len() != var_names.len() {
+                return Err(e);
+            }
+            flat_details::eval_flatex_checked(
+                vars,
+                &uncompiled.nodes,
+                &uncompiled.ops,
+                &uncompiled.prio_indices,
+                &uncompiled.var_names,
+                &operators,
+            )
+            .and(Err(e))
+        }
+        (res, _) => res,
+    }
 }
 
 /// This is the core data type representing a flattened expression and the result of
@@ -259,7 +431,27 @@ where
 /// variable values in the alphabetical order of the variable names.
 /// In this example, we want to evaluate the expression for the varibale values `x=2.0` and `y=1.5`.
 ///
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+/// Expressions can be combined with `+`, `-`, `*`, `/`, and negated with unary `-`. Thereby,
+/// the operators of the operator factory with these representations are used. The variables
+/// of the result are the union of the variables of the operands. Since the operator might be
+/// missing or the expressions might need to be re-parsed, the result is an [`ExResult`](ExResult).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// let expr = (FlatEx::<f64>::from_str("x^2")? + FlatEx::<f64>::from_str("2*y")?)?;
+/// assert_eq!(expr.n_vars(), 2);
+/// assert!((expr.eval(&[3.0, 0.5])? - 10.0).abs() < 1e-12);
+/// let expr = (-expr)?;
+/// assert!((expr.eval(&[3.0, 0.5])? + 10.0).abs() < 1e-12);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+#[derive(Clone, Debug)]
 pub struct FlatEx<'a, T, OF = FloatOpsFactory<T>, LMF = NumberMatcher>
 where
     T: Clone + Debug,
@@ -270,7 +462,13 @@ where
     ops: FlatOpVec<T>,
     prio_indices: ExprIdxVec,
     n_unique_vars: usize,
+    var_names: SmallVec<[&'a str; N_VARS_ON_STACK]>,
+    affine: Option<Affine<T>>,
+    /// Evaluation plan with shared sub-expressions created by `with_cse`.
+    cse: Option<CsePlan<T>>,
     deepex: Option<DeepEx<'a, T>>,
+    /// Nesting depth of the deep expression the flat expression has been created from.
+    depth: Option<usize>,
     text: Option<&'a str>,
     dummy_ops_factory: PhantomData<OF>,
     dummy_literal_matcher_factory: PhantomData<LMF>,
@@ -283,14 +481,21 @@ where
     LMF: MatchLiteral,
 {
     fn flatten(deepex: DeepEx<'a, T>) -> Self {
-        let (nodes, ops) = flat_details::flatten_vecs(&deepex, 0);
+        let operators = OF::make();
+        let (mut nodes, mut ops) = flat_details::flatten_vecs(&deepex, 0, &operators);
         let indices = flat_details::prioritized_indices_flat(&ops, &nodes);
+        flat_details::specialize_bin_ops(&mut nodes, &mut ops, &indices, OF::specialize_bin);
         let n_unique_vars = deepex.n_vars();
+        let affine = flat_details::find_affine(&nodes, &ops, &indices, &operators);
         Self {
             nodes,
             ops,
             prio_indices: indices,
             n_unique_vars,
+            var_names: deepex.var_names().iter().copied().collect(),
+            affine,
+            cse: None,
+            depth: Some(deepex.depth()),
             deepex: Some(deepex),
             text: None,
             dummy_ops_factory: PhantomData,
@@ -298,91 +503,524 @@ where
         }
     }
 
+    /// Creates a constant expression without parsing a string.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let two = FlatEx::<f64>::from_number(2.0);
+    /// let x = FlatEx::<f64>::from_variable("x");
+    /// let expr = (two * x)?;
+    /// assert_eq!(expr.var_names(), ["x"]);
+    /// assert!((expr.eval(&[1.5])? - 3.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn from_number(n: T) -> Self {
+        Self::flatten(DeepEx::from_node(DeepNode::Num(n)))
+    }
+
+    /// Creates an expression that consists of the variable `name` only.
+    pub fn from_variable(name: &'a str) -> Self {
+        Self::flatten(DeepEx::from_node(DeepNode::Var((0, name))))
+    }
+
+    /// Creates the constant expression `0`.
+    pub fn zero() -> Self
+    where
+        T: Float,
+    {
+        Self::from_number(T::zero())
+    }
+
+    /// Creates the constant expression `1`.
+    pub fn one() -> Self
+    where
+        T: Float,
+    {
+        Self::from_number(T::one())
+    }
+
     /// Executes calculations that can trivially be executed, e.g., two numbers that need to be
-    /// multiplied anyway.
+    /// multiplied anyway. Expressions created by [`from_str`](Express::from_str) are compiled
+    /// already. Expressions created by [`from_str_wo_compile`](FlatEx::from_str_wo_compile)
+    /// can be compiled on demand, e.g., before a long evaluation loop. Since calculations
+    /// with stochastic operators need to be executed in each evaluation, nothing happens if
+    /// the operator factory contains stochastic operators.
     pub fn compile(&mut self) {
-        let mut num_inds = self.prio_indices.clone();
-        let mut used_prio_indices = ExprIdxVec::new();
+        let operators = OF::make();
+        if operators.iter().any(|op| op.is_stochastic()) {
+            return;
+        }
+        flat_details::compile(&mut self.nodes, &mut self.ops, &mut self.prio_indices);
+        flat_details::specialize_bin_ops(
+            &mut self.nodes,
+            &mut self.ops,
+            &self.prio_indices,
+            OF::specialize_bin,
+        );
+        self.affine =
+            flat_details::find_affine(&self.nodes, &self.ops, &self.prio_indices, &operators);
+    }
 
-        let mut already_declined: SmallVec<[bool; N_NODES_ON_STACK]> =
-            smallvec::smallvec![false; self.nodes.len()];
+    /// Returns the deep expression or re-creates it from the text if it has been optimized away.
+    pub(super) fn deepex(&self) -> ExResult<Cow<'_, DeepEx<'a, T>>>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        match (&self.deepex, self.text) {
+            (Some(deepex), _) => Ok(Cow::Borrowed(deepex)),
+            (None, Some(t)) => Ok(Cow::Owned(DeepEx::from_ops(t, &OF::make())?)),
+            (None, None) => Err(ExError::new(
+                "Need either text or deep expression. Did you call `reduce_memory`?",
+            )),
+        }
+    }
 
-        for node in &mut self.nodes {
-            if let FlatNodeKind::Num(num) = &node.kind {
-                *node = FlatNode::from_kind(FlatNodeKind::Num(node.unary_op.apply(num.clone())));
-            }
+    /// Combines `self` and `rhs` by the binary operator `op_repr` of the operator factory.
+    fn merge_bin(self, rhs: Self, op_repr: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let bin_op = flat_details::find_bin_op_with_reprs(op_repr, &OF::make())?;
+        let merged = self
+            .deepex()?
+            .into_owned()
+            .operate_bin(rhs.deepex()?.into_owned(), bin_op);
+        Ok(Self::flatten(merged))
+    }
+
+    /// Applies the unary operator `op_repr` of the operator factory to `self`.
+    fn apply_unary(self, op_repr: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let unary_op = flat_details::find_unary_op_with_reprs(op_repr, &OF::make())?;
+        Ok(Self::flatten(self.deepex()?.into_owned().operate_unary(unary_op)))
+    }
+
+    /// Replaces the variable with index `var_idx` by `value` and evaluates the resulting
+    /// constant sub-expressions. The returned expression has one variable less and the
+    /// indices of the variables after `var_idx` are reduced by one.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("2*y + x")?;
+    /// let bound = expr.bind(1, 3.0)?;
+    /// assert_eq!(bound.n_vars(), 1);
+    /// assert_eq!(bound.unparse()?, "6.0+{x}");
+    /// assert!((bound.eval(&[1.0])? - 7.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * If `var_idx` is not smaller than the number of variables, we return an [`ExError`](ExError).
+    /// * If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn bind(self, var_idx: usize, value: T) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::bind_deepex(self.deepex()?.as_ref(), var_idx, value)?;
+        Ok(Self::flatten(deepex))
+    }
+
+    /// Replaces several variables at once by the pairs `(var_idx, value)` of `fixed` and
+    /// evaluates the resulting constant sub-expressions, e.g., to specialize an expression
+    /// for a batch of evaluations with some variables being constant. The returned
+    /// expression depends only on the remaining variables, which are re-indexed in
+    /// alphabetical order. In contrast to [`partial_eval`](Express::partial_eval), the
+    /// variables are addressed by their indices.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("x + t*y")?;
+    /// let bound = expr.bind_many(&[(0, 2.0)])?;
+    /// assert_eq!(bound.var_names(), ["x", "y"]);
+    /// assert_eq!(bound.unparse()?, "{x}+2.0*{y}");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * If an index is not smaller than the number of variables or is contained twice in `fixed`, we return an [`ExError`](ExError).
+    /// * If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn bind_many(self, fixed: &[(usize, T)]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::bind_many_deepex(self.deepex()?.as_ref(), fixed)?;
+        Ok(Self::flatten(deepex))
+    }
+
+    /// Applies the algebraic identities `x*1 = x`, `x+0 = x`, `x-0 = x`, `x*0 = 0`,
+    /// `x^1 = x`, `x^0 = 1`, `0^x = 0`, `1^x = 1`, `log(exp(x)) = x`, and `exp(log(x)) = x`
+    /// repeatedly until none of them applies anymore, e.g., to clean up partial derivatives.
+    /// The identities do not hold for all floating point values, e.g., `x*0` is not `0` for
+    /// `x = NaN`. The variables are kept even if they vanish from the expression.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = exmex::parse::<f64>("x*1+0*y+z^1-0")?.simplify()?;
+    /// assert_eq!(expr.unparse()?, "{x}+{z}");
+    /// assert_eq!(expr.var_names(), ["x", "y", "z"]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn simplify(self) -> ExResult<Self>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = simplify::simplify_deepex(self.deepex()?.as_ref(), &OF::make())?;
+        Ok(Self::flatten(deepex))
+    }
+
+    /// Evaluates sub-expressions that occur more than once only once per evaluation. For
+    /// instance, `x+y` is evaluated once in `sin(x+y)^2 + cos(x+y)^2`. Returns the expression
+    /// and the number of eliminated duplicates. The shared sub-expressions are used by
+    /// [`eval`](Eval::eval) and [`eval_many`](Express::eval_many), all other operations
+    /// work on the original expression. Expressions with stochastic operators are returned
+    /// unchanged, since each occurrence needs to be evaluated separately.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("sin(x+y)^2 + cos(x+y)^2")?;
+    /// let (expr, n_eliminated) = expr.with_cse()?;
+    /// assert_eq!(n_eliminated, 1);
+    /// assert!((expr.eval(&[0.3, 0.4])? - 1.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn with_cse(mut self) -> ExResult<(Self, usize)>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        if OF::make().iter().any(|op| op.is_stochastic()) {
+            return Ok((self, 0));
         }
-        for (i, &bin_op_idx) in self.prio_indices.iter().enumerate() {
-            let num_idx = num_inds[i];
-            let node_1 = &self.nodes[num_idx];
-            let node_2 = &self.nodes[num_idx + 1];
-            if let (FlatNodeKind::Num(num_1), FlatNodeKind::Num(num_2)) =
-                (node_1.kind.clone(), node_2.kind.clone())
-            {
-                if !(already_declined[num_idx] || already_declined[num_idx + 1]) {
-                    let op_result =
-                        self.ops[bin_op_idx]
-                            .unary_op
-                            .apply((self.ops[bin_op_idx].bin_op.apply)(num_1, num_2));
-                    self.nodes[num_idx] = FlatNode::from_kind(FlatNodeKind::Num(op_result));
-                    self.nodes.remove(num_idx + 1);
-                    already_declined.remove(num_idx + 1);
-                    // reduce indices after removed position
-                    for num_idx_after in num_inds.iter_mut() {
-                        if *num_idx_after > num_idx {
-                            *num_idx_after -= 1;
-                        }
-                    }
-                    used_prio_indices.push(bin_op_idx);
-                } else {
-                    already_declined[num_idx] = true;
-                    already_declined[num_idx + 1] = true;
-                }
-            } else {
-                already_declined[num_idx] = true;
-                already_declined[num_idx + 1] = true;
+        let (cse, n_eliminated) = flat_details::cse_plan(self.deepex()?.as_ref(), &OF::make());
+        self.cse = cse;
+        Ok((self, n_eliminated))
+    }
+
+    /// Renames variables by the pairs `(old_name, new_name)` of `renames`. All variables are
+    /// renamed simultaneously such that, e.g., two variables can be swapped. The variables
+    /// are re-indexed according to the alphabetical order of the new names.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("a - 2*b")?;
+    /// let renamed = expr.remap_vars(&[("a", "z")])?;
+    /// assert_eq!(renamed.var_names(), ["b", "z"]);
+    /// assert_eq!(renamed.eval(&[1.0, 3.0])?, 1.0);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * If an old name is not a variable of the expression or is renamed twice, we return an [`ExError`](ExError).
+    /// * If two variables would have the same name after renaming, we return an [`ExError`](ExError).
+    /// * If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn remap_vars(self, renames: &[(&str, &'a str)]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::rename_deepex(self.deepex()?.as_ref(), renames)?;
+        Ok(Self::flatten(deepex))
+    }
+
+    /// Creates a compact string of the expression, e.g., for URLs. Variables are replaced
+    /// by positional placeholders `{0}`, `{1}`, ... in the order of their indices and numbers
+    /// are written as short as possible, e.g., `.5` instead of `0.5`. Only non-numeric names
+    /// whose curly form `{name}` is shorter than their placeholder, e.g., single-character
+    /// names of variables with index 10 or larger, are kept in curly brackets. Returns the
+    /// compact string and the names of the variables. The expression is recreated by
+    /// [`parse_minified`](FlatEx::parse_minified).
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("0.5 * velocity^2.0 * mass")?;
+    /// let (minified, names) = expr.minify()?;
+    /// assert_eq!(minified, ".5*{1}^2*{0}");
+    /// assert_eq!(names, ["mass", "velocity"]);
+    /// let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+    /// let recreated = FlatEx::<f64>::parse_minified(&minified, &names)?;
+    /// assert_eq!(recreated.eval(&[2.0, 3.0])?, expr.eval(&[2.0, 3.0])?);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](ExError).
+    ///
+    pub fn minify(&self) -> ExResult<(String, Vec<String>)>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(flat_details::minify_deepex(
+            self.deepex()?.as_ref(),
+            |text| LMF::is_literal(text) == Some(text),
+        ))
+    }
+
+    /// Parses a compact string created by [`minify`](FlatEx::minify) where `names` are
+    /// the names of the variables in the order of the placeholders.
+    ///
+    /// # Errors
+    ///
+    /// If the text cannot be parsed or the number of names does not match the number of
+    /// variables, we return an [`ExError`](ExError).
+    ///
+    pub fn parse_minified(text: &'a str, names: &[&'a str]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let flatex = Self::from_str(text)?;
+        let renames = flat_details::placeholder_renames(&flatex.var_names, names)?;
+        flatex.remap_vars(&renames)
+    }
+
+    /// Wraps the expression together with default values of variables that are used by
+    /// [`eval_with_defaults`](WithDefaults::eval_with_defaults) if no value is given, see
+    /// [`WithDefaults`](WithDefaults).
+    ///
+    /// # Errors
+    ///
+    /// If a name is contained twice in `defaults`, we return an [`ExError`](ExError).
+    ///
+    pub fn with_defaults(self, defaults: &[(&str, T)]) -> ExResult<WithDefaults<Self, T>>
+    where
+        T: DataType,
+    {
+        WithDefaults::new(self).with_defaults(defaults)
+    }
+
+    /// Evaluates the expression like [`eval`](crate::Eval::eval) with the same results but
+    /// stores intermediate results in `buffer` instead of allocating memory. Large
+    /// expressions that are evaluated in hot loops profit from re-using the buffer.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::{prelude::*, EvalBuffer};
+    /// let expr = FlatEx::<f64>::from_str("x*y+sin(x)")?;
+    /// let mut buffer = EvalBuffer::new();
+    /// for x in [0.0, 0.5, 1.0] {
+    ///     assert_eq!(expr.eval_with_buffer(&[x, 2.0], &mut buffer)?, expr.eval(&[x, 2.0])?);
+    /// }
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the number of variables does not match, we return an [`ExError`](ExError).
+    ///
+    pub fn eval_with_buffer(&self, vars: &[T], buffer: &mut EvalBuffer<T>) -> ExResult<T> {
+        match &self.affine {
+            Some(affine) if vars.len() == self.n_unique_vars => {
+                Ok(affine.eval(vars[affine.var_idx].clone()))
             }
+            _ => flat_details::eval_flatex_with_buffer(
+                vars,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+                &mut buffer.numbers,
+                &mut buffer.ignore,
+            ),
         }
+    }
 
-        self.ops = self
-            .ops
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| !used_prio_indices.contains(i))
-            .map(|(_, op)| op.clone())
-            .collect();
-
-        self.prio_indices = flat_details::prioritized_indices_flat(&self.ops, &self.nodes);
+    /// Parses UTF-8 encoded bytes into an expression, e.g., a formula received via network.
+    /// A leading byte order mark is skipped.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_bytes(b"2*x")?;
+    /// assert!((expr.eval(&[1.5])? - 3.0).abs() < 1e-12);
+    /// let err = FlatEx::<f64>::from_bytes(b"2*\xFFx").unwrap_err();
+    /// assert_eq!(err.position, Some(2));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * If the bytes are not valid UTF-8, we return an [`ExError`](ExError) whose position is the character position of the first invalid byte in the decoded text without a leading byte order mark. The message contains the byte offset in `bytes`.
+    /// * If the decoded text cannot be parsed, we return the error of [`from_str`](Express::from_str).
+    ///
+    pub fn from_bytes(bytes: &'a [u8]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Self::from_str(parser::str_from_bytes(bytes)?)
     }
 
-    /// Parses into an expression without compilation. Allow slightly faster direct evaluation of strings.
-    pub fn from_str_wo_compile(text: &'a str) -> ExResult<Self>
+    /// Parses like [`from_str`](Express::from_str) where names of variables in curly
+    /// brackets are treated as determined by `curly_var_names`. By default, i.e., with
+    /// [`from_str`](Express::from_str), surrounding whitespace is trimmed. In both cases,
+    /// [`unparse`](crate::Eval::unparse) returns the original text.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::{prelude::*, CurlyVarNames};
+    /// let expr = FlatEx::<f64>::from_str("{x} + {x }")?;
+    /// assert_eq!(expr.var_names(), ["x"]);
+    /// let expr = FlatEx::<f64>::from_str_with_curly_var_names("{x} + {x }", CurlyVarNames::Verbatim)?;
+    /// assert_eq!(expr.var_names(), ["x", "x "]);
+    /// assert_eq!(expr.unparse()?, "{x} + {x }");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Expressions parsed with [`CurlyVarNames::Verbatim`](CurlyVarNames::Verbatim) keep their
+    /// deep expression also after [`reduce_memory`](Express::reduce_memory), since it
+    /// cannot be re-created from the text with default settings.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as in [`from_str`](Express::from_str) are returned.
+    ///
+    pub fn from_str_with_curly_var_names(
+        text: &'a str,
+        curly_var_names: CurlyVarNames,
+    ) -> ExResult<Self>
     where
         T: DataType,
         <T as FromStr>::Err: Debug,
     {
         let ops = OF::make();
-        parse_wo_compile(text, &ops)
+        parse_with_curly_var_names(text, &ops, curly_var_names)
     }
-}
 
-impl<'a, T, OF, LMF> Express<'a, T> for FlatEx<'a, T, OF, LMF>
-where
-    T: DataType,
-    OF: MakeOperators<T>,
-    LMF: MatchLiteral,
-{
-    fn from_str(text: &'a str) -> ExResult<Self>
+    /// Parses into an expression without compilation. Allow slightly faster direct evaluation of strings.
+    /// Constant sub-expressions such as `2-0.1` in `1*sin(2-0.1)` are kept and evaluated in
+    /// each evaluation until [`compile`](FlatEx::compile) is called.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let mut expr = FlatEx::<f64>::from_str_wo_compile("1*sin(2-0.1)+x")?;
+    /// assert_eq!(expr.unparse()?, "1*sin(2-0.1)+x");
+    /// assert_eq!(expr.n_binops(), 3);
+    /// expr.compile();
+    /// assert_eq!(expr.n_binops(), 1);
+    /// assert_eq!(expr.eval(&[1.0])?, FlatEx::<f64>::from_str("1*sin(2-0.1)+x")?.eval(&[1.0])?);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The same errors as in [`from_str`](Express::from_str) are returned.
+    ///
+    pub fn from_str_wo_compile(text: &'a str) -> ExResult<Self>
     where
-        <T as std::str::FromStr>::Err: Debug,
         T: DataType,
+        <T as FromStr>::Err: Debug,
     {
         let ops = OF::make();
-        parse(text, &ops)
+        parse_wo_compile(text, &ops, CurlyVarNames::default())
     }
 
-    fn eval(&self, vars: &[T]) -> ExResult<T> {
-        flat_details::eval_flatex(
+    /// Evaluates the expression like [`eval`](crate::Eval::eval) but uses compensated summation
+    /// for additions, i.e., binary operators with the id `add` such as `+` of
+    /// [`FloatOpsFactory`](crate::FloatOpsFactory), see [`Operator::with_id`]. This reduces the accumulated rounding error of sums
+    /// like `a+b+c+d` with summands of disparate magnitudes at the cost of a slower evaluation.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("a+b+c+d")?;
+    /// let vars = [1e16, 1.0, -1e16, 1.0];
+    /// assert_eq!(expr.eval(&vars)?, 1.0);
+    /// assert_eq!(expr.eval_compensated(&vars)?, 2.0);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The same errors as in [`eval`](crate::Eval::eval) are returned.
+    ///
+    pub fn eval_compensated(&self, vars: &[T]) -> ExResult<T>
+    where
+        T: Float,
+    {
+        flat_details::eval_flatex_compensated(
             vars,
             &self.nodes,
             &self.ops,
@@ -390,33 +1028,305 @@ where
             self.n_unique_vars,
         )
     }
-    fn partial(mut self, var_idx: usize) -> ExResult<Self>
+
+    /// Evaluates the expression like [`eval`](crate::Eval::eval) but returns an error as soon
+    /// as an operator results in a non-finite number, i.e., in `NaN` or an infinite number.
+    /// The error message contains the representation of the operator and the sub-expression
+    /// it has been applied to. The plain [`eval`](crate::Eval::eval) keeps the usual
+    /// floating point semantics.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("x/y + 1")?;
+    /// assert!(expr.eval(&[0.0, 0.0])?.is_nan());
+    /// let err = expr.eval_checked(&[0.0, 0.0]).unwrap_err();
+    /// assert!(err.msg.contains("'/' in '({x}/{y})'"));
+    /// assert_eq!(expr.eval_checked(&[1.0, 2.0])?, 1.5);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * The same errors as in [`eval`](crate::Eval::eval) are returned.
+    /// * An error is returned if a variable, a number, or the result of an operator is not
+    ///   finite.
+    ///
+    pub fn eval_checked(&self, vars: &[T]) -> ExResult<T>
     where
-        T: DataType + Float,
+        T: Float + DataType,
         <T as FromStr>::Err: Debug,
     {
-        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
-        let ops = FloatOpsFactory::make();
+        eval_checked::<T, OF, LMF, _>(
+            vars,
+            &self.nodes,
+            &self.ops,
+            &self.prio_indices,
+            &self.var_names,
+            self.text,
+        )
+    }
 
-        if self.deepex.is_none() {
-            self.deepex = match self.text {
-                Some(t) => Some(DeepEx::from_ops(t, &OF::make())?),
-                None => {
-                    return Err(ExError::new(
-                        "Need either text or deep expression. Did you call `reduce_memory`?",
-                    ));
-                }
+    /// Turns the expression into a closure that evaluates it, e.g., to pass it to numerical
+    /// libraries that expect functions. The closure owns the expression as
+    /// [`OwnedFlatEx`](OwnedFlatEx) and can hence be sent to other threads.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let f = FlatEx::<f64>::from_str("x*y")?.into_fn();
+    /// assert_eq!(f(&[2.0, 3.0])?, 6.0);
+    /// assert!(f(&[2.0]).is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn into_fn(self) -> impl Fn(&[T]) -> ExResult<T> + Send + Sync + 'static
+    where
+        T: DataType + Send + Sync + 'static,
+        OF: Send + Sync + 'static,
+        LMF: Send + Sync + 'static,
+    {
+        OwnedFlatEx::from_flatex(self).into_fn()
+    }
+
+    /// Turns an expression of one variable into a closure of one argument, see
+    /// [`into_fn`](FlatEx::into_fn).
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let f = FlatEx::<f64>::from_str("x^2")?.into_fn1()?;
+    /// assert_eq!(f(3.0)?, 9.0);
+    /// assert!(FlatEx::<f64>::from_str("x^y")?.into_fn1().is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the expression does not have exactly one variable, we return an [`ExError`](ExError).
+    ///
+    pub fn into_fn1(self) -> ExResult<impl Fn(T) -> ExResult<T> + Send + Sync + 'static>
+    where
+        T: DataType + Send + Sync + 'static,
+        OF: Send + Sync + 'static,
+        LMF: Send + Sync + 'static,
+    {
+        OwnedFlatEx::from_flatex(self).into_fn1()
+    }
+
+    /// Lists the evaluation plan of the flat expression line by line for debugging or for
+    /// porting the expression to another runtime. First, the variables are declared. Then,
+    /// numbers and variables are pushed and binary operators are applied in the order of
+    /// evaluation. Ternary operators are pushed and followed by the plans of their three
+    /// arguments, each enclosed by `arg` and `end`. Unary operators follow a `|`. Operators are
+    /// represented as in the operator factory `OF`.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("sin(x)+2*y")?;
+    /// let bytecode = expr.dump_bytecode();
+    /// assert_eq!(
+    ///     bytecode,
+    ///     "var x\nvar y\npush var 0 | sin\npush num 2.0\npush var 1\napply 1 *\napply 0 +\n"
+    /// );
+    /// let loaded = FlatEx::<f64>::load_bytecode(&bytecode)?;
+    /// assert_eq!(loaded.eval(&[1.0, 3.0])?, expr.eval(&[1.0, 3.0])?);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn dump_bytecode(&self) -> String {
+        flat_details::dump_bytecode(&self.nodes, &self.ops, &self.prio_indices, &self.var_names)
+    }
+
+    /// Returns the representations of the unary operators attached to the node with index
+    /// `node_idx` in the order of their application, i.e., innermost first. The nodes are the
+    /// operands of the flat expression in the order of the `push` lines of
+    /// [`dump_bytecode`](FlatEx::dump_bytecode). It is guaranteed that `a(b(x))` applies `b`
+    /// before `a`, independently of whether the unary operators are written with or without
+    /// parentheses. Functions that are not part of the operator factory are represented by `?`.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("sin(-x)+cos(abs(y))")?;
+    /// assert_eq!(expr.unary_chain(0)?, vec!["-", "sin"]);
+    /// assert_eq!(expr.unary_chain(1)?, vec!["abs", "cos"]);
+    /// assert!(expr.unary_chain(2).is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `node_idx` is out of bounds, we return an [`ExError`](ExError).
+    ///
+    pub fn unary_chain(&self, node_idx: usize) -> ExResult<Vec<&'static str>> {
+        flat_details::unary_chain(&self.nodes, node_idx)
+    }
+
+    /// Iterates read-only over the operands of the flat expression in the order of their
+    /// occurrence, e.g., to visualize the evaluation order together with
+    /// [`ops`](FlatEx::ops).
+    pub fn nodes(&self) -> impl Iterator<Item = FlatNodeView<T>> + '_ {
+        node_views(&self.nodes)
+    }
+
+    /// Iterates read-only over the binary operators of the flat expression in the order of
+    /// their evaluation. Operators that combine numbers only might have been evaluated
+    /// already during parsing, see [`from_str_wo_compile`](FlatEx::from_str_wo_compile).
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::{prelude::*, FlatNodeView};
+    /// let expr = FlatEx::<f64>::from_str_wo_compile("1*sin(2-0.1)+x")?;
+    /// let wrap = |mut operand: String, unary_reprs: &[&str]| {
+    ///     for repr in unary_reprs {
+    ///         operand = format!("{}({})", repr, operand);
+    ///     }
+    ///     operand
+    /// };
+    /// let mut operands = expr
+    ///     .nodes()
+    ///     .map(|node| match node {
+    ///         FlatNodeView::Num { value, unary_reprs } => wrap(value.to_string(), &unary_reprs),
+    ///         FlatNodeView::Var { var_idx, unary_reprs } => {
+    ///             wrap(expr.var_names()[var_idx].to_string(), &unary_reprs)
+    ///         }
+    ///         FlatNodeView::Ternary { repr, unary_reprs } => {
+    ///             wrap(format!("{}(..)", repr), &unary_reprs)
+    ///         }
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// let mut remaining_ops = (0..expr.ops().count()).collect::<Vec<_>>();
+    /// let mut steps = vec![];
+    /// for op in expr.ops() {
+    ///     let pos = remaining_ops.iter().position(|&idx| idx == op.bin_op_idx).unwrap();
+    ///     let right = operands.remove(pos + 1);
+    ///     let combined = format!("{}{}{}", operands[pos], op.repr, right);
+    ///     operands[pos] = wrap(combined, &op.unary_reprs);
+    ///     remaining_ops.remove(pos);
+    ///     steps.push(operands[pos].clone());
+    /// }
+    /// assert_eq!(steps, ["sin(2-0.1)", "1*sin(2-0.1)", "1*sin(2-0.1)+x"]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn ops(&self) -> impl Iterator<Item = FlatOpView> + '_ {
+        op_views(&self.ops, &self.prio_indices)
+    }
+
+    /// Creates an expression from the output of [`dump_bytecode`](FlatEx::dump_bytecode).
+    /// The operators are looked up by their representation in the operator factory `OF`.
+    /// Since the expression is not created from a string, it behaves like after a call of
+    /// [`reduce_memory`](Express::reduce_memory), e.g., it cannot be unparsed.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a line cannot be interpreted, an operator is unknown, or
+    /// the operators do not fit to the pushed nodes.
+    ///
+    pub fn load_bytecode(text: &'a str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let operators = OF::make();
+        let (mut nodes, mut ops, prio_indices, var_names) =
+            flat_details::load_bytecode(text, &operators)?;
+        flat_details::specialize_bin_ops(&mut nodes, &mut ops, &prio_indices, OF::specialize_bin);
+        let affine = flat_details::find_affine(&nodes, &ops, &prio_indices, &operators);
+        Ok(Self {
+            nodes,
+            ops,
+            prio_indices,
+            n_unique_vars: var_names.len(),
+            var_names,
+            affine,
+            cse: None,
+            deepex: None,
+            depth: None,
+            text: None,
+            dummy_ops_factory: PhantomData,
+            dummy_literal_matcher_factory: PhantomData,
+        })
+    }
+
+    /// String that identifies the structure of the expression, see
+    /// [`canonical_form`](flat_details::canonical_form).
+    pub(crate) fn canonical_form(&self) -> String {
+        flat_details::canonical_form(&self.nodes, &self.ops, &self.prio_indices, &self.var_names)
+    }
+
+    /// Compares the compiled structures of `self` and `other`. In contrast to `==`, the order
+    /// of the operands of commutative operators does not matter and `T` does not need to be
+    /// totally ordered. Whitespace and redundant parentheses of the parsed strings are
+    /// irrelevant in both cases. Operators are compared by their representations, numbers by
+    /// their debug representations, and variables by their names. Hence, like with `==`,
+    /// different functions with the same representation are considered equal.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str(" x+1")?;
+    /// assert!(expr.equivalent(&FlatEx::<f64>::from_str("(x) + 1")?));
+    /// assert!(expr.equivalent(&FlatEx::<f64>::from_str("1+x")?));
+    /// assert!(!expr.equivalent(&FlatEx::<f64>::from_str("x-1")?));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn equivalent(&self, other: &Self) -> bool {
+        self.canonical_form() == other.canonical_form()
+    }
+}
+
+impl<'a, T, OF, LMF> Eval<T> for FlatEx<'a, T, OF, LMF>
+where
+    T: DataType,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    fn eval(&self, vars: &[T]) -> ExResult<T> {
+        match (&self.affine, &self.cse) {
+            (Some(affine), _) if vars.len() == self.n_unique_vars => {
+                Ok(affine.eval(vars[affine.var_idx].clone()))
             }
+            (_, Some(cse)) => cse.eval(vars, self.n_unique_vars),
+            _ => flat_details::eval_flatex(
+                vars,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+            ),
         }
-
-        let d_i = partial_derivatives::partial_deepex(
-            var_idx,
-            self.deepex
-                .expect("This is bug. deepex cannot be None here."),
-            &ops,
-        )?;
-        Ok(Self::flatten(d_i))
     }
+
     fn unparse(&self) -> ExResult<String> {
         match self.text {
             Some(t) => Ok(t.to_string()),
@@ -424,38 +1334,338 @@ where
                 Some(deepex) => Ok(deepex.unparse_raw()),
                 None => Err(ExError {
                     msg: "unparse impossible, since deep expression optimized away".to_string(),
+                    position: None,
                 }),
             },
         }
     }
-    fn reduce_memory(&mut self) {
-        self.deepex = None;
-    }
 
     fn n_vars(&self) -> usize {
         self.n_unique_vars
     }
+
+    fn var_names(&self) -> Vec<&str> {
+        self.var_names.to_vec()
+    }
 }
 
-/// The expression is displayed as a string created by [`unparse`](FlatEx::unparse).
-impl<'a, T, OF, LMF> Display for FlatEx<'a, T, OF, LMF>
+impl<'a, T, OF, LMF> Express<'a, T> for FlatEx<'a, T, OF, LMF>
 where
     T: DataType,
     OF: MakeOperators<T>,
     LMF: MatchLiteral,
 {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let unparsed = self.unparse();
-        match unparsed {
-            Err(e) => write!(f, "{}", e.msg),
-            Ok(s) => write!(f, "{}", s),
-        }
-    }
-}
-
-/// This is another representation of a flattened expression besides [`FlatEx`](FlatEx).
-/// The difference is that [`OwnedFlatEx`](OwnedFlatEx) can be used without
-/// a lifetime parameter. All the data that [`FlatEx`](FlatEx) borrowed is kept in a
+    fn from_str(text: &'a str) -> ExResult<Self>
+    where
+        <T as std::str::FromStr>::Err: Debug,
+        T: DataType,
+    {
+        let ops = OF::make();
+        parse(text, &ops)
+    }
+
+    fn eval_named(&self, vars: &[(&str, T)]) -> ExResult<T> {
+        self.eval(&flat_details::vars_by_name(vars, &self.var_names)?)
+    }
+
+    fn eval_many(&self, var_rows: &[&[T]]) -> ExResult<Vec<T>> {
+        match (&self.affine, &self.cse) {
+            (Some(affine), _) => var_rows
+                .iter()
+                .enumerate()
+                .map(|(row_idx, vars)| {
+                    flat_details::check_n_vars_of_row(row_idx, vars, self.n_unique_vars)?;
+                    Ok(affine.eval(vars[affine.var_idx].clone()))
+                })
+                .collect(),
+            (None, Some(cse)) => var_rows
+                .iter()
+                .enumerate()
+                .map(|(row_idx, vars)| {
+                    flat_details::check_n_vars_of_row(row_idx, vars, self.n_unique_vars)?;
+                    cse.eval(vars, self.n_unique_vars)
+                })
+                .collect(),
+            (None, None) => flat_details::eval_flatex_many(
+                var_rows,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+            ),
+        }
+    }
+    fn partial(self, var_idx: usize) -> ExResult<Self>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = self.deepex()?;
+        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
+        let ops = FloatOpsFactory::make();
+        let d_i = partial_derivatives::partial_deepex(var_idx, deepex.into_owned(), &ops)?;
+        Ok(Self::flatten(d_i))
+    }
+    fn partial_n(self, var_idx: usize, order: u32) -> ExResult<Self>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
+        if order == 0 {
+            return Ok(self);
+        }
+        let ops = FloatOpsFactory::make();
+        let deepex = self.deepex()?.into_owned();
+        let d = partial_derivatives::partial_deepex_n(var_idx, order, deepex, &ops)?;
+        Ok(Self::flatten(d))
+    }
+    fn gradient(self) -> ExResult<Vec<Self>>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let deepex = self.deepex()?;
+        (0..self.n_vars())
+            .map(|var_idx| {
+                let d_i =
+                    partial_derivatives::partial_deepex(var_idx, deepex.as_ref().clone(), &ops)?;
+                Ok(Self::flatten(d_i))
+            })
+            .collect()
+    }
+    fn hessian(self) -> ExResult<Vec<Vec<Self>>>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let hessian = partial_derivatives::hessian_deepex(self.deepex()?.into_owned(), &ops)?;
+        Ok(hessian
+            .into_iter()
+            .map(|row| row.into_iter().map(Self::flatten).collect())
+            .collect())
+    }
+    fn directional(self, direction: &[T]) -> ExResult<Self>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let deepex = self.deepex()?;
+        let d = partial_derivatives::directional_deepex(direction, deepex.into_owned(), &ops)?;
+        Ok(Self::flatten(d))
+    }
+    fn partial_eval(self, bindings: &HashMap<&str, T>) -> ExResult<Self>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::partial_eval_deepex(self.deepex()?.as_ref(), bindings)?;
+        Ok(Self::flatten(deepex))
+    }
+    fn substitute(self, var_idx: usize, replacement: Self) -> ExResult<Self>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::substitute_deepex(
+            self.deepex()?.as_ref(),
+            var_idx,
+            replacement.deepex()?.as_ref(),
+        )?;
+        Ok(Self::flatten(deepex))
+    }
+    fn as_polynomial(&self, var_idx: usize) -> ExResult<Vec<T>>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
+        polynomial::polynomial_deepex(var_idx, self.deepex()?.as_ref())
+    }
+    fn used_operators(&self) -> ExResult<Vec<String>>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(flat_details::used_operator_ids(
+            self.deepex()?.as_ref(),
+            &OF::make(),
+        ))
+    }
+    fn operator_reprs(&self) -> Vec<String> {
+        flat_details::operator_reprs(&self.nodes, &self.ops)
+    }
+    fn occurring_vars(&self) -> Vec<&str> {
+        flat_details::used_var_names(&self.nodes, &self.var_names)
+    }
+    fn unparse_latex(&self) -> ExResult<String>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(self.deepex()?.unparse_latex())
+    }
+    fn to_dot(&self) -> ExResult<String>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(self.deepex()?.to_dot())
+    }
+    fn reduce_memory(&mut self) {
+        // if there is a text, the deep expression is only stored because it cannot be
+        // re-created from the text, e.g., with verbatim names of curly variables
+        if self.text.is_none() {
+            self.deepex = None;
+        }
+    }
+
+    fn n_binops(&self) -> usize {
+        self.ops.len()
+    }
+
+    fn n_unary_ops(&self) -> usize {
+        flat_details::n_unary_ops(&self.nodes, &self.ops)
+    }
+
+    fn is_const(&self) -> bool {
+        flat_details::is_const(&self.nodes, &self.ops, &OF::make())
+    }
+
+    fn const_value(&self) -> ExResult<T> {
+        flat_details::const_value(
+            &self.nodes,
+            &self.ops,
+            &self.prio_indices,
+            &self.var_names,
+            &OF::make(),
+        )
+    }
+
+    fn depth(&self) -> ExResult<usize>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        match self.depth {
+            Some(depth) => Ok(depth),
+            None => Ok(self.deepex()?.depth()),
+        }
+    }
+}
+
+/// The expression is displayed as a string created by [`unparse`](FlatEx::unparse).
+impl<'a, T, OF, LMF> Display for FlatEx<'a, T, OF, LMF>
+where
+    T: DataType,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let unparsed = self.unparse();
+        match unparsed {
+            Err(e) => write!(f, "{}", e.msg),
+            Ok(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Read-only view of an operand of a flat expression, see [`FlatEx::nodes`](FlatEx::nodes).
+/// The representations of the unary operators of the operand are listed in the order of
+/// their application, i.e., innermost first.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlatNodeView<T> {
+    Num {
+        value: T,
+        unary_reprs: Vec<&'static str>,
+    },
+    Var {
+        var_idx: usize,
+        unary_reprs: Vec<&'static str>,
+    },
+    /// Ternary operator whose arguments are evaluated separately, see
+    /// [`FlatEx::dump_bytecode`](FlatEx::dump_bytecode).
+    Ternary {
+        repr: &'static str,
+        unary_reprs: Vec<&'static str>,
+    },
+}
+
+/// Read-only view of a binary operator of a flat expression, see [`FlatEx::ops`](FlatEx::ops).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlatOpView {
+    /// Position of the operator between the operands, i.e., the operator with index `i`
+    /// combines what remains of the operands `i` and `i+1` when it is applied.
+    pub bin_op_idx: usize,
+    pub repr: &'static str,
+    /// Priority of the operator increased by the nesting depth of parentheses.
+    pub prio: i64,
+    pub is_commutative: bool,
+    /// Unary operators applied to the result of the binary operator, innermost first.
+    pub unary_reprs: Vec<&'static str>,
+}
+
+fn node_views<T: Clone>(nodes: &FlatNodeVec<T>) -> impl Iterator<Item = FlatNodeView<T>> + '_ {
+    nodes.iter().map(|node| {
+        let unary_reprs = flat_details::unary_reprs(&node.unary_op);
+        match &node.kind {
+            FlatNodeKind::Num(value) => FlatNodeView::Num {
+                value: value.clone(),
+                unary_reprs,
+            },
+            FlatNodeKind::Var(var_idx) => FlatNodeView::Var {
+                var_idx: *var_idx,
+                unary_reprs,
+            },
+            FlatNodeKind::Ternary(t) => FlatNodeView::Ternary {
+                repr: t.repr,
+                unary_reprs,
+            },
+        }
+    })
+}
+
+fn op_views<'b, T: Clone>(
+    ops: &'b FlatOpVec<T>,
+    prio_indices: &'b ExprIdxVec,
+) -> impl Iterator<Item = FlatOpView> + 'b {
+    prio_indices.iter().map(move |&bin_op_idx| {
+        let op = &ops[bin_op_idx];
+        FlatOpView {
+            bin_op_idx,
+            repr: op.repr,
+            prio: op.bin_op.prio,
+            is_commutative: op.bin_op.is_commutative,
+            unary_reprs: flat_details::unary_reprs(&op.unary_op),
+        }
+    })
+}
+
+/// Re-usable memory for intermediate results of evaluations with
+/// [`FlatEx::eval_with_buffer`](FlatEx::eval_with_buffer) and
+/// [`OwnedFlatEx::eval_with_buffer`](OwnedFlatEx::eval_with_buffer). The buffer grows
+/// lazily to the number of nodes of the largest expression it has been used with and can be
+/// shared among different expressions.
+#[derive(Clone, Debug)]
+pub struct EvalBuffer<T> {
+    numbers: Vec<T>,
+    ignore: Vec<bool>,
+}
+impl<T> EvalBuffer<T> {
+    pub fn new() -> Self {
+        EvalBuffer {
+            numbers: vec![],
+            ignore: vec![],
+        }
+    }
+}
+impl<T> Default for EvalBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// This is another representation of a flattened expression besides [`FlatEx`](FlatEx).
+/// The difference is that [`OwnedFlatEx`](OwnedFlatEx) can be used without
+/// a lifetime parameter. All the data that [`FlatEx`](FlatEx) borrowed is kept in a
 /// buffer by [`OwnedFlatEx`](OwnedFlatEx). The drawback is that parsing takes longer, since
 /// additional allocations are necessary. Evaluation time should be about the same for
 /// [`FlatEx`](FlatEx) and [`OwnedFlatEx`](OwnedFlatEx).
@@ -464,7 +1674,7 @@ where
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// use exmex::{Express, OwnedFlatEx};
+/// use exmex::{Eval, Express, OwnedFlatEx};
 /// let to_be_parsed = "log(z) + 2* (-z^(x-2) + sin(4*y))";
 /// let expr_owned = OwnedFlatEx::<f64>::from_str(to_be_parsed)?;
 /// assert!((expr_owned.eval(&[4.0, 3.7, 2.5])? - 14.992794866624788 as f64).abs() < 1e-12);
@@ -472,7 +1682,7 @@ where
 /// #     Ok(())
 /// # }
 /// ```
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[derive(Clone, Debug)]
 pub struct OwnedFlatEx<T, OF = FloatOpsFactory<T>, LMF = NumberMatcher>
 where
     T: Clone + Debug,
@@ -484,30 +1694,442 @@ where
     ops: FlatOpVec<T>,
     prio_indices: ExprIdxVec,
     n_unique_vars: usize,
+    var_names: Vec<String>,
+    affine: Option<Affine<T>>,
+    /// Evaluation plan with shared sub-expressions created by `with_cse`.
+    cse: Option<CsePlan<T>>,
+    depth: Option<usize>,
     text: Option<String>,
     dummy_ops_factory: PhantomData<OF>,
     dummy_literal_matcher_factory: PhantomData<LMF>,
 }
-impl<T, OF, LMF> OwnedFlatEx<T, OF, LMF>
+impl<T, OF, LMF> OwnedFlatEx<T, OF, LMF>
+where
+    T: Clone + Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    /// Creates an `OwnedFlatEx` instance from an instance of `FlatEx`.
+    pub fn from_flatex(flatex: FlatEx<T, OF, LMF>) -> Self {
+        Self {
+            deepex_buf: flatex.deepex.map(|d| DeepBuf::from_deepex(&d)),
+            nodes: flatex.nodes,
+            ops: flatex.ops,
+            prio_indices: flatex.prio_indices,
+            n_unique_vars: flatex.n_unique_vars,
+            var_names: flatex.var_names.iter().map(|s| s.to_string()).collect(),
+            affine: flatex.affine,
+            cse: flatex.cse,
+            depth: flatex.depth,
+            text: flatex.text.map(|s| s.to_string()),
+            dummy_ops_factory: PhantomData,
+            dummy_literal_matcher_factory: PhantomData,
+        }
+    }
+
+    /// Parses like [`from_str`](Express::from_str) with operators that have already been
+    /// created by the operator factory.
+    pub(crate) fn from_str_with_ops(text: &str, ops: &[Operator<'static, T>]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(Self::from_flatex(parse(text, ops)?))
+    }
+
+    /// Parses a string into an expression, see [`from_str`](Express::from_str). Since
+    /// `OwnedFlatEx` also implements [`FromStr`](std::str::FromStr), this function avoids
+    /// ambiguities if both traits are in scope.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `text` cannot be parsed.
+    ///
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(text: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        <Self as Express<T>>::from_str(text)
+    }
+
+    /// Parses with the given treatment of names of variables in curly brackets, see
+    /// [`FlatEx::from_str_with_curly_var_names`](FlatEx::from_str_with_curly_var_names).
+    pub fn from_str_with_curly_var_names(
+        text: &str,
+        curly_var_names: CurlyVarNames,
+    ) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = OF::make();
+        Ok(Self::from_flatex(parse_with_curly_var_names(
+            text,
+            &ops,
+            curly_var_names,
+        )?))
+    }
+
+    /// Parses into an expression without compilation, see
+    /// [`FlatEx::from_str_wo_compile`](FlatEx::from_str_wo_compile).
+    pub fn from_str_wo_compile(text: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(Self::from_flatex(FlatEx::from_str_wo_compile(text)?))
+    }
+
+    /// Executes calculations that can trivially be executed, see
+    /// [`FlatEx::compile`](FlatEx::compile).
+    pub fn compile(&mut self) {
+        let operators = OF::make();
+        if operators.iter().any(|op| op.is_stochastic()) {
+            return;
+        }
+        flat_details::compile(&mut self.nodes, &mut self.ops, &mut self.prio_indices);
+        flat_details::specialize_bin_ops(
+            &mut self.nodes,
+            &mut self.ops,
+            &self.prio_indices,
+            OF::specialize_bin,
+        );
+        self.affine =
+            flat_details::find_affine(&self.nodes, &self.ops, &self.prio_indices, &operators);
+    }
+
+    /// Creates a constant expression, see [`FlatEx::from_number`](FlatEx::from_number).
+    pub fn from_number(n: T) -> Self {
+        Self::from_flatex(FlatEx::from_number(n))
+    }
+
+    /// Creates an expression of a single variable, see
+    /// [`FlatEx::from_variable`](FlatEx::from_variable).
+    pub fn from_variable(name: &str) -> Self {
+        Self::from_flatex(FlatEx::from_variable(name))
+    }
+
+    /// Creates the constant expression `0`.
+    pub fn zero() -> Self
+    where
+        T: Float,
+    {
+        Self::from_number(T::zero())
+    }
+
+    /// Creates the constant expression `1`.
+    pub fn one() -> Self
+    where
+        T: Float,
+    {
+        Self::from_number(T::one())
+    }
+
+    /// Returns the deep expression or re-creates it from the text if it has been optimized away.
+    pub(super) fn deepex(&self) -> ExResult<DeepEx<'_, T>>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        match (&self.deepex_buf, &self.text) {
+            (Some(deep_buf), _) => deep_buf.to_deepex(&OF::make()),
+            (None, Some(t)) => DeepEx::from_ops(t.as_str(), &OF::make()),
+            (None, None) => Err(ExError::new(
+                "Need either text or deep expression. Did you call `reduce_memory`?",
+            )),
+        }
+    }
+
+    /// Combines `self` and `rhs` by the binary operator `op_repr` of the operator factory.
+    fn merge_bin(self, rhs: Self, op_repr: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let bin_op = flat_details::find_bin_op_with_reprs(op_repr, &OF::make())?;
+        let merged = self.deepex()?.operate_bin(rhs.deepex()?, bin_op);
+        Ok(Self::from_flatex(FlatEx::flatten(merged)))
+    }
+
+    /// Applies the unary operator `op_repr` of the operator factory to `self`.
+    fn apply_unary(self, op_repr: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let unary_op = flat_details::find_unary_op_with_reprs(op_repr, &OF::make())?;
+        Ok(Self::from_flatex(FlatEx::flatten(
+            self.deepex()?.operate_unary(unary_op),
+        )))
+    }
+
+    /// Evaluates the expression with re-usable memory, see
+    /// [`FlatEx::eval_with_buffer`](FlatEx::eval_with_buffer).
+    pub fn eval_with_buffer(&self, vars: &[T], buffer: &mut EvalBuffer<T>) -> ExResult<T> {
+        match &self.affine {
+            Some(affine) if vars.len() == self.n_unique_vars => {
+                Ok(affine.eval(vars[affine.var_idx].clone()))
+            }
+            _ => flat_details::eval_flatex_with_buffer(
+                vars,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+                &mut buffer.numbers,
+                &mut buffer.ignore,
+            ),
+        }
+    }
+
+    /// Parses UTF-8 encoded bytes, see [`FlatEx::from_bytes`](FlatEx::from_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(Self::from_flatex(FlatEx::from_bytes(bytes)?))
+    }
+
+    /// Replaces the variable with index `var_idx` by `value`, see [`FlatEx::bind`](FlatEx::bind).
+    pub fn bind(self, var_idx: usize, value: T) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::bind_deepex(&self.deepex()?, var_idx, value)?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+
+    /// Replaces several variables by values, see [`FlatEx::bind_many`](FlatEx::bind_many).
+    pub fn bind_many(self, fixed: &[(usize, T)]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::bind_many_deepex(&self.deepex()?, fixed)?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+
+    /// Applies algebraic identities until none of them applies anymore, see
+    /// [`FlatEx::simplify`](FlatEx::simplify).
+    pub fn simplify(self) -> ExResult<Self>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = simplify::simplify_deepex(&self.deepex()?, &OF::make())?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+
+    /// Evaluates sub-expressions that occur more than once only once per evaluation, see
+    /// [`FlatEx::with_cse`](FlatEx::with_cse).
+    pub fn with_cse(mut self) -> ExResult<(Self, usize)>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        if OF::make().iter().any(|op| op.is_stochastic()) {
+            return Ok((self, 0));
+        }
+        let (cse, n_eliminated) = flat_details::cse_plan(&self.deepex()?, &OF::make());
+        self.cse = cse;
+        Ok((self, n_eliminated))
+    }
+
+    /// Turns the expression into a closure that evaluates it, see
+    /// [`FlatEx::into_fn`](FlatEx::into_fn).
+    pub fn into_fn(self) -> impl Fn(&[T]) -> ExResult<T> + Send + Sync + 'static
+    where
+        T: DataType + Send + Sync + 'static,
+        OF: Send + Sync + 'static,
+        LMF: Send + Sync + 'static,
+    {
+        move |vars: &[T]| self.eval(vars)
+    }
+
+    /// Turns an expression of one variable into a closure of one argument, see
+    /// [`FlatEx::into_fn1`](FlatEx::into_fn1).
+    pub fn into_fn1(self) -> ExResult<impl Fn(T) -> ExResult<T> + Send + Sync + 'static>
+    where
+        T: DataType + Send + Sync + 'static,
+        OF: Send + Sync + 'static,
+        LMF: Send + Sync + 'static,
+    {
+        if self.n_vars() != 1 {
+            return Err(format_exerr!(
+                "'{}' has {} variables but a function of one argument needs one",
+                self,
+                self.n_vars()
+            ));
+        }
+        Ok(move |x: T| self.eval(&[x]))
+    }
+
+    /// Renames variables simultaneously, e.g., after deserialization to match the current
+    /// naming of variables, see [`FlatEx::remap_vars`](FlatEx::remap_vars).
+    pub fn remap_vars(self, renames: &[(&str, &str)]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::rename_deepex(&self.deepex()?, renames)?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+
+    /// Creates a compact string of the expression, see [`FlatEx::minify`](FlatEx::minify).
+    pub fn minify(&self) -> ExResult<(String, Vec<String>)>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(flat_details::minify_deepex(&self.deepex()?, |text| {
+            LMF::is_literal(text) == Some(text)
+        }))
+    }
+
+    /// Parses a compact string created by [`minify`](OwnedFlatEx::minify), see
+    /// [`FlatEx::parse_minified`](FlatEx::parse_minified).
+    pub fn parse_minified(text: &str, names: &[&str]) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(Self::from_flatex(FlatEx::parse_minified(text, names)?))
+    }
+
+    /// Wraps the expression together with default values of variables, see
+    /// [`FlatEx::with_defaults`](FlatEx::with_defaults).
+    pub fn with_defaults(self, defaults: &[(&str, T)]) -> ExResult<WithDefaults<Self, T>>
+    where
+        T: DataType,
+    {
+        WithDefaults::new(self).with_defaults(defaults)
+    }
+
+    /// Evaluates the expression with compensated summation for additions, i.e., binary
+    /// operators with the id `add`, see [`FlatEx::eval_compensated`](FlatEx::eval_compensated).
+    pub fn eval_compensated(&self, vars: &[T]) -> ExResult<T>
+    where
+        T: Float,
+    {
+        flat_details::eval_flatex_compensated(
+            vars,
+            &self.nodes,
+            &self.ops,
+            &self.prio_indices,
+            self.n_unique_vars,
+        )
+    }
+
+    /// Evaluates the expression and returns an error if an operator results in a
+    /// non-finite number, see [`FlatEx::eval_checked`](FlatEx::eval_checked).
+    pub fn eval_checked(&self, vars: &[T]) -> ExResult<T>
+    where
+        T: Float + DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        eval_checked::<T, OF, LMF, _>(
+            vars,
+            &self.nodes,
+            &self.ops,
+            &self.prio_indices,
+            &self.var_names,
+            self.text.as_deref(),
+        )
+    }
+
+    /// Lists the evaluation plan line by line, see
+    /// [`FlatEx::dump_bytecode`](FlatEx::dump_bytecode).
+    pub fn dump_bytecode(&self) -> String {
+        flat_details::dump_bytecode(&self.nodes, &self.ops, &self.prio_indices, &self.var_names)
+    }
+
+    /// Returns the representations of the unary operators attached to a node in the order
+    /// of their application, see [`FlatEx::unary_chain`](FlatEx::unary_chain).
+    pub fn unary_chain(&self, node_idx: usize) -> ExResult<Vec<&'static str>> {
+        flat_details::unary_chain(&self.nodes, node_idx)
+    }
+
+    /// Iterates read-only over the operands, see [`FlatEx::nodes`](FlatEx::nodes).
+    pub fn nodes(&self) -> impl Iterator<Item = FlatNodeView<T>> + '_ {
+        node_views(&self.nodes)
+    }
+
+    /// Iterates read-only over the binary operators in the order of their evaluation, see
+    /// [`FlatEx::ops`](FlatEx::ops).
+    pub fn ops(&self) -> impl Iterator<Item = FlatOpView> + '_ {
+        op_views(&self.ops, &self.prio_indices)
+    }
+
+    /// Creates an expression from the output of [`dump_bytecode`](OwnedFlatEx::dump_bytecode),
+    /// see [`FlatEx::load_bytecode`](FlatEx::load_bytecode).
+    pub fn load_bytecode(text: &str) -> ExResult<Self>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(Self::from_flatex(FlatEx::load_bytecode(text)?))
+    }
+
+    /// Compares the compiled structures of `self` and `other`, see
+    /// [`FlatEx::equivalent`](FlatEx::equivalent).
+    pub fn equivalent(&self, other: &Self) -> bool {
+        flat_details::canonical_form(&self.nodes, &self.ops, &self.prio_indices, &self.var_names)
+            == flat_details::canonical_form(
+                &other.nodes,
+                &other.ops,
+                &other.prio_indices,
+                &other.var_names,
+            )
+    }
+}
+impl<T, OF, LMF> Eval<T> for OwnedFlatEx<T, OF, LMF>
 where
-    T: Clone + Debug,
+    T: DataType,
     OF: MakeOperators<T>,
     LMF: MatchLiteral,
 {
-    /// Creates an `OwnedFlatEx` instance from an instance of `FlatEx`.
-    pub fn from_flatex(flatex: FlatEx<T, OF, LMF>) -> Self {
-        Self {
-            deepex_buf: flatex.deepex.map(|d| DeepBuf::from_deepex(&d)),
-            nodes: flatex.nodes,
-            ops: flatex.ops,
-            prio_indices: flatex.prio_indices,
-            n_unique_vars: flatex.n_unique_vars,
-            text: flatex.text.map(|s| s.to_string()),
-            dummy_ops_factory: PhantomData,
-            dummy_literal_matcher_factory: PhantomData,
+    fn eval(&self, vars: &[T]) -> ExResult<T> {
+        match (&self.affine, &self.cse) {
+            (Some(affine), _) if vars.len() == self.n_unique_vars => {
+                Ok(affine.eval(vars[affine.var_idx].clone()))
+            }
+            (_, Some(cse)) => cse.eval(vars, self.n_unique_vars),
+            _ => flat_details::eval_flatex(
+                vars,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+            ),
+        }
+    }
+
+    fn unparse(&self) -> ExResult<String> {
+        match &self.text {
+            Some(t) => Ok(t.clone()),
+            None => match &self.deepex_buf {
+                Some(deepex) => Ok(deepex.unparsed.clone()),
+                None => Err(ExError {
+                    msg: "unparse impossible, since deep expression optimized away".to_string(),
+                    position: None,
+                }),
+            },
         }
     }
+
+    fn n_vars(&self) -> usize {
+        self.n_unique_vars
+    }
+
+    fn var_names(&self) -> Vec<&str> {
+        self.var_names.iter().map(|name| name.as_str()).collect()
+    }
 }
+
 impl<'a, T, OF, LMF> Express<'a, T> for OwnedFlatEx<T, OF, LMF>
 where
     T: DataType,
@@ -522,66 +2144,228 @@ where
         Ok(Self::from_flatex(FlatEx::from_str(text)?))
     }
 
-    fn eval(&self, vars: &[T]) -> ExResult<T> {
-        flat_details::eval_flatex(
-            vars,
-            &self.nodes,
-            &self.ops,
-            &self.prio_indices,
-            self.n_unique_vars,
-        )
+    fn eval_named(&self, vars: &[(&str, T)]) -> ExResult<T> {
+        self.eval(&flat_details::vars_by_name(vars, &self.var_names)?)
     }
 
-    fn partial(mut self, var_idx: usize) -> ExResult<Self>
+    fn eval_many(&self, var_rows: &[&[T]]) -> ExResult<Vec<T>> {
+        match (&self.affine, &self.cse) {
+            (Some(affine), _) => var_rows
+                .iter()
+                .enumerate()
+                .map(|(row_idx, vars)| {
+                    flat_details::check_n_vars_of_row(row_idx, vars, self.n_unique_vars)?;
+                    Ok(affine.eval(vars[affine.var_idx].clone()))
+                })
+                .collect(),
+            (None, Some(cse)) => var_rows
+                .iter()
+                .enumerate()
+                .map(|(row_idx, vars)| {
+                    flat_details::check_n_vars_of_row(row_idx, vars, self.n_unique_vars)?;
+                    cse.eval(vars, self.n_unique_vars)
+                })
+                .collect(),
+            (None, None) => flat_details::eval_flatex_many(
+                var_rows,
+                &self.nodes,
+                &self.ops,
+                &self.prio_indices,
+                self.n_unique_vars,
+            ),
+        }
+    }
+
+    fn partial(self, var_idx: usize) -> ExResult<Self>
     where
         T: Float,
         <T as FromStr>::Err: Debug,
     {
+        let deepex = self.deepex()?;
         flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
-
         let ops = FloatOpsFactory::make();
-
-        if self.deepex_buf.is_none() {
-            self.deepex_buf = match self.text {
-                Some(t) => {
-                    let deepex = DeepEx::from_ops(t.as_str(), &OF::make())?;
-                    Some(DeepBuf::from_deepex(&deepex))
-                }
-                None => {
-                    return Err(ExError::new(
-                        "Need either text or deep expression. Did you call `reduce_memory`?",
-                    ));
-                }
-            };
-        }
-
-        let deep_buf = self
-            .deepex_buf
-            .expect("This is bug. deepex buffer cannot be None here.");
-        let deepex = deep_buf.to_deepex(&ops)?;
         let d_i = partial_derivatives::partial_deepex(var_idx, deepex, &ops)?;
         Ok(Self::from_flatex(FlatEx::flatten(d_i)))
     }
-    fn unparse(&self) -> ExResult<String> {
-        match &self.text {
-            Some(t) => Ok(t.clone()),
-            None => match &self.deepex_buf {
-                Some(deepex) => Ok(deepex.unparsed.clone()),
-                None => Err(ExError {
-                    msg: "unparse impossible, since deep expression optimized away".to_string(),
-                }),
-            },
+    fn partial_n(self, var_idx: usize, order: u32) -> ExResult<Self>
+    where
+        T: Float,
+        <T as FromStr>::Err: Debug,
+    {
+        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
+        if order == 0 {
+            return Ok(self);
         }
+        let ops = FloatOpsFactory::make();
+        let d = partial_derivatives::partial_deepex_n(var_idx, order, self.deepex()?, &ops)?;
+        Ok(Self::from_flatex(FlatEx::flatten(d)))
+    }
+    fn gradient(self) -> ExResult<Vec<Self>>
+    where
+        T: Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let deepex = self.deepex()?;
+        (0..self.n_vars())
+            .map(|var_idx| {
+                let d_i = partial_derivatives::partial_deepex(var_idx, deepex.clone(), &ops)?;
+                Ok(Self::from_flatex(FlatEx::flatten(d_i)))
+            })
+            .collect()
+    }
+    fn hessian(self) -> ExResult<Vec<Vec<Self>>>
+    where
+        T: Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let hessian = partial_derivatives::hessian_deepex(self.deepex()?, &ops)?;
+        Ok(hessian
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|d_ij| Self::from_flatex(FlatEx::flatten(d_ij)))
+                    .collect()
+            })
+            .collect())
+    }
+    fn directional(self, direction: &[T]) -> ExResult<Self>
+    where
+        T: Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let ops = FloatOpsFactory::make();
+        let d = partial_derivatives::directional_deepex(direction, self.deepex()?, &ops)?;
+        Ok(Self::from_flatex(FlatEx::flatten(d)))
+    }
+    fn partial_eval(self, bindings: &HashMap<&str, T>) -> ExResult<Self>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex = flat_details::partial_eval_deepex(&self.deepex()?, bindings)?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+    fn substitute(self, var_idx: usize, replacement: Self) -> ExResult<Self>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        let deepex =
+            flat_details::substitute_deepex(&self.deepex()?, var_idx, &replacement.deepex()?)?;
+        Ok(Self::from_flatex(FlatEx::flatten(deepex)))
+    }
+    fn as_polynomial(&self, var_idx: usize) -> ExResult<Vec<T>>
+    where
+        T: Float,
+        <T as FromStr>::Err: Debug,
+    {
+        flat_details::check_partial_index(var_idx, self.n_vars(), self.unparse()?.as_str())?;
+        polynomial::polynomial_deepex(var_idx, &self.deepex()?)
+    }
+    fn used_operators(&self) -> ExResult<Vec<String>>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(flat_details::used_operator_ids(&self.deepex()?, &OF::make()))
+    }
+    fn operator_reprs(&self) -> Vec<String> {
+        flat_details::operator_reprs(&self.nodes, &self.ops)
+    }
+    fn occurring_vars(&self) -> Vec<&str> {
+        flat_details::used_var_names(&self.nodes, &self.var_names)
+    }
+    fn unparse_latex(&self) -> ExResult<String>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(self.deepex()?.unparse_latex())
+    }
+    fn to_dot(&self) -> ExResult<String>
+    where
+        <T as FromStr>::Err: Debug,
+    {
+        Ok(self.deepex()?.to_dot())
     }
-
     fn reduce_memory(&mut self) {
-        self.deepex_buf = None;
+        // see the comment in the implementation of FlatEx
+        if self.text.is_none() {
+            self.deepex_buf = None;
+        }
     }
 
-    fn n_vars(&self) -> usize {
-        self.n_unique_vars
+    fn n_binops(&self) -> usize {
+        self.ops.len()
+    }
+
+    fn n_unary_ops(&self) -> usize {
+        flat_details::n_unary_ops(&self.nodes, &self.ops)
+    }
+
+    fn is_const(&self) -> bool {
+        flat_details::is_const(&self.nodes, &self.ops, &OF::make())
+    }
+
+    fn const_value(&self) -> ExResult<T> {
+        flat_details::const_value(
+            &self.nodes,
+            &self.ops,
+            &self.prio_indices,
+            &self.var_names,
+            &OF::make(),
+        )
+    }
+
+    fn depth(&self) -> ExResult<usize>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        match self.depth {
+            Some(depth) => Ok(depth),
+            None => Ok(self.deepex()?.depth()),
+        }
+    }
+}
+impl<'a, T, OF, LMF> ParseExpress<T> for FlatEx<'a, T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    type Owned = OwnedFlatEx<T, OF, LMF>;
+    fn parse_owned(text: &str) -> ExResult<Self::Owned> {
+        <Self::Owned as Express<T>>::from_str(text)
+    }
+}
+
+impl<T, OF, LMF> ParseExpress<T> for OwnedFlatEx<T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    type Owned = Self;
+    fn parse_owned(text: &str) -> ExResult<Self> {
+        <Self as Express<T>>::from_str(text)
+    }
+}
+
+/// Enables parsing with [`str::parse`](str::parse), e.g., `"x^2".parse::<OwnedFlatEx<f64>>()`.
+impl<T, OF, LMF> FromStr for OwnedFlatEx<T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    type Err = ExError;
+    fn from_str(text: &str) -> ExResult<Self> {
+        <Self as Express<T>>::from_str(text)
     }
 }
+
 /// The expression is displayed as a string created by [`unparse`](OwnedFlatEx::unparse).
 impl<T, OF, LMF> Display for OwnedFlatEx<T, OF, LMF>
 where
@@ -598,6 +2382,134 @@ where
     }
 }
 
+/// Implements comparisons and hashing for [`FlatEx`](FlatEx) and [`OwnedFlatEx`](OwnedFlatEx).
+/// Expressions are compared by their flattened nodes and operators and by their variable
+/// names. The string an expression was parsed from is not considered. Numbers are compared
+/// with [`TotalCmp`](crate::TotalCmp). Hence, the order is well-defined also for expressions
+/// with `NaN` constants, which makes expressions usable as keys of ordered maps. The hash is
+/// computed from the same data such that equal expressions have equal hashes, also if one of
+/// them has been [`reduce_memory`](Express::reduce_memory)ed. A `FlatEx` and an `OwnedFlatEx`
+/// of the same expression have the same hash. Operators are compared and hashed by their
+/// representations and not by their functions. Hence, if two custom operator factories reuse
+/// the same representation for different functions, their operators collide.
+macro_rules! impl_total_order {
+    ($($lt:lifetime)?; $type:ident) => {
+        impl<$($lt,)? T, OF, LMF> Ord for $type<$($lt,)? T, OF, LMF>
+        where
+            T: Clone + Debug + TotalCmp,
+            OF: MakeOperators<T>,
+            LMF: MatchLiteral,
+        {
+            fn cmp(&self, other: &Self) -> Ordering {
+                flat_details::cmp_flat(&self.nodes, &self.ops, &other.nodes, &other.ops)
+                    .then_with(|| self.var_names.cmp(&other.var_names))
+            }
+        }
+        impl<$($lt,)? T, OF, LMF> PartialOrd for $type<$($lt,)? T, OF, LMF>
+        where
+            T: Clone + Debug + TotalCmp,
+            OF: MakeOperators<T>,
+            LMF: MatchLiteral,
+        {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl<$($lt,)? T, OF, LMF> PartialEq for $type<$($lt,)? T, OF, LMF>
+        where
+            T: Clone + Debug + TotalCmp,
+            OF: MakeOperators<T>,
+            LMF: MatchLiteral,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+        impl<$($lt,)? T, OF, LMF> Eq for $type<$($lt,)? T, OF, LMF>
+        where
+            T: Clone + Debug + TotalCmp,
+            OF: MakeOperators<T>,
+            LMF: MatchLiteral,
+        {
+        }
+        impl<$($lt,)? T, OF, LMF> Hash for $type<$($lt,)? T, OF, LMF>
+        where
+            T: Clone + Debug + TotalCmp,
+            OF: MakeOperators<T>,
+            LMF: MatchLiteral,
+        {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                flat_details::hash_flat(&self.nodes, &self.ops, state);
+                self.var_names.len().hash(state);
+                for name in self.var_names.iter() {
+                    AsRef::<str>::as_ref(name).hash(state);
+                }
+            }
+        }
+    };
+}
+impl_total_order!('a; FlatEx);
+impl_total_order!(; OwnedFlatEx);
+
+/// Implements the arithmetic operator traits for [`FlatEx`](FlatEx) and
+/// [`OwnedFlatEx`](OwnedFlatEx) based on the operators of the operator factory.
+macro_rules! impl_arithmetic_traits {
+    ($($trait:ident, $method:ident, $repr:literal);*) => {
+        $(
+            impl<'a, T, OF, LMF> $trait for FlatEx<'a, T, OF, LMF>
+            where
+                T: DataType,
+                <T as FromStr>::Err: Debug,
+                OF: MakeOperators<T>,
+                LMF: MatchLiteral,
+            {
+                type Output = ExResult<Self>;
+                fn $method(self, rhs: Self) -> Self::Output {
+                    self.merge_bin(rhs, $repr)
+                }
+            }
+            impl<T, OF, LMF> $trait for OwnedFlatEx<T, OF, LMF>
+            where
+                T: DataType,
+                <T as FromStr>::Err: Debug,
+                OF: MakeOperators<T>,
+                LMF: MatchLiteral,
+            {
+                type Output = ExResult<Self>;
+                fn $method(self, rhs: Self) -> Self::Output {
+                    self.merge_bin(rhs, $repr)
+                }
+            }
+        )*
+    };
+}
+impl_arithmetic_traits!(Add, add, "+"; Sub, sub, "-"; Mul, mul, "*"; Div, div, "/");
+
+impl<'a, T, OF, LMF> Neg for FlatEx<'a, T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    type Output = ExResult<Self>;
+    fn neg(self) -> Self::Output {
+        self.apply_unary("-")
+    }
+}
+impl<T, OF, LMF> Neg for OwnedFlatEx<T, OF, LMF>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    type Output = ExResult<Self>;
+    fn neg(self) -> Self::Output {
+        self.apply_unary("-")
+    }
+}
+
 #[cfg(test)]
 use crate::util::assert_float_eq_f64;
 
@@ -685,6 +2597,75 @@ fn test_flat_compile() -> ExResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_affine() -> ExResult<()> {
+    fn test(text: &str, vars: &[f64], is_affine: bool) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.affine.is_some(), is_affine);
+        let reference = flat_details::eval_flatex(
+            vars,
+            &flatex.nodes,
+            &flatex.ops,
+            &flatex.prio_indices,
+            flatex.n_unique_vars,
+        )?;
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let flatex = OwnedFlatEx::<f64>::from_flatex(flatex);
+        assert_eq!(flatex.affine.is_some(), is_affine);
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test("1.8*c+32", &[21.5], true)?;
+    test("32+c*1.8", &[21.5], true)?;
+    test("x", &[2.3], true)?;
+    test("2*x", &[2.3], true)?;
+    test("x/2", &[2.3], true)?;
+    test("3-x/2", &[2.3], true)?;
+    test("(3-x)/2", &[2.3], true)?;
+    test("2*3*x+4*5", &[2.3], true)?;
+    test("z-4*2", &[2.3], true)?;
+    test("2/(3-x)", &[2.3], false)?;
+    test("3/x", &[2.3], false)?;
+    test("x^2", &[2.3], false)?;
+    test("2^x", &[2.3], false)?;
+    test("x%3", &[2.3], false)?;
+    test("a*x+b", &[2.0, 3.0, 4.0], false)?;
+    test("a*x+1", &[2.0, 3.0], false)?;
+    test("x*x+1", &[2.0], false)?;
+    test("-x+1", &[2.3], false)?;
+    test("sin(x)", &[2.3], false)?;
+    test("2*sin(x)+1", &[2.3], false)?;
+    test("1+2*x-3", &[2.3], false)?;
+
+    // operators are identified by their ids and not by their representations
+    #[derive(Clone, Debug)]
+    struct MaxOpsFactory;
+    impl MakeOperators<f64> for MaxOpsFactory {
+        fn make<'a>() -> Vec<Operator<'a, f64>> {
+            vec![Operator::make_bin(
+                "+",
+                crate::BinOp {
+                    apply: f64::max,
+                    prio: 0,
+                    is_commutative: true,
+                },
+            )]
+        }
+    }
+    let flatex = FlatEx::<f64, MaxOpsFactory>::from_str("x+2")?;
+    assert!(flatex.affine.is_none());
+    assert_float_eq_f64(flatex.eval(&[1.0])?, 2.0);
+
+    let flatex = FlatEx::<f64>::from_str("1.8*c+32")?;
+    assert!(flatex.eval(&[]).is_err());
+    assert!(flatex.eval(&[1.0, 2.0]).is_err());
+    let deri = flatex.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[21.5])?, 1.8);
+    Ok(())
+}
+
 #[test]
 fn test_unparse() -> ExResult<()> {
     fn test(text: &str, text_ref: &str) -> ExResult<()> {
@@ -722,3 +2703,139 @@ fn test_unparse() -> ExResult<()> {
     test(text, text_ref)?;
     Ok(())
 }
+#[test]
+fn test_bind() -> ExResult<()> {
+    fn test(
+        text: &str,
+        var_idx: usize,
+        value: f64,
+        vars: &[f64],
+        n_nodes: usize,
+        unparsed: &str,
+    ) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let n_vars = flatex.n_vars();
+        let mut all_vars = vars.to_vec();
+        all_vars.insert(var_idx, value);
+        let reference = flatex.eval(&all_vars)?;
+        let owned = OwnedFlatEx::from_flatex(flatex.clone());
+        let bound = flatex.bind(var_idx, value)?;
+        assert_eq!(bound.nodes.len(), n_nodes);
+        assert_eq!(bound.n_vars(), n_vars - 1);
+        assert_eq!(bound.unparse()?, unparsed);
+        assert_float_eq_f64(bound.eval(vars)?, reference);
+        let bound = owned.bind(var_idx, value)?;
+        assert_eq!(bound.nodes.len(), n_nodes);
+        assert_eq!(bound.n_vars(), n_vars - 1);
+        assert_eq!(bound.unparse()?, unparsed);
+        assert_float_eq_f64(bound.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test("2*y + x", 1, 3.0, &[1.5], 2, "6.0+{x}")?;
+    test("2*y + x", 0, 3.0, &[0.5], 3, "2.0*{y}+3.0")?;
+    test("a*x+b*z", 2, 2.0, &[0.5, 3.0, 4.0], 4, "{a}*2.0+{b}*{z}")?;
+    test("sin(x)", 0, 0.0, &[], 1, "0.0")?;
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert!(flatex.bind(2, 1.0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_bind_many() -> ExResult<()> {
+    fn test(
+        text: &str,
+        fixed: &[(usize, f64)],
+        vars: &[f64],
+        var_names: &[&str],
+        unparsed: &str,
+    ) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let mut all_vars = vars.to_vec();
+        let mut sorted = fixed.to_vec();
+        sorted.sort_by_key(|(var_idx, _)| *var_idx);
+        for (var_idx, value) in sorted {
+            all_vars.insert(var_idx, value);
+        }
+        let reference = flatex.eval(&all_vars)?;
+        let owned = OwnedFlatEx::from_flatex(flatex.clone());
+        let bound = flatex.bind_many(fixed)?;
+        assert_eq!(bound.var_names(), var_names);
+        assert_eq!(bound.unparse()?, unparsed);
+        assert_float_eq_f64(bound.eval(vars)?, reference);
+        let bound = owned.bind_many(fixed)?;
+        assert_eq!(bound.var_names(), var_names);
+        assert_eq!(bound.unparse()?, unparsed);
+        assert_float_eq_f64(bound.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test(
+        "x + t*y",
+        &[(0, 2.0)],
+        &[1.0, 3.0],
+        &["x", "y"],
+        "{x}+2.0*{y}",
+    )?;
+    test(
+        "a*x+b*z",
+        &[(3, 2.0), (0, 3.0)],
+        &[0.5, 4.0],
+        &["b", "x"],
+        "3.0*{x}+{b}*2.0",
+    )?;
+    test("sin(x)*y", &[(0, 0.0), (1, 1.0)], &[], &[], "0.0")?;
+    test("x+y", &[], &[1.0, 2.0], &["x", "y"], "{x}+{y}")?;
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert!(flatex.clone().bind_many(&[(2, 1.0)]).is_err());
+    assert!(flatex.bind_many(&[(0, 1.0), (0, 2.0)]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_minify() -> ExResult<()> {
+    fn test(text: &str, vars: &[f64], minified_ref: &str) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let (minified, names) = flatex.minify()?;
+        assert_eq!(minified, minified_ref);
+        let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, flatex.var_names());
+        let recreated = FlatEx::<f64>::parse_minified(&minified, &names)?;
+        assert_eq!(recreated.var_names(), flatex.var_names());
+        assert_float_eq_f64(recreated.eval(vars)?, flatex.eval(vars)?);
+        let owned = OwnedFlatEx::<f64>::from_str(text)?;
+        let (minified_owned, _) = owned.minify()?;
+        assert_eq!(minified_owned, minified);
+        let recreated = OwnedFlatEx::<f64>::parse_minified(&minified, &names)?;
+        assert_float_eq_f64(recreated.eval(vars)?, flatex.eval(vars)?);
+        println!("...ok.");
+        Ok(())
+    }
+    test("x + y", &[1.0, 2.0], "{0}+{1}")?;
+    // placeholders can be longer than short names
+    test("x*yy", &[1.0, 2.0], "{0}*{1}")?;
+    test("2.0 * long_name - 0.25", &[3.0], "2*{0}-.25")?;
+    test(
+        "sin(alpha)^2 + cos(alpha)^2",
+        &[0.3],
+        "sin({0})^2+cos({0})^2",
+    )?;
+    test("-(x*1.5)/exp(y)", &[0.5, 2.0], "-({0}*1.5)/exp({1})")?;
+    test(
+        "x*0.02*sin(-(3.0*(2.0*sin(x-1.0/(sin(y*5.0)+(5.0-1.0/z))))))",
+        &[0.5, 1.5, 2.5],
+        "{0}*.02*sin(-(3*(2*sin({0}-1/(sin({1}*5)+(5-1/{2}))))))",
+    )?;
+    // names whose curly form is shorter than their placeholders are kept
+    test(
+        "a+b+c+d+e+f+g+h+i+j+k+l",
+        &[1.0; 12],
+        "{0}+{1}+{2}+{3}+{4}+{5}+{6}+{7}+{8}+{9}+{k}+{l}",
+    )?;
+    assert!(FlatEx::<f64>::parse_minified("{0}+{1}", &["x"]).is_err());
+    assert!(FlatEx::<f64>::parse_minified("{0}+{2}", &["x", "y"]).is_err());
+    Ok(())
+}
@@ -1,36 +1,105 @@
-use std::fmt::Debug;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 
+use num::Float;
 use smallvec::{smallvec, SmallVec};
 
 use crate::{
-    definitions::N_NODES_ON_STACK,
-    operators::{BinOp, UnaryOp},
+    data_type::{DataType, TotalCmp},
+    definitions::{N_NODES_ON_STACK, N_UNARYOPS_OF_DEEPEX_ON_STACK, N_VARS_ON_STACK},
+    format_exerr,
+    operators::{BinOp, Operator, TernaryOp, UnaryOp},
     ExError, ExResult,
 };
 
-use super::deep::{DeepEx, DeepNode, ExprIdxVec};
+use super::deep::{
+    BinOpsWithReprs, DeepEx, DeepNode, ExprIdxVec, NodeReprs, TernaryOpWithArgs, UnaryOpWithReprs,
+};
 
 pub type FlatNodeVec<T> = SmallVec<[FlatNode<T>; N_NODES_ON_STACK]>;
 pub type FlatOpVec<T> = SmallVec<[FlatOp<T>; N_NODES_ON_STACK]>;
+type BinFn<T> = fn(T, T) -> T;
 
 /// A `FlatOp` contains besides a binary operation an optional unary operation that
 /// will be executed after the binary operation in case of its existence.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct FlatOp<T: Clone> {
-    pub unary_op: UnaryOp<T>,
+    pub unary_op: UnaryOpWithReprs<'static, T>,
     pub bin_op: BinOp<T>,
+    /// Representation of the binary operation in the operator factory, see [`factory_repr`].
+    pub repr: &'static str,
+    /// True if the binary operation has the id `add`, used for compensated summation.
+    pub is_addition: bool,
+}
+
+/// A ternary operator whose arguments are flat evaluation plans of their own, such that
+/// only the selected argument of a selection is evaluated.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct FlatTernary<T: Clone> {
+    pub op: TernaryOp<T>,
+    /// Representation of the operator in the operator factory, see [`factory_repr`].
+    pub repr: &'static str,
+    pub args: [FlatPlan<T>; 3],
+}
+
+impl<T> FlatTernary<T>
+where
+    T: Clone + Debug,
+{
+    fn eval(&self, vars: &[T]) -> T {
+        let [c, a, b] = &self.args;
+        match self.op {
+            TernaryOp::Select(is_first) => {
+                if is_first(&c.eval_unchecked(vars)) {
+                    a.eval_unchecked(vars)
+                } else {
+                    b.eval_unchecked(vars)
+                }
+            }
+            TernaryOp::Apply(apply) => apply(
+                c.eval_unchecked(vars),
+                a.eval_unchecked(vars),
+                b.eval_unchecked(vars),
+            ),
+        }
+    }
+
+    /// Evaluates the operator where the arguments are evaluated by `eval_arg`.
+    fn eval_with<F>(&self, mut eval_arg: F) -> ExResult<T>
+    where
+        F: FnMut(&FlatPlan<T>) -> ExResult<T>,
+    {
+        let [c, a, b] = &self.args;
+        Ok(match self.op {
+            TernaryOp::Select(is_first) => {
+                if is_first(&eval_arg(c)?) {
+                    eval_arg(a)?
+                } else {
+                    eval_arg(b)?
+                }
+            }
+            TernaryOp::Apply(apply) => apply(eval_arg(c)?, eval_arg(a)?, eval_arg(b)?),
+        })
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
-pub enum FlatNodeKind<T> {
+pub enum FlatNodeKind<T: Clone> {
     Num(T),
     Var(usize),
+    /// Boxing this due to <https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant>
+    Ternary(Box<FlatTernary<T>>),
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
-pub struct FlatNode<T> {
+pub struct FlatNode<T: Clone> {
     pub kind: FlatNodeKind<T>,
-    pub unary_op: UnaryOp<T>,
+    pub unary_op: UnaryOpWithReprs<'static, T>,
 }
 
 impl<T> FlatNode<T>
@@ -40,8 +109,414 @@ where
     pub fn from_kind(kind: FlatNodeKind<T>) -> FlatNode<T> {
         FlatNode {
             kind,
-            unary_op: UnaryOp::new(),
+            unary_op: UnaryOpWithReprs::new(),
+        }
+    }
+}
+
+/// Representation of the operator of the operator factory with representation `repr`.
+/// In contrast to `repr`, the result lives as long as the operator factory. Operators that
+/// are not part of the operator factory are represented by `?`.
+pub fn factory_repr<T: Clone>(repr: &str, operators: &[Operator<'static, T>]) -> &'static str {
+    operators
+        .iter()
+        .find(|op| op.repr() == repr)
+        .map_or("?", |op| op.repr())
+}
+
+/// True if the binary operator of the operator factory with representation `repr` has the
+/// id `add`, see [`FlatOp::is_addition`].
+pub fn is_addition<T: Clone>(repr: &str, operators: &[Operator<'static, T>]) -> bool {
+    operators
+        .iter()
+        .any(|op| op.repr() == repr && op.has_bin() && op.id() == "add")
+}
+
+/// Unary operator of the functions and representations `funcs` ordered outermost-first
+/// with the representations of the operator factory, see [`factory_repr`].
+pub fn factory_unary_op<'a, T, I>(
+    funcs: I,
+    operators: &[Operator<'static, T>],
+) -> UnaryOpWithReprs<'static, T>
+where
+    T: Clone,
+    I: Iterator<Item = (fn(T) -> T, &'a str)>,
+{
+    let funcs = funcs.collect::<SmallVec<[_; N_UNARYOPS_OF_DEEPEX_ON_STACK]>>();
+    UnaryOpWithReprs {
+        reprs: funcs
+            .iter()
+            .map(|(_, repr)| factory_repr(repr, operators))
+            .collect(),
+        op: UnaryOp::from_iter(funcs.iter().map(|(f, _)| *f)),
+    }
+}
+
+/// Copy of `unary_op` with the representations of the operator factory, see
+/// [`factory_repr`].
+pub fn to_factory_unary_op<T: Clone>(
+    unary_op: &UnaryOpWithReprs<T>,
+    operators: &[Operator<'static, T>],
+) -> UnaryOpWithReprs<'static, T> {
+    UnaryOpWithReprs {
+        reprs: unary_op
+            .reprs
+            .iter()
+            .map(|repr| factory_repr(repr, operators))
+            .collect(),
+        op: unary_op.op.clone(),
+    }
+}
+
+/// True if all nodes are numbers without unary operators, no unary operators are applied to
+/// results of binary operators, and the operators are deterministic. Hence, no variable
+/// occurs in the expression. Variables that have vanished, e.g., by differentiation, are
+/// not taken into account.
+pub fn is_const<T: Clone + Debug>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    operators: &[Operator<T>],
+) -> bool {
+    nodes
+        .iter()
+        .all(|node| matches!(node.kind, FlatNodeKind::Num(_)) && node.unary_op.op.is_empty())
+        && ops.iter().all(|op| op.unary_op.op.is_empty())
+        && !operators.iter().any(|op| op.is_stochastic())
+}
+
+fn collect_var_indices<T: Clone>(nodes: &FlatNodeVec<T>, var_indices: &mut Vec<usize>) {
+    for node in nodes {
+        match &node.kind {
+            FlatNodeKind::Var(idx) => var_indices.push(*idx),
+            FlatNodeKind::Ternary(t) => {
+                for arg in &t.args {
+                    collect_var_indices(&arg.nodes, var_indices);
+                }
+            }
+            FlatNodeKind::Num(_) => (),
+        }
+    }
+}
+
+/// Names of the variables that occur in the nodes in alphabetical order. Variables that
+/// have vanished from the nodes, e.g., by differentiation, are not contained.
+pub fn used_var_names<'b, T: Clone, S: AsRef<str>>(
+    nodes: &FlatNodeVec<T>,
+    var_names: &'b [S],
+) -> Vec<&'b str> {
+    let mut var_indices = vec![];
+    collect_var_indices(nodes, &mut var_indices);
+    // variable names are sorted, hence, sorted indices result in sorted names
+    var_indices.sort_unstable();
+    var_indices.dedup();
+    var_indices
+        .iter()
+        .map(|idx| var_names[*idx].as_ref())
+        .collect()
+}
+
+/// Evaluates a flat expression that is constant, see [`is_const`].
+pub fn const_value<T: Clone + Debug, S: AsRef<str>>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    var_names: &[S],
+    operators: &[Operator<T>],
+) -> ExResult<T> {
+    if is_const(nodes, ops, operators) {
+        eval_flatex(&[], nodes, ops, prio_indices, 0)
+    } else {
+        Err(format_exerr!(
+            "expression is not constant, the variables {:?} occur",
+            used_var_names(nodes, var_names)
+        ))
+    }
+}
+
+/// Executes calculations that can trivially be executed, e.g., two numbers that need to be
+/// multiplied anyway.
+pub fn compile<T: Clone + Debug>(
+    nodes: &mut FlatNodeVec<T>,
+    ops: &mut FlatOpVec<T>,
+    prio_indices: &mut ExprIdxVec,
+) {
+    let mut num_inds = prio_indices.clone();
+    let mut used_prio_indices = ExprIdxVec::new();
+
+    let mut already_declined: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+
+    for node in nodes.iter_mut() {
+        if let FlatNodeKind::Ternary(t) = &mut node.kind {
+            for arg in t.args.iter_mut() {
+                compile(&mut arg.nodes, &mut arg.ops, &mut arg.prio_indices);
+            }
+            if let Some(mut folded) = fold_ternary(t) {
+                folded.unary_op.append_front(&node.unary_op);
+                *node = folded;
+            }
+        }
+    }
+    for node in nodes.iter_mut() {
+        if let FlatNodeKind::Num(num) = &node.kind {
+            *node = FlatNode::from_kind(FlatNodeKind::Num(node.unary_op.op.apply(num.clone())));
+        }
+    }
+    for (i, &bin_op_idx) in prio_indices.iter().enumerate() {
+        let num_idx = num_inds[i];
+        let node_1 = &nodes[num_idx];
+        let node_2 = &nodes[num_idx + 1];
+        if let (FlatNodeKind::Num(num_1), FlatNodeKind::Num(num_2)) =
+            (node_1.kind.clone(), node_2.kind.clone())
+        {
+            if !(already_declined[num_idx] || already_declined[num_idx + 1]) {
+                let op_result = ops[bin_op_idx]
+                    .unary_op
+                    .op
+                    .apply((ops[bin_op_idx].bin_op.apply)(num_1, num_2));
+                nodes[num_idx] = FlatNode::from_kind(FlatNodeKind::Num(op_result));
+                nodes.remove(num_idx + 1);
+                already_declined.remove(num_idx + 1);
+                // reduce indices after removed position
+                for num_idx_after in num_inds.iter_mut() {
+                    if *num_idx_after > num_idx {
+                        *num_idx_after -= 1;
+                    }
+                }
+                used_prio_indices.push(bin_op_idx);
+            } else {
+                already_declined[num_idx] = true;
+                already_declined[num_idx + 1] = true;
+            }
+        } else {
+            already_declined[num_idx] = true;
+            already_declined[num_idx + 1] = true;
+        }
+    }
+
+    *ops = ops
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !used_prio_indices.contains(i))
+        .map(|(_, op)| op.clone())
+        .collect();
+
+    *prio_indices = prioritized_indices_flat(ops, nodes);
+}
+
+/// Node that replaces a ternary operator with compiled arguments if the result does not
+/// depend on variables, i.e., the selected argument of a selection with a constant condition
+/// if the argument consists of a single node or the result of a function applied to three
+/// numbers.
+fn fold_ternary<T: Clone + Debug>(ternary: &FlatTernary<T>) -> Option<FlatNode<T>> {
+    let single_node = |plan: &FlatPlan<T>| match &plan.nodes[..] {
+        [node] => Some(node.clone()),
+        _ => None,
+    };
+    let num = |plan: &FlatPlan<T>| match single_node(plan)? {
+        FlatNode {
+            kind: FlatNodeKind::Num(n),
+            unary_op,
+        } if unary_op.op.is_empty() => Some(n),
+        _ => None,
+    };
+    let [c, a, b] = &ternary.args;
+    match ternary.op {
+        TernaryOp::Select(is_first) => single_node(if is_first(&num(c)?) { a } else { b }),
+        TernaryOp::Apply(apply) => Some(FlatNode::from_kind(FlatNodeKind::Num(apply(
+            num(c)?,
+            num(a)?,
+            num(b)?,
+        )))),
+    }
+}
+
+/// Replaces the implementations of binary operators whose right operand is a number by the
+/// specializations of the operator factory, e.g., `powi` for `x^3` instead of `powf`. The
+/// specializations are looked up by the representations of the operators. Hence, applying
+/// this function again, e.g., after further compilation, does not change specialized
+/// operators. The arguments of ternary operators are specialized, too.
+pub fn specialize_bin_ops<T: Clone + Debug>(
+    nodes: &mut FlatNodeVec<T>,
+    ops: &mut FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    specialize: fn(&str, &T) -> Option<BinFn<T>>,
+) {
+    for node in nodes.iter_mut() {
+        if let FlatNodeKind::Ternary(t) = &mut node.kind {
+            for arg in t.args.iter_mut() {
+                specialize_bin_ops(&mut arg.nodes, &mut arg.ops, &arg.prio_indices, specialize);
+            }
+        }
+    }
+    let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    let mut is_result: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+        let right = &nodes[idx_2];
+        let op = &mut ops[bin_op_idx];
+        let is_candidate = !is_result[idx_2] && right.unary_op.op.is_empty();
+        if let (FlatNodeKind::Num(num), true) = (&right.kind, is_candidate) {
+            if let Some(apply) = specialize(op.repr, num) {
+                op.bin_op.apply = apply;
+            }
+        }
+        is_result[idx_1] = true;
+        ignore[idx_2] = true;
+    }
+}
+
+/// Number of unary operations attached to the nodes and binary operations including the
+/// arguments of ternary operators.
+pub fn n_unary_ops<T: Clone>(nodes: &FlatNodeVec<T>, ops: &FlatOpVec<T>) -> usize {
+    nodes
+        .iter()
+        .map(|node| {
+            let n_of_args = match &node.kind {
+                FlatNodeKind::Ternary(t) => t
+                    .args
+                    .iter()
+                    .map(|arg| n_unary_ops(&arg.nodes, &arg.ops))
+                    .sum(),
+                _ => 0,
+            };
+            node.unary_op.op.len() + n_of_args
+        })
+        .sum::<usize>()
+        + ops.iter().map(|op| op.unary_op.op.len()).sum::<usize>()
+}
+
+/// One binary operation of an affine expression where one operand is a number
+/// and the other operand is the intermediate result.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct AffineStep<T: Clone> {
+    pub bin_op: BinOp<T>,
+    pub num: T,
+    pub is_result_left: bool,
+}
+
+impl<T> AffineStep<T>
+where
+    T: Clone,
+{
+    fn new(op: &FlatOp<T>, num: &T, is_result_left: bool) -> AffineStep<T> {
+        AffineStep {
+            bin_op: op.bin_op.clone(),
+            num: num.clone(),
+            is_result_left,
+        }
+    }
+
+    fn apply(&self, x: T) -> T {
+        if self.is_result_left {
+            (self.bin_op.apply)(x, self.num.clone())
+        } else {
+            (self.bin_op.apply)(self.num.clone(), x)
+        }
+    }
+}
+
+/// Expressions with a single variable and at most two binary operations with numbers,
+/// e.g., `1.8*c+32`, are evaluated without the generic evaluation loop.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Affine<T: Clone> {
+    pub var_idx: usize,
+    pub first: Option<AffineStep<T>>,
+    pub second: Option<AffineStep<T>>,
+}
+
+impl<T> Affine<T>
+where
+    T: Clone,
+{
+    pub fn eval(&self, x: T) -> T {
+        let x = match &self.first {
+            Some(step) => step.apply(x),
+            None => x,
+        };
+        match &self.second {
+            Some(step) => step.apply(x),
+            None => x,
+        }
+    }
+}
+
+/// Detects flat expressions that consist of one variable and at most two numbers connected by
+/// binary operators without any unary operators. Only the operators with the ids `add`,
+/// `sub`, and `mul` of the operator factory are accepted, and `div` if the intermediate result
+/// is divided by the number. Hence, the variable is never a divisor or an exponent.
+pub fn find_affine<T: Clone + Debug>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    operators: &[Operator<T>],
+) -> Option<Affine<T>> {
+    if nodes
+        .iter()
+        .any(|node| !node.unary_op.op.is_empty() || matches!(node.kind, FlatNodeKind::Ternary(_)))
+        || ops.iter().any(|op| !op.unary_op.op.is_empty())
+    {
+        return None;
+    }
+    let mut var_positions = nodes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, node)| match node.kind {
+            FlatNodeKind::Var(var_idx) => Some((i, var_idx)),
+            _ => None,
+        });
+    let (var_pos, var_idx) = var_positions.next()?;
+    if var_positions.next().is_some() {
+        return None;
+    }
+    let num = |i: usize| match &nodes[i].kind {
+        FlatNodeKind::Num(n) => Some(n),
+        _ => None,
+    };
+    let step = |op: &FlatOp<T>, num: &T, is_result_left: bool| {
+        let id = operators
+            .iter()
+            .find(|factory_op| factory_op.repr() == op.repr && factory_op.has_bin())?
+            .id();
+        match id {
+            "add" | "sub" | "mul" => Some(AffineStep::new(op, num, is_result_left)),
+            "div" if is_result_left => Some(AffineStep::new(op, num, is_result_left)),
+            _ => None,
+        }
+    };
+    // the binary operator with index i is applied to the nodes i and i+1
+    let first_step = |first: usize| {
+        if var_pos == first {
+            step(&ops[first], num(first + 1)?, true)
+        } else if var_pos == first + 1 {
+            step(&ops[first], num(first)?, false)
+        } else {
+            None
         }
+    };
+    match (nodes.len(), &prio_indices[..]) {
+        (1, []) => Some(Affine {
+            var_idx,
+            first: None,
+            second: None,
+        }),
+        (2, [first]) => Some(Affine {
+            var_idx,
+            first: Some(first_step(*first)?),
+            second: None,
+        }),
+        (3, [first, second]) => {
+            let second_step = if *second == first + 1 {
+                step(&ops[*second], num(2)?, true)
+            } else {
+                step(&ops[*second], num(0)?, false)
+            };
+            Some(Affine {
+                var_idx,
+                first: Some(first_step(*first)?),
+                second: Some(second_step?),
+            })
+        }
+        _ => None,
     }
 }
 
@@ -52,15 +527,377 @@ pub fn check_partial_index(var_idx: usize, n_vars: usize, unparsed: &str) -> ExR
                 "index {} is invalid since we have only {} vars in {}",
                 var_idx, n_vars, unparsed
             ),
+            position: None,
         })
     } else {
         Ok(())
     }
 }
 
+/// Maps the operator representations used in `deepex` to the ids of the operators in `ops`.
+/// Representations that are not found in `ops` are used as ids.
+pub fn used_operator_ids<T: Clone + Debug>(deepex: &DeepEx<T>, ops: &[Operator<T>]) -> Vec<String> {
+    let mut ids = deepex
+        .operator_reprs()
+        .iter()
+        .map(|repr| match ops.iter().find(|op| op.repr() == *repr) {
+            Some(op) => op.id().to_string(),
+            None => repr.to_string(),
+        })
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Orders the values of `named_vars` like the sorted variable names `var_names` of an expression.
+pub fn vars_by_name<T: Clone, S: AsRef<str>>(
+    named_vars: &[(&str, T)],
+    var_names: &[S],
+) -> ExResult<SmallVec<[T; N_VARS_ON_STACK]>> {
+    vars_by_name_or_default::<T, S, S>(named_vars, &[], var_names)
+}
+
+/// Orders the values of `named_vars` like the sorted variable names `var_names` of an
+/// expression. Variables without a value in `named_vars` are taken from `defaults`.
+pub fn vars_by_name_or_default<T: Clone, D: AsRef<str>, S: AsRef<str>>(
+    named_vars: &[(&str, T)],
+    defaults: &[(D, T)],
+    var_names: &[S],
+) -> ExResult<SmallVec<[T; N_VARS_ON_STACK]>> {
+    let expected = || var_names.iter().map(|n| n.as_ref()).collect::<Vec<_>>();
+    if let Some((name, _)) = named_vars
+        .iter()
+        .find(|(name, _)| !var_names.iter().any(|n| n.as_ref() == *name))
+    {
+        return Err(format_exerr!(
+            "unknown variable '{}', expected one of {:?}",
+            name,
+            expected()
+        ));
+    }
+    var_names
+        .iter()
+        .map(|var_name| {
+            named_vars
+                .iter()
+                .find(|(name, _)| *name == var_name.as_ref())
+                .map(|(_, value)| value)
+                .or_else(|| {
+                    defaults
+                        .iter()
+                        .find(|(name, _)| name.as_ref() == var_name.as_ref())
+                        .map(|(_, value)| value)
+                })
+                .cloned()
+                .ok_or_else(|| {
+                    format_exerr!(
+                        "missing value of variable '{}', expected values of {:?}",
+                        var_name.as_ref(),
+                        expected()
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Adds the `new_defaults` of variables in `var_names` to `defaults` or overrides existing
+/// ones. Defaults of names that are not variables are ignored.
+pub fn merge_defaults<T: Clone, S: AsRef<str>>(
+    defaults: &mut Vec<(String, T)>,
+    new_defaults: &[(&str, T)],
+    var_names: &[S],
+) -> ExResult<()> {
+    for (i, (name, value)) in new_defaults.iter().enumerate() {
+        if new_defaults[..i].iter().any(|(other, _)| other == name) {
+            return Err(format_exerr!("default of variable '{}' given twice", name));
+        }
+        if var_names.iter().any(|n| n.as_ref() == *name) {
+            match defaults.iter_mut().find(|(n, _)| n.as_str() == *name) {
+                Some(default) => default.1 = value.clone(),
+                None => defaults.push((name.to_string(), value.clone())),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the variables of `deepex` that are contained in `bindings` by their values.
+pub fn partial_eval_deepex<'a, T: Clone + Debug>(
+    deepex: &DeepEx<'a, T>,
+    bindings: &HashMap<&str, T>,
+) -> ExResult<DeepEx<'a, T>> {
+    if let Some(name) = bindings
+        .keys()
+        .find(|name| !deepex.var_names().contains(name))
+    {
+        return Err(format_exerr!(
+            "cannot bind '{}' since it is not a variable of {}",
+            name,
+            deepex
+        ));
+    }
+    deepex.bind_vars(&|var_name| bindings.get(var_name).cloned())
+}
+
+fn find_operator<'a, 'b, T: Clone>(
+    repr: &str,
+    ops: &'b [Operator<'a, T>],
+) -> ExResult<&'b Operator<'a, T>> {
+    ops.iter()
+        .find(|op| op.repr() == repr)
+        .ok_or_else(|| format_exerr!("did not find operator {}", repr))
+}
+
+/// Finds the binary operator with representation `repr` to combine deep expressions.
+pub fn find_bin_op_with_reprs<'a, T: Clone + Debug>(
+    repr: &str,
+    ops: &[Operator<'a, T>],
+) -> ExResult<BinOpsWithReprs<'a, T>> {
+    let op = find_operator(repr, ops)?;
+    Ok(BinOpsWithReprs {
+        reprs: smallvec![op.repr()],
+        ops: smallvec![op.bin()?],
+        is_function_style: smallvec![op.is_function_style()],
+    })
+}
+
+/// Finds the unary operator with representation `repr` to apply to deep expressions.
+pub fn find_unary_op_with_reprs<'a, T: Clone + Debug>(
+    repr: &str,
+    ops: &[Operator<'a, T>],
+) -> ExResult<UnaryOpWithReprs<'a, T>> {
+    let op = find_operator(repr, ops)?;
+    Ok(UnaryOpWithReprs {
+        reprs: smallvec![op.repr()],
+        op: UnaryOp::from_vec(smallvec![op.unary()?]),
+    })
+}
+
+/// Replaces the variable with index `var_idx` of `deepex` by `value` and folds constants.
+pub fn bind_deepex<'a, T: Clone + Debug>(
+    deepex: &DeepEx<'a, T>,
+    var_idx: usize,
+    value: T,
+) -> ExResult<DeepEx<'a, T>> {
+    bind_many_deepex(deepex, &[(var_idx, value)])
+}
+
+/// Replaces the variables with the indices of `fixed` of `deepex` by their values and folds
+/// constants.
+pub fn bind_many_deepex<'a, T: Clone + Debug>(
+    deepex: &DeepEx<'a, T>,
+    fixed: &[(usize, T)],
+) -> ExResult<DeepEx<'a, T>> {
+    for (i, (var_idx, _)) in fixed.iter().enumerate() {
+        check_partial_index(*var_idx, deepex.n_vars(), deepex.unparse_raw().as_str())?;
+        if fixed[..i].iter().any(|(other, _)| other == var_idx) {
+            return Err(format_exerr!("variable index {} is bound twice", var_idx));
+        }
+    }
+    let var_names = deepex.var_names();
+    let mut bound = deepex.bind_vars(&|name| {
+        fixed
+            .iter()
+            .find(|(var_idx, _)| var_names[*var_idx] == name)
+            .map(|(_, value)| value.clone())
+    })?;
+    bound.compile();
+    Ok(bound)
+}
+
+/// Renames the variables of `deepex` by the pairs `(old_name, new_name)` of `renames`
+/// simultaneously, such that, e.g., two variables can be swapped.
+pub fn rename_deepex<'a, T: Clone + Debug>(
+    deepex: &DeepEx<'a, T>,
+    renames: &[(&str, &'a str)],
+) -> ExResult<DeepEx<'a, T>> {
+    for (idx, (old_name, _)) in renames.iter().enumerate() {
+        if !deepex.var_names().contains(old_name) {
+            return Err(format_exerr!(
+                "variable '{}' to be renamed not found in '{}'",
+                old_name,
+                deepex.unparse_raw()
+            ));
+        }
+        if renames[..idx].iter().any(|(other, _)| other == old_name) {
+            return Err(format_exerr!("variable '{}' is renamed twice", old_name));
+        }
+    }
+    let new_name = |name: &str| {
+        renames
+            .iter()
+            .find(|(old_name, _)| *old_name == name)
+            .map(|(_, new_name)| *new_name)
+    };
+    let mut new_names = deepex
+        .var_names()
+        .iter()
+        .map(|name| new_name(name).unwrap_or(name))
+        .collect::<SmallVec<[&str; N_VARS_ON_STACK]>>();
+    new_names.sort_unstable();
+    if let Some(w) = new_names.windows(2).find(|w| w[0] == w[1]) {
+        return Err(format_exerr!(
+            "renaming results in the duplicate variable '{}'",
+            w[0]
+        ));
+    }
+    deepex.rename_vars(&new_name)
+}
+
+pub fn substitute_deepex<'a, T: Clone + Debug>(
+    deepex: &DeepEx<'a, T>,
+    var_idx: usize,
+    replacement: &DeepEx<'a, T>,
+) -> ExResult<DeepEx<'a, T>> {
+    check_partial_index(var_idx, deepex.n_vars(), deepex.unparse_raw().as_str())?;
+    let mut substituted = deepex.substitute_var(deepex.var_names()[var_idx], replacement)?;
+    substituted.compile();
+    Ok(substituted)
+}
+
+fn cmp_slices<U, F>(slice_1: &[U], slice_2: &[U], cmp: F) -> Ordering
+where
+    F: Fn(&U, &U) -> Ordering,
+{
+    slice_1
+        .iter()
+        .zip(slice_2)
+        .map(|(elt_1, elt_2)| cmp(elt_1, elt_2))
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or_else(|| slice_1.len().cmp(&slice_2.len()))
+}
+
+/// Compares the nodes and operators of two flat expressions. Numbers are compared by their
+/// total order and operators by their representations, see [`factory_repr`]. Hence, two
+/// different functions with the same representation are considered equal, e.g., operators
+/// of two custom operator factories that reuse the same representation or functions that
+/// are not part of the operator factory and are represented by `?`.
+pub fn cmp_flat<T: Clone + TotalCmp>(
+    nodes_1: &[FlatNode<T>],
+    ops_1: &[FlatOp<T>],
+    nodes_2: &[FlatNode<T>],
+    ops_2: &[FlatOp<T>],
+) -> Ordering {
+    let rank = |kind: &FlatNodeKind<T>| match kind {
+        FlatNodeKind::Num(_) => 0,
+        FlatNodeKind::Var(_) => 1,
+        FlatNodeKind::Ternary(_) => 2,
+    };
+    let cmp_node = |node_1: &FlatNode<T>, node_2: &FlatNode<T>| {
+        let kind_ord = match (&node_1.kind, &node_2.kind) {
+            (FlatNodeKind::Num(x_1), FlatNodeKind::Num(x_2)) => x_1.total_cmp(x_2),
+            (FlatNodeKind::Var(idx_1), FlatNodeKind::Var(idx_2)) => idx_1.cmp(idx_2),
+            (FlatNodeKind::Ternary(t_1), FlatNodeKind::Ternary(t_2)) => {
+                t_1.repr.cmp(t_2.repr).then_with(|| {
+                    cmp_slices(&t_1.args, &t_2.args, |arg_1, arg_2| {
+                        cmp_flat(&arg_1.nodes, &arg_1.ops, &arg_2.nodes, &arg_2.ops)
+                    })
+                })
+            }
+            (kind_1, kind_2) => rank(kind_1).cmp(&rank(kind_2)),
+        };
+        kind_ord.then_with(|| node_1.unary_op.reprs.cmp(&node_2.unary_op.reprs))
+    };
+    let cmp_op = |op_1: &FlatOp<T>, op_2: &FlatOp<T>| {
+        let key = |op: &FlatOp<T>| (op.repr, op.bin_op.prio, op.bin_op.is_commutative);
+        key(op_1)
+            .cmp(&key(op_2))
+            .then_with(|| op_1.unary_op.reprs.cmp(&op_2.unary_op.reprs))
+    };
+    cmp_slices(nodes_1, nodes_2, cmp_node).then_with(|| cmp_slices(ops_1, ops_2, cmp_op))
+}
+
+/// Hashes the nodes and operators of a flat expression consistently with [`cmp_flat`].
+/// Numbers are hashed by their debug representation and operators by their
+/// representations. Like in [`cmp_flat`], different functions with the same representation
+/// collide.
+pub fn hash_flat<T: Clone + Debug, H: Hasher>(
+    nodes: &[FlatNode<T>],
+    ops: &[FlatOp<T>],
+    state: &mut H,
+) {
+    nodes.len().hash(state);
+    for node in nodes {
+        match &node.kind {
+            FlatNodeKind::Num(x) => {
+                0u8.hash(state);
+                format!("{:?}", x).hash(state);
+            }
+            FlatNodeKind::Var(idx) => {
+                1u8.hash(state);
+                idx.hash(state);
+            }
+            FlatNodeKind::Ternary(t) => {
+                2u8.hash(state);
+                t.repr.hash(state);
+                for arg in &t.args {
+                    hash_flat(&arg.nodes, &arg.ops, state);
+                }
+            }
+        }
+        node.unary_op.reprs.hash(state);
+    }
+    ops.len().hash(state);
+    for op in ops {
+        op.repr.hash(state);
+        op.bin_op.prio.hash(state);
+        op.bin_op.is_commutative.hash(state);
+        op.unary_op.reprs.hash(state);
+    }
+}
+
+/// Creates a string that identifies the structure of a flat expression independently of
+/// redundant parentheses and of the order of the two operands of commutative operators.
+/// Operators are identified by their representations, see [`factory_repr`], numbers by their
+/// debug representation, and variables by their names.
+pub fn canonical_form<T: Clone + Debug, S: AsRef<str>>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    var_names: &[S],
+) -> String {
+    let mut forms = nodes
+        .iter()
+        .map(|node| {
+            let operand = match &node.kind {
+                FlatNodeKind::Num(n) => format!("{:?}", n),
+                FlatNodeKind::Var(idx) => format!("{{{}}}", var_names[*idx].as_ref()),
+                FlatNodeKind::Ternary(t) => {
+                    let [c, a, b] = &t.args;
+                    let form = |arg: &FlatPlan<T>| {
+                        canonical_form(&arg.nodes, &arg.ops, &arg.prio_indices, var_names)
+                    };
+                    format!("{}({},{},{})", t.repr, form(c), form(a), form(b))
+                }
+            };
+            unary_form(&node.unary_op, operand)
+        })
+        .collect::<SmallVec<[_; N_NODES_ON_STACK]>>();
+    let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+        let op = &ops[bin_op_idx];
+        let mut operands = [
+            std::mem::take(&mut forms[idx_1]),
+            std::mem::take(&mut forms[idx_2]),
+        ];
+        if op.bin_op.is_commutative {
+            operands.sort();
+        }
+        let bin_form = format!("{}({},{})", op.repr, operands[0], operands[1]);
+        forms[idx_1] = unary_form(&op.unary_op, bin_form);
+        ignore[idx_2] = true;
+    }
+    std::mem::take(&mut forms[0])
+}
+
+/// Flattens `deep_expr` into nodes and binary operators. The representations of the
+/// operators are looked up in `operators`, see [`factory_repr`].
 pub fn flatten_vecs<T: Clone + Debug>(
     deep_expr: &DeepEx<T>,
     prio_offset: i64,
+    operators: &[Operator<'static, T>],
 ) -> (FlatNodeVec<T>, FlatOpVec<T>) {
     let mut flat_nodes = FlatNodeVec::<T>::new();
     let mut flat_ops = FlatOpVec::<T>::new();
@@ -76,10 +913,15 @@ pub fn flatten_vecs<T: Clone + Debug>(
                 flat_nodes.push(flat_node);
             }
             DeepNode::Expr(e) => {
-                let (mut sub_nodes, mut sub_ops) = flatten_vecs(e, prio_offset + 100i64);
+                let (mut sub_nodes, mut sub_ops) = flatten_vecs(e, prio_offset + 100i64, operators);
                 flat_nodes.append(&mut sub_nodes);
                 flat_ops.append(&mut sub_ops);
             }
+            DeepNode::Ternary(t) => {
+                let flat_node =
+                    FlatNode::from_kind(FlatNodeKind::Ternary(flatten_ternary(t, operators)));
+                flat_nodes.push(flat_node);
+            }
         };
         if node_idx < deep_expr.bin_ops().ops.len() {
             let prio_adapted_bin_op = BinOp {
@@ -87,14 +929,18 @@ pub fn flatten_vecs<T: Clone + Debug>(
                 prio: deep_expr.bin_ops().ops[node_idx].prio + prio_offset,
                 is_commutative: deep_expr.bin_ops().ops[node_idx].is_commutative,
             };
+            let repr = deep_expr.bin_ops().reprs[node_idx];
             flat_ops.push(FlatOp {
                 bin_op: prio_adapted_bin_op,
-                unary_op: UnaryOp::new(),
+                unary_op: UnaryOpWithReprs::new(),
+                repr: factory_repr(repr, operators),
+                is_addition: is_addition(repr, operators),
             });
         }
     }
 
-    if deep_expr.unary_op().op.len() > 0 {
+    if !deep_expr.unary_op().op.is_empty() {
+        let unary_op = to_factory_unary_op(deep_expr.unary_op(), operators);
         if !flat_ops.is_empty() {
             // find the last binary operator with the lowest priority of this expression,
             // since this will be executed as the last one
@@ -102,18 +948,33 @@ pub fn flatten_vecs<T: Clone + Debug>(
                 None => panic!("cannot have more than one flat node but no binary ops"),
                 Some(x) => x,
             };
-            low_prio_op
-                .unary_op
-                .append_after(&deep_expr.unary_op().op.clone());
+            low_prio_op.unary_op.append_front(&unary_op);
         } else {
-            flat_nodes[0]
-                .unary_op
-                .append_after(&deep_expr.unary_op().op.clone());
+            flat_nodes[0].unary_op.append_front(&unary_op);
         }
     }
     (flat_nodes, flat_ops)
 }
 
+/// Flattens the arguments of a ternary operator. The flat arguments are large and live in a
+/// function of their own to keep the stack frames of the recursion in [`flatten_vecs`] small.
+#[inline(never)]
+fn flatten_ternary<T: Clone + Debug>(
+    ternary: &TernaryOpWithArgs<T>,
+    operators: &[Operator<'static, T>],
+) -> Box<FlatTernary<T>> {
+    let [c, a, b] = &ternary.args;
+    Box::new(FlatTernary {
+        op: ternary.op,
+        repr: factory_repr(ternary.repr, operators),
+        args: [
+            FlatPlan::new(c, operators),
+            FlatPlan::new(a, operators),
+            FlatPlan::new(b, operators),
+        ],
+    })
+}
+
 pub fn prioritized_indices_flat<T: Clone + Debug>(
     ops: &[FlatOp<T>],
     nodes: &FlatNodeVec<T>,
@@ -135,49 +996,920 @@ pub fn prioritized_indices_flat<T: Clone + Debug>(
     indices
 }
 
-pub fn eval_flatex<T: Clone + Debug>(
+fn check_n_vars<T>(vars: &[T], n_unique_vars: usize) -> ExResult<()> {
+    if n_unique_vars != vars.len() {
+        Err(ExError {
+            msg: format!(
+                "parsed expression contains {} vars but passed slice has {} elements",
+                n_unique_vars,
+                vars.len()
+            ),
+            position: None,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Value of `node` including its unary operator.
+fn eval_node<T: Clone + Debug>(vars: &[T], node: &FlatNode<T>) -> T {
+    node.unary_op.op.apply(match &node.kind {
+        FlatNodeKind::Num(n) => n.clone(),
+        FlatNodeKind::Var(idx) => vars[*idx].clone(),
+        FlatNodeKind::Ternary(t) => t.eval(vars),
+    })
+}
+
+fn eval_nodes<T: Clone + Debug>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+) -> SmallVec<[T; N_NODES_ON_STACK]> {
+    nodes.iter().map(|node| eval_node(vars, node)).collect()
+}
+
+/// Returns the indices of the operands of the binary operator with index `bin_op_idx`
+/// skipping operands that have already been merged into their left neighbor.
+fn find_operands(ignore: &[bool], bin_op_idx: usize) -> (usize, usize) {
+    let mut shift_left = 0usize;
+    while ignore[bin_op_idx - shift_left] {
+        shift_left += 1usize;
+    }
+    let mut shift_right = 1usize;
+    while ignore[bin_op_idx + shift_right] {
+        shift_right += 1usize;
+    }
+    (bin_op_idx - shift_left, bin_op_idx + shift_right)
+}
+
+/// Evaluates like [`eval_flatex`] but stores intermediate results in the given buffers
+/// that are resized to the number of nodes.
+pub fn eval_flatex_with_buffer<T: Clone + Debug>(
     vars: &[T],
     nodes: &FlatNodeVec<T>,
     ops: &FlatOpVec<T>,
     prio_indices: &ExprIdxVec,
     n_unique_vars: usize,
+    numbers: &mut Vec<T>,
+    ignore: &mut Vec<bool>,
 ) -> ExResult<T> {
+    check_n_vars(vars, n_unique_vars)?;
+    numbers.clear();
+    numbers.extend(nodes.iter().map(|node| eval_node(vars, node)));
+    ignore.clear();
+    ignore.resize(nodes.len(), false);
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(ignore, bin_op_idx);
+        let num_1 = numbers[idx_1].clone();
+        let num_2 = numbers[idx_2].clone();
+        numbers[idx_1] = {
+            let bop_res = (ops[bin_op_idx].bin_op.apply)(num_1, num_2);
+            ops[bin_op_idx].unary_op.op.apply(bop_res)
+        };
+        ignore[idx_2] = true;
+    }
+    Ok(numbers[0].clone())
+}
+
+pub fn check_n_vars_of_row<T>(row_idx: usize, vars: &[T], n_unique_vars: usize) -> ExResult<()> {
     if n_unique_vars != vars.len() {
-        return Err(ExError {
-            msg: format!(
-                "parsed expression contains {} vars but passed slice has {} elements",
-                n_unique_vars,
-                vars.len()
-            ),
-        });
+        Err(format_exerr!(
+            "row {} has {} elements but the expression contains {} vars",
+            row_idx,
+            vars.len(),
+            n_unique_vars
+        ))
+    } else {
+        Ok(())
     }
+}
+
+/// Evaluates like [`eval_flatex`] for each row of variable values. The operands of the
+/// binary operators are determined only once and the buffer of numbers is re-used.
+pub fn eval_flatex_many<T: Clone + Debug>(
+    var_rows: &[&[T]],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    n_unique_vars: usize,
+) -> ExResult<Vec<T>> {
+    let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    let operands = prio_indices
+        .iter()
+        .map(|&bin_op_idx| {
+            let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+            ignore[idx_2] = true;
+            (bin_op_idx, idx_1, idx_2)
+        })
+        .collect::<SmallVec<[_; N_NODES_ON_STACK]>>();
+    let mut numbers = SmallVec::<[T; N_NODES_ON_STACK]>::with_capacity(nodes.len());
+    var_rows
+        .iter()
+        .enumerate()
+        .map(|(row_idx, vars)| {
+            check_n_vars_of_row(row_idx, vars, n_unique_vars)?;
+            numbers.clear();
+            numbers.extend(nodes.iter().map(|node| eval_node(vars, node)));
+            for &(bin_op_idx, idx_1, idx_2) in operands.iter() {
+                let num_1 = numbers[idx_1].clone();
+                let num_2 = numbers[idx_2].clone();
+                let bop_res = (ops[bin_op_idx].bin_op.apply)(num_1, num_2);
+                numbers[idx_1] = ops[bin_op_idx].unary_op.op.apply(bop_res);
+            }
+            Ok(numbers[0].clone())
+        })
+        .collect()
+}
+
+/// Evaluates like [`eval_flatex`] but sums with compensated summation, more precisely
+/// the Kahan-Babuška variant by Neumaier, when binary operators with the id `add` are applied.
+/// Compensation terms are added to the values before any other operator is applied.
+pub fn eval_flatex_compensated<T: Float + Debug>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    n_unique_vars: usize,
+) -> ExResult<T> {
+    check_n_vars(vars, n_unique_vars)?;
+    eval_compensated_unchecked(vars, nodes, ops, prio_indices)
+}
+
+fn eval_compensated_unchecked<T: Float + Debug>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+) -> ExResult<T> {
     let mut numbers = nodes
         .iter()
-        .map(|node| {
-            node.unary_op.apply(match &node.kind {
-                FlatNodeKind::Num(n) => n.clone(),
-                FlatNodeKind::Var(idx) => vars[*idx].clone(),
-            })
+        .map(|node| match &node.kind {
+            FlatNodeKind::Ternary(t) => Ok(node.unary_op.op.apply(t.eval_with(|arg| {
+                eval_compensated_unchecked(vars, &arg.nodes, &arg.ops, &arg.prio_indices)
+            })?)),
+            _ => Ok(eval_node(vars, node)),
         })
-        .collect::<SmallVec<[T; N_NODES_ON_STACK]>>();
+        .collect::<ExResult<SmallVec<[T; N_NODES_ON_STACK]>>>()?;
+    let mut compensations: SmallVec<[T; N_NODES_ON_STACK]> = smallvec![T::zero(); nodes.len()];
     let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
-    for (i, &bin_op_idx) in prio_indices.iter().enumerate() {
-        let num_idx = prio_indices[i];
-        let mut shift_left = 0usize;
-        while ignore[num_idx - shift_left] {
-            shift_left += 1usize;
-        }
-        let mut shift_right = 1usize;
-        while ignore[num_idx + shift_right] {
-            shift_right += 1usize;
-        }
-        let num_1 = numbers[num_idx - shift_left].clone();
-        let num_2 = numbers[num_idx + shift_right].clone();
-        numbers[num_idx - shift_left] = {
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+        let op = &ops[bin_op_idx];
+        let (res, compensation) = if op.is_addition {
+            let (a, b) = (numbers[idx_1], numbers[idx_2]);
+            let sum = (op.bin_op.apply)(a, b);
+            let rounding_error = if !sum.is_finite() {
+                T::zero()
+            } else if a.abs() >= b.abs() {
+                (a - sum) + b
+            } else {
+                (b - sum) + a
+            };
+            (
+                sum,
+                compensations[idx_1] + compensations[idx_2] + rounding_error,
+            )
+        } else {
+            let res = (op.bin_op.apply)(
+                numbers[idx_1] + compensations[idx_1],
+                numbers[idx_2] + compensations[idx_2],
+            );
+            (res, T::zero())
+        };
+        if op.unary_op.op.is_empty() {
+            numbers[idx_1] = res;
+            compensations[idx_1] = compensation;
+        } else {
+            numbers[idx_1] = op.unary_op.op.apply(res + compensation);
+            compensations[idx_1] = T::zero();
+        }
+        ignore[idx_2] = true;
+    }
+    Ok(numbers[0] + compensations[0])
+}
+
+/// Evaluates the nodes with `node_value` and applies the binary operators in the order of
+/// `prio_indices` with `apply_op`.
+fn fold_flat<T, U, N, B>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    node_value: N,
+    mut apply_op: B,
+) -> ExResult<U>
+where
+    T: Clone,
+    N: FnMut(&FlatNode<T>) -> ExResult<U>,
+    B: FnMut(&FlatOp<T>, U, U) -> ExResult<U>,
+{
+    let mut values = nodes
+        .iter()
+        .map(node_value)
+        .map(|value| value.map(Some))
+        .collect::<ExResult<SmallVec<[_; N_NODES_ON_STACK]>>>()?;
+    let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+        let value_1 = values[idx_1].take().expect("operands are used once");
+        let value_2 = values[idx_2].take().expect("operands are used once");
+        values[idx_1] = Some(apply_op(&ops[bin_op_idx], value_1, value_2)?);
+        ignore[idx_2] = true;
+    }
+    Ok(values[0].take().expect("there is at least one node"))
+}
+
+/// Wraps `operand` into the functions of `unary_op`.
+fn unary_form<T: Clone>(unary_op: &UnaryOpWithReprs<T>, operand: String) -> String {
+    // the last unary function is the innermost one
+    unary_op
+        .reprs
+        .iter()
+        .rev()
+        .fold(operand, |s, repr| format!("{}({})", repr, s))
+}
+
+/// Writes binary operators that are written like functions in `operators` also like
+/// functions.
+fn bin_form<T: Clone>(
+    op: &FlatOp<T>,
+    operators: &[Operator<T>],
+    form_1: &str,
+    form_2: &str,
+) -> String {
+    let factory_op = operators.iter().find(|o| o.repr() == op.repr);
+    if factory_op.is_some_and(|o| o.is_function_style()) {
+        format!("{}({}, {})", op.repr, form_1, form_2)
+    } else {
+        format!("({}{}{})", form_1, op.repr, form_2)
+    }
+}
+
+/// Sub-expression of a flat expression as it is contained in the errors of
+/// [`eval_flatex_checked`].
+fn checked_form<T: Clone + Debug, S: AsRef<str>>(
+    plan: &FlatPlan<T>,
+    var_names: &[S],
+    operators: &[Operator<T>],
+) -> String {
+    let form = fold_flat(
+        &plan.nodes,
+        &plan.ops,
+        &plan.prio_indices,
+        |node| {
+            let operand = match &node.kind {
+                FlatNodeKind::Num(n) => format!("{:?}", n),
+                FlatNodeKind::Var(idx) => format!("{{{}}}", var_names[*idx].as_ref()),
+                FlatNodeKind::Ternary(t) => {
+                    let [c, a, b] = &t.args;
+                    let form = |arg| checked_form(arg, var_names, operators);
+                    format!("{}({}, {}, {})", t.repr, form(c), form(a), form(b))
+                }
+            };
+            Ok(unary_form(&node.unary_op, operand))
+        },
+        |op, form_1, form_2| {
+            Ok(unary_form(
+                &op.unary_op,
+                bin_form(op, operators, &form_1, &form_2),
+            ))
+        },
+    );
+    form.expect("creating forms does not fail")
+}
+
+/// Evaluates like [`eval_flatex`] but returns an error as soon as a unary, binary, or
+/// ternary operator results in a non-finite number. The error contains the representation
+/// of the operator and the sub-expression the operator has been applied to. Binary
+/// operators that are written like functions in `operators` are also written like functions
+/// in the error. Only the selected argument of a selection is evaluated and checked.
+pub fn eval_flatex_checked<T: Float + Debug, S: AsRef<str>>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    var_names: &[S],
+    operators: &[Operator<T>],
+) -> ExResult<T> {
+    fn check<T: Float + Debug>(x: T, repr: &str, form: &str) -> ExResult<()> {
+        if x.is_finite() {
+            Ok(())
+        } else {
+            Err(format_exerr!(
+                "operator '{}' in '{}' results in {:?}",
+                repr,
+                form,
+                x
+            ))
+        }
+    }
+    fn apply_unary<T: Float + Debug>(
+        unary_op: &UnaryOpWithReprs<T>,
+        (mut x, mut form): (T, String),
+    ) -> ExResult<(T, String)> {
+        // the last unary function is the innermost one
+        for (f, repr) in unary_op.op.funcs().iter().zip(unary_op.reprs.iter()).rev() {
+            x = f(x);
+            form = format!("{}({})", repr, form);
+            check(x, repr, &form)?;
+        }
+        Ok((x, form))
+    }
+    fn eval_ternary<T: Float + Debug, S: AsRef<str>>(
+        vars: &[T],
+        ternary: &FlatTernary<T>,
+        var_names: &[S],
+        operators: &[Operator<T>],
+    ) -> ExResult<(T, String)> {
+        let eval_arg = |arg: &FlatPlan<T>| {
+            eval_checked(
+                vars,
+                &arg.nodes,
+                &arg.ops,
+                &arg.prio_indices,
+                var_names,
+                operators,
+            )
+        };
+        let [c, a, b] = &ternary.args;
+        let (c, form_c) = eval_arg(c)?;
+        match ternary.op {
+            TernaryOp::Select(is_first) => {
+                let (res, form_a, form_b) = if is_first(&c) {
+                    let (res, form_a) = eval_arg(a)?;
+                    (res, form_a, checked_form(b, var_names, operators))
+                } else {
+                    let (res, form_b) = eval_arg(b)?;
+                    (res, checked_form(a, var_names, operators), form_b)
+                };
+                let form = format!("{}({}, {}, {})", ternary.repr, form_c, form_a, form_b);
+                Ok((res, form))
+            }
+            TernaryOp::Apply(apply) => {
+                let (a, form_a) = eval_arg(a)?;
+                let (b, form_b) = eval_arg(b)?;
+                let res = apply(c, a, b);
+                let form = format!("{}({}, {}, {})", ternary.repr, form_c, form_a, form_b);
+                check(res, ternary.repr, &form)?;
+                Ok((res, form))
+            }
+        }
+    }
+    fn eval_checked<T: Float + Debug, S: AsRef<str>>(
+        vars: &[T],
+        nodes: &FlatNodeVec<T>,
+        ops: &FlatOpVec<T>,
+        prio_indices: &ExprIdxVec,
+        var_names: &[S],
+        operators: &[Operator<T>],
+    ) -> ExResult<(T, String)> {
+        fold_flat(
+            nodes,
+            ops,
+            prio_indices,
+            |node| {
+                let operand = match &node.kind {
+                    FlatNodeKind::Num(n) => {
+                        if !n.is_finite() {
+                            return Err(format_exerr!(
+                                "the expression contains the number {:?}",
+                                n
+                            ));
+                        }
+                        (*n, format!("{:?}", n))
+                    }
+                    FlatNodeKind::Var(idx) => {
+                        let name = var_names[*idx].as_ref();
+                        if !vars[*idx].is_finite() {
+                            return Err(format_exerr!(
+                                "the value of the variable '{}' is {:?}",
+                                name,
+                                vars[*idx]
+                            ));
+                        }
+                        (vars[*idx], format!("{{{}}}", name))
+                    }
+                    FlatNodeKind::Ternary(t) => eval_ternary(vars, t, var_names, operators)?,
+                };
+                apply_unary(&node.unary_op, operand)
+            },
+            |op, (num_1, form_1), (num_2, form_2)| {
+                let res = (op.bin_op.apply)(num_1, num_2);
+                let form = bin_form(op, operators, &form_1, &form_2);
+                check(res, op.repr, &form)?;
+                apply_unary(&op.unary_op, (res, form))
+            },
+        )
+    }
+    check_n_vars(vars, var_names.len())?;
+    Ok(eval_checked(vars, nodes, ops, prio_indices, var_names, operators)?.0)
+}
+
+fn bytecode_unary_suffix<T: Clone>(unary_op: &UnaryOpWithReprs<T>) -> String {
+    if unary_op.op.is_empty() {
+        String::new()
+    } else {
+        format!(" | {}", unary_op.reprs.join(" "))
+    }
+}
+
+/// Representations of the operators of the flat expression in the order of their first
+/// occurrence, each listed once. Functions that are not part of the operator factory are
+/// represented by `?`.
+pub fn operator_reprs<T: Clone>(nodes: &FlatNodeVec<T>, ops: &FlatOpVec<T>) -> Vec<String> {
+    fn collect<T: Clone>(nodes: &FlatNodeVec<T>, ops: &FlatOpVec<T>, reprs: &mut Vec<String>) {
+        for (node_idx, node) in nodes.iter().enumerate() {
+            let mut found = vec![];
+            if let FlatNodeKind::Ternary(t) = &node.kind {
+                // arguments are evaluated before the ternary operator
+                for arg in &t.args {
+                    collect(&arg.nodes, &arg.ops, reprs);
+                }
+                found.push(t.repr);
+            }
+            found.extend(unary_reprs(&node.unary_op));
+            if let Some(op) = ops.get(node_idx) {
+                found.push(op.repr);
+                found.extend(unary_reprs(&op.unary_op));
+            }
+            for repr in found {
+                if !reprs.iter().any(|r| r == repr) {
+                    reprs.push(repr.to_string());
+                }
+            }
+        }
+    }
+    let mut reprs: Vec<String> = vec![];
+    collect(nodes, ops, &mut reprs);
+    reprs
+}
+
+/// Representations of the unary operators attached to the node with index `node_idx` in the
+/// order of their application, i.e., innermost first. Functions that are not part of the
+/// operator factory are represented by `?`.
+pub fn unary_chain<T: Clone>(
+    nodes: &FlatNodeVec<T>,
+    node_idx: usize,
+) -> ExResult<Vec<&'static str>> {
+    let node = nodes.get(node_idx).ok_or_else(|| {
+        format_exerr!(
+            "node index {} out of bounds for {} nodes",
+            node_idx,
+            nodes.len()
+        )
+    })?;
+    Ok(unary_reprs(&node.unary_op))
+}
+
+/// Representations of the functions of `unary_op` in the order of their application, i.e.,
+/// innermost first.
+pub fn unary_reprs<T: Clone>(unary_op: &UnaryOpWithReprs<'static, T>) -> Vec<&'static str> {
+    // the last unary function is the innermost one
+    unary_op.reprs.iter().rev().copied().collect()
+}
+
+/// Lists the flat evaluation plan line by line. First, the variable names are declared with
+/// `var <name>`. Then, the nodes are pushed with `push num <number>`, `push var <index>`,
+/// or `push ternary <repr>`. A ternary operator is followed by its three arguments, each
+/// listed as indented plan between `arg` and `end`. Finally, the binary operators between
+/// the nodes are applied in the order of `prio_indices` with `apply <index> <repr>`. Unary
+/// operators of nodes and binary operators follow a `|` outermost-first. Functions that are
+/// not part of the operator factory are represented by `?`.
+pub fn dump_bytecode<T: Clone + Debug, S: AsRef<str>>(
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    var_names: &[S],
+) -> String {
+    fn dump_plan<T: Clone + Debug>(
+        nodes: &FlatNodeVec<T>,
+        ops: &FlatOpVec<T>,
+        prio_indices: &ExprIdxVec,
+        indent: &str,
+        lines: &mut Vec<String>,
+    ) {
+        for node in nodes {
+            let unary = bytecode_unary_suffix(&node.unary_op);
+            match &node.kind {
+                FlatNodeKind::Num(n) => lines.push(format!("{}push num {:?}{}", indent, n, unary)),
+                FlatNodeKind::Var(idx) => {
+                    lines.push(format!("{}push var {}{}", indent, idx, unary))
+                }
+                FlatNodeKind::Ternary(t) => {
+                    lines.push(format!("{}push ternary {}{}", indent, t.repr, unary));
+                    let arg_indent = format!("{}    ", indent);
+                    for arg in &t.args {
+                        lines.push(format!("{}  arg", indent));
+                        dump_plan(&arg.nodes, &arg.ops, &arg.prio_indices, &arg_indent, lines);
+                        lines.push(format!("{}  end", indent));
+                    }
+                }
+            }
+        }
+        for &idx in prio_indices.iter() {
+            let unary = bytecode_unary_suffix(&ops[idx].unary_op);
+            lines.push(format!(
+                "{}apply {} {}{}",
+                indent, idx, ops[idx].repr, unary
+            ));
+        }
+    }
+    let mut lines = var_names
+        .iter()
+        .map(|name| format!("var {}", name.as_ref()))
+        .collect::<Vec<_>>();
+    dump_plan(nodes, ops, prio_indices, "", &mut lines);
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+/// Nodes, operators, prioritized operator indices, and variable names of a flat expression.
+pub type FlatParts<'a, T> = (
+    FlatNodeVec<T>,
+    FlatOpVec<T>,
+    ExprIdxVec,
+    SmallVec<[&'a str; N_VARS_ON_STACK]>,
+);
+
+fn find_bytecode_op<'b, T: Clone>(
+    repr: &str,
+    line_idx: usize,
+    operators: &'b [Operator<'static, T>],
+) -> ExResult<&'b Operator<'static, T>> {
+    operators
+        .iter()
+        .find(|op| op.repr() == repr)
+        .ok_or_else(|| format_exerr!("unknown operator '{}' in line {}", repr, line_idx))
+}
+
+fn parse_bytecode_idx(idx: &str, line_idx: usize) -> ExResult<usize> {
+    idx.parse::<usize>()
+        .map_err(|_| format_exerr!("cannot parse index '{}' in line {}", idx, line_idx))
+}
+
+fn parse_bytecode_unary<T: Clone>(
+    unary: Option<&str>,
+    line_idx: usize,
+    operators: &[Operator<'static, T>],
+) -> ExResult<UnaryOpWithReprs<'static, T>> {
+    let funcs = unary
+        .map(|u| u.split_whitespace().collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|repr| {
+            let op = find_bytecode_op(repr, line_idx, operators)?;
+            Ok((op.unary()?, op.repr()))
+        })
+        .collect::<ExResult<Vec<_>>>()?;
+    Ok(factory_unary_op(funcs.into_iter(), operators))
+}
+
+/// Reads the nodes and operators of a plan from `lines` until the `end` of an argument of a
+/// ternary operator if `is_arg` or until the end of the text otherwise.
+fn load_plan<'a, T: DataType, I>(
+    lines: &mut I,
+    operators: &[Operator<'static, T>],
+    var_names: &mut SmallVec<[&'a str; N_VARS_ON_STACK]>,
+    is_arg: bool,
+) -> ExResult<FlatPlan<T>>
+where
+    I: Iterator<Item = (usize, &'a str)>,
+    <T as FromStr>::Err: Debug,
+{
+    let mut nodes = FlatNodeVec::new();
+    let mut ops: SmallVec<[Option<FlatOp<T>>; N_NODES_ON_STACK]> = SmallVec::new();
+    let mut prio_indices = ExprIdxVec::new();
+    let mut is_closed = !is_arg;
+    while let Some((line_idx, line)) = lines.next() {
+        let (code, unary) = match line.split_once(" | ") {
+            Some((code, unary)) => (code, Some(unary)),
+            None => (line, None),
+        };
+        let unary_op = parse_bytecode_unary(unary, line_idx, operators)?;
+        if code == "end" && is_arg {
+            is_closed = true;
+            break;
+        } else if let Some(name) = code.strip_prefix("var ").filter(|_| !is_arg) {
+            var_names.push(name);
+        } else if let Some(num) = code.strip_prefix("push num ") {
+            let num = num.parse::<T>().map_err(|e| {
+                format_exerr!(
+                    "cannot parse number '{}' in line {}, {:?}",
+                    num,
+                    line_idx,
+                    e
+                )
+            })?;
+            nodes.push(FlatNode {
+                kind: FlatNodeKind::Num(num),
+                unary_op,
+            });
+        } else if let Some(idx) = code.strip_prefix("push var ") {
+            let idx = parse_bytecode_idx(idx, line_idx)?;
+            if idx >= var_names.len() {
+                return Err(format_exerr!(
+                    "undeclared variable {} in line {}",
+                    idx,
+                    line_idx
+                ));
+            }
+            nodes.push(FlatNode {
+                kind: FlatNodeKind::Var(idx),
+                unary_op,
+            });
+        } else if let Some(repr) = code.strip_prefix("push ternary ") {
+            let op = find_bytecode_op(repr, line_idx, operators)?;
+            let ternary_op = *op.ternary().ok_or_else(|| {
+                format_exerr!("'{}' in line {} is not a ternary operator", repr, line_idx)
+            })?;
+            let mut load_arg = || match lines.next() {
+                Some((_, "arg")) => load_plan(lines, operators, var_names, true),
+                _ => Err(format_exerr!(
+                    "ternary operator '{}' in line {} expects 3 arguments",
+                    repr,
+                    line_idx
+                )),
+            };
+            let args = [load_arg()?, load_arg()?, load_arg()?];
+            nodes.push(FlatNode {
+                kind: FlatNodeKind::Ternary(Box::new(FlatTernary {
+                    op: ternary_op,
+                    repr: op.repr(),
+                    args,
+                })),
+                unary_op,
+            });
+        } else if let Some(apply) = code.strip_prefix("apply ") {
+            let (idx, repr) = apply
+                .split_once(' ')
+                .ok_or_else(|| format_exerr!("missing operator in line {}", line_idx))?;
+            let idx = parse_bytecode_idx(idx, line_idx)?;
+            let op = find_bytecode_op(repr, line_idx, operators)?;
+            if ops.len() <= idx {
+                ops.resize(idx + 1, None);
+            }
+            if ops[idx].is_some() {
+                return Err(format_exerr!("operator {} is applied twice", idx));
+            }
+            ops[idx] = Some(FlatOp {
+                unary_op,
+                bin_op: op.bin()?,
+                repr: op.repr(),
+                is_addition: op.id() == "add",
+            });
+            prio_indices.push(idx);
+        } else {
+            return Err(format_exerr!(
+                "cannot interpret line {} '{}'",
+                line_idx,
+                line
+            ));
+        }
+    }
+    if !is_closed {
+        return Err(ExError::new("argument of a ternary operator is not closed"));
+    }
+    if nodes.is_empty() || ops.len() + 1 != nodes.len() {
+        return Err(format_exerr!(
+            "{} operators cannot be applied to {} nodes",
+            ops.len(),
+            nodes.len()
+        ));
+    }
+    let ops = ops
+        .into_iter()
+        .enumerate()
+        .map(|(idx, op)| op.ok_or_else(|| format_exerr!("operator {} is never applied", idx)))
+        .collect::<ExResult<FlatOpVec<T>>>()?;
+    Ok(FlatPlan {
+        nodes,
+        ops,
+        prio_indices,
+    })
+}
+
+/// Reconstructs the flat evaluation plan from the output of [`dump_bytecode`].
+pub fn load_bytecode<'a, T: DataType>(
+    text: &'a str,
+    operators: &[Operator<'static, T>],
+) -> ExResult<FlatParts<'a, T>>
+where
+    <T as FromStr>::Err: Debug,
+{
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty());
+    let mut var_names = SmallVec::new();
+    let plan = load_plan(&mut lines, operators, &mut var_names, false)?;
+    Ok((plan.nodes, plan.ops, plan.prio_indices, var_names))
+}
+
+pub fn eval_flatex<T: Clone + Debug>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+    n_unique_vars: usize,
+) -> ExResult<T> {
+    check_n_vars(vars, n_unique_vars)?;
+    Ok(eval_flatex_unchecked(vars, nodes, ops, prio_indices))
+}
+
+fn eval_flatex_unchecked<T: Clone + Debug>(
+    vars: &[T],
+    nodes: &FlatNodeVec<T>,
+    ops: &FlatOpVec<T>,
+    prio_indices: &ExprIdxVec,
+) -> T {
+    let mut numbers = eval_nodes(vars, nodes);
+    let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; nodes.len()];
+    for &bin_op_idx in prio_indices.iter() {
+        let (idx_1, idx_2) = find_operands(&ignore, bin_op_idx);
+        let num_1 = numbers[idx_1].clone();
+        let num_2 = numbers[idx_2].clone();
+        numbers[idx_1] = {
             let bop_res = (ops[bin_op_idx].bin_op.apply)(num_1, num_2);
-            ops[bin_op_idx].unary_op.apply(bop_res)
+            ops[bin_op_idx].unary_op.op.apply(bop_res)
         };
-        ignore[num_idx + shift_right] = true;
+        ignore[idx_2] = true;
     }
-    Ok(numbers[0].clone())
+    numbers[0].clone()
+}
+
+/// Nodes, operators, and evaluation order of a flattened deep expression or of an argument
+/// of a ternary operator.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct FlatPlan<T: Clone> {
+    nodes: FlatNodeVec<T>,
+    ops: FlatOpVec<T>,
+    prio_indices: ExprIdxVec,
+}
+
+impl<T> FlatPlan<T>
+where
+    T: Clone + Debug,
+{
+    fn new(deepex: &DeepEx<T>, operators: &[Operator<'static, T>]) -> Self {
+        let (nodes, ops) = flatten_vecs(deepex, 0, operators);
+        Self::from_parts(nodes, ops)
+    }
+
+    pub fn from_parts(nodes: FlatNodeVec<T>, ops: FlatOpVec<T>) -> Self {
+        let prio_indices = prioritized_indices_flat(&ops, &nodes);
+        FlatPlan {
+            nodes,
+            ops,
+            prio_indices,
+        }
+    }
+
+    fn eval(&self, vars: &[T]) -> ExResult<T> {
+        eval_flatex(vars, &self.nodes, &self.ops, &self.prio_indices, vars.len())
+    }
+
+    /// Evaluates without checking the number of variables, the variables of arguments of
+    /// ternary operators are the variables of the whole expression.
+    fn eval_unchecked(&self, vars: &[T]) -> T {
+        eval_flatex_unchecked(vars, &self.nodes, &self.ops, &self.prio_indices)
+    }
+}
+
+/// Evaluation plan after common sub-expression elimination, see
+/// [`eliminate_common_subexprs`](DeepEx::eliminate_common_subexprs). The temporaries
+/// are evaluated first and their results are appended to the variables.
+#[derive(Clone, Debug)]
+pub struct CsePlan<T: Clone> {
+    temporaries: Vec<FlatPlan<T>>,
+    main: FlatPlan<T>,
+}
+
+impl<T> CsePlan<T>
+where
+    T: Clone + Debug,
+{
+    pub fn new(
+        temporaries: &[DeepEx<T>],
+        main: &DeepEx<T>,
+        operators: &[Operator<'static, T>],
+    ) -> Self {
+        CsePlan {
+            temporaries: temporaries
+                .iter()
+                .map(|tmp| FlatPlan::new(tmp, operators))
+                .collect(),
+            main: FlatPlan::new(main, operators),
+        }
+    }
+
+    pub fn eval(&self, vars: &[T], n_unique_vars: usize) -> ExResult<T> {
+        check_n_vars(vars, n_unique_vars)?;
+        let mut all_vars =
+            SmallVec::<[T; N_VARS_ON_STACK]>::with_capacity(vars.len() + self.temporaries.len());
+        all_vars.extend(vars.iter().cloned());
+        for tmp in &self.temporaries {
+            let res = tmp.eval(&all_vars)?;
+            all_vars.push(res);
+        }
+        self.main.eval(&all_vars)
+    }
+}
+
+/// Creates the evaluation plan with shared sub-expressions of `deepex` if there are any and
+/// returns it together with the number of eliminated duplicates.
+pub fn cse_plan<T: Clone + Debug>(
+    deepex: &DeepEx<T>,
+    operators: &[Operator<'static, T>],
+) -> (Option<CsePlan<T>>, usize) {
+    let (temporaries, main, n_eliminated) = deepex.eliminate_common_subexprs();
+    if temporaries.is_empty() {
+        (None, 0)
+    } else {
+        (
+            Some(CsePlan::new(&temporaries, &main, operators)),
+            n_eliminated,
+        )
+    }
+}
+
+/// Shortest representation of `num` that is accepted by `is_literal` and parsed to the
+/// same number, e.g., `.5` for `0.5` or `2` for `2.0`.
+pub fn minimal_number_repr<T: DataType, F: Fn(&str) -> bool>(num: &T, is_literal: F) -> String {
+    let repr = format!("{:?}", num);
+    let is_same = |candidate: &str| {
+        is_literal(candidate)
+            && candidate
+                .parse::<T>()
+                .is_ok_and(|parsed| format!("{:?}", parsed) == repr)
+    };
+    let candidates = vec![
+        repr.strip_suffix(".0").map(|int| int.to_string()),
+        repr.strip_prefix("0.").map(|frac| format!(".{}", frac)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .filter(|candidate| is_same(candidate))
+        .fold(repr.clone(), |shortest, candidate| {
+            if candidate.len() < shortest.len() {
+                candidate
+            } else {
+                shortest
+            }
+        })
+}
+
+/// Unparses `deepex` compactly with variables as positional placeholders `{0}`, `{1}`, ...
+/// and numbers as short as possible. Names that are shorter than their placeholders are
+/// kept. Returns the compact string and the names of the variables in index order.
+pub fn minify_deepex<T: DataType, F: Fn(&str) -> bool>(
+    deepex: &DeepEx<T>,
+    is_literal: F,
+) -> (String, Vec<String>) {
+    let var_repr = |idx: usize, name: &str| {
+        let placeholder = format!("{{{}}}", idx);
+        if name.len() + 2 < placeholder.len() && name.parse::<usize>().is_err() {
+            format!("{{{}}}", name)
+        } else {
+            placeholder
+        }
+    };
+    let minified = deepex.unparse_with(&NodeReprs {
+        num: &|num| minimal_number_repr(num, &is_literal),
+        var: &var_repr,
+    });
+    let names = deepex
+        .var_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    (minified, names)
+}
+
+/// Determines the renames from positional placeholders of a minified expression to the
+/// names in `names`, see [`minify_deepex`].
+pub fn placeholder_renames<'a>(
+    placeholders: &[&'a str],
+    names: &[&'a str],
+) -> ExResult<Vec<(&'a str, &'a str)>> {
+    if placeholders.len() != names.len() {
+        return Err(format_exerr!(
+            "minified expression has {} variables but {} names are given",
+            placeholders.len(),
+            names.len()
+        ));
+    }
+    placeholders
+        .iter()
+        .filter_map(|placeholder| {
+            placeholder
+                .parse::<usize>()
+                .ok()
+                .map(|idx| (*placeholder, idx))
+        })
+        .map(|(placeholder, idx)| {
+            names
+                .get(idx)
+                .map(|name| (placeholder, *name))
+                .ok_or_else(|| format_exerr!("no name for placeholder '{{{}}}'", idx))
+        })
+        .collect()
 }
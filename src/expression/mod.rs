@@ -1,19 +1,111 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
-use crate::{data_type::DataType, parser, ExResult};
+use crate::{
+    data_type::DataType, definitions::N_VARS_TRUTH_TABLE_MAX, format_exerr, parser, EvalSink,
+    ExError, ExResult,
+};
 use num::Float;
 
 pub mod deep;
 mod deep_details;
+pub mod defaults;
 pub mod flat;
 mod flat_details;
 mod partial_derivatives;
+mod polynomial;
+mod simplify;
+pub mod reactive;
 #[cfg(feature = "serde")]
 mod serde;
 
+/// Object-safe part of [`Express`](Express) that evaluates and unparses expressions, e.g.,
+/// to store expressions of different types as `Box<dyn Eval<T>>`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, ExResult, OwnedFlatEx};
+/// let exprs: Vec<Box<dyn Eval<f64> + Send + Sync>> = vec![
+///     Box::new(FlatEx::<f64>::from_str("x*y")?),
+///     Box::new(OwnedFlatEx::<f64>::from_str("x+y")?),
+/// ];
+/// let results = exprs
+///     .iter()
+///     .map(|expr| expr.eval(&[2.0, 3.0]))
+///     .collect::<ExResult<Vec<_>>>()?;
+/// assert_eq!(results, [6.0, 5.0]);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+pub trait Eval<T> {
+    /// Evaluates an expression with the given variable values and returns the computed
+    /// result.
+    ///
+    /// # Arguments
+    ///
+    /// * `vars` - Values of the variables of the expression; the n-th value corresponds to
+    ///            the n-th variable in alphabetical order.
+    ///            Thereby, only the first occurrence of the variable in the string is relevant.
+    ///            If an expression has been created by partial derivation, the variables always
+    ///            coincide with those of the antiderivatives even in cases where variables are
+    ///            irrelevant such as `(x)'=1`.
+    ///
+    /// # Errors
+    ///
+    /// If the number of variables in the parsed expression are different from the length of
+    /// the variable slice, we return an [`ExError`](super::result::ExError).
+    ///
+    fn eval(&self, vars: &[T]) -> ExResult<T>;
+
+    /// Returns the number of variables of the expression
+    fn n_vars(&self) -> usize;
+
+    /// Creates an expression string that corresponds to the `FlatEx` instance.
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let flatex = FlatEx::<f64>::from_str("--sin ( z) +  {another var} + 1 + 2")?;
+    /// assert_eq!(format!("{}", flatex), "--sin ( z) +  {another var} + 1 + 2");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn unparse(&self) -> ExResult<String>;
+
+    /// Returns the variable names in alphabetical order, i.e., the n-th name belongs to the
+    /// n-th value passed to [`eval`](Eval::eval). Curly brackets around variable names
+    /// are not part of the names. The names are still available after
+    /// [`reduce_memory`](Express::reduce_memory).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("z + x*{y y}")?;
+    /// assert_eq!(expr.var_names(), ["x", "y y", "z"]);
+    /// assert_eq!(expr.var_idx("z"), Some(2));
+    /// assert_eq!(expr.var_idx("w"), None);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn var_names(&self) -> Vec<&str>;
+}
+
 /// Expressions implementing this trait can be evaluated for specific variable values,
 /// differentiated partially, and unparsed, i.e., transformed into a string representation.  
-pub trait Express<'a, T> {
+pub trait Express<'a, T>: Eval<T> {
     /// Parses a string into an expression that can be evaluated.
     ///
     /// # Arguments
@@ -30,24 +122,400 @@ pub trait Express<'a, T> {
         T: FromStr,
         Self: Sized;
 
-    /// Evaluates an expression with the given variable values and returns the computed
-    /// result.
+    /// Parses a string like [`from_str`](Express::from_str) and additionally returns the
+    /// result of [`unparse`](Eval::unparse). The string is still available after
+    /// [`reduce_memory`](Express::reduce_memory), e.g., to cache it with the expression.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let (mut expr, unparsed) = FlatEx::<f64>::parse_with_unparsed("2 * x+ y")?;
+    /// expr.reduce_memory();
+    /// assert_eq!(unparsed, "2 * x+ y");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `text` cannot be parsed.
+    ///
+    fn parse_with_unparsed(text: &'a str) -> ExResult<(Self, String)>
+    where
+        <T as std::str::FromStr>::Err: Debug,
+        T: FromStr,
+        Self: Sized,
+    {
+        let expr = Self::from_str(text)?;
+        let unparsed = expr.unparse()?;
+        Ok((expr, unparsed))
+    }
+
+    /// Evaluates an expression like [`eval`](Eval::eval) but accepts slices with more
+    /// values than variables. Only the first [`n_vars`](Eval::n_vars) values are used, e.g.,
+    /// for rows of a table that has more columns than the expression has variables.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x*y")?;
+    /// let row = [2.0, 3.0, 7.0, 9.0];
+    /// assert!((expr.eval_relaxed(&row)? - 6.0).abs() < 1e-12);
+    /// assert!(expr.eval(&row).is_err());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If the slice has less values than the expression has variables, we return an
+    /// [`ExError`](super::result::ExError).
+    ///
+    fn eval_relaxed(&self, vars: &[T]) -> ExResult<T> {
+        let n_vars = self.n_vars();
+        if vars.len() < n_vars {
+            Err(format_exerr!(
+                "parsed expression contains {} vars but passed slice has only {} elements",
+                n_vars,
+                vars.len()
+            ))
+        } else {
+            self.eval(&vars[..n_vars])
+        }
+    }
+
+    /// Evaluates an expression with variable values that are passed together with the variable
+    /// names. Thereby, the order of the pairs is irrelevant. To look up the values in a map,
+    /// see [`eval_map`](Express::eval_map).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("{x y}*2 + a")?;
+    /// let res = expr.eval_named(&[("x y", 1.5), ("a", 1.0)])?;
+    /// assert!((res - 4.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If a name is not a variable of the expression or if a variable of the expression is
+    /// missing in `vars`, we return an [`ExError`](super::result::ExError).
+    ///
+    fn eval_named(&self, vars: &[(&str, T)]) -> ExResult<T>;
+
+    /// Evaluates an expression with variable values that are looked up by the variable names
+    /// in `vars`. Entries of `vars` that are not variables of the expression are ignored.
+    /// For a slice of name-value pairs, see [`eval_named`](Express::eval_named).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::collections::HashMap;
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("gamma*alpha - beta")?;
+    /// let vars = [("alpha", 2.0), ("beta", 1.0), ("gamma", 3.0), ("delta", 4.0)]
+    ///     .iter()
+    ///     .copied()
+    ///     .collect::<HashMap<_, _>>();
+    /// assert!((expr.eval_map(&vars)? - 5.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If a variable of the expression is missing in `vars`, we return an
+    /// [`ExError`](super::result::ExError) that names the variable.
+    ///
+    fn eval_map(&self, vars: &HashMap<&str, T>) -> ExResult<T>
+    where
+        T: Clone,
+    {
+        let values = self
+            .var_names()
+            .iter()
+            .map(|name| {
+                vars.get(name)
+                    .cloned()
+                    .ok_or_else(|| format_exerr!("missing value of variable '{}'", name))
+            })
+            .collect::<ExResult<Vec<_>>>()?;
+        self.eval(&values)
+    }
+
+    /// Evaluates the expression for each set of variable values, e.g., for Monte Carlo
+    /// simulations or parameter sweeps. Thin wrapper around
+    /// [`eval_many`](Express::eval_many) for sets of variables that are not slices.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("x*y")?;
+    /// let res = expr.eval_batch(&[[1.0, 2.0], [3.0, 4.0]])?;
+    /// assert_eq!(res, vec![2.0, 12.0]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// See [`eval_many`](Express::eval_many).
+    ///
+    fn eval_batch<V>(&self, var_sets: &[V]) -> ExResult<Vec<T>>
+    where
+        V: AsRef<[T]>,
+    {
+        let var_rows = var_sets
+            .iter()
+            .map(|vars| vars.as_ref())
+            .collect::<Vec<_>>();
+        self.eval_many(&var_rows)
+    }
+
+    /// Evaluates the expression for each row of variable values and returns one result per
+    /// row. The default implementation calls [`eval`](Eval::eval) for each row.
+    /// [`FlatEx`](crate::FlatEx) and [`OwnedFlatEx`](crate::OwnedFlatEx) override it and
+    /// determine the order of the operations only once for all rows. This is the entry point
+    /// of the batch evaluations [`eval_batch`](Express::eval_batch) and `eval_batch_par`
+    /// that arrange their input as rows and call this method.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("x^2+y")?;
+    /// let res = expr.eval_many(&[&[1.0, 2.0], &[3.0, 4.0]])?;
+    /// assert_eq!(res, vec![3.0, 13.0]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the length of a row differs from the number of variables, an error is returned
+    /// and the remaining rows are not evaluated.
+    ///
+    fn eval_many(&self, var_rows: &[&[T]]) -> ExResult<Vec<T>> {
+        var_rows
+            .iter()
+            .enumerate()
+            .map(|(row_idx, vars)| {
+                flat_details::check_n_vars_of_row(row_idx, vars, self.n_vars())?;
+                self.eval(vars)
+            })
+            .collect()
+    }
+
+    /// Evaluates the expression for data that is stored column-wise, i.e., with one slice of
+    /// values per variable in alphabetical order. The `i`-th element of the result is
+    /// computed from the `i`-th elements of all columns. The values of each row are gathered
+    /// from the columns into one buffer that is reused for all rows and evaluated with
+    /// [`eval`](Eval::eval). Expressions without variables are evaluated once.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let expr = FlatEx::<f64>::from_str("x*y")?;
+    /// let xs = [1.0, 3.0, 5.0];
+    /// let ys = [2.0, 4.0, 6.0];
+    /// assert_eq!(expr.eval_columns(&[&xs, &ys])?, vec![2.0, 12.0, 30.0]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the number of columns differs from the number of variables or if the columns have
+    /// different lengths, we return an [`ExError`](super::result::ExError). Further, the first
+    /// error of an evaluation is returned.
+    ///
+    fn eval_columns(&self, columns: &[&[T]]) -> ExResult<Vec<T>>
+    where
+        T: Clone,
+    {
+        let var_names = self.var_names();
+        if columns.len() != var_names.len() {
+            return Err(format_exerr!(
+                "expected one column per variable of {:?}, got {} columns",
+                var_names,
+                columns.len()
+            ));
+        }
+        let n_rows = match columns.first() {
+            Some(column) => column.len(),
+            None => 1,
+        };
+        if let Some((var_name, column)) = var_names
+            .iter()
+            .zip(columns.iter())
+            .find(|(_, column)| column.len() != n_rows)
+        {
+            return Err(format_exerr!(
+                "column of variable '{}' has {} elements, but the column of '{}' has {}",
+                var_name,
+                column.len(),
+                var_names[0],
+                n_rows
+            ));
+        }
+        let mut row = Vec::with_capacity(columns.len());
+        (0..n_rows)
+            .map(|row_idx| {
+                row.clear();
+                row.extend(columns.iter().map(|column| column[row_idx].clone()));
+                self.eval(&row)
+            })
+            .collect()
+    }
+
+    /// Parallel version of [`eval_batch`](Express::eval_batch) that splits the sets of
+    /// variable values into one chunk per thread of [`rayon`](https://docs.rs/rayon)'s global
+    /// thread pool. Each chunk is evaluated with [`eval_many`](Express::eval_many). The
+    /// order of the results corresponds to the order of `var_sets`. Requires the feature
+    /// `rayon`.
+    ///
+    /// # Errors
+    ///
+    /// See [`eval_many`](Express::eval_many).
+    ///
+    #[cfg(feature = "rayon")]
+    fn eval_batch_par<V>(&self, var_sets: &[V]) -> ExResult<Vec<T>>
+    where
+        Self: Sync,
+        T: Send,
+        V: AsRef<[T]> + Sync,
+    {
+        use rayon::prelude::*;
+        // rows are checked upfront such that errors refer to the index in var_sets
+        for (row_idx, vars) in var_sets.iter().enumerate() {
+            flat_details::check_n_vars_of_row(row_idx, vars.as_ref(), self.n_vars())?;
+        }
+        let chunk_size = (var_sets.len() / rayon::current_num_threads()).max(1);
+        let chunks = var_sets
+            .par_chunks(chunk_size)
+            .map(|chunk| self.eval_batch(chunk))
+            .collect::<ExResult<Vec<_>>>()?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+
+    /// Evaluates the expression for each row of variable values and passes the results
+    /// to `sink` instead of returning them.
     ///
     /// # Arguments
     ///
-    /// * `vars` - Values of the variables of the expression; the n-th value corresponds to
-    ///            the n-th variable in alphabetical order.
-    ///            Thereby, only the first occurrence of the variable in the string is relevant.
-    ///            If an expression has been created by partial derivation, the variables always
-    ///            coincide with those of the antiderivatives even in cases where variables are
-    ///            irrelevant such as `(x)'=1`.
+    /// * `vars_rows` - iterator over slices of variable values, each slice is passed to
+    ///   [`eval`](Eval::eval)
+    /// * `sink` - receives the result of each row together with the row's index
     ///
     /// # Errors
     ///
-    /// If the number of variables in the parsed expression are different from the length of
-    /// the variable slice, we return an [`ExError`](super::result::ExError).
+    /// If the evaluation of a row fails, the error is passed to
+    /// [`EvalSink::reject`](crate::EvalSink::reject). Errors returned from there are
+    /// passed on and stop the evaluation.
     ///
-    fn eval(&self, vars: &[T]) -> ExResult<T>;
+    fn eval_into<'b, I, S>(&self, vars_rows: I, sink: &mut S) -> ExResult<()>
+    where
+        I: Iterator<Item = &'b [T]>,
+        S: EvalSink<T>,
+        T: 'b,
+    {
+        for (row_idx, vars) in vars_rows.enumerate() {
+            match self.eval(vars) {
+                Ok(value) => sink.accept(row_idx, value),
+                Err(e) => sink.reject(row_idx, e)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates a boolean expression for all combinations of its variables. Each row of
+    /// the table contains the values of the variables in alphabetical order and the
+    /// result. The first row contains only `false`s, the last row only `true`s.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// use exmex::{BinOp, MakeOperators, Operator, ops_factory};
+    /// ops_factory!(
+    ///     AndOpsFactory,
+    ///     bool,
+    ///     Operator::make_bin(
+    ///         "&&",
+    ///         BinOp{
+    ///             apply: |a, b| a && b,
+    ///             prio: 1,
+    ///             is_commutative: true,
+    ///         }
+    ///     )
+    /// );
+    /// let expr = FlatEx::<bool, AndOpsFactory>::from_str("a && b")?;
+    /// let table = expr.truth_table()?;
+    /// assert_eq!(table[0], (vec![false, false], false));
+    /// assert_eq!(table[1], (vec![false, true], false));
+    /// assert_eq!(table[3], (vec![true, true], true));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the expression has more than 20 variables, the table would have more than
+    /// `2^20` rows and we return an [`ExError`](super::result::ExError). Errors from
+    /// evaluation are passed on.
+    ///
+    fn truth_table(&self) -> ExResult<Vec<(Vec<bool>, bool)>>
+    where
+        T: From<bool> + Into<bool>,
+    {
+        let n_vars = self.n_vars();
+        if n_vars > N_VARS_TRUTH_TABLE_MAX {
+            return Err(format_exerr!(
+                "truth table of expression with {} variables is too large, at most {} are allowed",
+                n_vars,
+                N_VARS_TRUTH_TABLE_MAX
+            ));
+        }
+        (0..1usize << n_vars)
+            .map(|row_idx| {
+                // the first variable corresponds to the most significant bit
+                let bools = (0..n_vars)
+                    .map(|var_idx| (row_idx >> (n_vars - 1 - var_idx)) & 1 == 1)
+                    .collect::<Vec<_>>();
+                let vars = bools.iter().map(|b| T::from(*b)).collect::<Vec<_>>();
+                Ok((bools, self.eval(&vars)?.into()))
+            })
+            .collect()
+    }
 
     /// This method computes a new instance that is a partial derivative of
     /// `self` with default operators.
@@ -91,27 +559,767 @@ pub trait Express<'a, T> {
         T: DataType + Float,
         <T as FromStr>::Err: Debug;
 
-    /// Creates an expression string that corresponds to the `FlatEx` instance.
+    /// Computes the partial derivative like [`partial`](Express::partial) with respect to the
+    /// variable with name `var_name` instead of its index.
+    ///
+    /// # Example
+    ///
     /// ```rust
     /// # use std::error::Error;
     /// # fn main() -> Result<(), Box<dyn Error>> {
     /// #
     /// use exmex::prelude::*;
-    /// let flatex = FlatEx::<f64>::from_str("--sin ( z) +  {another var} + 1 + 2")?;
-    /// assert_eq!(format!("{}", flatex), "--sin ( z) +  {another var} + 1 + 2");
+    ///
+    /// let expr = FlatEx::<f64>::from_str("y*x^2")?;
+    /// let dexpr_dx = expr.partial_by_name("x")?;
+    /// assert!((dexpr_dx.eval(&[2.0, 3.0])? - 12.0).abs() < 1e-12);
     /// #
     /// #     Ok(())
     /// # }
     /// ```
+    /// # Errors
     ///
-    fn unparse(&self) -> ExResult<String>;
-
-    /// This function frees some memory. After calling [`partial`](Express::partial) memory might
-    /// be re-allocated.
-    fn reduce_memory(&mut self);
+    /// * If `var_name` is not a variable of the expression, we return an [`ExError`](super::result::ExError).
+    /// * The same errors as in [`partial`](Express::partial) are returned.
+    ///
+    fn partial_by_name(self, var_name: &str) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        match self.var_idx(var_name) {
+            Some(var_idx) => self.partial(var_idx),
+            None => Err(format_exerr!(
+                "cannot differentiate with respect to '{}', expected one of {:?}",
+                var_name,
+                self.var_names()
+            )),
+        }
+    }
 
-    /// Returns the number of variables of the expression
-    fn n_vars(&self) -> usize;
+    /// Returns true if the expression is linear in the variable with index `var_idx`, i.e.,
+    /// if its second partial derivative with respect to this variable is the constant `0`,
+    /// see [`is_const`](Express::is_const). Second derivatives that are zero but are not
+    /// simplified to a number, e.g., of `sin(x)^2+cos(x)^2`, are not recognized. If the
+    /// derivative cannot be computed, `false` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("2*x + 3*y*x")?;
+    /// assert!(expr.is_linear(0));
+    /// assert!(expr.is_linear(1));
+    /// // linear in each variable separately is not enough to be affine
+    /// assert!(!expr.is_affine());
+    /// let expr = FlatEx::<f64>::from_str("x^2 + y")?;
+    /// assert!(!expr.is_linear(0));
+    /// assert!(expr.is_linear(1));
+    /// assert!(!expr.is_affine());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn is_linear(&self, var_idx: usize) -> bool
+    where
+        Self: Sized + Clone,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        match self.clone().partial_n(var_idx, 2) {
+            Ok(dd) => {
+                // the derivative keeps the variables even if they do not occur anymore
+                dd.occurring_vars().is_empty()
+                    && dd
+                        .eval(&vec![T::zero(); dd.n_vars()])
+                        .is_ok_and(|v| v == T::zero())
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns true if the expression is affine, i.e., if all its first partial derivatives are
+    /// constant, see [`is_const`](Express::is_const). Equivalently, all second partial
+    /// derivatives including the mixed ones vanish. Hence, products of variables such as `x*y`
+    /// are not affine, although they are linear in each variable separately, see
+    /// [`is_linear`](Express::is_linear). Expressions without variables are affine. As for
+    /// [`is_linear`](Express::is_linear), derivatives that are constant but are not simplified
+    /// to a number are not recognized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// assert!(FlatEx::<f64>::from_str("2*x - 3*y + 1")?.is_affine());
+    /// assert!(!FlatEx::<f64>::from_str("x*y")?.is_affine());
+    /// assert!(!FlatEx::<f64>::from_str("x + sin(y)")?.is_affine());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn is_affine(&self) -> bool
+    where
+        Self: Sized + Clone,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        (0..self.n_vars()).all(|var_idx| self.clone().partial(var_idx).is_ok_and(|d| d.is_const()))
+    }
+
+    /// Computes the partial derivative of order `order` with respect to the variable with
+    /// index `var_idx`. In contrast to calling [`partial`](Express::partial) `order` times,
+    /// the intermediate derivatives are not flattened. For `order == 0` the expression is
+    /// returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x^3*y")?;
+    /// let d3_dx3 = expr.partial_n(0, 3)?;
+    /// assert!((d3_dx3.eval(&[2.0, 5.0])? - 30.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// The same errors as in [`partial`](Express::partial) are returned.
+    ///
+    fn partial_n(self, var_idx: usize, order: u32) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug;
+
+    /// Computes the partial derivatives with respect to all variables in the order of the
+    /// variable indices. The deep expression is created only once and shared for all
+    /// derivatives instead of once per call of [`partial`](Express::partial).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x^2 + y^2")?;
+    /// let gradient = expr.gradient()?;
+    /// assert_eq!(gradient.len(), 2);
+    /// assert!((gradient[0].eval(&[3.0, 4.0])? - 6.0).abs() < 1e-12);
+    /// assert!((gradient[1].eval(&[3.0, 4.0])? - 8.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// The same errors as in [`partial`](Express::partial) are returned, e.g., if `self` has
+    /// been [`reduce_memory`](Express::reduce_memory)ed and cannot be re-created from its text.
+    ///
+    fn gradient(self) -> ExResult<Vec<Self>>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug;
+
+    /// Computes the Hessian matrix, i.e., the second order partial derivatives
+    /// `∂²f/∂xᵢ∂xⱼ`, where the entry `[i][j]` is the derivative with respect to the
+    /// variables with indices `i` and `j`. Since the matrix is symmetric, only the upper
+    /// triangle is computed and the entries of the lower triangle are clones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x^2*y")?;
+    /// let hessian = expr.hessian()?;
+    /// assert!((hessian[0][0].eval(&[3.0, 4.0])? - 8.0).abs() < 1e-12);
+    /// assert!((hessian[0][1].eval(&[3.0, 4.0])? - 6.0).abs() < 1e-12);
+    /// assert!((hessian[1][1].eval(&[3.0, 4.0])?).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// The same errors as in [`partial`](Express::partial) are returned, e.g., if `self` has
+    /// been [`reduce_memory`](Express::reduce_memory)ed and cannot be re-created from its text.
+    ///
+    fn hessian(self) -> ExResult<Vec<Vec<Self>>>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug;
+
+    /// Computes the directional derivative `∇f·d` of the expression `f` along the constant
+    /// direction `d`, i.e., the sum of the partial derivatives weighted by the components
+    /// of `direction`. Partial derivatives of zero components are not computed and do not
+    /// appear in the result.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x^2 + y*z")?;
+    /// let dir_deriv = expr.directional(&[0.0, 2.0, 0.0])?;
+    /// assert_eq!(dir_deriv.unparse()?, "2.0*{z}");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If the length of `direction` differs from the number of variables, we return an
+    /// [`ExError`](super::result::ExError). Further, the errors of
+    /// [`partial`](Express::partial) can occur.
+    ///
+    fn directional(self, direction: &[T]) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug;
+
+    /// Computes the normalized sensitivities, also known as elasticities, of the expression
+    /// `f` at the point `vars`, i.e., `(∂f/∂x_i) * (x_i / f)` for each variable `x_i`.
+    /// Variables with larger absolute sensitivities have a larger relative impact on `f`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x^2 * y")?;
+    /// let sensitivity = expr.sensitivity(&[3.0, 4.0])?;
+    /// assert!((sensitivity[0] - 2.0).abs() < 1e-12);
+    /// assert!((sensitivity[1] - 1.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// * If `f` evaluates to zero at `vars`, the sensitivities are not defined and we return
+    ///   an [`ExError`](super::result::ExError).
+    /// * The errors of [`gradient`](Express::gradient) and [`eval`](Eval::eval) can occur.
+    ///
+    fn sensitivity(self, vars: &[T]) -> ExResult<Vec<T>>
+    where
+        Self: Sized,
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug,
+    {
+        let f = self.eval(vars)?;
+        if f == T::zero() {
+            return Err(format_exerr!(
+                "sensitivity at {:?} not defined, since the expression is zero there",
+                vars
+            ));
+        }
+        self.gradient()?
+            .iter()
+            .zip(vars.iter())
+            .map(|(d_i, x_i)| Ok(d_i.eval(vars)? * *x_i / f))
+            .collect()
+    }
+
+    /// Replaces the variables in `bindings` by their values and evaluates the resulting
+    /// constant sub-expressions. The returned expression depends only on the remaining
+    /// variables in alphabetical order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use std::collections::HashMap;
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("a*x + b")?;
+    /// let bindings = [("a", 2.0), ("b", 3.0)].iter().copied().collect::<HashMap<_, _>>();
+    /// let specialized = expr.partial_eval(&bindings)?;
+    /// assert_eq!(specialized.n_vars(), 1);
+    /// assert_eq!(specialized.unparse()?, "2.0*{x}+3.0");
+    /// assert!((specialized.eval(&[1.5])? - 6.0).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// * If `bindings` contains a name that is not a variable of the expression, we return an [`ExError`](super::result::ExError).
+    /// * If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn partial_eval(self, bindings: &HashMap<&str, T>) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// Replaces the variable with index `var_idx` by the expression `replacement`, e.g.,
+    /// substituting `y = sin(z)` into `x + y` results in `x + sin(z)`. The variables of the
+    /// result are the union of the remaining variables of `self` and the variables of
+    /// `replacement` in alphabetical order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("x + y")?;
+    /// let replacement = FlatEx::<f64>::from_str("sin(z)")?;
+    /// let substituted = expr.substitute(1, replacement)?;
+    /// assert_eq!(substituted.var_names(), ["x", "z"]);
+    /// assert!((substituted.eval(&[1.0, 0.5])? - (1.0 + 0.5f64.sin())).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// * If `var_idx` is not smaller than the number of variables, we return an [`ExError`](super::result::ExError).
+    /// * If `self` or `replacement` have been [`reduce_memory`](Express::reduce_memory)ed and have no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn substitute(self, var_idx: usize, replacement: Self) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// Composes the expression `self` of one variable with `inner`, i.e., plugs `inner` in for
+    /// the variable of `self`. The variables of the result are the ones of `inner`. This is
+    /// a special case of [`substitute`](Express::substitute), useful to chain models.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let f = FlatEx::<f64>::from_str("sin(u)")?;
+    /// let g = FlatEx::<f64>::from_str("x+y")?;
+    /// let f_of_g = f.compose(g)?;
+    /// assert_eq!(f_of_g.unparse()?, "sin({x}+{y})");
+    /// assert!((f_of_g.eval(&[0.5, 0.25])? - 0.75f64.sin()).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// * If `self` does not have exactly one variable, we return an [`ExError`](super::result::ExError).
+    /// * If `self` or `inner` have been [`reduce_memory`](Express::reduce_memory)ed and have no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn compose(self, inner: Self) -> ExResult<Self>
+    where
+        Self: Sized,
+        T: DataType,
+        <T as FromStr>::Err: Debug,
+    {
+        if self.n_vars() != 1 {
+            return Err(format_exerr!(
+                "composition needs an outer expression with one variable, found {}",
+                self.n_vars()
+            ));
+        }
+        self.substitute(0, inner)
+    }
+
+    /// Interprets the expression as polynomial in the variable with index `var_idx` and
+    /// returns its coefficients. The coefficient of the constant term comes first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("3*x^2 + 2*x + 1")?;
+    /// assert_eq!(expr.as_polynomial(0)?, vec![1.0, 2.0, 3.0]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Arguments
+    ///
+    /// * `var_idx` - variable the polynomial is built from
+    ///
+    /// # Errors
+    ///
+    /// * If the expression is not a polynomial in the given variable, e.g., since it
+    ///   contains `sin(x)`, other variables, or non-integer exponents, we return an [`ExError`](super::result::ExError).
+    /// * If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn as_polynomial(&self, var_idx: usize) -> ExResult<Vec<T>>
+    where
+        T: DataType + Float,
+        <T as FromStr>::Err: Debug;
+
+    /// Returns the ids of the operators used in the expression in alphabetical order, each
+    /// listed once. In contrast to representations such as `^`, ids such as `pow` are
+    /// stable, see [`Operator::with_id`](crate::Operator::with_id). Constants and operators
+    /// that have been evaluated during parsing such as the `*` in `2*3` are not listed.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("sin(x)^2 + 2*3")?;
+    /// assert_eq!(expr.used_operators()?, vec!["add", "pow", "sin"]);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn used_operators(&self) -> ExResult<Vec<String>>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// Returns the representations of the operators of the expression, each listed once, e.g.,
+    /// to check user formulas against a whitelist. The operators are ordered by their first
+    /// occurrence in the flat representation, i.e., roughly from left to right where unary
+    /// operators applied to the result of a binary operation follow the binary operator,
+    /// e.g., `sin(x/y)` results in `["/", "sin"]`. In contrast to [`used_operators`](Express::used_operators), the
+    /// operators are determined from the flat representation such that this also works
+    /// after [`reduce_memory`](Express::reduce_memory). Operators that have been evaluated
+    /// during parsing such as the `*` in `2*3` are not listed, unary and binary operators
+    /// with the same representation such as `-` are listed once. Operators that are not
+    /// found in the operator factory are represented by `?`.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let mut expr = exmex::parse::<f64>("sin(x)+y^2")?;
+    /// expr.reduce_memory();
+    /// assert_eq!(expr.operator_reprs(), ["sin", "+", "^"]);
+    /// assert!(expr.uses_operator("^"));
+    /// assert!(!expr.uses_operator("exp"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn operator_reprs(&self) -> Vec<String>;
+
+    /// Checks whether the operator with representation `repr` occurs in the expression, see
+    /// [`operator_reprs`](Express::operator_reprs).
+    fn uses_operator(&self, repr: &str) -> bool {
+        self.operator_reprs().iter().any(|r| r == repr)
+    }
+
+    /// Creates a LaTeX representation of the expression. Operators of
+    /// [`FloatOpsFactory`](crate::FloatOpsFactory) are mapped to their LaTeX counterparts,
+    /// e.g., `*` to `\cdot` and `sqrt` to `\sqrt{...}`. Other operators are written as
+    /// `\operatorname{repr}`. Nested fractions use `\dfrac`. Variable names with more than one
+    /// character or with LaTeX special characters are wrapped in `\text{}` and escaped.
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let flatex = FlatEx::<f64>::from_str("sin(x)/5 + x^2")?;
+    /// assert_eq!(flatex.unparse_latex()?, r"\frac{\sin(x)}{5}+x^{2}");
+    /// let flatex = FlatEx::<f64>::from_str("{rate_1}/(1/y)")?;
+    /// assert_eq!(flatex.unparse_latex()?, r"\frac{\text{rate\_1}}{\dfrac{1}{y}}");
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](super::result::ExError).
+    ///
+    #[doc(alias = "to_latex")]
+    fn unparse_latex(&self) -> ExResult<String>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// Creates a [Graphviz](https://graphviz.org/) DOT representation of the expression tree
+    /// with nodes labeled by operators, numbers, and variable names. Binary operators point to
+    /// their left and right operands in the order of evaluation.
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// let flatex = FlatEx::<f64>::from_str("sin(x)/5")?;
+    /// let dot = flatex.to_dot()?;
+    /// assert!(dot.starts_with("digraph {"));
+    /// assert!(dot.contains("[label=\"sin\"]"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If `self` has been [`reduce_memory`](Express::reduce_memory)ed and has no text, we return an [`ExError`](super::result::ExError).
+    ///
+    fn to_dot(&self) -> ExResult<String>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// This function frees some memory. After calling [`partial`](Express::partial) memory might
+    /// be re-allocated.
+    fn reduce_memory(&mut self);
+
+    /// Returns the index of the variable with name `var_name`, i.e., the position of its value
+    /// in the slice passed to [`eval`](Eval::eval), or `None` if there is no such variable.
+    fn var_idx(&self, var_name: &str) -> Option<usize> {
+        self.var_names().iter().position(|name| *name == var_name)
+    }
+
+    /// Returns `true` if the variable `var_name` occurs in the compiled expression, i.e., if it
+    /// is contained in [`occurring_vars`](Express::occurring_vars). Names can be passed with
+    /// or without curly brackets. Variables that have vanished, e.g., after partial
+    /// differentiation of `x` to `1`, are still contained in [`var_names`](Eval::var_names)
+    /// but the expression does not depend on them anymore.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("PI*x+{χ}")?;
+    /// assert!(expr.depends_on_var("χ"));
+    /// assert!(expr.depends_on_var("{χ}"));
+    /// assert!(!expr.depends_on_var("PI"));
+    /// assert_eq!(expr.occurring_vars(), ["x", "χ"]);
+    /// let d_expr = expr.partial(0)?;
+    /// assert_eq!(d_expr.var_names(), ["x", "χ"]);
+    /// assert!(!d_expr.depends_on_var("x"));
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn depends_on_var(&self, var_name: &str) -> bool {
+        let var_name = var_name
+            .strip_prefix('{')
+            .and_then(|name| name.strip_suffix('}'))
+            .unwrap_or(var_name);
+        self.occurring_vars().contains(&var_name)
+    }
+
+    /// Returns the names of the variables that occur in the compiled expression in
+    /// alphabetical order, i.e., the variables the expression depends on, see
+    /// [`depends_on_var`](Express::depends_on_var). In contrast to
+    /// [`var_names`](Eval::var_names), variables that have vanished, e.g., by
+    /// differentiation or by [`simplify`](crate::FlatEx::simplify), are not contained. Operations are not analyzed symbolically, e.g., `x-x` still
+    /// depends on `x`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("3*x+y^2")?;
+    /// assert_eq!(expr.occurring_vars(), ["x", "y"]);
+    /// let d_expr = expr.partial(0)?;
+    /// assert_eq!(d_expr.var_names(), ["x", "y"]);
+    /// assert!(d_expr.occurring_vars().is_empty());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn occurring_vars(&self) -> Vec<&str>;
+
+    /// Returns the number of binary operations that are executed during evaluation.
+    /// Operations between numbers that have been computed during parsing are not counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("sin(cos(x+1))*2")?;
+    /// assert_eq!(expr.n_binops(), 2);
+    /// assert_eq!(expr.n_unary_ops(), 2);
+    /// assert_eq!(expr.depth()?, 3);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn n_binops(&self) -> usize;
+
+    /// Returns the number of unary operations that are executed during evaluation, see
+    /// [`n_binops`](Express::n_binops).
+    fn n_unary_ops(&self) -> usize;
+
+    /// Returns the nesting depth of the expression, i.e., 1 for an expression without
+    /// sub-expressions such as `2*x+1`, 2 for `sin(x)+1`, and 3 for `sin(cos(x+1))*2`.
+    /// Redundant parentheses do not count. The depth is still available after
+    /// [`reduce_memory`](Express::reduce_memory).
+    ///
+    /// # Errors
+    ///
+    /// If the deep expression needs to be re-created from the text and this fails, an
+    /// [`ExError`](ExError) is returned.
+    ///
+    fn depth(&self) -> ExResult<usize>
+    where
+        T: DataType,
+        <T as FromStr>::Err: Debug;
+
+    /// Returns true if no variable occurs in the expression and all its nodes are numbers
+    /// after compilation, i.e., if [`occurring_vars`](Express::occurring_vars) is empty
+    /// and nothing is left to compute. Derivatives that have been simplified to a number are
+    /// constant, although they still know the variables of the original expression. Numbers
+    /// with unary operators, e.g., of uncompiled expressions, and expressions of stochastic
+    /// operator factories are not constant.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("sin(PI/2)*2")?;
+    /// assert!(expr.is_const());
+    /// assert_eq!(expr.const_value()?, 2.0);
+    /// let expr = FlatEx::<f64>::from_str("x^2")?;
+    /// assert!(!expr.is_const());
+    /// let dd_expr = expr.partial_n(0, 2)?;
+    /// assert_eq!(dd_expr.n_vars(), 1);
+    /// assert!(dd_expr.is_const());
+    /// assert_eq!(dd_expr.const_value()?, 2.0);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn is_const(&self) -> bool;
+
+    /// Evaluates a constant expression, see [`is_const`](Express::is_const), without values
+    /// of variables.
+    ///
+    /// # Errors
+    ///
+    /// If the expression is not constant, we return an [`ExError`](ExError).
+    ///
+    fn const_value(&self) -> ExResult<T>;
+
+    /// Same as [`is_const`](Express::is_const).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    ///
+    /// let expr = FlatEx::<f64>::from_str("3.14")?;
+    /// assert!(expr.is_constant());
+    /// assert_eq!(expr.eval_if_constant().unwrap()?, 3.14);
+    /// let expr = FlatEx::<f64>::from_str("x+1")?;
+    /// assert!(!expr.is_constant());
+    /// assert!(expr.eval_if_constant().is_none());
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    fn is_constant(&self) -> bool {
+        self.is_const()
+    }
+
+    /// Evaluates the expression if it is constant, see [`is_const`](Express::is_const),
+    /// and returns `None` otherwise.
+    fn eval_if_constant(&self) -> Option<ExResult<T>> {
+        if self.is_const() {
+            Some(self.const_value())
+        } else {
+            None
+        }
+    }
+}
+
+/// Construction of expressions that do not borrow the parsed string. Code that is generic
+/// over [`FlatEx`](flat::FlatEx) and [`OwnedFlatEx`](flat::OwnedFlatEx) can use this trait
+/// to create expressions from strings that are only available at runtime.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, ExResult, OwnedFlatEx, ParseExpress};
+/// fn make<E: ParseExpress<f64>>(s: &str) -> ExResult<E::Owned> {
+///     E::parse_owned(s)
+/// }
+/// let text = String::from("2*x+y");
+/// let from_flatex = make::<FlatEx<f64>>(&text)?;
+/// let from_owned = make::<OwnedFlatEx<f64>>(&text)?;
+/// drop(text);
+/// assert!((from_flatex.eval(&[1.0, 3.0])? - 5.0).abs() < 1e-12);
+/// assert!((from_owned.eval(&[1.0, 3.0])? - 5.0).abs() < 1e-12);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub trait ParseExpress<T> {
+    /// Expression type without lifetime parameter that owns its string.
+    type Owned: for<'a> Express<'a, T>;
+    /// Parses `text` into an expression that does not borrow `text`.
+    fn parse_owned(text: &str) -> ExResult<Self::Owned>;
 }
 
 /// Implement this trait to create a matcher for custom literals of operands.
@@ -1,10 +1,13 @@
 use crate::{
     definitions::N_BINOPS_OF_DEEPEX_ON_STACK,
     expression::{
-        deep::{BinOpsWithReprs, DeepEx, DeepNode, ExprIdxVec, UnaryOpWithReprs},
+        deep::{
+            BinOpsWithReprs, DeepEx, DeepNode, ExprIdxVec, TernaryOpWithArgs, UnaryOpWithReprs,
+        },
         deep_details,
     },
-    operators::{Operator, UnaryOp},
+    format_exerr,
+    operators::{Operator, TernaryOp, UnaryOp},
     ExError, ExResult,
 };
 use num::Float;
@@ -44,10 +47,12 @@ fn find_as_bin_op_with_reprs<'a, T: Copy + Debug>(
 ) -> ExResult<BinOpsWithReprs<'a, T>> {
     let op = find_op(repr, ops).ok_or(ExError {
         msg: format!("did not find operator {}", repr),
+        position: None,
     })?;
     Ok(BinOpsWithReprs {
         reprs: smallvec![op.repr()],
         ops: smallvec![op.bin()?],
+        is_function_style: smallvec![op.is_function_style()],
     })
 }
 
@@ -57,6 +62,7 @@ fn find_as_unary_op_with_reprs<'a, T: Copy + Debug>(
 ) -> ExResult<UnaryOpWithReprs<'a, T>> {
     let op = find_op(repr, ops).ok_or(ExError {
         msg: format!("did not find operator {}", repr),
+        position: None,
     })?;
     Ok(UnaryOpWithReprs {
         reprs: smallvec![op.repr()],
@@ -67,6 +73,7 @@ fn find_as_unary_op_with_reprs<'a, T: Copy + Debug>(
 fn make_op_missing_err(repr: &str) -> ExError {
     ExError {
         msg: format!("operator {} needed for outer partial derivative", repr),
+        position: None,
     }
 }
 
@@ -93,6 +100,67 @@ fn partial_derivative_outer<'a, T: Float + Debug>(
     })
 }
 
+/// The derivative of a selection `if(c, a, b)` selects between the derivatives of the
+/// branches, i.e., `if(c, a', b')`. Since `wrap_range(x, lo, hi)` is `x` minus a piecewise
+/// constant multiple of `hi - lo`, its derivative is
+/// `x' - (x - wrap_range(x, lo, hi)) / (hi - lo) * (hi' - lo')`.
+fn partial_ternary<'a, T: Float + Debug>(
+    var_idx: usize,
+    ternary: &TernaryOpWithArgs<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    let [c, a, b] = &ternary.args;
+    match ternary.op {
+        TernaryOp::Select(_) => {
+            let d_ternary = TernaryOpWithArgs {
+                repr: ternary.repr,
+                op: ternary.op,
+                args: [
+                    c.clone(),
+                    partial_deepex(var_idx, a.clone(), ops)?,
+                    partial_deepex(var_idx, b.clone(), ops)?,
+                ],
+            };
+            DeepEx::new(
+                vec![DeepNode::Ternary(Box::new(d_ternary))],
+                BinOpsWithReprs::new(),
+                UnaryOpWithReprs::new(),
+            )
+        }
+        TernaryOp::Apply(_) if ternary.repr == "wrap_range" => {
+            let [x, lo, hi] = &ternary.args;
+            let d_x = partial_deepex(var_idx, x.clone(), ops)?;
+            let d_width = sub(
+                partial_deepex(var_idx, hi.clone(), ops)?,
+                partial_deepex(var_idx, lo.clone(), ops)?,
+                sub_find(ops)?,
+            )?;
+            if d_width.is_zero() {
+                return Ok(d_x);
+            }
+            let wrapped = DeepEx::new(
+                vec![DeepNode::Ternary(Box::new(ternary.clone()))],
+                BinOpsWithReprs::new(),
+                UnaryOpWithReprs::new(),
+            )?;
+            let n_periods = div(
+                sub(x.clone(), wrapped, sub_find(ops)?)?,
+                sub(hi.clone(), lo.clone(), sub_find(ops)?)?,
+                div_find(ops)?,
+            )?;
+            sub(
+                d_x,
+                mul(n_periods, d_width, mul_find(ops)?)?,
+                sub_find(ops)?,
+            )
+        }
+        TernaryOp::Apply(_) => Err(format_exerr!(
+            "derivative operator of {} needed for partial derivative",
+            ternary.repr
+        )),
+    }
+}
+
 fn partial_derivative_inner<'a, T: Float + Debug>(
     var_idx: usize,
     deepex: DeepEx<'a, T>,
@@ -111,6 +179,7 @@ fn partial_derivative_inner<'a, T: Float + Debug>(
                 }
             }
             DeepNode::Expr(e) => partial_deepex(var_idx, *e, ops)?,
+            DeepNode::Ternary(t) => partial_ternary(var_idx, &t, ops)?,
         };
         let (res, _) = res.var_names_union(deepex);
         return Ok(res);
@@ -149,6 +218,7 @@ fn partial_derivative_inner<'a, T: Float + Debug>(
                         "derivative operator of {} needed for partial derivative",
                         repr
                     ),
+                    position: None,
                 })
         })
         .collect::<ExResult<SmallVec<[&PartialDerivative<'a, T>; N_BINOPS_OF_DEEPEX_ON_STACK]>>>(
@@ -166,10 +236,12 @@ fn partial_derivative_inner<'a, T: Float + Debug>(
             let pdo = &partial_bin_ops_of_deepex[bin_op_idx];
             pdo.bin_op.ok_or(ExError {
                 msg: format!("cannot find binary op for {}", pdo.repr),
+                position: None,
             })?(n1, n2, ops)
         } else {
             Err(ExError {
                 msg: "nodes do not contain values in partial derivative".to_string(),
+                position: None,
             })
         }?;
         nodes[num_idx] = Some(pd_deepex);
@@ -186,6 +258,7 @@ fn partial_derivative_inner<'a, T: Float + Debug>(
         .take()
         .ok_or(ExError {
             msg: "node 0 needs to contain valder at the end of partial derviative".to_string(),
+            position: None,
         })?
         .der;
     let (res, _) = res.var_names_union(deepex);
@@ -203,6 +276,64 @@ pub fn partial_deepex<'a, T: Float + Debug>(
     mul(inner, outer, mul_find(ops)?)
 }
 
+/// Differentiates `order` times with respect to the variable with index `var_idx`. The
+/// intermediate derivatives are not flattened.
+pub fn partial_deepex_n<'a, T: Float + Debug>(
+    var_idx: usize,
+    order: u32,
+    deepex: DeepEx<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    (0..order).try_fold(deepex, |d, _| partial_deepex(var_idx, d, ops))
+}
+
+/// Computes the matrix of second order partial derivatives. Only the upper triangle is
+/// differentiated, the lower triangle is mirrored.
+pub fn hessian_deepex<'a, T: Float + Debug>(
+    deepex: DeepEx<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<Vec<Vec<DeepEx<'a, T>>>> {
+    let n_vars = deepex.n_vars();
+    let mut res: Vec<Vec<DeepEx<'a, T>>> = Vec::with_capacity(n_vars);
+    for i in 0..n_vars {
+        let d_i = partial_deepex(i, deepex.clone(), ops)?;
+        let mut row = Vec::with_capacity(n_vars);
+        row.extend(res.iter().map(|upper_row| upper_row[i].clone()));
+        for j in i..n_vars {
+            row.push(partial_deepex(j, d_i.clone(), ops)?);
+        }
+        res.push(row);
+    }
+    Ok(res)
+}
+
+/// Computes the directional derivative, i.e., the sum of the partial derivatives weighted by
+/// the components of `direction`. Terms of zero components are not created.
+pub fn directional_deepex<'a, T: Float + Debug>(
+    direction: &[T],
+    deepex: DeepEx<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    if direction.len() != deepex.n_vars() {
+        return Err(format_exerr!(
+            "direction has {} components but the expression has {} variables",
+            direction.len(),
+            deepex.n_vars()
+        ));
+    }
+    let mut res = DeepEx::zero().var_names_like_other(&deepex);
+    for (var_idx, component) in direction.iter().enumerate() {
+        if *component == T::zero() {
+            continue;
+        }
+        let d_i = partial_deepex(var_idx, deepex.clone(), ops)?;
+        let term = mul(DeepEx::from_num(*component), d_i, mul_find(ops)?)?;
+        res = add(res, term, add_find(ops)?)?;
+    }
+    res.compile();
+    Ok(res)
+}
+
 fn add<'a, T: Float + Debug>(
     summand_1: DeepEx<'a, T>,
     summand_2: DeepEx<'a, T>,
@@ -280,6 +411,7 @@ fn pow<'a, T: Float + Debug>(
     Ok(if base.is_zero() && exponent.is_zero() {
         return Err(ExError {
             msg: "base and exponent both zero. help. fatal. ah. help.".to_string(),
+            position: None,
         });
     } else if base.is_zero() {
         zero
@@ -391,7 +523,7 @@ pub fn make_partial_derivative_ops<'a, T: Float + Debug>() -> Vec<PartialDerivat
                 |_: DeepEx<'a, T>, ops: &[Operator<'a, T>]| -> ExResult<DeepEx<'a, T>> {
                     let one = DeepEx::one();
                     let minus = minus_find_unary(ops)?;
-                    Ok(one.with_new_unary_op(minus))
+                    Ok(one.operate_unary(minus))
                 },
             ),
         },
@@ -441,6 +573,111 @@ pub fn make_partial_derivative_ops<'a, T: Float + Debug>() -> Vec<PartialDerivat
             ),
             unary_outer_op: None,
         },
+        PartialDerivative {
+            repr: "%",
+            bin_op: Some(
+                |f: ValueDerivative<T>,
+                 g: ValueDerivative<T>,
+                 ops: &[Operator<'a, T>]|
+                 -> ExResult<ValueDerivative<T>> {
+                    let rem_op = find_as_bin_op_with_reprs("%", ops)?;
+                    let mul_op = mul_find(ops)?;
+                    let div_op = div_find(ops)?;
+                    let sub_op = sub_find(ops)?;
+
+                    let val = f.val.clone().operate_bin(g.val.clone(), rem_op);
+
+                    // f = q*g + val with the integer q = (f - val)/g that is constant apart
+                    // from the discontinuities, which are ignored
+                    let quotient = div(sub(f.val, val.clone(), sub_op.clone())?, g.val, div_op)?;
+                    let der = sub(f.der, mul(g.der, quotient, mul_op)?, sub_op)?;
+                    Ok(ValueDerivative { val, der })
+                },
+            ),
+            unary_outer_op: None,
+        },
+        PartialDerivative {
+            repr: "atan2",
+            bin_op: Some(
+                |f: ValueDerivative<T>,
+                 g: ValueDerivative<T>,
+                 ops: &[Operator<'a, T>]|
+                 -> ExResult<ValueDerivative<T>> {
+                    let atan2_op = find_as_bin_op_with_reprs("atan2", ops)?;
+                    let mul_op = mul_find(ops)?;
+                    let div_op = div_find(ops)?;
+                    let add_op = add_find(ops)?;
+                    let sub_op = sub_find(ops)?;
+
+                    let val = f.val.clone().operate_bin(g.val.clone(), atan2_op);
+
+                    let numerator = sub(
+                        mul(g.val.clone(), f.der, mul_op.clone())?,
+                        mul(f.val.clone(), g.der, mul_op.clone())?,
+                        sub_op,
+                    )?;
+                    let denominator = add(
+                        mul(f.val.clone(), f.val, mul_op.clone())?,
+                        mul(g.val.clone(), g.val, mul_op)?,
+                        add_op,
+                    )?;
+                    Ok(ValueDerivative {
+                        val,
+                        der: div(numerator, denominator, div_op)?,
+                    })
+                },
+            ),
+            unary_outer_op: None,
+        },
+        PartialDerivative {
+            repr: "absdiff",
+            bin_op: Some(
+                |f: ValueDerivative<T>,
+                 g: ValueDerivative<T>,
+                 ops: &[Operator<'a, T>]|
+                 -> ExResult<ValueDerivative<T>> {
+                    let absdiff_op = find_as_bin_op_with_reprs("absdiff", ops)?;
+                    let sign_op = find_as_unary_op_with_reprs("sign", ops)?;
+                    let mul_op = mul_find(ops)?;
+                    let sub_op = sub_find(ops)?;
+
+                    let val = f.val.clone().operate_bin(g.val.clone(), absdiff_op);
+
+                    // the sign of zero is zero, hence the subgradient 0 is used for f == g
+                    let sign = sub(f.val, g.val, sub_op.clone())?.operate_unary(sign_op);
+                    let der = sub(f.der, g.der, sub_op)?;
+                    Ok(ValueDerivative {
+                        val,
+                        der: mul(sign, der, mul_op)?,
+                    })
+                },
+            ),
+            unary_outer_op: None,
+        },
+        PartialDerivative {
+            repr: "sqdiff",
+            bin_op: Some(
+                |f: ValueDerivative<T>,
+                 g: ValueDerivative<T>,
+                 ops: &[Operator<'a, T>]|
+                 -> ExResult<ValueDerivative<T>> {
+                    let sqdiff_op = find_as_bin_op_with_reprs("sqdiff", ops)?;
+                    let mul_op = mul_find(ops)?;
+                    let sub_op = sub_find(ops)?;
+
+                    let val = f.val.clone().operate_bin(g.val.clone(), sqdiff_op);
+
+                    let two = DeepEx::from_num(T::from(2.0).unwrap());
+                    let diff = sub(f.val, g.val, sub_op.clone())?;
+                    let der = sub(f.der, g.der, sub_op)?;
+                    Ok(ValueDerivative {
+                        val,
+                        der: mul(mul(two, diff, mul_op.clone())?, der, mul_op)?,
+                    })
+                },
+            ),
+            unary_outer_op: None,
+        },
         PartialDerivative {
             repr: "sqrt",
             bin_op: None,
@@ -454,6 +691,21 @@ pub fn make_partial_derivative_ops<'a, T: Float + Debug>() -> Vec<PartialDerivat
                 },
             ),
         },
+        PartialDerivative {
+            repr: "recip",
+            bin_op: None,
+            unary_outer_op: Some(
+                |f: DeepEx<'a, T>, ops: &[Operator<'a, T>]| -> ExResult<DeepEx<'a, T>> {
+                    let pow_op = pow_find(ops)?;
+                    let div_op = div_find(ops)?;
+                    let minus_op = minus_find_unary(ops)?;
+                    let two = DeepEx::from_num(T::from(2.0).unwrap());
+                    let inner_squared =
+                        pow(f.with_new_unary_op(UnaryOpWithReprs::new()), two, pow_op)?;
+                    Ok(div(DeepEx::one(), inner_squared, div_op)?.operate_unary(minus_op))
+                },
+            ),
+        },
         PartialDerivative {
             repr: "log",
             bin_op: None,
@@ -475,6 +727,28 @@ pub fn make_partial_derivative_ops<'a, T: Float + Debug>() -> Vec<PartialDerivat
                 |f: DeepEx<'a, T>, _: &[Operator<'a, T>]| -> ExResult<DeepEx<'a, T>> { Ok(f) },
             ),
         },
+        PartialDerivative {
+            repr: "exp2",
+            bin_op: None,
+            unary_outer_op: Some(
+                |f: DeepEx<'a, T>, ops: &[Operator<'a, T>]| -> ExResult<DeepEx<'a, T>> {
+                    let mul_op = mul_find(ops)?;
+                    let ln2 = DeepEx::from_num(T::from(std::f64::consts::LN_2).unwrap());
+                    mul(f, ln2, mul_op)
+                },
+            ),
+        },
+        PartialDerivative {
+            repr: "exp10",
+            bin_op: None,
+            unary_outer_op: Some(
+                |f: DeepEx<'a, T>, ops: &[Operator<'a, T>]| -> ExResult<DeepEx<'a, T>> {
+                    let mul_op = mul_find(ops)?;
+                    let ln10 = DeepEx::from_num(T::from(std::f64::consts::LN_10).unwrap());
+                    mul(f, ln10, mul_op)
+                },
+            ),
+        },
         PartialDerivative {
             repr: "sin",
             bin_op: None,
@@ -0,0 +1,181 @@
+use crate::{
+    expression::{
+        deep::{DeepEx, DeepNode},
+        deep_details,
+    },
+    format_exerr,
+    operators::BinOp,
+    ExError, ExResult,
+};
+use num::Float;
+use std::fmt::Debug;
+
+fn trim<T: Float>(mut coeffs: Vec<T>) -> Vec<T> {
+    while coeffs.len() > 1 && coeffs[coeffs.len() - 1] == T::zero() {
+        coeffs.pop();
+    }
+    coeffs
+}
+
+fn add<T: Float>(p1: &[T], p2: &[T]) -> Vec<T> {
+    let n = p1.len().max(p2.len());
+    let get = |p: &[T], i: usize| if i < p.len() { p[i] } else { T::zero() };
+    trim((0..n).map(|i| get(p1, i) + get(p2, i)).collect())
+}
+
+fn neg<T: Float>(p: &[T]) -> Vec<T> {
+    p.iter().map(|c| -*c).collect()
+}
+
+fn mul<T: Float>(p1: &[T], p2: &[T]) -> Vec<T> {
+    let mut res = vec![T::zero(); p1.len() + p2.len() - 1];
+    for (i, c1) in p1.iter().enumerate() {
+        for (j, c2) in p2.iter().enumerate() {
+            res[i + j] = res[i + j] + *c1 * *c2;
+        }
+    }
+    trim(res)
+}
+
+fn not_polynomial_err(reason: &str, var_idx: usize) -> ExError {
+    format_exerr!(
+        "expression is not a polynomial in the variable with index {}, {}",
+        var_idx,
+        reason
+    )
+}
+
+fn apply_bin<T: Float + Debug>(
+    p1: Vec<T>,
+    p2: Vec<T>,
+    repr: &str,
+    bin_op: &BinOp<T>,
+    var_idx: usize,
+) -> ExResult<Vec<T>> {
+    if p1.len() == 1 && p2.len() == 1 {
+        return Ok(vec![(bin_op.apply)(p1[0], p2[0])]);
+    }
+    match repr {
+        "+" => Ok(add(&p1, &p2)),
+        "-" => Ok(add(&p1, &neg(&p2))),
+        "*" => Ok(mul(&p1, &p2)),
+        "/" if p2.len() == 1 && p2[0] != T::zero() => {
+            Ok(trim(p1.iter().map(|c| *c / p2[0]).collect()))
+        }
+        "^" if p2.len() == 1 && p2[0] >= T::zero() && p2[0].fract() == T::zero() => {
+            let exponent = p2[0].to_usize().ok_or_else(|| {
+                not_polynomial_err(&format!("cannot use {:?} as exponent", p2[0]), var_idx)
+            })?;
+            Ok((0..exponent).fold(vec![T::one()], |res, _| mul(&res, &p1)))
+        }
+        _ => Err(not_polynomial_err(
+            &format!("binary operator '{}' is not supported here", repr),
+            var_idx,
+        )),
+    }
+}
+
+fn polynomial_of_node<T: Float + Debug>(var_idx: usize, node: &DeepNode<T>) -> ExResult<Vec<T>> {
+    match node {
+        DeepNode::Num(n) => Ok(vec![*n]),
+        DeepNode::Var((idx, var_name)) => {
+            if *idx == var_idx {
+                Ok(vec![T::zero(), T::one()])
+            } else {
+                Err(not_polynomial_err(
+                    &format!("it contains the variable {}", var_name),
+                    var_idx,
+                ))
+            }
+        }
+        DeepNode::Expr(e) => polynomial_deepex(var_idx, e),
+        DeepNode::Ternary(t) => Err(not_polynomial_err(
+            &format!("ternary operator '{}' is not supported here", t.repr),
+            var_idx,
+        )),
+    }
+}
+
+/// Collects the coefficients of a deep expression that is a polynomial in the variable
+/// with index `var_idx`. The coefficient of the constant term comes first.
+pub fn polynomial_deepex<T: Float + Debug>(var_idx: usize, deepex: &DeepEx<T>) -> ExResult<Vec<T>> {
+    let mut polys = deepex
+        .nodes()
+        .iter()
+        .map(|node| polynomial_of_node(var_idx, node))
+        .collect::<ExResult<Vec<_>>>()?;
+
+    let prio_indices = deep_details::prioritized_indices(&deepex.bin_ops().ops, deepex.nodes());
+    let mut num_inds = prio_indices.clone();
+    for (i, &bin_op_idx) in prio_indices.iter().enumerate() {
+        let num_idx = num_inds[i];
+        let p2 = polys.remove(num_idx + 1);
+        let p1 = std::mem::take(&mut polys[num_idx]);
+        polys[num_idx] = apply_bin(
+            p1,
+            p2,
+            deepex.bin_ops().reprs[bin_op_idx],
+            &deepex.bin_ops().ops[bin_op_idx],
+            var_idx,
+        )?;
+        // reduce indices after removed position
+        for num_idx_after in num_inds.iter_mut() {
+            if *num_idx_after > num_idx {
+                *num_idx_after -= 1;
+            }
+        }
+    }
+    let res = trim(polys.remove(0));
+    let unary_op = deepex.unary_op();
+    if unary_op.op.is_empty() {
+        Ok(res)
+    } else if res.len() == 1 {
+        Ok(vec![unary_op.op.apply(res[0])])
+    } else {
+        unary_op
+            .reprs
+            .iter()
+            .try_fold(res, |res, repr| match *repr {
+                "-" => Ok(neg(&res)),
+                "+" => Ok(res),
+                _ => Err(not_polynomial_err(
+                    &format!("unary operator '{}' is applied to the variable", repr),
+                    var_idx,
+                )),
+            })
+    }
+}
+
+#[cfg(test)]
+use crate::expression::deep;
+
+#[test]
+fn test_polynomial() -> ExResult<()> {
+    fn test(text: &str, var_idx: usize, reference: &[f64]) -> ExResult<()> {
+        let deepex = deep::from_str(text)?;
+        assert_eq!(polynomial_deepex(var_idx, &deepex)?, reference);
+        Ok(())
+    }
+    test("3*x^2 + 2*x + 1", 0, &[1.0, 2.0, 3.0])?;
+    test("x", 0, &[0.0, 1.0])?;
+    test("2.5", 0, &[2.5])?;
+    test("-(x-1)*(x+1)", 0, &[1.0, 0.0, -1.0])?;
+    test("(x+1)^3/2", 0, &[0.5, 1.5, 1.5, 0.5])?;
+    test("x^2 - x*x + sin(0)*x + 4", 0, &[4.0])?;
+    test("y*2+1", 0, &[1.0, 2.0])?;
+    test("x^0", 0, &[1.0])?;
+
+    fn test_err(text: &str, var_idx: usize) -> ExResult<()> {
+        let deepex = deep::from_str(text)?;
+        assert!(polynomial_deepex(var_idx, &deepex).is_err());
+        Ok(())
+    }
+    test_err("sin(x)", 0)?;
+    test_err("x+y", 0)?;
+    test_err("1/x", 0)?;
+    test_err("x^0.5", 0)?;
+    test_err("x^x", 0)?;
+    test_err("2^x", 0)?;
+    test_err("x^(-1)", 0)?;
+    Ok(())
+}
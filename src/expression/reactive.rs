@@ -0,0 +1,320 @@
+use std::{fmt::Debug, str::FromStr};
+
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    data_type::DataType,
+    definitions::N_NODES_ON_STACK,
+    expression::{
+        deep::{DeepEx, DeepNode},
+        deep_details,
+        flat::{FlatEx, OwnedFlatEx},
+        MatchLiteral,
+    },
+    format_exerr,
+    operators::{BinOp, TernaryOp, UnaryOp},
+    ExError, ExResult, MakeOperators,
+};
+
+#[derive(Clone, Debug)]
+enum ReactiveNode<T> {
+    Num(T),
+    Var(usize),
+    /// Index of the sub-expression in the list of sub-expressions of [`ReactiveEx`].
+    Expr(usize),
+    /// Ternary operator and the indices of the sub-expressions of its arguments.
+    Ternary(TernaryOp<T>, [usize; 3]),
+}
+
+#[derive(Clone, Debug)]
+struct SubEx<T: Clone> {
+    nodes: Vec<ReactiveNode<T>>,
+    bin_ops: Vec<BinOp<T>>,
+    prio_indices: Vec<usize>,
+    unary_op: UnaryOp<T>,
+    /// Sorted indices of the variables this sub-expression depends on.
+    var_indices: Vec<usize>,
+    /// Memoized value, `None` if the sub-expression has to be re-computed.
+    value: Option<T>,
+}
+
+/// Expression that memoizes the values of its sub-expressions and is evaluated
+/// incrementally. Variables are set one by one. When a variable changes, only the
+/// sub-expressions that contain the variable are re-computed by the next call of
+/// [`eval`](ReactiveEx::eval).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, ReactiveEx};
+/// let expr = FlatEx::<f64>::from_str("sin(x)*(y+2)")?;
+/// let mut reactive = ReactiveEx::from_flatex(&expr)?;
+/// reactive.set_var(0, 0.0)?;
+/// reactive.set_var_by_name("y", 1.0)?;
+/// assert_eq!(reactive.eval()?, 0.0);
+/// // only the sub-expression y+2 and the root are re-computed
+/// reactive.set_var_by_name("y", 3.0)?;
+/// assert_eq!(reactive.n_outdated(), 2);
+/// assert_eq!(reactive.eval()?, expr.eval(&[0.0, 3.0])?);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReactiveEx<T: Clone> {
+    /// Sub-expressions in post-order, i.e., children come before their parents and the
+    /// root is the last element.
+    sub_exprs: Vec<SubEx<T>>,
+    var_names: Vec<String>,
+    vars: Vec<Option<T>>,
+}
+
+impl<T> ReactiveEx<T>
+where
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+{
+    fn from_deepex(deepex: &DeepEx<T>) -> Self {
+        fn collect<T: DataType>(deepex: &DeepEx<T>, sub_exprs: &mut Vec<SubEx<T>>) -> usize {
+            let mut var_indices = vec![];
+            let nodes = deepex
+                .nodes()
+                .iter()
+                .map(|node| match node {
+                    DeepNode::Num(n) => ReactiveNode::Num(n.clone()),
+                    DeepNode::Var((idx, _)) => {
+                        var_indices.push(*idx);
+                        ReactiveNode::Var(*idx)
+                    }
+                    DeepNode::Expr(e) => {
+                        let sub_idx = collect(e, sub_exprs);
+                        var_indices.extend(sub_exprs[sub_idx].var_indices.iter().copied());
+                        ReactiveNode::Expr(sub_idx)
+                    }
+                    DeepNode::Ternary(t) => {
+                        let [c, a, b] = &t.args;
+                        let arg_indices = [
+                            collect(c, sub_exprs),
+                            collect(a, sub_exprs),
+                            collect(b, sub_exprs),
+                        ];
+                        for arg_idx in arg_indices {
+                            var_indices.extend(sub_exprs[arg_idx].var_indices.iter().copied());
+                        }
+                        ReactiveNode::Ternary(t.op, arg_indices)
+                    }
+                })
+                .collect::<Vec<_>>();
+            var_indices.sort_unstable();
+            var_indices.dedup();
+            let bin_ops = &deepex.bin_ops().ops;
+            sub_exprs.push(SubEx {
+                nodes,
+                bin_ops: bin_ops.to_vec(),
+                prio_indices: deep_details::prioritized_indices(bin_ops, deepex.nodes()).to_vec(),
+                unary_op: deepex.unary_op().op.clone(),
+                var_indices,
+                value: None,
+            });
+            sub_exprs.len() - 1
+        }
+        let mut sub_exprs = vec![];
+        collect(deepex, &mut sub_exprs);
+        ReactiveEx {
+            sub_exprs,
+            var_names: deepex.var_names().iter().map(|n| n.to_string()).collect(),
+            vars: vec![None; deepex.n_vars()],
+        }
+    }
+
+    /// Creates a reactive expression from a flat expression. All variables are unset.
+    pub fn from_flatex<OF, LMF>(flatex: &FlatEx<T, OF, LMF>) -> ExResult<Self>
+    where
+        OF: MakeOperators<T>,
+        LMF: MatchLiteral,
+    {
+        Ok(Self::from_deepex(flatex.deepex()?.as_ref()))
+    }
+
+    /// Creates a reactive expression from an owned flat expression. All variables are unset.
+    pub fn from_owned_flatex<OF, LMF>(flatex: &OwnedFlatEx<T, OF, LMF>) -> ExResult<Self>
+    where
+        OF: MakeOperators<T>,
+        LMF: MatchLiteral,
+    {
+        Ok(Self::from_deepex(&flatex.deepex()?))
+    }
+
+    /// Sets the variable with index `var_idx` and marks all sub-expressions that depend
+    /// on it as outdated.
+    ///
+    /// # Errors
+    ///
+    /// If the index is out of bounds, an error is returned.
+    pub fn set_var(&mut self, var_idx: usize, value: T) -> ExResult<()> {
+        if var_idx >= self.vars.len() {
+            return Err(format_exerr!(
+                "index {} is invalid since we have only {} variables",
+                var_idx,
+                self.vars.len()
+            ));
+        }
+        self.vars[var_idx] = Some(value);
+        for sub_ex in self.sub_exprs.iter_mut() {
+            if sub_ex.var_indices.binary_search(&var_idx).is_ok() {
+                sub_ex.value = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the variable called `var_name`, see [`set_var`](ReactiveEx::set_var).
+    ///
+    /// # Errors
+    ///
+    /// If there is no variable with the given name, an error is returned.
+    pub fn set_var_by_name(&mut self, var_name: &str, value: T) -> ExResult<()> {
+        let var_idx = self
+            .var_names
+            .iter()
+            .position(|n| n == var_name)
+            .ok_or_else(|| {
+                format_exerr!(
+                    "unknown variable '{}', expected one of {:?}",
+                    var_name,
+                    self.var_names
+                )
+            })?;
+        self.set_var(var_idx, value)
+    }
+
+    /// Evaluates the expression. Only outdated sub-expressions are re-computed, all others
+    /// are taken from the cache. Branches that are not selected by a ternary operator are
+    /// not computed.
+    ///
+    /// # Errors
+    ///
+    /// If a variable the expression depends on has not been set, an error is returned.
+    pub fn eval(&mut self) -> ExResult<T> {
+        self.value(self.sub_exprs.len() - 1)
+    }
+
+    /// Returns the memoized value of the sub-expression `sub_idx` or computes it.
+    fn value(&mut self, sub_idx: usize) -> ExResult<T> {
+        if let Some(value) = &self.sub_exprs[sub_idx].value {
+            return Ok(value.clone());
+        }
+        let value = self.compute(sub_idx)?;
+        self.sub_exprs[sub_idx].value = Some(value.clone());
+        Ok(value)
+    }
+
+    /// Computes the sub-expression `sub_idx` and its outdated children.
+    fn compute(&mut self, sub_idx: usize) -> ExResult<T> {
+        let mut numbers = SmallVec::<[T; N_NODES_ON_STACK]>::new();
+        for node_idx in 0..self.sub_exprs[sub_idx].nodes.len() {
+            let number = match self.sub_exprs[sub_idx].nodes[node_idx].clone() {
+                ReactiveNode::Num(n) => n,
+                ReactiveNode::Var(idx) => self.vars[idx].clone().ok_or_else(|| {
+                    format_exerr!("variable '{}' has not been set", self.var_names[idx])
+                })?,
+                ReactiveNode::Expr(idx) => self.value(idx)?,
+                ReactiveNode::Ternary(TernaryOp::Select(is_first), [c, a, b]) => {
+                    let cond = self.value(c)?;
+                    self.value(if is_first(&cond) { a } else { b })?
+                }
+                ReactiveNode::Ternary(TernaryOp::Apply(apply), [c, a, b]) => {
+                    apply(self.value(c)?, self.value(a)?, self.value(b)?)
+                }
+            };
+            numbers.push(number);
+        }
+        let sub_ex = &self.sub_exprs[sub_idx];
+        let mut ignore: SmallVec<[bool; N_NODES_ON_STACK]> = smallvec![false; numbers.len()];
+        for &bin_op_idx in sub_ex.prio_indices.iter() {
+            let num_idx = bin_op_idx;
+            let mut shift_left = 0usize;
+            while ignore[num_idx - shift_left] {
+                shift_left += 1usize;
+            }
+            let mut shift_right = 1usize;
+            while ignore[num_idx + shift_right] {
+                shift_right += 1usize;
+            }
+            let num_1 = numbers[num_idx - shift_left].clone();
+            let num_2 = numbers[num_idx + shift_right].clone();
+            numbers[num_idx - shift_left] = (sub_ex.bin_ops[bin_op_idx].apply)(num_1, num_2);
+            ignore[num_idx + shift_right] = true;
+        }
+        Ok(sub_ex.unary_op.apply(numbers[0].clone()))
+    }
+
+    /// Number of sub-expressions that will be re-computed by the next call of
+    /// [`eval`](ReactiveEx::eval).
+    pub fn n_outdated(&self) -> usize {
+        self.sub_exprs.iter().filter(|s| s.value.is_none()).count()
+    }
+
+    pub fn var_names(&self) -> &[String] {
+        &self.var_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        util::assert_float_eq_f64, Eval, ExResult, Express, FlatEx, OwnedFlatEx, ReactiveEx,
+    };
+
+    #[test]
+    fn test_reactive() -> ExResult<()> {
+        fn test(text: &str, updates: &[(usize, f64, usize)]) -> ExResult<()> {
+            println!("testing {}...", text);
+            let flatex = FlatEx::<f64>::from_str(text)?;
+            let mut reactive = ReactiveEx::from_flatex(&flatex)?;
+            let n_vars = flatex.n_vars();
+            let mut vars = vec![0.0; n_vars];
+            for (var_idx, v) in vars.iter().enumerate() {
+                reactive.set_var(var_idx, *v)?;
+            }
+            assert_float_eq_f64(reactive.eval()?, flatex.eval(&vars)?);
+            assert_eq!(reactive.n_outdated(), 0);
+            for &(var_idx, value, n_outdated) in updates {
+                vars[var_idx] = value;
+                reactive.set_var(var_idx, value)?;
+                assert_eq!(reactive.n_outdated(), n_outdated);
+                assert_float_eq_f64(reactive.eval()?, flatex.eval(&vars)?);
+                assert_eq!(reactive.n_outdated(), 0);
+            }
+            println!("...ok.");
+            Ok(())
+        }
+        test("x*2", &[(0, 3.0, 1)])?;
+        test("sin(x)+cos(y)", &[(0, 1.0, 2), (1, 2.0, 2)])?;
+        test(
+            "(x+1)*(y-2)*(z+3)",
+            &[(1, 1.5, 2), (2, -1.0, 2), (0, 4.0, 2)],
+        )?;
+        test(
+            "exp(x*(y+1))/(z^2+1)",
+            &[(2, 0.5, 2), (1, 2.0, 3), (0, 1.0, 2)],
+        )?;
+        test("x^2+2*x*y-y", &[(0, 1.5, 1), (1, 0.5, 1)])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reactive_errors() -> ExResult<()> {
+        let expr = OwnedFlatEx::<f64>::from_str("x+sqrt(y)")?;
+        let mut reactive = ReactiveEx::from_owned_flatex(&expr)?;
+        assert_eq!(reactive.var_names(), ["x", "y"]);
+        reactive.set_var_by_name("x", 1.0)?;
+        assert!(reactive.eval().is_err());
+        assert!(reactive.set_var(2, 1.0).is_err());
+        assert!(reactive.set_var_by_name("z", 1.0).is_err());
+        reactive.set_var_by_name("y", 4.0)?;
+        assert_float_eq_f64(reactive.eval()?, 3.0);
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
-use std::{fmt, fmt::Debug, marker::PhantomData};
+use std::{fmt, fmt::Debug, marker::PhantomData, str::FromStr};
 
-use serde::{de, de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de, de::MapAccess, de::Visitor, ser::SerializeStruct, Deserialize, Deserializer, Serialize,
+    Serializer,
+};
 
 use crate::data_type::DataType;
 
-use crate::{prelude::*, MatchLiteral, MakeOperators, OwnedFlatEx};
+use crate::{prelude::*, MatchLiteral, MakeOperators, OwnedFlatEx, WithDefaults};
 
 fn serialize<'a, T: Clone, S: Serializer, Ex: Express<'a, T>>(
     serializer: S,
@@ -127,6 +130,101 @@ where
     }
 }
 
+/// Expressions with defaults are serialized as a struct with the fields `text` and
+/// `defaults`, where the defaults are pairs of variable names and values formatted with
+/// `Debug`.
+impl<Ex: Serialize, T: Debug> Serialize for WithDefaults<Ex, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let defaults = self
+            .defaults()
+            .iter()
+            .map(|(name, value)| (name.as_str(), format!("{:?}", value)))
+            .collect::<Vec<_>>();
+        let mut state = serializer.serialize_struct("WithDefaults", 2)?;
+        state.serialize_field("text", self.expr())?;
+        state.serialize_field("defaults", &defaults)?;
+        state.end()
+    }
+}
+
+const WITH_DEFAULTS_FIELDS: &[&str] = &["text", "defaults"];
+
+impl<'de, Ex, T> Deserialize<'de> for WithDefaults<Ex, T>
+where
+    Ex: Deserialize<'de> + Eval<T>,
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "WithDefaults",
+            WITH_DEFAULTS_FIELDS,
+            WithDefaultsVisitor {
+                expr_dummy: PhantomData,
+                generic_dummy: PhantomData,
+            },
+        )
+    }
+}
+
+#[derive(Debug)]
+struct WithDefaultsVisitor<Ex, T> {
+    expr_dummy: PhantomData<Ex>,
+    generic_dummy: PhantomData<T>,
+}
+
+impl<'de, Ex, T> Visitor<'de> for WithDefaultsVisitor<Ex, T>
+where
+    Ex: Deserialize<'de> + Eval<T>,
+    T: DataType,
+    <T as FromStr>::Err: Debug,
+{
+    type Value = WithDefaults<Ex, T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an expression with the fields `text` and `defaults`")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut expr = None;
+        let mut defaults = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "text" => expr = Some(map.next_value::<Ex>()?),
+                "defaults" => defaults = Some(map.next_value::<Vec<(String, String)>>()?),
+                _ => return Err(de::Error::unknown_field(&key, WITH_DEFAULTS_FIELDS)),
+            }
+        }
+        let expr = expr.ok_or_else(|| de::Error::missing_field("text"))?;
+        let defaults = defaults
+            .ok_or_else(|| de::Error::missing_field("defaults"))?
+            .into_iter()
+            .map(|(name, value)| {
+                let value = value.parse::<T>().map_err(|e| {
+                    de::Error::custom(format!("cannot parse default '{}' - {:?}", value, e))
+                })?;
+                Ok((name, value))
+            })
+            .collect::<Result<Vec<_>, A::Error>>()?;
+        let defaults = defaults
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect::<Vec<_>>();
+        WithDefaults::new(expr)
+            .with_defaults(&defaults)
+            .map_err(|epe| de::Error::custom(format!("Parse error - {}", epe.msg)))
+    }
+}
+
 #[cfg(test)]
 use {
     crate::operators::{BinOp, Operator},
@@ -201,3 +299,47 @@ fn test_ser_de_non_float() {
     test("4 / 2 / a", 2);
     test("4 / 2 / 2 / a", 1);
 }
+
+#[test]
+fn test_ser_de_remap_vars() {
+    let expr = OwnedFlatEx::<f64>::from_str("alpha*2.0-beta/gamma").unwrap();
+    let serialized = serde_json::to_string(&expr).unwrap();
+    let deserialized = serde_json::from_str::<OwnedFlatEx<f64>>(serialized.as_str()).unwrap();
+    assert_eq!(deserialized.var_names(), ["alpha", "beta", "gamma"]);
+    // alpha becomes the last variable
+    let remapped = deserialized.remap_vars(&[("alpha", "zeta")]).unwrap();
+    assert_eq!(remapped.var_names(), ["beta", "gamma", "zeta"]);
+    assert_eq!(remapped.eval(&[3.0, 2.0, 1.0]).unwrap(), 0.5);
+    let serialized = serde_json::to_string(&remapped).unwrap();
+    let deserialized = serde_json::from_str::<OwnedFlatEx<f64>>(serialized.as_str()).unwrap();
+    assert_eq!(deserialized.eval(&[3.0, 2.0, 1.0]).unwrap(), 0.5);
+    assert!(deserialized.remap_vars(&[("alpha", "zeta")]).is_err());
+}
+
+#[test]
+fn test_ser_de_defaults() {
+    let expr = FlatEx::<f64>::from_str("x*scale+offset")
+        .unwrap()
+        .with_defaults(&[("offset", 1.5), ("scale", 2.0), ("unused", 3.0)])
+        .unwrap();
+    let serialized = serde_json::to_string(&expr).unwrap();
+    let deserialized =
+        serde_json::from_str::<WithDefaults<FlatEx<f64>, f64>>(serialized.as_str()).unwrap();
+    assert_eq!(
+        deserialized.defaults(),
+        [("offset".to_string(), 1.5), ("scale".to_string(), 2.0)]
+    );
+    assert_eq!(deserialized.eval_with_defaults(&[("x", 2.0)]).unwrap(), 5.5);
+
+    let expr = expr.into_owned();
+    let serialized = serde_json::to_string(&expr).unwrap();
+    let deserialized =
+        serde_json::from_str::<WithDefaults<OwnedFlatEx<f64>, f64>>(serialized.as_str()).unwrap();
+    assert_eq!(deserialized.eval_with_defaults(&[("x", 2.0)]).unwrap(), 5.5);
+    assert!(deserialized.eval_with_defaults(&[("scale", 2.0)]).is_err());
+
+    // the wrapped expression is still serialized as string
+    serde_test::assert_ser_tokens(deserialized.expr(), &[Token::Str("x*scale+offset")]);
+    let broken = r#"{"text":"x+offset","defaults":[["offset","one"]]}"#;
+    assert!(serde_json::from_str::<WithDefaults<OwnedFlatEx<f64>, f64>>(broken).is_err());
+}
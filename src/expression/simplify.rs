@@ -0,0 +1,164 @@
+use crate::{
+    expression::{
+        deep::{BinOpsWithReprs, DeepEx, DeepNode, UnaryOpWithReprs},
+        flat_details,
+    },
+    operators::Operator,
+    ExResult,
+};
+use num::Float;
+use smallvec::smallvec;
+use std::fmt::Debug;
+
+fn are_inverse(repr_outer: &str, repr_inner: &str) -> bool {
+    matches!((repr_outer, repr_inner), ("log", "exp") | ("exp", "log"))
+}
+
+fn push_operand<'a, T: Float + Debug>(
+    operand: DeepEx<'a, T>,
+    splice: bool,
+    nodes: &mut Vec<DeepNode<'a, T>>,
+    bin_ops: &mut BinOpsWithReprs<'a, T>,
+) {
+    if splice {
+        nodes.extend(operand.nodes().iter().cloned());
+        bin_ops
+            .reprs
+            .extend(operand.bin_ops().reprs.iter().copied());
+        bin_ops.ops.extend(operand.bin_ops().ops.iter().cloned());
+        bin_ops
+            .is_function_style
+            .extend(operand.bin_ops().is_function_style.iter().copied());
+    } else {
+        nodes.push(DeepNode::Expr(Box::new(operand)));
+    }
+}
+
+/// Combines the operands `a` and `b` by `bin_op`. The nodes of an operand are put into the
+/// resulting node list directly if the order of evaluation permits, such that no redundant
+/// parentheses are introduced.
+fn join<'a, T: Float + Debug>(
+    a: DeepEx<'a, T>,
+    b: DeepEx<'a, T>,
+    bin_op: BinOpsWithReprs<'a, T>,
+) -> ExResult<DeepEx<'a, T>> {
+    let prio = bin_op.ops[0].prio;
+    let is_function_style = bin_op.is_function_style[0];
+    // binary operators of the same priority are evaluated from left to right
+    let can_splice = |operand: &DeepEx<'a, T>, is_right: bool| {
+        !is_function_style
+            && operand.unary_op().op.is_empty()
+            && !operand.bin_ops().is_function_style.contains(&true)
+            && operand
+                .bin_ops()
+                .ops
+                .iter()
+                .all(|op| op.prio > prio || (!is_right && op.prio == prio))
+    };
+    let (splice_a, splice_b) = (can_splice(&a, false), can_splice(&b, true));
+    let mut nodes = vec![];
+    let mut bin_ops = BinOpsWithReprs::new();
+    push_operand(a, splice_a, &mut nodes, &mut bin_ops);
+    bin_ops.reprs.push(bin_op.reprs[0]);
+    bin_ops.ops.push(bin_op.ops[0].clone());
+    bin_ops.is_function_style.push(is_function_style);
+    push_operand(b, splice_b, &mut nodes, &mut bin_ops);
+    DeepEx::new(nodes, bin_ops, UnaryOpWithReprs::new())
+}
+
+fn simplify_bin<'a, T: Float + Debug>(
+    a: DeepEx<'a, T>,
+    b: DeepEx<'a, T>,
+    bin_op: BinOpsWithReprs<'a, T>,
+) -> ExResult<DeepEx<'a, T>> {
+    match bin_op.reprs[0] {
+        "+" if a.is_zero() => Ok(b),
+        "+" | "-" if b.is_zero() => Ok(a),
+        "*" if a.is_zero() || b.is_zero() => Ok(DeepEx::zero()),
+        "*" if a.is_one() => Ok(b),
+        "*" if b.is_one() => Ok(a),
+        "^" if b.is_zero() || a.is_one() => Ok(DeepEx::one()),
+        "^" if a.is_zero() => Ok(DeepEx::zero()),
+        "^" if b.is_one() => Ok(a),
+        _ => join(a, b, bin_op),
+    }
+}
+
+/// Applies `unary_op` to `operand` where inverse pairs of unary operators cancel out.
+fn simplify_unary<'a, T: Float + Debug>(
+    operand: DeepEx<'a, T>,
+    unary_op: &UnaryOpWithReprs<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    if unary_op.reprs.is_empty() {
+        return Ok(operand);
+    }
+    // innermost operator first
+    let mut remaining: Vec<&str> = vec![];
+    for repr in unary_op
+        .reprs
+        .iter()
+        .chain(operand.unary_op().reprs.iter())
+        .rev()
+    {
+        match remaining.last() {
+            Some(inner) if are_inverse(repr, inner) => {
+                remaining.pop();
+            }
+            _ => remaining.push(repr),
+        }
+    }
+    if remaining.len() == unary_op.reprs.len() + operand.unary_op().reprs.len() {
+        return Ok(operand.operate_unary(unary_op.clone()));
+    }
+    remaining.iter().try_fold(
+        operand.with_new_unary_op(UnaryOpWithReprs::new()),
+        |res, repr| Ok(res.operate_unary(flat_details::find_unary_op_with_reprs(repr, ops)?)),
+    )
+}
+
+fn simplify_once<'a, T: Float + Debug>(
+    deepex: &DeepEx<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    let operands = deepex
+        .nodes()
+        .iter()
+        .map(|node| match node {
+            DeepNode::Expr(e) => simplify_once(e, ops),
+            DeepNode::Ternary(t) => Ok(DeepEx::from_node(DeepNode::Ternary(Box::new(
+                t.map_args(|arg| simplify_once(arg, ops))?,
+            )))),
+            _ => Ok(DeepEx::from_node(node.clone())),
+        })
+        .collect::<Vec<_>>();
+    let bin_ops = deepex.bin_ops();
+    let folded = deepex.fold_in_prio_order(operands, |bin_op_idx, a, b| {
+        let bin_op = BinOpsWithReprs {
+            reprs: smallvec![bin_ops.reprs[bin_op_idx]],
+            ops: smallvec![bin_ops.ops[bin_op_idx].clone()],
+            is_function_style: smallvec![bin_ops.is_function_style[bin_op_idx]],
+        };
+        simplify_bin(a?, b?, bin_op)
+    })?;
+    simplify_unary(folded, deepex.unary_op(), ops)
+}
+
+/// Applies the identities `x*1 = x`, `x+0 = x`, `x-0 = x`, `x*0 = 0`, `x^1 = x`, `x^0 = 1`,
+/// `0^x = 0`, `1^x = 1`, `log(exp(x)) = x`, and `exp(log(x)) = x` until nothing changes
+/// anymore. The variables of `deepex` are kept, also if they vanish from the expression.
+pub fn simplify_deepex<'a, T: Float + Debug>(
+    deepex: &DeepEx<'a, T>,
+    ops: &[Operator<'a, T>],
+) -> ExResult<DeepEx<'a, T>> {
+    let mut simplified = deepex.clone();
+    loop {
+        let next = simplify_once(&simplified, ops)?;
+        if next.unparse_raw() == simplified.unparse_raw() {
+            break;
+        }
+        simplified = next;
+    }
+    let (simplified, _) = simplified.var_names_union(deepex.clone());
+    Ok(simplified)
+}
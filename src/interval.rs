@@ -0,0 +1,498 @@
+use std::{
+    marker::PhantomData,
+    ops::{Add, Div, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use num::Float;
+
+use crate::{
+    expression::MatchLiteral, format_exerr, literal_matcher_from_pattern, BinOp, ExError, FlatEx,
+    MakeOperators, Operator, OwnedFlatEx,
+};
+
+/// Closed interval `[lo, hi]` for interval arithmetic. Evaluating an expression with
+/// intervals as variables results in an interval that contains all values of the
+/// expression for variables within the passed intervals. To account for rounding errors,
+/// non-zero bounds of results are widened outwards by one machine epsilon relative to
+/// their magnitude. Literals are parsed to the nearest floating point number.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, FlatExInterval, Interval};
+/// let expr = FlatExInterval::<f64>::from_str("x*y - [1, 2]")?;
+/// let res = expr.eval(&[Interval::new(-1.0, 2.0), Interval::new(3.0, 4.0)])?;
+/// assert!(res.contains(-6.0) && res.contains(7.0));
+/// assert!(res.lo > -6.001 && res.hi < 7.001);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Interval<T: Float> {
+    pub lo: T,
+    pub hi: T,
+}
+
+impl<T: Float> Interval<T> {
+    pub fn new(lo: T, hi: T) -> Self {
+        Interval { lo, hi }
+    }
+
+    /// Creates the degenerate interval `[x, x]`.
+    pub fn point(x: T) -> Self {
+        Self::new(x, x)
+    }
+
+    /// Creates the interval `[-∞, ∞]`.
+    pub fn entire() -> Self {
+        Self::new(T::neg_infinity(), T::infinity())
+    }
+
+    /// Creates the empty interval that results from, e.g., the logarithm of negative numbers.
+    /// Its bounds are `NaN`.
+    pub fn empty() -> Self {
+        Self::new(T::nan(), T::nan())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo.is_nan() || self.hi.is_nan() || self.lo > self.hi
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    pub fn width(&self) -> T {
+        self.hi - self.lo
+    }
+
+    pub fn mid(&self) -> T {
+        self.lo + (self.hi - self.lo) / (T::one() + T::one())
+    }
+
+    /// Widens the bounds outwards to compensate rounding errors of their computation.
+    fn outward(lo: T, hi: T) -> Self {
+        if lo.is_nan() || hi.is_nan() {
+            Self::empty()
+        } else {
+            Self::new(lo - lo.abs() * T::epsilon(), hi + hi.abs() * T::epsilon())
+        }
+    }
+
+    /// Applies a monotonically increasing function to the bounds.
+    fn increasing(self, f: impl Fn(T) -> T) -> Self {
+        if self.is_empty() {
+            Self::empty()
+        } else {
+            Self::outward(f(self.lo), f(self.hi))
+        }
+    }
+
+    /// Intersection with `[0, ∞]` to restrict the interval to the domain of, e.g., the logarithm.
+    fn non_negative_part(self) -> Self {
+        if self.is_empty() || self.hi < T::zero() {
+            Self::empty()
+        } else {
+            Self::new(self.lo.max(T::zero()), self.hi)
+        }
+    }
+
+    /// Checks whether `offset + 2kπ` is contained in the interval for some integer `k`.
+    fn contains_periodic(&self, offset: T) -> bool {
+        let two_pi = T::from(2.0 * std::f64::consts::PI).unwrap();
+        let k = ((self.lo - offset) / two_pi).ceil();
+        offset + k * two_pi <= self.hi
+    }
+
+    /// Interval extension of sine or cosine, where the function has its maximum at
+    /// `max_offset + 2kπ` and its minimum at `max_offset + π + 2kπ`.
+    fn periodic(self, f: impl Fn(T) -> T, max_offset: T) -> Self {
+        let two_pi = T::from(2.0 * std::f64::consts::PI).unwrap();
+        let pi = T::from(std::f64::consts::PI).unwrap();
+        if self.is_empty() {
+            Self::empty()
+        } else if self.width() >= two_pi {
+            Self::new(-T::one(), T::one())
+        } else {
+            let (f_lo, f_hi) = (f(self.lo), f(self.hi));
+            let res = Self::outward(f_lo.min(f_hi), f_lo.max(f_hi));
+            let lo = if self.contains_periodic(max_offset + pi) {
+                -T::one()
+            } else {
+                res.lo.max(-T::one())
+            };
+            let hi = if self.contains_periodic(max_offset) {
+                T::one()
+            } else {
+                res.hi.min(T::one())
+            };
+            Self::new(lo, hi)
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        if self.is_empty() {
+            Self::empty()
+        } else if self.lo >= T::zero() {
+            self
+        } else if self.hi <= T::zero() {
+            -self
+        } else {
+            Self::new(T::zero(), (-self.lo).max(self.hi))
+        }
+    }
+
+    pub fn sin(self) -> Self {
+        self.periodic(T::sin, T::from(std::f64::consts::FRAC_PI_2).unwrap())
+    }
+
+    pub fn cos(self) -> Self {
+        self.periodic(T::cos, T::zero())
+    }
+
+    pub fn exp(self) -> Self {
+        self.increasing(T::exp).intersect_non_negative_bound()
+    }
+
+    pub fn ln(self) -> Self {
+        self.non_negative_part().increasing(T::ln)
+    }
+
+    pub fn sqrt(self) -> Self {
+        self.non_negative_part()
+            .increasing(T::sqrt)
+            .intersect_non_negative_bound()
+    }
+
+    /// Lower bounds of non-negative functions might have been widened below zero.
+    fn intersect_non_negative_bound(self) -> Self {
+        if self.is_empty() {
+            self
+        } else {
+            Self::new(self.lo.max(T::zero()), self.hi)
+        }
+    }
+
+    /// Integer powers are computed with the rules for even and odd exponents such that,
+    /// e.g., `[-1, 2]^2 = [0, 4]`. Other exponents are only defined for non-negative bases
+    /// and computed as `exp(exponent * ln(base))`.
+    pub fn powf(self, exponent: Self) -> Self {
+        if self.is_empty() || exponent.is_empty() {
+            return Self::empty();
+        }
+        let n = exponent.lo;
+        if exponent.lo == exponent.hi && n.fract() == T::zero() && n.abs() < T::from(1e9).unwrap() {
+            let n = n.to_i32().unwrap();
+            if n == 0 {
+                Self::point(T::one())
+            } else if n < 0 {
+                Self::point(T::one()) / self.powf(Self::point(T::from(-n).unwrap()))
+            } else if n % 2 == 1 {
+                self.increasing(|x| x.powi(n))
+            } else {
+                let abs = self.abs();
+                abs.increasing(|x| x.powi(n)).intersect_non_negative_bound()
+            }
+        } else {
+            let base = self.non_negative_part();
+            (exponent * base.ln()).exp()
+        }
+    }
+}
+
+impl<T: Float> Add for Interval<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::outward(self.lo + other.lo, self.hi + other.hi)
+    }
+}
+
+impl<T: Float> Sub for Interval<T> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::outward(self.lo - other.hi, self.hi - other.lo)
+    }
+}
+
+impl<T: Float> Mul for Interval<T> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        if self.is_empty() || other.is_empty() {
+            return Self::empty();
+        }
+        // zero times infinity is treated as zero since 0 is the only element of [0, 0]
+        let mul = |a: T, b: T| {
+            if a == T::zero() || b == T::zero() {
+                T::zero()
+            } else {
+                a * b
+            }
+        };
+        let products = [
+            mul(self.lo, other.lo),
+            mul(self.lo, other.hi),
+            mul(self.hi, other.lo),
+            mul(self.hi, other.hi),
+        ];
+        let lo = products.iter().fold(T::infinity(), |a, b| a.min(*b));
+        let hi = products.iter().fold(T::neg_infinity(), |a, b| a.max(*b));
+        Self::outward(lo, hi)
+    }
+}
+
+impl<T: Float> Div for Interval<T> {
+    type Output = Self;
+    /// Division by an interval that contains zero results in `[-∞, ∞]`.
+    fn div(self, other: Self) -> Self {
+        if self.is_empty() || other.is_empty() {
+            Self::empty()
+        } else if other.contains(T::zero()) {
+            Self::entire()
+        } else {
+            self * Self::new(other.hi.recip(), other.lo.recip())
+        }
+    }
+}
+
+impl<T: Float> Neg for Interval<T> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.hi, -self.lo)
+    }
+}
+
+/// Parses intervals `[lo, hi]` or numbers `x` as degenerate intervals `[x, x]`.
+impl<T: Float + FromStr> FromStr for Interval<T> {
+    type Err = ExError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<T>()
+                .map_err(|_| format_exerr!("cannot parse '{}' as interval bound", s))
+        };
+        let s = s.trim();
+        match s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(inner) => {
+                let (lo, hi) = inner
+                    .split_once(',')
+                    .ok_or_else(|| format_exerr!("interval '{}' needs two bounds", s))?;
+                let (lo, hi) = (parse(lo)?, parse(hi)?);
+                if lo > hi {
+                    Err(format_exerr!(
+                        "lower bound of interval '{}' exceeds upper bound",
+                        s
+                    ))
+                } else {
+                    Ok(Self::new(lo, hi))
+                }
+            }
+            None => parse(s).map(Self::point),
+        }
+    }
+}
+
+/// Factory of default operators for [`Interval`](Interval)s. The operators are interval
+/// extensions of the corresponding functions, i.e., the result contains all function values
+/// of all numbers within the operands.
+///
+/// |representation|description|
+/// |--------------|-----------|
+/// |`^`| power, for non-integer exponents only non-negative parts of bases are considered |
+/// |`*`| product |
+/// |`/`| division, results in `[-∞, ∞]` if the divisor contains zero |
+/// |`+`| addition as binary or identity as unary operator|
+/// |`-`| subtraction as binary or inverting the sign as unary operator |
+/// |`abs`| absolute value |
+/// |`sin`| sine |
+/// |`cos`| cosine |
+/// |`exp`| exponential function |
+/// |`sqrt`| square root of the non-negative part |
+/// |`log`| natural logarithm of the non-negative part |
+/// |`PI`| constant π  |
+/// |`π`| second representation of constant π  |
+/// |`E`| Euler's number |
+///
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct IntervalOpsFactory<F: Float = f64> {
+    dummy: PhantomData<F>,
+}
+
+impl<F: Float> MakeOperators<Interval<F>> for IntervalOpsFactory<F> {
+    /// Returns the default operators.
+    fn make<'a>() -> Vec<Operator<'a, Interval<F>>> {
+        let pi = F::from(std::f64::consts::PI).unwrap();
+        let e = F::from(std::f64::consts::E).unwrap();
+        let eps = F::epsilon();
+        vec![
+            Operator::make_bin(
+                "^",
+                BinOp {
+                    apply: |a: Interval<F>, b| a.powf(b),
+                    prio: 4,
+                    is_commutative: false,
+                },
+            )
+            .with_id("pow"),
+            Operator::make_bin(
+                "*",
+                BinOp {
+                    apply: |a, b| a * b,
+                    prio: 2,
+                    is_commutative: true,
+                },
+            )
+            .with_id("mul"),
+            Operator::make_bin(
+                "/",
+                BinOp {
+                    apply: |a, b| a / b,
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_id("div"),
+            Operator::make_bin_unary(
+                "+",
+                BinOp {
+                    apply: |a, b| a + b,
+                    prio: 0,
+                    is_commutative: true,
+                },
+                |a| a,
+            )
+            .with_id("add"),
+            Operator::make_bin_unary(
+                "-",
+                BinOp {
+                    apply: |a, b| a - b,
+                    prio: 1,
+                    is_commutative: false,
+                },
+                |a: Interval<F>| -a,
+            )
+            .with_id("sub"),
+            Operator::make_unary("abs", |a| a.abs()),
+            Operator::make_unary("sin", |a| a.sin()),
+            Operator::make_unary("cos", |a| a.cos()),
+            Operator::make_unary("exp", |a| a.exp()),
+            Operator::make_unary("sqrt", |a| a.sqrt()),
+            Operator::make_unary("log", |a: Interval<F>| a.ln()).with_id("ln"),
+            Operator::make_constant("PI", Interval::new(pi - pi * eps, pi + pi * eps))
+                .with_id("pi"),
+            Operator::make_constant("π", Interval::new(pi - pi * eps, pi + pi * eps)).with_id("pi"),
+            Operator::make_constant("E", Interval::new(e - e * eps, e + e * eps)).with_id("e"),
+        ]
+    }
+}
+
+/// Factory to match literals of intervals such as `[-1, 2.5]` or numbers such as `2.5`.
+const PATTERN: &str = r"^(\[\s*-?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?\s*,\s*-?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?\s*\]|([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?)";
+literal_matcher_from_pattern!(IntervalMatcher, PATTERN);
+
+/// Alias for [`FlatEx`](FlatEx) with [`Interval`](Interval) as data type and
+/// [`IntervalOpsFactory`](IntervalOpsFactory) as operator factory.
+pub type FlatExInterval<'a, F = f64> =
+    FlatEx<'a, Interval<F>, IntervalOpsFactory<F>, IntervalMatcher>;
+/// Alias for [`OwnedFlatEx`](OwnedFlatEx) with [`Interval`](Interval) as data type and
+/// [`IntervalOpsFactory`](IntervalOpsFactory) as operator factory.
+pub type OwnedFlatExInterval<F = f64> =
+    OwnedFlatEx<Interval<F>, IntervalOpsFactory<F>, IntervalMatcher>;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Eval, ExResult, Express, FlatEx, FlatExInterval, Interval, OwnedFlatExInterval};
+
+    #[test]
+    fn test_interval() -> ExResult<()> {
+        // the resulting interval needs to contain the values at all sample points and the
+        // bounds need to be close to the extrema of the sample points
+        fn test(text: &str, vars: &[(f64, f64)], tol: f64) -> ExResult<()> {
+            println!("testing {}...", text);
+            let flatex = FlatEx::<f64>::from_str(text)?;
+            let interval_expr = FlatExInterval::<f64>::from_str(text)?;
+            let intervals = vars
+                .iter()
+                .map(|(lo, hi)| Interval::new(*lo, *hi))
+                .collect::<Vec<_>>();
+            let res = interval_expr.eval(&intervals)?;
+            assert_eq!(
+                OwnedFlatExInterval::<f64>::from_str(text)?.eval(&intervals)?,
+                res
+            );
+            let n_samples = 50usize;
+            let mut sample_min = f64::INFINITY;
+            let mut sample_max = f64::NEG_INFINITY;
+            let n_points = n_samples.pow(vars.len() as u32);
+            for point_idx in 0..n_points {
+                let mut rest = point_idx;
+                let point = vars
+                    .iter()
+                    .map(|(lo, hi)| {
+                        let i = rest % n_samples;
+                        rest /= n_samples;
+                        lo + (hi - lo) * i as f64 / (n_samples - 1) as f64
+                    })
+                    .collect::<Vec<_>>();
+                let value = flatex.eval(&point)?;
+                assert!(res.contains(value), "{:?} not in {:?}", value, res);
+                sample_min = sample_min.min(value);
+                sample_max = sample_max.max(value);
+            }
+            assert!(sample_min - res.lo < tol, "{} {:?}", sample_min, res);
+            assert!(res.hi - sample_max < tol, "{} {:?}", sample_max, res);
+            println!("...ok.");
+            Ok(())
+        }
+        test("x+y", &[(-1.0, 2.0), (0.5, 0.7)], 1e-12)?;
+        test("x-y", &[(-1.0, 2.0), (0.5, 0.7)], 1e-12)?;
+        test("x*y", &[(-1.0, 2.0), (-3.0, 0.7)], 1e-12)?;
+        test("x/y", &[(-1.0, 2.0), (0.5, 0.7)], 1e-12)?;
+        test("-x/y", &[(-1.0, 2.0), (-0.7, -0.5)], 1e-12)?;
+        test("x^2", &[(-1.0, 2.0)], 1e-3)?;
+        test("x^3", &[(-1.0, 2.0)], 1e-12)?;
+        test("x^-2", &[(0.5, 2.0)], 1e-12)?;
+        test("x^y", &[(0.5, 2.0), (0.5, 1.5)], 1e-12)?;
+        test("x^0.5", &[(0.5, 2.0)], 1e-12)?;
+        test("sin(x)", &[(-1.0, 2.0)], 1e-3)?;
+        test("sin(x)", &[(2.0, 5.0)], 1e-3)?;
+        test("cos(x)", &[(-1.0, 2.0)], 1e-3)?;
+        test("cos(x)", &[(2.0, 4.0)], 1e-3)?;
+        test("cos(x)", &[(-10.0, 10.0)], 0.1)?;
+        test("exp(x)*log(y)", &[(-1.0, 2.0), (0.5, 0.7)], 1e-12)?;
+        test("sqrt(x)+abs(y)", &[(1.0, 2.0), (-0.5, 0.7)], 0.05)?;
+        test("x*PI+E", &[(1.0, 2.0)], 1e-12)?;
+
+        let expr = FlatExInterval::<f64>::from_str("x*[1, 2]-[0.5,1.5]")?;
+        let res = expr.eval(&[Interval::new(1.0, 2.0)])?;
+        assert!(res.contains(-0.5) && res.contains(3.5));
+        assert!(res.lo > -0.5001 && res.hi < 3.5001);
+
+        // dependency problem of interval arithmetic: the result is an enclosure but not tight
+        let expr = FlatExInterval::<f64>::from_str("x-x")?;
+        let res = expr.eval(&[Interval::new(1.0, 2.0)])?;
+        assert!(res.contains(-1.0) && res.contains(1.0));
+
+        let expr = FlatExInterval::<f64>::from_str("1/x")?;
+        assert_eq!(expr.eval(&[Interval::new(-1.0, 2.0)])?, Interval::entire());
+        let expr = FlatExInterval::<f64>::from_str("log(x)")?;
+        assert!(expr.eval(&[Interval::new(-2.0, -1.0)])?.is_empty());
+        let res = expr.eval(&[Interval::new(-2.0, 1.0)])?;
+        assert_eq!(res.lo, f64::NEG_INFINITY);
+        assert_eq!(res.hi, 0.0);
+
+        assert_eq!(
+            "[-1, 2.5]".parse::<Interval<f64>>()?,
+            Interval::new(-1.0, 2.5)
+        );
+        assert_eq!("2.5".parse::<Interval<f64>>()?, Interval::point(2.5));
+        assert!("[2, 1]".parse::<Interval<f64>>().is_err());
+        assert!("[2]".parse::<Interval<f64>>().is_err());
+        assert!("[a, 1]".parse::<Interval<f64>>().is_err());
+        assert!(FlatExInterval::<f64>::from_str("x+").is_err());
+        Ok(())
+    }
+}
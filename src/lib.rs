@@ -1,4 +1,4 @@
-#![doc(html_root_url = "https://docs.rs/exmex/0.13.1")]
+#![doc(html_root_url = "https://docs.rs/exmex/0.14.0")]
 //! Exmex is an extendable mathematical expression parser and evaluator. Ease of use, flexibility, and efficient evaluations are its main design goals.
 //! Exmex can parse mathematical expressions possibly containing variables and operators. On the one hand, it comes with a list of default operators
 //! for floating point values. For differentiable default operators, Exmex can compute partial derivatives. On the other hand, users can define their
@@ -28,7 +28,7 @@
 //! Additionally, variables should consist only of letters, greek letters, numbers, and underscores. More precisely, they
 //! need to fit the regular expression `r"[a-zA-Zα-ωΑ-Ω_]+[a-zA-Zα-ωΑ-Ω_0-9]*"`, if they are not between curly brackets.
 //!
-//! Variables' values are passed as slices to [`eval`](Express::eval).
+//! Variables' values are passed as slices to [`eval`](Eval::eval).
 //! ```rust
 //! # use std::error::Error;
 //! # fn main() -> Result<(), Box<dyn Error>> {
@@ -61,9 +61,9 @@
 //! # }
 //! ```
 //! The value returned by [`parse`](parse) is an instance of the struct [`FlatEx`](FlatEx)
-//! that implements the [`Express`](Express) trait. Moreover, [`FlatEx`](FlatEx) and
-//! [`Express`](Express) are the only items made accessible by the wildcard import from
-//! [`prelude`](prelude).
+//! that implements the [`Express`](Express) trait and its object-safe supertrait
+//! [`Eval`](Eval). Moreover, [`FlatEx`](FlatEx), [`Express`](Express), and [`Eval`](Eval)
+//! are the only items made accessible by the wildcard import from [`prelude`](prelude).
 //!
 //! ## Partial Derivatives
 //!
@@ -115,7 +115,7 @@
 //! # use std::error::Error;
 //! # fn main() -> Result<(), Box<dyn Error>> {
 //! #
-//! use exmex::{ExResult, Express, OwnedFlatEx};
+//! use exmex::{Eval, ExResult, Express, OwnedFlatEx};
 //! fn create_expr() -> ExResult<OwnedFlatEx::<f64>> {
 //!     let to_be_parsed = "log(z) + 2* (-z^2 + sin(4*y))";
 //!     OwnedFlatEx::<f64>::from_str(to_be_parsed)
@@ -139,21 +139,25 @@
 //! operators can be
 //! * binary such as `*`,
 //! * unary such as `sin`,
-//! * binary as well as unary such as `-`, or
+//! * binary as well as unary such as `-`,
+//! * ternary such as `if`, or
 //! * constant such as `PI`.
 //!
 //! An operator's representation is defined in the field
 //! [`repr`](Operator::repr). A token of the string-to-be-parsed is identified as operator if it matches the operator's
 //! representation exactly. For instance, `PI` will be parsed as the constant π while `PI5` will be parsed as a variable with name `PI5`.
 //! When an operator's representation is used in a string-to-be-parsed, the following applies:
-//! * Binary operators are positioned between their operands, e.g., `4 ^ 5`.
+//! * Binary operators are positioned between their operands, e.g., `4 ^ 5`. Binary operators marked
+//!   with [`with_function_style`](Operator::with_function_style) are called like functions with two
+//!   arguments instead, e.g., `atan2(y, x)`.
+//! * Ternary operators are called like functions with three arguments, e.g., `if(x > 0, x, 0)`.
 //! * Unary operators are positioned in front of their operands, e.g., `-1` or `sin(4)`. Note that `sin4`
 //! is parsed as variable name, but  `sin 4` is equivalent to `sin(4)`.
 //! * Constant operators are handled as if they were numbers and are replaced by their numeric values during parsing.
 //! They can be used as in `sin(PI)` or `4 + E`. Note that the calling notation of constant operators such as `PI()` is invalid.
 //!
-//! Binary, unary, and constant operators can be created with the functions [`make_bin`](Operator::make_bin), [`make_unary`](Operator::make_unary),
-//! and [`make_constant`](Operator::make_constant), respectively.
+//! Binary, unary, ternary, and constant operators can be created with the functions [`make_bin`](Operator::make_bin), [`make_unary`](Operator::make_unary),
+//! [`make_ternary`](Operator::make_ternary), and [`make_constant`](Operator::make_constant), respectively.
 //! Operators need to be created by factories to make serialization via [`serde`](https://serde.rs/) possible as
 //! shown in the following.
 //! ```rust
@@ -265,6 +269,99 @@
 //! #     Ok(())
 //! # }
 //! ```
+//! Categories such as the variants of an enum can be used, e.g., for rules of a rule engine.
+//! Since comparisons of categories result in bools, the data type is a small union of
+//! categories and bools. Its [`FromStr`](std::str::FromStr) implementation and the
+//! matcher recognize the names of the variants.
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use std::str::FromStr;
+//! use exmex::prelude::*;
+//! use exmex::{
+//!     BinOp, MakeOperators, MatchLiteral, Operator,
+//!     literal_matcher_from_pattern, ops_factory
+//! };
+//! #[derive(Clone, Copy, PartialEq, Debug)]
+//! enum Color {
+//!     Red,
+//!     Green,
+//!     Blue,
+//! }
+//! #[derive(Clone, PartialEq, Debug)]
+//! enum Category {
+//!     Color(Color),
+//!     Bool(bool),
+//!     Error,
+//! }
+//! impl FromStr for Category {
+//!     type Err = String;
+//!     fn from_str(s: &str) -> Result<Self, Self::Err> {
+//!         match s {
+//!             "Red" => Ok(Category::Color(Color::Red)),
+//!             "Green" => Ok(Category::Color(Color::Green)),
+//!             "Blue" => Ok(Category::Color(Color::Blue)),
+//!             _ => s.parse::<bool>().map(Category::Bool).map_err(|e| e.to_string()),
+//!         }
+//!     }
+//! }
+//! fn logical(a: Category, b: Category, f: fn(bool, bool) -> bool) -> Category {
+//!     match (a, b) {
+//!         (Category::Bool(a), Category::Bool(b)) => Category::Bool(f(a, b)),
+//!         _ => Category::Error,
+//!     }
+//! }
+//! ops_factory!(
+//!     CategoryOpsFactory,
+//!     Category,
+//!     Operator::make_bin(
+//!         "==",
+//!         BinOp{
+//!             apply: |a, b| Category::Bool(a == b),
+//!             prio: 1,
+//!             is_commutative: true,
+//!         }
+//!     ),
+//!     Operator::make_bin(
+//!         "!=",
+//!         BinOp{
+//!             apply: |a, b| Category::Bool(a != b),
+//!             prio: 1,
+//!             is_commutative: true,
+//!         }
+//!     ),
+//!     Operator::make_bin(
+//!         "&&",
+//!         BinOp{
+//!             apply: |a, b| logical(a, b, |a, b| a && b),
+//!             prio: 0,
+//!             is_commutative: true,
+//!         }
+//!     ),
+//!     Operator::make_bin(
+//!         "||",
+//!         BinOp{
+//!             apply: |a, b| logical(a, b, |a, b| a || b),
+//!             prio: 0,
+//!             is_commutative: true,
+//!         }
+//!     )
+//! );
+//! literal_matcher_from_pattern!(CategoryMatcher, r"^(Red|Green|Blue|true|false)\b");
+//! type FlatExCategory<'a> = FlatEx::<'a, Category, CategoryOpsFactory, CategoryMatcher>;
+//! let rule = FlatExCategory::from_str("(color == Red || color == Blue) && urgent != false")?;
+//! assert_eq!(rule.var_names(), ["color", "urgent"]);
+//! let eval_rule = |color, urgent| rule.eval(&[Category::Color(color), Category::Bool(urgent)]);
+//! assert_eq!(eval_rule(Color::Red, true)?, Category::Bool(true));
+//! assert_eq!(eval_rule(Color::Green, true)?, Category::Bool(false));
+//! assert_eq!(eval_rule(Color::Blue, false)?, Category::Bool(false));
+//! let rule = FlatExCategory::from_str("color && urgent")?;
+//! assert_eq!(rule.eval(&[Category::Color(Color::Red), Category::Bool(true)])?, Category::Error);
+//! #
+//! #     Ok(())
+//! # }
+//! ```
 //! Two examples of exmex with non-trivial data types are:
 //! * Numbers can be operators and operators can operate on operators, see, e.g.,
 //! also a blog post on [ninety.de](https://www.ninety.de/log/index.php/en/2021/11/11/parsing-operators-in-rust/).
@@ -290,7 +387,7 @@
 //! ## Display
 //!
 //! Instances of [`FlatEx`](FlatEx) and [`OwnedFlatEx`](OwnedFlatEx) can be displayed as string. This
-//! [`unparse`](Express::unparse)d string coincides with the original
+//! [`unparse`](Eval::unparse)d string coincides with the original
 //! string.
 //!
 //! ```rust
@@ -306,12 +403,18 @@
 //! ```
 //!
 //! ## Features
-//! Exmex comes with two features that can be activated in the `Cargo.toml` via
+//! Exmex comes with six features that can be activated in the `Cargo.toml` via
 //! ```text
 //! [dependencies]
-//! exmex = { ..., features = ["serde", "value"] }
+//! exmex = { ..., features = ["serde", "value", "rayon", "library", "derive", "rand"] }
 //! ```
-//! `serde` enables serialization and deserialization and `value` a more general value type.
+//! `serde` enables serialization and deserialization, `value` a more general value type,
+//! `rayon` the parallel batch evaluation `Express::eval_batch_par` and parallel parsing
+//! with [`parse_batch`](parse_batch), `library`
+//! ready-made expressions of common formulas in the module `library`, `derive` the
+//! macro `#[derive(ExVars)]` that implements [`VarProvider`](VarProvider) for structs with
+//! fields of type `f64`, and `rand` the stochastic operators of `StochasticOpsFactory`
+//! that are evaluated with `FlatEx::eval_rng`.
 //! ### Serialization and Deserialization
 //!
 //! To use [`serde`](https://serde.rs/) you can activate the feature `serde`.
@@ -326,73 +429,265 @@
 //! use the more general type `Val`. The additional flexibility comes with higher parsing
 //! and evaluation run times, see the [benchmarks](https://github.com/bertiqwerty/exmex#benchmarks-v0120).
 //!
+//! ### Complex Numbers
+//!
+//! Expressions over [`Complex`](num::Complex) numbers can be parsed with the operator factory
+//! [`ComplexOpsFactory`](ComplexOpsFactory) and the literal matcher [`ComplexMatcher`](ComplexMatcher)
+//! that matches imaginary literals such as `3i`. The aliases [`FlatExComplex`](FlatExComplex)
+//! and [`OwnedFlatExComplex`](OwnedFlatExComplex) combine both.
+//!
+//! ### Dual Numbers
+//!
+//! Besides symbolic [`partial`](Express::partial) derivatives, expressions can be
+//! differentiated in forward mode by evaluating them with [`DualNumber`](DualNumber)s and
+//! the operator factory [`DualOpsFactory`](DualOpsFactory). The aliases
+//! [`FlatExDual`](FlatExDual) and [`OwnedFlatExDual`](OwnedFlatExDual) combine both.
+//!
+//! ### Interval Arithmetic
+//!
+//! Enclosures of all values an expression takes on for variables within given ranges are
+//! computed by evaluating it with [`Interval`](Interval)s and the operator factory
+//! [`IntervalOpsFactory`](IntervalOpsFactory). The literal matcher
+//! [`IntervalMatcher`](IntervalMatcher) accepts interval literals such as `[-1, 2.5]`. The
+//! aliases [`FlatExInterval`](FlatExInterval) and [`OwnedFlatExInterval`](OwnedFlatExInterval)
+//! combine all three.
+//!
 
 use std::{fmt::Debug, str::FromStr};
 
-use data_type::DataType;
-use num::Float;
+mod cache;
+mod check;
+mod complex;
 mod definitions;
+mod dual;
+mod equality;
 mod expression;
+mod interval;
 #[macro_use]
 mod operators;
 mod data_type;
 mod parser;
 mod result;
+mod sink;
+mod table;
 mod util;
+mod var_provider;
 
 pub use {
+    cache::ExprCache,
+    check::{check, CheckReport},
+    complex::{ComplexMatcher, ComplexOpsFactory, FlatExComplex, OwnedFlatExComplex},
+    data_type::{ExpressionNumber, TotalCmp},
+    dual::{DualNumber, DualOpsFactory, FlatExDual, OwnedFlatExDual},
+    equality::{
+        semantically_equal, semantically_equal_with, EqualityConfig, EqualityMethod, EqualityReport,
+    },
     expression::{
-        flat::{FlatEx, OwnedFlatEx},
-        Express, MatchLiteral, NumberMatcher,
+        defaults::WithDefaults,
+        flat::{EvalBuffer, FlatEx, FlatNodeView, FlatOpView, OwnedFlatEx},
+        reactive::ReactiveEx,
+        Eval, Express, MatchLiteral, NumberMatcher, ParseExpress,
+    },
+    interval::{
+        FlatExInterval, Interval, IntervalMatcher, IntervalOpsFactory, OwnedFlatExInterval,
     },
-    operators::{BinOp, FloatOpsFactory, MakeOperators, Operator},
+    operators::{
+        apply_bin, apply_unary, override_prios, BinOp, FloatOpsFactory,
+        IndeterminateAsNanOpsFactory, MakeOperators, Operator, TernaryOp, UnaryOp,
+    },
+    parser::CurlyVarNames,
     result::{ExError, ExResult},
+    sink::{EvalSink, MeanMinMax},
+    table::TableFormula,
+    var_provider::VarProvider,
 };
 
+#[cfg(feature = "derive")]
+pub use exmex_derive::ExVars;
+
+#[cfg(feature = "library")]
+pub mod library;
+#[cfg(feature = "rand")]
+mod stochastic;
+#[cfg(feature = "rand")]
+pub use stochastic::StochasticOpsFactory;
 #[cfg(feature = "value")]
 mod value;
 #[cfg(feature = "value")]
 pub use value::{
-    parse_val, parse_val_owned, FlatExVal, OwnedFlatExVal, Val, ValMatcher,
+    parse_val, parse_val_owned, FlatExVal, OwnedFlatExVal, Val, ValKind, ValMatcher,
     ValOpsFactory,
 };
 
-/// To use the expression trait [`Express`](Express) and its implementation [`FlatEx`](FlatEx)
-/// one can `use exmex::prelude::*;`.
+/// To use the expression traits [`Express`](Express) and [`Eval`](Eval) and their
+/// implementation [`FlatEx`](FlatEx) one can `use exmex::prelude::*;`.
 pub mod prelude {
-    pub use super::expression::{flat::FlatEx, Express};
+    pub use super::expression::{flat::FlatEx, Eval, Express};
 }
 
-/// Parses a string, evaluates the expression, and returns the resulting number.
+/// Parses a string, evaluates the expression, and returns the resulting number. The
+/// operators and literals are defined by the number type, see
+/// [`ExpressionNumber`](ExpressionNumber).
 ///
 /// # Errrors
 ///
 /// In case the parsing went wrong, e.g., due to an invalid input string, an
 /// [`ExError`](ExError) is returned.
 ///
-pub fn eval_str<T: Float + DataType>(text: &str) -> ExResult<T>
+pub fn eval_str<T: ExpressionNumber>(text: &str) -> ExResult<T>
+where
+    <T as FromStr>::Err: Debug,
+{
+    let flatex = FlatEx::<T, T::OpsFactory, T::LiteralMatcher>::from_str_wo_compile(text);
+    if let Ok(flatex) = &flatex {
+        if flatex.n_vars() == 0 {
+            return flatex.eval(&[]);
+        }
+    }
+    let (parsed_tokens, _) =
+        parser::tokenize_and_analyze(text, &T::OpsFactory::make(), T::LiteralMatcher::is_literal)?;
+    let found_vars = parser::find_parsed_var_spans(text, &parsed_tokens);
+    match flatex {
+        Err(e) if found_vars.is_empty() => Err(e),
+        _ => {
+            let found_vars = found_vars
+                .iter()
+                .map(|(name, spans)| format!("'{}' at {:?}", name, spans))
+                .collect::<Vec<_>>();
+            Err(format_exerr!(
+                "input string '{}' contains variables, {}",
+                text,
+                found_vars.join(", ")
+            ))
+        }
+    }
+}
+
+/// Parses a string and evaluates the expression with variable values that are passed
+/// together with the variable names, see [`eval_named`](Express::eval_named).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// let res = exmex::eval_str_vars::<f64>("x*y+PI", &[("x", 2.0), ("y", 3.0)])?;
+/// assert!((res - 6.0 - std::f64::consts::PI).abs() < 1e-12);
+/// assert!(exmex::eval_str_vars::<f64>("x*y", &[("x", 2.0)]).is_err());
+/// assert!(exmex::eval_str_vars::<f64>("x", &[("x", 2.0), ("z", 3.0)]).is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// In case the parsing went wrong, if a name does not occur as variable in the expression,
+/// or if a variable of the expression has no value, an [`ExError`](ExError) is returned.
+///
+pub fn eval_str_vars<T: ExpressionNumber>(text: &str, vars: &[(&str, T)]) -> ExResult<T>
+where
+    <T as FromStr>::Err: Debug,
+{
+    FlatEx::<T, T::OpsFactory, T::LiteralMatcher>::from_str(text)?.eval_named(vars)
+}
+
+/// Returns the names of the variables of a string in alphabetical order, i.e., in the
+/// order expected by [`eval`](Eval::eval). The operators and literals are defined by the
+/// number type, see [`ExpressionNumber`](ExpressionNumber). The string is only split into
+/// tokens, nothing is evaluated. This is, e.g., handy to find out which parts of a string are
+/// not recognized as operators such as a misspelled `sqr` in `sqr(2)`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// assert_eq!(exmex::vars_in::<f64>("sqr(2)*y+x")?, vec!["sqr", "x", "y"]);
+/// assert!(exmex::eval_str::<f64>("sqr(2)").is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// In case the parsing went wrong, e.g., due to an invalid input string, an
+/// [`ExError`](ExError) is returned.
+///
+pub fn vars_in<T: ExpressionNumber>(text: &str) -> ExResult<Vec<String>>
+where
+    <T as FromStr>::Err: Debug,
+{
+    let (parsed_tokens, _) =
+        parser::tokenize_and_analyze(text, &T::OpsFactory::make(), T::LiteralMatcher::is_literal)?;
+    Ok(parser::find_parsed_vars(&parsed_tokens)
+        .iter()
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// Parses many strings into expressions that do not borrow the strings. The operators and
+/// literals are defined by the number type, see [`ExpressionNumber`](ExpressionNumber).
+/// The results are in the order of `texts` and each string is parsed independently, i.e., one invalid string
+/// does not prevent the others from being parsed. With the feature `rayon` the strings are
+/// distributed among the threads of [`rayon`](https://docs.rs/rayon)'s global thread pool
+/// and the operators are created only once per thread.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// let exprs = exmex::parse_batch::<f64>(&["2*x", "x+", "sin(y)"]);
+/// assert_eq!(exprs[0].clone()?.eval(&[1.5])?, 3.0);
+/// assert!(exprs[1].is_err());
+/// assert_eq!(exprs[2].clone()?.eval(&[0.0])?, 0.0);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn parse_batch<T>(
+    texts: &[&str],
+) -> Vec<ExResult<OwnedFlatEx<T, T::OpsFactory, T::LiteralMatcher>>>
 where
+    T: ExpressionNumber + Send,
+    T::OpsFactory: Send,
+    T::LiteralMatcher: Send,
     <T as FromStr>::Err: Debug,
 {
-    let flatex = FlatEx::<T>::from_str_wo_compile(text)?;
-    if flatex.n_vars() > 0 {
-        return Err(ExError {
-            msg: format!("input string contains variables, '{}' ", text),
-        });
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        texts
+            .par_iter()
+            .map_init(T::OpsFactory::make, |ops, text| {
+                OwnedFlatEx::from_str_with_ops(text, ops)
+            })
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        let ops = T::OpsFactory::make();
+        texts
+            .iter()
+            .map(|text| OwnedFlatEx::from_str_with_ops(text, &ops))
+            .collect()
     }
-    flatex.eval(&[])
 }
 
-/// Parses a string and returns the expression that can be evaluated.
+/// Parses a string and returns the expression that can be evaluated. The operators and
+/// literals are defined by the number type, see [`ExpressionNumber`](ExpressionNumber).
 ///
 /// # Errrors
 ///
 /// In case the parsing went wrong, e.g., due to an invalid input string, an
 /// [`ExError`](ExError) is returned.
 ///
-pub fn parse<T: Float + DataType>(text: &str) -> ExResult<FlatEx<T>>
+pub fn parse<T: ExpressionNumber>(
+    text: &str,
+) -> ExResult<FlatEx<T, T::OpsFactory, T::LiteralMatcher>>
 where
     <T as FromStr>::Err: Debug,
 {
-    FlatEx::<T>::from_str(text)
+    FlatEx::from_str(text)
 }
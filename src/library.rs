@@ -0,0 +1,67 @@
+//! *`feature = "library"`* - Ready-made expressions of common formulas. Each formula is
+//! parsed once at its first use. Afterwards, the constructors return clones of the cached
+//! expression. The variables of each expression are listed in alphabetical order, i.e., in
+//! the order expected by [`eval`](crate::Eval::eval).
+//!
+//! ```rust
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! #
+//! use exmex::prelude::*;
+//! let logistic = exmex::library::logistic();
+//! assert!((logistic.eval(&[0.0])? - 0.5).abs() < 1e-12);
+//! let deri = logistic.partial(0)?;
+//! assert!((deri.eval(&[0.0])? - 0.25).abs() < 1e-12);
+//! #
+//! #     Ok(())
+//! # }
+//! ```
+use lazy_static::lazy_static;
+
+use crate::OwnedFlatEx;
+
+macro_rules! library_formula {
+    ($(#[$meta:meta])* $name:ident, $text:expr) => {
+        $(#[$meta])*
+        pub fn $name() -> OwnedFlatEx<f64> {
+            lazy_static! {
+                static ref EXPR: OwnedFlatEx<f64> = OwnedFlatEx::from_str($text).unwrap();
+            }
+            EXPR.clone()
+        }
+    };
+}
+
+library_formula!(
+    /// Logistic function `1/(1+exp(-x))`.
+    ///
+    /// Variables: `x`
+    logistic,
+    "1/(1+exp(-x))"
+);
+
+library_formula!(
+    /// Softplus function `log(1+exp(x))`, a smooth approximation of `max(0, x)`.
+    ///
+    /// Variables: `x`
+    softplus,
+    "log(1+exp(x))"
+);
+
+library_formula!(
+    /// Probability density function of the normal distribution with mean `mu` and
+    /// standard deviation `sigma` evaluated at `x`.
+    ///
+    /// Variables: `mu`, `sigma`, `x`
+    normal_pdf,
+    "exp(-0.5*((x-mu)/sigma)^2)/(sigma*sqrt(2*PI))"
+);
+
+library_formula!(
+    /// Great-circle distance on a sphere with radius `r` between the points
+    /// `(lat1, lon1)` and `(lat2, lon2)` given in radians.
+    ///
+    /// Variables: `lat1`, `lat2`, `lon1`, `lon2`, `r`
+    haversine,
+    "2*r*asin(sqrt(sin((lat2-lat1)/2)^2+cos(lat1)*cos(lat2)*sin((lon2-lon1)/2)^2))"
+);
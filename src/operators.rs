@@ -1,7 +1,11 @@
 use crate::{definitions::N_UNARYOPS_OF_DEEPEX_ON_STACK, format_exerr, ExError, ExResult};
 use num::Float;
 use smallvec::{smallvec, SmallVec};
-use std::{fmt::Debug, marker::PhantomData};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
 
 enum OperatorType {
     Bin,
@@ -21,13 +25,24 @@ fn make_op_not_available_error(repr: &str, op_type: OperatorType) -> ExError {
 pub struct Operator<'a, T: Clone> {
     /// Representation of the operator in the string to be parsed, e.g., `-` or `sin`.
     repr: &'a str,
+    /// Stable identifier of the operator that does not change if the representation
+    /// changes, e.g., `pow` for `^`. Defaults to the representation.
+    id: &'a str,
     /// Binary operator that contains a priority besides a function pointer.
     bin_op: Option<BinOp<T>>,
     /// Unary operator that does not have an explicit priority. Unary operators have
     /// higher priority than binary opertors, e.g., `-1^2 == 1`.
     unary_op: Option<fn(T) -> T>,
+    /// Operator with three arguments that is always written like a function, e.g.,
+    /// `if(cond, a, b)`.
+    ternary_op: Option<TernaryOp<T>>,
     /// An operator can also be constant.
     constant: Option<T>,
+    /// True if the binary operator is written like a function with two arguments,
+    /// e.g., `atan2(y, x)`, instead of in between its operands.
+    is_function_style: bool,
+    /// True if the operator is non-deterministic such as drawing a random number.
+    is_stochastic: bool,
 }
 
 fn unwrap_operator<'a, O>(
@@ -63,9 +78,13 @@ impl<'a, T: Clone> Operator<'a, T> {
         }
         Operator {
             repr,
+            id: repr,
             bin_op,
             unary_op,
+            ternary_op: None,
             constant,
+            is_function_style: false,
+            is_stochastic: false,
         }
     }
 
@@ -87,11 +106,135 @@ impl<'a, T: Clone> Operator<'a, T> {
     ) -> Operator<'a, T> {
         Operator::new(repr, Some(bin_op), Some(unary_op), None)
     }
+    /// Creates an operator with three arguments that is written like a function, e.g.,
+    /// `if(cond, a, b)`, see [`TernaryOp`](TernaryOp).
+    pub fn make_ternary(repr: &'a str, ternary_op: TernaryOp<T>) -> Operator<'a, T> {
+        let mut op = Operator::new(repr, None, None, None);
+        op.ternary_op = Some(ternary_op);
+        op
+    }
     /// Creates a constant operator. If an operator is constant it cannot be additionally binary or unary.
     pub fn make_constant(repr: &'a str, constant: T) -> Operator<'a, T> {
         Operator::new(repr, None, None, Some(constant))
     }
 
+    /// Sets an identifier that is distinct from the representation, e.g., to
+    /// log the usage of operators independently of their representations.
+    ///
+    /// ```rust
+    /// use exmex::Operator;
+    /// let op = Operator::make_unary("ln", |a: f64| a.ln()).with_id("log");
+    /// assert_eq!(op.repr(), "ln");
+    /// assert_eq!(op.id(), "log");
+    /// ```
+    pub fn with_id(mut self, id: &'a str) -> Operator<'a, T> {
+        self.id = id;
+        self
+    }
+
+    /// Turns the binary operator into a function-style operator that is written with
+    /// parentheses and a comma such as `atan2(y, x)` instead of `y atan2 x`. If the
+    /// operator is also unary, e.g., `log(x)` and `log(x, 2)`, the number of arguments
+    /// decides which one is used.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #
+    /// use exmex::prelude::*;
+    /// use exmex::{BinOp, MakeOperators, Operator, ops_factory};
+    /// ops_factory!(
+    ///     Atan2OpsFactory,
+    ///     f64,
+    ///     Operator::make_bin(
+    ///         "atan2",
+    ///         BinOp {
+    ///             apply: |y: f64, x| y.atan2(x),
+    ///             prio: 0,
+    ///             is_commutative: false,
+    ///         }
+    ///     )
+    ///     .with_function_style(),
+    ///     Operator::make_bin(
+    ///         "+",
+    ///         BinOp {
+    ///             apply: |a, b| a + b,
+    ///             prio: 0,
+    ///             is_commutative: true,
+    ///         }
+    ///     )
+    /// );
+    /// let expr = FlatEx::<f64, Atan2OpsFactory>::from_str("atan2(y, x+1)")?;
+    /// assert!((expr.eval(&[0.0, 1.0])? - 1f64.atan2(1.0)).abs() < 1e-12);
+    /// #
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn with_function_style(mut self) -> Operator<'a, T> {
+        self.is_function_style = true;
+        self
+    }
+
+    /// Changes the priority of the binary operator. Operators without binary operator are
+    /// returned unchanged.
+    ///
+    /// ```rust
+    /// use exmex::{BinOp, Operator};
+    /// let op = Operator::make_bin(
+    ///     "^",
+    ///     BinOp {
+    ///         apply: |a: f64, b| a.powf(b),
+    ///         prio: 4,
+    ///         is_commutative: false,
+    ///     },
+    /// )
+    /// .with_prio(1);
+    /// assert_eq!(op.bin().unwrap().prio, 1);
+    /// ```
+    pub fn with_prio(mut self, prio: i64) -> Operator<'a, T> {
+        if let Some(bin_op) = &mut self.bin_op {
+            bin_op.prio = prio;
+        }
+        self
+    }
+
+    /// Changes the commutativity of the binary operator. Operators without binary operator
+    /// are returned unchanged.
+    pub fn with_commutativity(mut self, is_commutative: bool) -> Operator<'a, T> {
+        if let Some(bin_op) = &mut self.bin_op {
+            bin_op.is_commutative = is_commutative;
+        }
+        self
+    }
+
+    /// Marks the operator as non-deterministic, e.g., an operator that draws random numbers.
+    /// Stochastic unary operators ignore their argument and can be called without argument
+    /// such as `rand()`. Expressions parsed with an operator factory that contains stochastic
+    /// operators are not [`compile`](crate::FlatEx::compile)d, since calculations with
+    /// numbers must not be executed only once during parsing. Further, there are no
+    /// deep expressions, e.g., for partial derivatives, of expressions that use stochastic
+    /// operators.
+    pub fn with_stochastic(mut self) -> Operator<'a, T> {
+        self.is_stochastic = true;
+        self
+    }
+
+    /// Returns a copy of the operator that can only be used as binary operator.
+    pub(crate) fn bin_only(&self) -> Operator<'a, T> {
+        let mut op = self.clone();
+        op.unary_op = None;
+        op
+    }
+
+    /// Returns a copy of the operator that can only be used as unary operator.
+    pub(crate) fn unary_only(&self) -> Operator<'a, T> {
+        let mut op = self.clone();
+        op.bin_op = None;
+        op.ternary_op = None;
+        op.is_function_style = false;
+        op
+    }
+
     pub fn bin(&self) -> ExResult<BinOp<T>> {
         let op = unwrap_operator(&self.bin_op, self.repr, OperatorType::Bin)?;
         Ok(op.clone())
@@ -106,12 +249,24 @@ impl<'a, T: Clone> Operator<'a, T> {
     pub fn repr(&self) -> &'a str {
         self.repr
     }
+    pub fn id(&self) -> &'a str {
+        self.id
+    }
     pub fn has_bin(&self) -> bool {
         self.bin_op.is_some()
     }
     pub fn has_unary(&self) -> bool {
         self.unary_op.is_some()
     }
+    pub fn ternary(&self) -> Option<&TernaryOp<T>> {
+        self.ternary_op.as_ref()
+    }
+    pub fn is_function_style(&self) -> bool {
+        (self.is_function_style && self.has_bin()) || self.ternary_op.is_some()
+    }
+    pub fn is_stochastic(&self) -> bool {
+        self.is_stochastic
+    }
     pub fn constant(&self) -> Option<T> {
         self.constant.clone()
     }
@@ -119,7 +274,9 @@ impl<'a, T: Clone> Operator<'a, T> {
 
 pub type VecOfUnaryFuncs<T> = SmallVec<[fn(T) -> T; N_UNARYOPS_OF_DEEPEX_ON_STACK]>;
 
-/// Container of unary operators of one expression
+/// Container of unary operators of one expression. The unary functions are stored
+/// outermost-first, i.e., in the order of their representations in the string. For instance,
+/// `sin(-x)` is stored as `[sin, -]` and `-` is applied before `sin`.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct UnaryOp<T> {
     funcs_to_be_composed: VecOfUnaryFuncs<T>,
@@ -130,6 +287,9 @@ where
     T: Clone,
 {
     /// Applies unary operators one after the other starting with the one with the highest index.
+    /// Hence, the last unary function is the innermost one and the first unary function the
+    /// outermost one. This order is guaranteed and used by all evaluations of exmex.
+    ///
     /// # Arguments
     ///
     /// * `x` - number the unary operators are applied to
@@ -144,7 +304,7 @@ where
     }
 
     /// Composes `self` with another unary operator.
-    /// The other unary operator will be applied after self.
+    /// The other unary operator will be applied after self, i.e., it becomes the outermost one.
     pub fn append_after(&mut self, other: &UnaryOp<T>) {
         self.append_after_iter(other.funcs_to_be_composed.iter().copied());
     }
@@ -165,18 +325,24 @@ where
         self.funcs_to_be_composed.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.funcs_to_be_composed.is_empty()
+    }
+
     pub fn new() -> Self {
         Self {
             funcs_to_be_composed: smallvec![],
         }
     }
 
+    /// Creates a unary operator from functions ordered outermost-first.
     pub fn from_vec(v: VecOfUnaryFuncs<T>) -> Self {
         Self {
             funcs_to_be_composed: v,
         }
     }
 
+    /// Creates a unary operator from functions ordered outermost-first.
     pub fn from_iter<I>(iter: I) -> Self
     where
         I: Iterator<Item = fn(T) -> T>,
@@ -189,6 +355,20 @@ where
     pub fn clear(&mut self) {
         self.funcs_to_be_composed.clear();
     }
+
+    /// Unary functions ordered outermost-first.
+    pub(crate) fn funcs(&self) -> &[fn(T) -> T] {
+        &self.funcs_to_be_composed
+    }
+}
+
+impl<T> Default for UnaryOp<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// A binary operator that consists of a function pointer, a priority, and a commutativity-flag.
@@ -206,6 +386,99 @@ pub struct BinOp<T: Clone> {
     pub is_commutative: bool,
 }
 
+/// An operator with three arguments `op(c, a, b)` such as `if(cond, a, b)`. Ternary operators
+/// are always called like functions. A selection evaluates its first argument and, depending
+/// on the predicate, only one of the other two arguments. Otherwise, all three arguments are
+/// evaluated and passed to the function.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// use exmex::{BinOp, MakeOperators, Operator, TernaryOp, ops_factory};
+/// ops_factory!(
+///     LerpOpsFactory,
+///     f64,
+///     // linear interpolation lerp(t, a, b) as (1-t)*a + t*b
+///     Operator::make_ternary(
+///         "lerp",
+///         TernaryOp::Apply(|t, a, b| (1.0 - t) * a + t * b)
+///     ),
+///     // selection of the second argument for negative first arguments
+///     Operator::make_ternary(
+///         "ifneg",
+///         TernaryOp::Select(|c| *c < 0.0)
+///     ),
+///     Operator::make_bin(
+///         "*",
+///         BinOp {
+///             apply: |a, b| a * b,
+///             prio: 1,
+///             is_commutative: true,
+///         }
+///     )
+/// );
+/// let expr = FlatEx::<f64, LerpOpsFactory>::from_str("lerp(x, 2, 2*y)")?;
+/// assert_eq!(expr.eval(&[0.0, 3.0])?, 2.0);
+/// assert_eq!(expr.eval(&[0.5, 3.0])?, 4.0);
+/// assert_eq!(expr.eval(&[1.0, 3.0])?, 6.0);
+/// let expr = FlatEx::<f64, LerpOpsFactory>::from_str("ifneg(x, 2*x, y)")?;
+/// assert_eq!(expr.eval(&[-1.0, 3.0])?, -2.0);
+/// assert_eq!(expr.eval(&[1.0, 3.0])?, 3.0);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub enum TernaryOp<T> {
+    /// Evaluates the first argument and passes it to the predicate. If the predicate is
+    /// true, the result is the second argument, otherwise the third. The argument that is
+    /// not selected is not evaluated.
+    Select(fn(&T) -> bool),
+    /// Evaluates all three arguments and applies the function to them.
+    Apply(fn(T, T, T) -> T),
+}
+
+impl<T> TernaryOp<T> {
+    /// Discriminant and address of the function used to compare ternary operators.
+    fn key(&self) -> (u8, usize) {
+        match self {
+            TernaryOp::Select(is_first) => (0, *is_first as usize),
+            TernaryOp::Apply(apply) => (1, *apply as usize),
+        }
+    }
+}
+impl<T> Clone for TernaryOp<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for TernaryOp<T> {}
+impl<T> PartialEq for TernaryOp<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+impl<T> Eq for TernaryOp<T> {}
+impl<T> PartialOrd for TernaryOp<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for TernaryOp<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+impl<T> Debug for TernaryOp<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TernaryOp::Select(_) => write!(f, "Select"),
+            TernaryOp::Apply(_) => write!(f, "Apply"),
+        }
+    }
+}
+
 /// To use custom operators one needs to create a factory that implements this trait.
 /// In this way, we make sure that we can deserialize expressions with
 /// [`serde`](docs.rs/serde) with the correct operators based on the type.
@@ -236,19 +509,249 @@ pub struct BinOp<T: Clone> {
 pub trait MakeOperators<T: Clone>: Clone {
     /// Function that creates a vector of operators.
     fn make<'a>() -> Vec<Operator<'a, T>>;
+
+    /// Returns a specialized implementation of the binary operator with representation
+    /// `repr` for the constant right operand `right` or `None` if there is no
+    /// specialization. During compilation of flat expressions, the specialization replaces
+    /// the implementation of the operator. The second argument passed to the specialization
+    /// is always `right`. By default, nothing is specialized.
+    fn specialize_bin(_repr: &str, _right: &T) -> Option<fn(T, T) -> T> {
+        None
+    }
+}
+
+fn find_op_of_factory<'a, T, OF>(repr: &str, op_type: OperatorType) -> ExResult<Operator<'a, T>>
+where
+    T: Clone,
+    OF: MakeOperators<T>,
+{
+    OF::make()
+        .into_iter()
+        .find(|op| op.repr() == repr)
+        .ok_or_else(|| make_op_not_available_error(repr, op_type))
+}
+
+/// Applies the binary operator with representation `repr` created by the factory `OF` to
+/// `a` and `b` exactly as exmex does during evaluation. Together with
+/// [`apply_unary`](apply_unary) and [`UnaryOp::apply`](UnaryOp::apply) this makes it possible
+/// to implement custom evaluators with the operator semantics of exmex.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{apply_bin, FloatOpsFactory, MakeOperators, UnaryOp};
+/// // evaluate `sin(-x)+2` that consists of the two nodes `sin(-x)` and `2`
+/// let ops = FloatOpsFactory::<f64>::make();
+/// let find_unary = |repr: &str| ops.iter().find(|op| op.repr() == repr).unwrap().unary();
+/// // unary operators are passed outermost-first
+/// let unary_op = UnaryOp::from_iter([find_unary("sin")?, find_unary("-")?].iter().copied());
+/// let x = 0.5;
+/// let nodes = [unary_op.apply(x), 2.0];
+/// let result = apply_bin::<f64, FloatOpsFactory<f64>>("+", nodes[0], nodes[1])?;
+/// assert!((result - ((-x).sin() + 2.0)).abs() < 1e-12);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the factory does not contain a binary operator with representation `repr`, we return
+/// an [`ExError`](ExError).
+///
+pub fn apply_bin<T, OF>(repr: &str, a: T, b: T) -> ExResult<T>
+where
+    T: Clone,
+    OF: MakeOperators<T>,
+{
+    let op = find_op_of_factory::<T, OF>(repr, OperatorType::Bin)?;
+    Ok((op.bin()?.apply)(a, b))
+}
+
+/// Applies the unary operator with representation `repr` created by the factory `OF` to `x`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{apply_unary, FloatOpsFactory};
+/// assert_eq!(apply_unary::<f64, FloatOpsFactory<f64>>("abs", -2.0)?, 2.0);
+/// assert!(apply_unary::<f64, FloatOpsFactory<f64>>("*", 2.0).is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the factory does not contain a unary operator with representation `repr`, we return
+/// an [`ExError`](ExError).
+///
+pub fn apply_unary<T, OF>(repr: &str, x: T) -> ExResult<T>
+where
+    T: Clone,
+    OF: MakeOperators<T>,
+{
+    let op = find_op_of_factory::<T, OF>(repr, OperatorType::Unary)?;
+    Ok(op.unary()?(x))
+}
+
+/// Overrides the priorities of binary operators, e.g., to let `^` bind weaker than `*`
+/// without re-implementing a whole operator factory. Each pair of `prios` consists of
+/// the representation of an operator and its new priority.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{override_prios, FloatOpsFactory, MakeOperators};
+/// let ops = override_prios(FloatOpsFactory::<f64>::make(), &[("^", 1)])?;
+/// let pow = ops.iter().find(|op| op.repr() == "^").unwrap();
+/// assert_eq!(pow.bin()?.prio, 1);
+/// assert!(override_prios(FloatOpsFactory::<f64>::make(), &[("sin", 1)]).is_err());
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If `ops` do not contain a binary operator with a representation of `prios`, we return
+/// an [`ExError`](ExError).
+///
+pub fn override_prios<'a, T: Clone>(
+    ops: Vec<Operator<'a, T>>,
+    prios: &[(&str, i64)],
+) -> ExResult<Vec<Operator<'a, T>>> {
+    for (repr, _) in prios {
+        if !ops.iter().any(|op| op.repr() == *repr && op.has_bin()) {
+            return Err(format_exerr!(
+                "there is no binary operator '{}' to override its priority",
+                repr
+            ));
+        }
+    }
+    Ok(ops
+        .into_iter()
+        .map(|op| {
+            let prio = prios.iter().rev().find(|(repr, _)| op.repr() == *repr);
+            match prio {
+                Some((_, prio)) => op.with_prio(*prio),
+                None => op,
+            }
+        })
+        .collect())
+}
+
+/// Rounds half-way cases to the nearest even integer, e.g., `2.5` to `2` and `3.5` to `4`.
+fn round_half_even<T: Float>(x: T) -> T {
+    let two = T::one() + T::one();
+    if (x - x.trunc()).abs() == T::one() / two {
+        two * (x / two).round()
+    } else {
+        x.round()
+    }
+}
+
+/// Largest number of digits accepted by `round_to`. `10^digits` is exact for larger `f64`
+/// exponents, but the product `x*10^digits` loses the digits that should be kept.
+const ROUND_TO_MAX_DIGITS: i32 = 15;
+
+/// Rounds `x` half-to-even to `digits` digits after the decimal point. Negative numbers of
+/// digits round to tens, hundreds, and so on. Non-integer numbers of digits are truncated.
+/// Numbers of digits outside of `-15..=15` yield NaN.
+///
+/// Half-way cases are decided based on the exact binary value of `x`. For instance,
+/// `2.675` is stored as `2.67499999999999982236431605997495353221893310546875` and hence
+/// rounded to `2.67` although `2.675*100` is rounded to `267.5` in floating point arithmetic.
+fn round_to<T: Float>(x: T, digits: T) -> T {
+    let digits = match digits.trunc().to_i32() {
+        Some(d) if d.abs() <= ROUND_TO_MAX_DIGITS => d,
+        _ => return T::nan(),
+    };
+    let scale = T::from(10.0).unwrap().powi(digits.abs());
+    // the residuals of the scaling are exact due to the fused multiply-add and have the
+    // sign of the difference between the exact and the rounded scaled value
+    let (scaled, residual) = if digits >= 0 {
+        let scaled = x * scale;
+        (scaled, x.mul_add(scale, -scaled))
+    } else {
+        let scaled = x / scale;
+        (scaled, (-scaled).mul_add(scale, x))
+    };
+    let two = T::one() + T::one();
+    let is_half_way = (scaled - scaled.trunc()).abs() == T::one() / two;
+    let rounded = if is_half_way && residual > T::zero() {
+        scaled.ceil()
+    } else if is_half_way && residual < T::zero() {
+        scaled.floor()
+    } else {
+        round_half_even(scaled)
+    };
+    if digits >= 0 {
+        rounded / scale
+    } else {
+        rounded * scale
+    }
+}
+
+/// Sign of `x` that is zero for zero in contrast to [`signum`](num::Float::signum).
+fn sign<T: Float>(x: T) -> T {
+    if x == T::zero() {
+        T::zero()
+    } else {
+        x.signum()
+    }
+}
+
+fn from_bool<T: Float>(b: bool) -> T {
+    if b {
+        T::one()
+    } else {
+        T::zero()
+    }
+}
+
+/// Maps `x` periodically into `[lo, hi)`. Swapped bounds are sorted and a range of zero
+/// width results in NaN.
+fn wrap_range<T: Float>(x: T, lo: T, hi: T) -> T {
+    let (lo, hi) = if hi < lo { (hi, lo) } else { (lo, hi) };
+    let width = hi - lo;
+    if width == T::zero() {
+        return T::nan();
+    }
+    let r = (x - lo) % width;
+    let wrapped = if r < T::zero() {
+        lo + r + width
+    } else {
+        lo + r
+    };
+    // rounding can hit the excluded upper bound
+    if wrapped >= hi {
+        lo
+    } else {
+        wrapped
+    }
 }
 
 /// Factory of default operators for floating point values.
 ///
 /// |representation|description|
 /// |--------------|-----------|
-/// |`^`| power |
+/// |`^`| power, indeterminate forms follow [`powf`](num::Float::powf), e.g., `0^0` is `1`, literal integer exponents between `-16` and `16` use [`powi`](num::Float::powi) |
 /// |`*`| product |
 /// |`/`| division |
+/// |`%`| Euclidean remainder like [`f64::rem_euclid`](f64::rem_euclid) in `[0, \|b\|)` also for negative operands, e.g., `-1 % 3` is `2` and `x % (2*PI)` wraps angles, the remainder of division by zero is NaN |
 /// |`+`| addition as binary or identity as unary operator|
 /// |`-`| subtraction as binary or inverting the sign as unary operator |
+/// |`atan2`| four-quadrant inverse tangent called as `atan2(y, x)` |
+/// |`round_to`| rounding half-to-even to a number of digits after the decimal point called as `round_to(x, digits)`, the number of digits is truncated to an integer and needs to be in `-15..=15`, otherwise the result is NaN, half-way cases follow the exact binary value, e.g., `round_to(2.675, 2)` is `2.67` since `2.675` is stored as `2.67499999...` |
+/// |`absdiff`| absolute difference called as `absdiff(a, b)`, i.e., `abs(a-b)` |
+/// |`sqdiff`| squared difference called as `sqdiff(a, b)`, i.e., `(a-b)^2` |
 /// |`abs`| absolute value |
-/// |`signum`| signum |
+/// |`signum`| signum, e.g., `signum(0)` is `1` and `signum(-0)` is `-1` |
+/// |`sign`| sign, `-1` for negative, `1` for positive numbers, and `0` for zero |
 /// |`sin`| sine |
 /// |`cos`| cosine |
 /// |`tan`| tangent |
@@ -260,17 +763,35 @@ pub trait MakeOperators<T: Clone>: Clone {
 /// |`tanh`| hyperbolic tangent |
 /// |`floor`| largest integer less than or equal to a number |
 /// |`ceil`| smallest integer greater than or equal to a number |
+/// |`round`| nearest integer, half-way cases are rounded away from zero |
+/// |`round_even`| nearest integer, half-way cases are rounded to the nearest even integer |
 /// |`trunc`| integer part of a number |
 /// |`fract`| fractional part of a number |
 /// |`exp`| exponential functionn |
+/// |`exp2`| exponential function with basis 2 |
+/// |`exp10`| exponential function with basis 10 |
 /// |`sqrt`| square root |
 /// |`cbrt`| cube root |
+/// |`recip`| reciprocal `1/x`, `recip(0)` is `inf` like `1/0` |
 /// |`log`| natural logarithm  |
 /// |`log2`| logarithm with basis 2  |
 /// |`PI`| constant π  |
 /// |`π`| second representations of constant π  |
 /// |`E`| Euler's number |
+/// |`<`, `>`, `<=`, `>=`| comparisons resulting in `1` for true and `0` for false |
+/// |`==`, `!=`| equality and inequality resulting in `1` for true and `0` for false |
+/// |`&&`| logical and, non-zero operands are true, the result is `1` or `0` |
+/// |`\|\|`| logical or, non-zero operands are true, the result is `1` or `0` |
+/// |`if`| selection called as `if(cond, a, b)` resulting in `a` for non-zero `cond` and `b` otherwise |
+/// |`wrap_range`| periodic wrapping called as `wrap_range(x, lo, hi)` that maps `x` into `[lo, hi)` by adding a multiple of `hi - lo`, e.g., `wrap_range(370, 0, 360)` is `10`, swapped bounds are sorted, the range `lo == hi` has zero width and results in NaN |
+///
+/// Comparisons have a lower priority than all arithmetic operators, `&&` binds stronger
+/// than `\|\|`, and both have a lower priority than comparisons. Hence,
+/// `x+1 > 2 && y < 0` is evaluated as `((x+1) > 2) && (y < 0)`.
 ///
+/// The result of `if` does not depend on the branch that is not selected. For instance,
+/// `if(x, 1/x, 0)` is `0` for `x == 0` and NaNs in the other branch are not propagated.
+/// The condition is evaluated once and only the selected branch is evaluated.
 ///
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct FloatOpsFactory<T: Float> {
@@ -284,11 +805,12 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
             Operator::make_bin(
                 "^",
                 BinOp {
-                    apply: |a, b| a.powf(b),
+                    apply: |a: T, b| a.powf(b),
                     prio: 4,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("pow"),
             Operator::make_bin(
                 "*",
                 BinOp {
@@ -296,7 +818,8 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("mul"),
             Operator::make_bin(
                 "/",
                 BinOp {
@@ -304,7 +827,8 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
                     prio: 3,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("div"),
             Operator::make_bin_unary(
                 "+",
                 BinOp {
@@ -313,7 +837,8 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
                     is_commutative: true,
                 },
                 |a| a,
-            ),
+            )
+            .with_id("add"),
             Operator::make_bin_unary(
                 "-",
                 BinOp {
@@ -321,8 +846,9 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
                     prio: 1,
                     is_commutative: false,
                 },
-                |a| -a,
-            ),
+                |a: T| -a,
+            )
+            .with_id("sub"),
             Operator::make_unary("abs", |a| a.abs()),
             Operator::make_unary("signum", |a| a.signum()),
             Operator::make_unary("sin", |a| a.sin()),
@@ -340,15 +866,235 @@ impl<T: Float> MakeOperators<T> for FloatOpsFactory<T> {
             Operator::make_unary("trunc", |a| a.trunc()),
             Operator::make_unary("fract", |a| a.fract()),
             Operator::make_unary("exp", |a| a.exp()),
+            Operator::make_unary("exp2", |a| a.exp2()),
+            Operator::make_unary("exp10", |a| T::from(10.0).unwrap().powf(a)),
             Operator::make_unary("sqrt", |a| a.sqrt()),
             Operator::make_unary("cbrt", |a| a.cbrt()),
-            Operator::make_unary("log", |a| a.ln()),
+            Operator::make_unary("log", |a: T| a.ln()).with_id("ln"),
             Operator::make_unary("log2", |a| a.log2()),
-            Operator::make_constant("PI", T::from(std::f64::consts::PI).unwrap()),
-            Operator::make_constant("π", T::from(std::f64::consts::PI).unwrap()),
-            Operator::make_constant("E", T::from(std::f64::consts::E).unwrap()),
+            Operator::make_constant("PI", T::from(std::f64::consts::PI).unwrap()).with_id("pi"),
+            Operator::make_constant("π", T::from(std::f64::consts::PI).unwrap()).with_id("pi"),
+            Operator::make_constant("E", T::from(std::f64::consts::E).unwrap()).with_id("e"),
+            Operator::make_bin(
+                "atan2",
+                BinOp {
+                    apply: |y: T, x| y.atan2(x),
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_function_style(),
+            Operator::make_bin(
+                "round_to",
+                BinOp {
+                    apply: round_to,
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_function_style(),
+            Operator::make_unary("round_even", round_half_even),
+            Operator::make_bin(
+                "absdiff",
+                BinOp {
+                    apply: |a: T, b| (a - b).abs(),
+                    prio: 3,
+                    is_commutative: true,
+                },
+            )
+            .with_function_style(),
+            Operator::make_bin(
+                "sqdiff",
+                BinOp {
+                    apply: |a: T, b| (a - b) * (a - b),
+                    prio: 3,
+                    is_commutative: true,
+                },
+            )
+            .with_function_style(),
+            Operator::make_unary("sign", sign),
+            Operator::make_bin(
+                "<",
+                BinOp {
+                    apply: |a: T, b| from_bool(a < b),
+                    prio: -1,
+                    is_commutative: false,
+                },
+            )
+            .with_id("lt"),
+            Operator::make_bin(
+                ">",
+                BinOp {
+                    apply: |a: T, b| from_bool(a > b),
+                    prio: -1,
+                    is_commutative: false,
+                },
+            )
+            .with_id("gt"),
+            Operator::make_bin(
+                "<=",
+                BinOp {
+                    apply: |a: T, b| from_bool(a <= b),
+                    prio: -1,
+                    is_commutative: false,
+                },
+            )
+            .with_id("le"),
+            Operator::make_bin(
+                ">=",
+                BinOp {
+                    apply: |a: T, b| from_bool(a >= b),
+                    prio: -1,
+                    is_commutative: false,
+                },
+            )
+            .with_id("ge"),
+            Operator::make_bin(
+                "==",
+                BinOp {
+                    apply: |a: T, b| from_bool(a == b),
+                    prio: -1,
+                    is_commutative: true,
+                },
+            )
+            .with_id("eq"),
+            Operator::make_bin(
+                "!=",
+                BinOp {
+                    apply: |a: T, b| from_bool(a != b),
+                    prio: -1,
+                    is_commutative: true,
+                },
+            )
+            .with_id("ne"),
+            Operator::make_bin(
+                "&&",
+                BinOp {
+                    apply: |a: T, b| from_bool(a != T::zero() && b != T::zero()),
+                    prio: -2,
+                    is_commutative: true,
+                },
+            )
+            .with_id("and"),
+            Operator::make_bin(
+                "||",
+                BinOp {
+                    apply: |a: T, b| from_bool(a != T::zero() || b != T::zero()),
+                    prio: -3,
+                    is_commutative: true,
+                },
+            )
+            .with_id("or"),
+            Operator::make_ternary("if", TernaryOp::Select(|c: &T| *c != T::zero())),
+            Operator::make_ternary("wrap_range", TernaryOp::Apply(wrap_range)),
+            Operator::make_unary("recip", |a| a.recip()),
+            Operator::make_bin(
+                "%",
+                BinOp {
+                    apply: |a: T, b: T| {
+                        let r = a % b;
+                        if r < T::zero() {
+                            r + b.abs()
+                        } else {
+                            r
+                        }
+                    },
+                    prio: 3,
+                    is_commutative: false,
+                },
+            )
+            .with_id("rem"),
         ]
     }
+
+    /// Replaces `powf` by `powi` for integer exponents between `-16` and `16`.
+    fn specialize_bin(repr: &str, right: &T) -> Option<fn(T, T) -> T> {
+        if repr == "^" {
+            pow_int_exponent(*right)
+        } else {
+            None
+        }
+    }
+}
+
+fn powi<T: Float, const N: i32>(base: T, _exponent: T) -> T {
+    base.powi(N)
+}
+
+/// Returns the power function for the literal `exponent` if it is an integer between `-16`
+/// and `16`.
+fn pow_int_exponent<T: Float>(exponent: T) -> Option<fn(T, T) -> T> {
+    macro_rules! powi_of {
+        ($exponent:expr; $($n:literal)*) => {
+            match $exponent {
+                $($n => Some(powi::<T, { $n }> as fn(T, T) -> T),)*
+                _ => None,
+            }
+        };
+    }
+    if exponent.fract() != T::zero() {
+        return None;
+    }
+    powi_of! {
+        exponent.to_i32()?;
+        -16 -15 -14 -13 -12 -11 -10 -9 -8 -7 -6 -5 -4 -3 -2 -1
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16
+    }
+}
+
+/// Factory of the operators of [`FloatOpsFactory`](FloatOpsFactory) with a power operator `^`
+/// that returns NaN for the indeterminate forms `0^0`, `∞^0`, and `1^∞` instead of `1`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, IndeterminateAsNanOpsFactory};
+/// assert_eq!(exmex::eval_str::<f64>("0^0")?, 1.0);
+/// let expr = FlatEx::<f64, IndeterminateAsNanOpsFactory<f64>>::from_str("x^0")?;
+/// assert!(expr.eval(&[0.0])?.is_nan());
+/// assert_eq!(expr.eval(&[2.0])?, 1.0);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct IndeterminateAsNanOpsFactory<T: Float> {
+    dummy: PhantomData<T>,
+}
+
+fn pow_indeterminate_as_nan<T: Float>(a: T, b: T) -> T {
+    let is_indeterminate = (b == T::zero() && (a == T::zero() || a.is_infinite()))
+        || (a == T::one() && b.is_infinite());
+    if is_indeterminate {
+        T::nan()
+    } else {
+        a.powf(b)
+    }
+}
+
+impl<T: Float> MakeOperators<T> for IndeterminateAsNanOpsFactory<T> {
+    /// Returns the default operators with the power operator replaced.
+    fn make<'a>() -> Vec<Operator<'a, T>> {
+        FloatOpsFactory::make()
+            .into_iter()
+            .map(|op| {
+                if op.repr() == "^" {
+                    Operator::make_bin(
+                        "^",
+                        BinOp {
+                            apply: pow_indeterminate_as_nan,
+                            prio: 4,
+                            is_commutative: false,
+                        },
+                    )
+                    .with_id(op.id())
+                } else {
+                    op
+                }
+            })
+            .collect()
+    }
 }
 
 /// This macro creates an operator factory struct that implements the trait
@@ -380,3 +1126,49 @@ macro_rules! ops_factory {
         }
     }
 }
+
+/// This macro creates an operator factory struct that implements the trait
+/// [`MakeOperators`](MakeOperators) with the operators of an existing factory whose
+/// priorities are overridden, see [`override_prios`](override_prios). You have to pass the
+/// name of the struct as first, the type of the operands as second, the existing factory as
+/// third, and pairs of operator representations and priorities as fourth to n-th argument.
+///
+/// # Example
+///
+/// ```
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// use exmex::{ops_factory_with_prios, FloatOpsFactory, MakeOperators, Operator};
+/// ops_factory_with_prios!(
+///     WeakPowOpsFactory,      // name of struct
+///     f64,                    // data type of operands
+///     FloatOpsFactory<f64>,   // factory with the operators
+///     ("^", 1)                // `^` binds weaker than `*`
+/// );
+/// let expr = FlatEx::<f64, WeakPowOpsFactory>::from_str("2*x^2")?;
+/// assert_eq!(expr.eval(&[3.0])?, 36.0);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Creating the operators panics if the existing factory does not contain a binary
+/// operator with one of the passed representations.
+///
+#[macro_export]
+macro_rules! ops_factory_with_prios {
+    ($name:ident, $T:ty, $base:ty, $( ($repr:expr, $prio:expr) ),*) => {
+        #[derive(Clone)]
+        struct $name;
+        impl MakeOperators<$T> for $name {
+            fn make<'a>() -> Vec<Operator<'a, $T>> {
+                $crate::override_prios(<$base>::make(), &[$(($repr, $prio),)*])
+                    .unwrap_or_else(|e| panic!("{}", e.msg))
+            }
+        }
+    }
+}
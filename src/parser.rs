@@ -6,14 +6,15 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use smallvec::SmallVec;
 use std::fmt::Debug;
+use std::ops::Range;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Paren {
     Open,
     Close,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ParsedToken<'a, T: DataType> {
     Num(T),
     Paren(Paren),
@@ -21,6 +22,17 @@ pub enum ParsedToken<'a, T: DataType> {
     Var(&'a str),
 }
 
+/// Determines how names of variables in curly brackets such as `{x }` are parsed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CurlyVarNames {
+    /// Surrounding whitespace is trimmed such that `{x}` and `{ x }` are the same variable `x`.
+    #[default]
+    Trim,
+    /// Names are taken verbatim including whitespace such that `{x}` and `{x }` are
+    /// different variables.
+    Verbatim,
+}
+
 /// Returns the index of the variable in the slice. Panics if not available!
 pub fn find_var_index<'a>(name: &str, parsed_vars: &[&'a str]) -> usize {
     let idx = parsed_vars.iter().enumerate().find(|(_, n)| **n == name);
@@ -72,6 +84,52 @@ pub fn find_parsed_vars<'a, T: DataType>(
     found_vars
 }
 
+/// Returns variable names in sorted order together with the byte ranges of their
+/// occurrences in `text`. The parsed tokens need to stem from `text`.
+pub fn find_parsed_var_spans<'a, T: DataType>(
+    text: &'a str,
+    parsed_tokens: &[ParsedToken<'a, T>],
+) -> Vec<(&'a str, Vec<Range<usize>>)> {
+    let mut found_vars: Vec<(&'a str, Vec<Range<usize>>)> = vec![];
+    for pt in parsed_tokens {
+        if let ParsedToken::Var(name) = pt {
+            // variable names are sub-slices of the text
+            let start = name.as_ptr() as usize - text.as_ptr() as usize;
+            let span = start..start + name.len();
+            match found_vars.iter_mut().find(|(n, _)| n == name) {
+                Some((_, spans)) => spans.push(span),
+                None => found_vars.push((*name, vec![span])),
+            }
+        }
+    }
+    found_vars.sort_unstable_by_key(|(name, _)| *name);
+    found_vars
+}
+
+/// Decodes UTF-8 encoded text and skips a leading byte order mark.
+///
+/// # Errors
+///
+/// If the bytes are not valid UTF-8, the position of the error is the character position of
+/// the first invalid byte in the decoded text, i.e., without a leading byte order mark, as for
+/// parse errors. The error message contains the byte offset of the first invalid byte in
+/// `bytes` including a leading byte order mark.
+///
+pub fn str_from_bytes(bytes: &[u8]) -> ExResult<&str> {
+    const BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+    let (n_bom_bytes, bytes) = match bytes.strip_prefix(BOM) {
+        Some(rest) => (BOM.len(), rest),
+        None => (0, bytes),
+    };
+    std::str::from_utf8(bytes).map_err(|e| {
+        let valid_up_to = e.valid_up_to();
+        // the prefix up to the first invalid byte is valid
+        let char_idx = std::str::from_utf8(&bytes[..valid_up_to]).map_or(0, |s| s.chars().count());
+        format_exerr!("invalid UTF-8 at byte {}, {}", n_bom_bytes + valid_up_to, e)
+            .with_position(char_idx)
+    })
+}
+
 pub fn is_numeric_text(text: &str) -> Option<&str> {
     let mut n_dots = 0;
     let n_num_chars = text
@@ -97,7 +155,11 @@ fn next_char_boundary(text: &str, start_idx: usize) -> usize {
         .expect("there has to be a char boundary somewhere")
 }
 
-/// Parses tokens of a text with regexes and returns them as a vector
+/// Character positions of parsed tokens in the parsed text.
+pub type TokenPositions = SmallVec<[usize; N_NODES_ON_STACK]>;
+
+/// Parses tokens of a text with regexes and returns them as a vector together with the
+/// character position of each token in the text
 ///
 /// # Arguments
 ///
@@ -107,13 +169,36 @@ fn next_char_boundary(text: &str, start_idx: usize) -> usize {
 ///
 /// # Errors
 ///
-/// See [`parse_with_number_pattern`](parse_with_number_pattern)
+/// See [`parse_with_number_pattern`](parse_with_number_pattern). Errors of unknown tokens
+/// contain the position of the token.
 ///
 pub fn tokenize_and_analyze<'a, T, F>(
     text: &'a str,
     ops_in: &[Operator<'a, T>],
     is_numeric: F,
-) -> ExResult<SmallVec<[ParsedToken<'a, T>; N_NODES_ON_STACK]>>
+) -> ExResult<(
+    SmallVec<[ParsedToken<'a, T>; N_NODES_ON_STACK]>,
+    TokenPositions,
+)>
+where
+    <T as std::str::FromStr>::Err: Debug,
+    T: DataType,
+    F: Fn(&'a str) -> Option<&'a str>,
+{
+    tokenize_and_analyze_with_curly_var_names(text, ops_in, is_numeric, CurlyVarNames::default())
+}
+
+/// Parses tokens like [`tokenize_and_analyze`] where names of variables in curly brackets
+/// are treated as determined by `curly_var_names`.
+pub fn tokenize_and_analyze_with_curly_var_names<'a, T, F>(
+    text: &'a str,
+    ops_in: &[Operator<'a, T>],
+    is_numeric: F,
+    curly_var_names: CurlyVarNames,
+) -> ExResult<(
+    SmallVec<[ParsedToken<'a, T>; N_NODES_ON_STACK]>,
+    TokenPositions,
+)>
 where
     <T as std::str::FromStr>::Err: Debug,
     T: DataType,
@@ -138,7 +223,7 @@ where
             let range_end = byte_offset + op.repr().len();
             if let Some(maybe_op) = text.get(byte_offset..range_end) {
                 op.repr() == maybe_op
-                    && (op.has_bin()
+                    && ((op.has_bin() && !op.is_function_style())
                         || range_end >= text.len()
                         || !RE_VAR_NAME_EXACT.is_match(
                             &text[byte_offset..range_end + next_char_boundary(text, range_end)],
@@ -149,13 +234,21 @@ where
         })
     };
     let mut res: SmallVec<[_; N_NODES_ON_STACK]> = SmallVec::new();
+    let mut positions = TokenPositions::new();
+    // commas are not tokens, we store the index of the token right of each comma
+    let mut comma_positions = SmallVec::<[usize; N_NODES_ON_STACK]>::new();
     let mut cur_byte_offset = 0usize;
-    for (i, c) in text.char_indices() {
+    for (char_idx, (i, c)) in text.char_indices().enumerate() {
         if c == ' ' && i == cur_byte_offset {
             cur_byte_offset += 1;
         } else if i == cur_byte_offset && cur_byte_offset < text.len() {
             let text_rest = &text[cur_byte_offset..];
             let cur_byte_offset_tmp = cur_byte_offset;
+            if c == ',' {
+                cur_byte_offset += 1;
+                comma_positions.push(res.len());
+                continue;
+            }
             let next_parsed_token = if c == '(' {
                 cur_byte_offset += 1;
                 ParsedToken::<T>::Paren(Paren::Open)
@@ -168,18 +261,50 @@ where
                     .take_while(|c| *c != '}')
                     .map(|c| c.len_utf8())
                     .sum();
-                let var_name = &text_rest[1..n_count];
+                if n_count == text_rest.len() {
+                    return Err(format_exerr!(
+                        "missing closing curly bracket of variable in {}",
+                        text_rest
+                    )
+                    .with_position(char_idx));
+                }
+                let var_name = match curly_var_names {
+                    CurlyVarNames::Trim => text_rest[1..n_count].trim(),
+                    CurlyVarNames::Verbatim => &text_rest[1..n_count],
+                };
                 cur_byte_offset += n_count + 1;
                 ParsedToken::<T>::Var(var_name)
-            } else if let Some(num_str) = is_numeric(text_rest) {
+            } else if let Some(num_str) = is_numeric(text_rest)
+                // literal matchers might match empty strings or not at the beginning
+                .filter(|num_str| !num_str.is_empty() && text_rest.starts_with(num_str))
+            {
                 let n_bytes = num_str.len();
                 cur_byte_offset += n_bytes;
                 ParsedToken::<T>::Num(num_str.parse::<T>().map_err(|e| ExError {
                     msg: format!("could not parse '{}', {:?}", num_str, e),
+                    position: Some(char_idx),
                 })?)
             } else if let Some(op) = find_ops(cur_byte_offset_tmp) {
                 let n_bytes = op.repr().len();
                 cur_byte_offset += n_bytes;
+                if op.is_stochastic() && op.has_unary() && text[cur_byte_offset..].starts_with("()")
+                {
+                    // stochastic operators ignore their argument, hence we can insert any number
+                    let zero = "0".parse::<T>().map_err(|e| ExError {
+                        msg: format!("could not parse '0', {:?}", e),
+                        position: Some(char_idx),
+                    })?;
+                    let open_char_idx = char_idx + op.repr().chars().count();
+                    res.push(ParsedToken::<T>::Op((*op).clone()));
+                    positions.push(char_idx);
+                    res.push(ParsedToken::<T>::Paren(Paren::Open));
+                    positions.push(open_char_idx);
+                    res.push(ParsedToken::<T>::Num(zero));
+                    positions.push(open_char_idx + 1);
+                    // the closing paren is parsed as usual
+                    cur_byte_offset += 1;
+                    continue;
+                }
                 match op.constant() {
                     Some(constant) => ParsedToken::<T>::Num(constant),
                     None => ParsedToken::<T>::Op((*op).clone()),
@@ -191,12 +316,218 @@ where
                 ParsedToken::<T>::Var(var_str)
             } else {
                 let msg = format!("don't know how to parse {}", text_rest);
-                return Err(ExError { msg });
+                return Err(ExError {
+                    msg,
+                    position: Some(char_idx),
+                });
             };
             res.push(next_parsed_token);
+            positions.push(char_idx);
         }
     }
-    Ok(res)
+    let has_function_style_ops = res.iter().any(|pt| match pt {
+        ParsedToken::Op(op) => op.is_function_style(),
+        _ => false,
+    });
+    if comma_positions.is_empty() && !has_function_style_ops {
+        Ok((res, positions))
+    } else {
+        resolve_function_style_ops(&res, &positions, &comma_positions)
+    }
+}
+
+pub fn find_closing_paren<T: DataType>(
+    parsed_tokens: &[ParsedToken<T>],
+    open_idx: usize,
+) -> Option<usize> {
+    let mut depth = 0i32;
+    (open_idx..parsed_tokens.len()).find(|i| {
+        match parsed_tokens[*i] {
+            ParsedToken::Paren(Paren::Open) => depth += 1,
+            ParsedToken::Paren(Paren::Close) => depth -= 1,
+            _ => (),
+        }
+        depth == 0
+    })
+}
+
+/// Replaces function-style binary operators `f(a, b)` by their infix form `((a)f(b))`
+/// such that they can be processed like all other binary operators. Function-style
+/// operators that are also unary are used as unary operators if there is no comma.
+/// Ternary operators `f(c, a, b)` are replaced by `f(c)(a)(b)` such that each argument is
+/// enclosed by its own parentheses.
+///
+/// # Errors
+///
+/// An error is returned if a function-style operator is not followed by parentheses,
+/// does not have two or three non-empty arguments, respectively, or if a comma is not within the arguments of
+/// a function-style operator. Inserted parentheses are positioned at the operator and
+/// at the first token of the second argument, respectively.
+///
+fn resolve_function_style_ops<'a, T: DataType>(
+    parsed_tokens: &[ParsedToken<'a, T>],
+    positions: &[usize],
+    comma_positions: &[usize],
+) -> ExResult<(
+    SmallVec<[ParsedToken<'a, T>; N_NODES_ON_STACK]>,
+    TokenPositions,
+)> {
+    fn resolve_range<'a, T: DataType>(
+        parsed_tokens: &[ParsedToken<'a, T>],
+        positions: &[usize],
+        comma_positions: &[usize],
+        range: std::ops::Range<usize>,
+        n_consumed_commas: &mut usize,
+        res: &mut SmallVec<[(ParsedToken<'a, T>, usize); N_NODES_ON_STACK]>,
+    ) -> ExResult<()> {
+        let mut idx = range.start;
+        while idx < range.end {
+            let op = match &parsed_tokens[idx] {
+                ParsedToken::Op(op) if op.is_function_style() => op,
+                pt => {
+                    res.push((pt.clone(), positions[idx]));
+                    idx += 1;
+                    continue;
+                }
+            };
+            let op_pos = positions[idx];
+            let closing_idx = match parsed_tokens.get(idx + 1) {
+                Some(ParsedToken::Paren(Paren::Open)) => find_closing_paren(parsed_tokens, idx + 1)
+                    .filter(|closing_idx| *closing_idx < range.end),
+                _ => None,
+            };
+            let closing_idx = match closing_idx {
+                Some(closing_idx) => closing_idx,
+                None if op.has_unary() => {
+                    res.push((ParsedToken::Op(op.unary_only()), op_pos));
+                    idx += 1;
+                    continue;
+                }
+                None => {
+                    return Err(format_exerr!(
+                        "the function-style operator '{}' needs its arguments in parentheses",
+                        op.repr()
+                    )
+                    .with_position(op_pos))
+                }
+            };
+            let mut depth = 0;
+            let mut separators = SmallVec::<[usize; 2]>::new();
+            let arg_tokens = parsed_tokens
+                .iter()
+                .enumerate()
+                .take(closing_idx)
+                .skip(idx + 2);
+            for (token_idx, pt) in arg_tokens {
+                // a comma at the current position is left of the current token
+                if depth == 0 && comma_positions.contains(&token_idx) {
+                    separators.push(token_idx);
+                }
+                match pt {
+                    ParsedToken::Paren(Paren::Open) => depth += 1,
+                    ParsedToken::Paren(Paren::Close) => depth -= 1,
+                    _ => (),
+                }
+            }
+            if comma_positions.contains(&closing_idx) {
+                separators.push(closing_idx);
+            }
+            let open = |pos| (ParsedToken::Paren(Paren::Open), pos);
+            let close = |pos| (ParsedToken::Paren(Paren::Close), pos);
+            match (separators.as_slice(), op.ternary()) {
+                ([], _) if op.has_unary() => {
+                    res.push((ParsedToken::Op(op.unary_only()), op_pos));
+                    idx += 1;
+                }
+                ([sep_idx_1, sep_idx_2], Some(_))
+                    if idx + 2 < *sep_idx_1
+                        && sep_idx_1 < sep_idx_2
+                        && *sep_idx_2 < closing_idx =>
+                {
+                    *n_consumed_commas += 2;
+                    // op(c, a, b) becomes op(c)(a)(b)
+                    res.push((ParsedToken::Op(op.clone()), op_pos));
+                    for (open_idx, arg_idx, end_idx) in [
+                        (idx + 1, idx + 2, *sep_idx_1),
+                        (*sep_idx_1, *sep_idx_1, *sep_idx_2),
+                        (*sep_idx_2, *sep_idx_2, closing_idx),
+                    ] {
+                        res.push(open(positions[open_idx]));
+                        resolve_range(
+                            parsed_tokens,
+                            positions,
+                            comma_positions,
+                            arg_idx..end_idx,
+                            n_consumed_commas,
+                            res,
+                        )?;
+                        res.push(close(positions[end_idx]));
+                    }
+                    idx = closing_idx + 1;
+                }
+                ([sep_idx], None) if idx + 2 < *sep_idx && *sep_idx < closing_idx => {
+                    *n_consumed_commas += 1;
+                    let sep_pos = positions[*sep_idx];
+                    let closing_pos = positions[closing_idx];
+                    res.push(open(op_pos));
+                    res.push(open(op_pos));
+                    resolve_range(
+                        parsed_tokens,
+                        positions,
+                        comma_positions,
+                        idx + 2..*sep_idx,
+                        n_consumed_commas,
+                        res,
+                    )?;
+                    res.push(close(sep_pos));
+                    res.push((ParsedToken::Op(op.bin_only()), op_pos));
+                    res.push(open(sep_pos));
+                    resolve_range(
+                        parsed_tokens,
+                        positions,
+                        comma_positions,
+                        *sep_idx..closing_idx,
+                        n_consumed_commas,
+                        res,
+                    )?;
+                    res.push(close(closing_pos));
+                    res.push(close(closing_pos));
+                    idx = closing_idx + 1;
+                }
+                (_, ternary) => {
+                    return Err(format_exerr!(
+                        "the function-style operator '{}' needs {} non-empty arguments separated by commas",
+                        op.repr(),
+                        if ternary.is_some() { 3 } else { 2 }
+                    )
+                    .with_position(op_pos))
+                }
+            }
+        }
+        Ok(())
+    }
+    let mut res = SmallVec::new();
+    let mut n_consumed_commas = 0;
+    resolve_range(
+        parsed_tokens,
+        positions,
+        comma_positions,
+        0..parsed_tokens.len(),
+        &mut n_consumed_commas,
+        &mut res,
+    )?;
+    if n_consumed_commas < comma_positions.len() {
+        Err(ExError::new(
+            "commas are only allowed between the arguments of function-style operators",
+        ))
+    } else {
+        Ok(res.into_iter().unzip())
+    }
+}
+
+/// True for unary and ternary operators that are positioned in front of their operands.
+fn is_prefix<T: DataType>(op: &Operator<T>) -> bool {
+    op.has_unary() || op.ternary().is_some()
 }
 
 struct PairPreCondition<'a, T: DataType> {
@@ -212,7 +543,7 @@ fn make_err<T: DataType>(msg: &str, left: &ParsedToken<T>, right: &ParsedToken<T
     ))
 }
 
-fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
+fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 10] {
     [
         PairPreCondition {
             apply: |left, right| {
@@ -259,7 +590,7 @@ fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
             apply: |left, right| {
                 match (left, right) {
                 (ParsedToken::Op(op_l), ParsedToken::Op(op_r))
-                    if !op_l.has_unary() && !op_r.has_unary() => Err(format_exerr!(
+                    if !op_l.has_unary() && !is_prefix(op_r) => Err(format_exerr!(
                         "a binary operator cannot be next to the binary operator, violated by '{}' left of '{}'",
                         op_l.repr(),
                         op_r.repr())),                
@@ -271,7 +602,7 @@ fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
             apply: |left, right| {
                 match (left, right) {
                 (ParsedToken::Op(op_l), ParsedToken::Op(op_r))
-                    if !op_l.has_bin() && !op_r.has_unary() => Err(format_exerr!(
+                    if !op_l.has_bin() && !is_prefix(op_r) => Err(format_exerr!(
                         "a unary operator cannot be on the left of a binary one, violated by '{}' left of '{}'",
                         op_l.repr(),
                         op_r.repr())),                
@@ -300,7 +631,7 @@ fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
         PairPreCondition {
             apply: |left, right| {
                 match (left, right) {
-                    (ParsedToken::Paren(_p @ Paren::Open), ParsedToken::Op(op)) if !op.has_unary() => {
+                    (ParsedToken::Paren(_p @ Paren::Open), ParsedToken::Op(op)) if !is_prefix(op) => {
                         Err(format_exerr!(
                             "a binary operator cannot be on the right of an opening paren, violated by '{}'", 
                             op.repr()))
@@ -318,6 +649,16 @@ fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
                 _ => Ok(()),
             },
         },
+        PairPreCondition {
+            apply: |left, right| match (left, right) {
+                (ParsedToken::Op(op_l), ParsedToken::Op(op_r))
+                    if op_l.has_bin() && op_l.has_unary() && !is_prefix(op_r) => Err(format_exerr!(
+                        "a binary operator cannot be on the right of another operator, violated by '{}' left of '{}'",
+                        op_l.repr(),
+                        op_r.repr())),
+                _ => Ok(()),
+            },
+        },
     ]
 }
 
@@ -326,68 +667,107 @@ fn make_pair_pre_conditions<'a, T: DataType>() -> [PairPreCondition<'a, T>; 9] {
 /// # Arguments
 ///
 /// * `parsed_tokens` - parsed tokens
+/// * `positions` - character positions of the parsed tokens
 ///
 /// # Errors
 ///
-/// See [`parse_with_number_pattern`](parse_with_number_pattern)
+/// See [`parse_with_number_pattern`](parse_with_number_pattern). The error contains the
+/// position of the offending token if there is one.
 ///
-pub fn check_parsed_token_preconditions<T>(parsed_tokens: &[ParsedToken<T>]) -> ExResult<()>
+pub fn check_parsed_token_preconditions<T>(
+    parsed_tokens: &[ParsedToken<T>],
+    positions: &[usize],
+) -> ExResult<()>
+where
+    T: DataType,
+{
+    match find_precondition_errors(parsed_tokens, positions)
+        .into_iter()
+        .next()
+    {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Collects all violated preconditions of the parsed tokens. The first error coincides with
+/// the error returned by [`check_parsed_token_preconditions`](check_parsed_token_preconditions).
+///
+/// # Arguments
+///
+/// * `parsed_tokens` - parsed tokens
+/// * `positions` - character positions of the parsed tokens
+///
+pub fn find_precondition_errors<T>(
+    parsed_tokens: &[ParsedToken<T>],
+    positions: &[usize],
+) -> Vec<ExError>
 where
     T: DataType,
 {
     if parsed_tokens.is_empty() {
-        return Err(ExError {
+        return vec![ExError {
             msg: "cannot parse empty string".to_string(),
-        });
+            position: None,
+        }];
     };
 
+    let mut errors = vec![];
+    if let ParsedToken::Op(op) = &parsed_tokens[0] {
+        if !is_prefix(op) {
+            errors.push(
+                format_exerr!(
+                    "a binary operator cannot be the first element, violated by '{}'",
+                    op.repr()
+                )
+                .with_position(positions[0]),
+            );
+        }
+    }
+
     let pair_pre_conditions = make_pair_pre_conditions::<T>();
-    (0..parsed_tokens.len() - 1)
-        .map(|i| {
-            let failed = pair_pre_conditions
+    let pair_errors = (0..parsed_tokens.len() - 1)
+        .filter_map(|i| {
+            pair_pre_conditions
                 .iter()
                 .map(|ppc| (ppc.apply)(&parsed_tokens[i], &parsed_tokens[i + 1]))
-                .find(|ppc_res| ppc_res.is_err());
-            match failed {
-                Some(failed_ppc) => failed_ppc,
-                None => Ok(()),
-            }
-        })
-        .collect::<ExResult<Vec<_>>>()?;
+                .find_map(|ppc_res| ppc_res.err())
+                // the right token of a pair is the first one that does not fit
+                .map(|e| e.with_position(positions[i + 1]))
+        });
+    errors.extend(pair_errors);
 
     let mut open_paren_cnt = 0i32;
-    parsed_tokens
-        .iter()
-        .enumerate()
-        .map(|(i, expr_elt)| -> ExResult<()> {
-            match expr_elt {
-                ParsedToken::Paren(p) => {
-                    open_paren_cnt += match p {
-                        Paren::Close => -1,
-                        Paren::Open => 1,
-                    };
-                    if open_paren_cnt < 0 {
-                        return Err(ExError {
-                            msg: format!("too many closing parentheses until position {}", i),
-                        });
-                    }
-                    Ok(())
-                }
-                _ => Ok(()),
+    let too_many_closings = parsed_tokens.iter().enumerate().find_map(|(i, expr_elt)| {
+        if let ParsedToken::Paren(p) = expr_elt {
+            open_paren_cnt += match p {
+                Paren::Close => -1,
+                Paren::Open => 1,
+            };
+            if open_paren_cnt < 0 {
+                return Some(ExError {
+                    msg: format!("too many closing parentheses until position {}", i),
+                    position: Some(positions[i]),
+                });
             }
-        })
-        .collect::<ExResult<Vec<_>>>()?;
-    if open_paren_cnt != 0 {
-        Err(ExError {
+        }
+        None
+    });
+    match too_many_closings {
+        Some(e) => errors.push(e),
+        None if open_paren_cnt != 0 => errors.push(ExError {
             msg: "parentheses mismatch".to_string(),
-        })
-    } else if let ParsedToken::Op(_) = parsed_tokens[parsed_tokens.len() - 1] {
-        Err(ExError {
+            position: None,
+        }),
+        None => (),
+    }
+    if let ParsedToken::Op(_) = parsed_tokens[parsed_tokens.len() - 1] {
+        errors.push(ExError {
             msg: "the last element cannot be an operator".to_string(),
-        })
-    } else {
-        Ok(())
+            position: Some(positions[parsed_tokens.len() - 1]),
+        });
     }
+    errors
 }
 
 #[cfg(test)]
@@ -404,6 +784,59 @@ fn test_is_numeric() {
     assert_eq!(is_numeric_text("23.414").unwrap(), "23.414");
 }
 
+#[test]
+fn test_find_parsed_var_spans() -> ExResult<()> {
+    let ops = FloatOpsFactory::<f64>::make();
+    let text = "sqr(x)+ {y} *x";
+    let (parsed_tokens, _) = tokenize_and_analyze(text, &ops, is_numeric_text)?;
+    let spans = find_parsed_var_spans(text, &parsed_tokens);
+    let names = spans.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["sqr", "x", "y"]);
+    let starts = spans
+        .iter()
+        .map(|(_, spans)| spans.iter().map(|s| s.start).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(starts, vec![vec![0], vec![4, 13], vec![9]]);
+    for (name, spans) in spans {
+        for span in spans {
+            assert_eq!(&text[span], name);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_str_from_bytes() -> ExResult<()> {
+    assert_eq!(str_from_bytes("x+α".as_bytes())?, "x+α");
+    assert_eq!(str_from_bytes(b"\xEF\xBB\xBFsin(x)")?, "sin(x)");
+    let err = str_from_bytes(b"\xEF\xBB\xBFx+\xCE\xB1*\xFF2").unwrap_err();
+    assert!(err.msg.contains("byte 8"));
+    assert_eq!(err.position, Some(4));
+    let err = str_from_bytes(b"x+\xCE\xB1*\xFF2").unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("parse error at char 4: invalid UTF-8 at byte 5"));
+    let err = str_from_bytes(b"\xCE\xB1*\xFF").unwrap_err();
+    assert_eq!(err.position, Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_token_positions() -> ExResult<()> {
+    let ops = FloatOpsFactory::<f64>::make();
+    let (_, positions) = tokenize_and_analyze("α *  {β}+sin(x)", &ops, is_numeric_text)?;
+    assert_eq!(positions.as_slice(), &[0, 2, 5, 8, 9, 12, 13, 14]);
+    // inserted parentheses are positioned at the operator, the second argument, and the
+    // closing parenthesis
+    let (parsed_tokens, positions) = tokenize_and_analyze("atan2(x, 2)", &ops, is_numeric_text)?;
+    assert_eq!(parsed_tokens.len(), positions.len());
+    assert_eq!(positions.as_slice(), &[0, 0, 6, 9, 0, 9, 9, 10, 10]);
+    let (parsed_tokens, positions) = tokenize_and_analyze("z+/Q", &ops, is_numeric_text)?;
+    let err = check_parsed_token_preconditions(&parsed_tokens, &positions).unwrap_err();
+    assert_eq!(err.position, Some(2));
+    Ok(())
+}
+
 #[test]
 fn test_preconditions() {
     fn test(text: &str, msg_part: &str) {
@@ -425,8 +858,8 @@ fn test_preconditions() {
         println!("{:?}", elts);
         match elts {
             Err(e) => check_err_msg::<Vec<ParsedToken<f32>>>(Err(e), msg_part),
-            Ok(elts) => {
-                let error = check_parsed_token_preconditions(&elts);
+            Ok((elts, positions)) => {
+                let error = check_parsed_token_preconditions(&elts, &positions);
                 check_err_msg(error, msg_part);
             }
         };
@@ -8,17 +8,29 @@ use std::{
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct ExError {
     pub msg: String,
+    /// Position of the offending token in the parsed string in characters, i.e., not in
+    /// bytes. Only parse errors carry a position.
+    pub position: Option<usize>,
 }
 impl ExError {
     pub fn new(msg: &str) -> ExError {
         ExError {
             msg: msg.to_string(),
+            position: None,
         }
     }
+    /// Attaches the character position of the offending token in the parsed string.
+    pub fn with_position(mut self, position: usize) -> ExError {
+        self.position = Some(position);
+        self
+    }
 }
 impl Display for ExError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{}", self.msg)
+        match self.position {
+            Some(position) => write!(f, "parse error at char {}: {}", position, self.msg),
+            None => write!(f, "{}", self.msg),
+        }
     }
 }
 impl Error for ExError {}
@@ -31,13 +43,13 @@ pub type ExResult<U> = Result<U, ExError>;
 /// # use std::error::Error;
 /// use exmex::{format_exerr, ExError};
 /// # fn main() -> Result<(), Box<dyn Error>> {
-/// assert_eq!(format_exerr!("some error {}", 1), ExError{msg: format!("some error {}", 1)});
+/// assert_eq!(format_exerr!("some error {}", 1), ExError::new(&format!("some error {}", 1)));
 /// #     Ok(())
 /// # }
 /// ```
 #[macro_export]
 macro_rules! format_exerr {
     ($s:literal, $( $exps:expr ),*) => {
-        ExError{msg: format!($s, $($exps,)*)}
+        ExError::new(&format!($s, $($exps,)*))
     }
 }
\ No newline at end of file
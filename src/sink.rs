@@ -0,0 +1,178 @@
+use num::Float;
+
+use crate::{ExError, ExResult};
+
+/// Implement this trait to receive the results of [`eval_into`](crate::Express::eval_into)
+/// directly in a custom accumulator instead of collecting them first.
+pub trait EvalSink<T> {
+    /// Is called with the result of each successfully evaluated row.
+    fn accept(&mut self, row_idx: usize, value: T);
+
+    /// Is called in case the evaluation of a row failed. By default, the error is passed
+    /// on and the evaluation stops. Return `Ok(())` to skip the row and continue.
+    fn reject(&mut self, row_idx: usize, error: ExError) -> ExResult<()> {
+        let _ = row_idx;
+        Err(error)
+    }
+}
+
+/// Results are pushed in the order of the rows.
+impl<T> EvalSink<T> for Vec<T> {
+    fn accept(&mut self, _row_idx: usize, value: T) {
+        self.push(value);
+    }
+}
+
+/// Keeps track of the number of results as well as their mean, minimum, and maximum
+/// without storing the results.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::prelude::*;
+/// use exmex::MeanMinMax;
+///
+/// let expr = FlatEx::<f64>::from_str("2*x")?;
+/// let rows = [[1.0], [2.0], [6.0]];
+/// let mut summary = MeanMinMax::new();
+/// expr.eval_into(rows.iter().map(|r| &r[..]), &mut summary)?;
+/// assert_eq!(summary.count(), 3);
+/// assert_eq!(summary.mean(), Some(6.0));
+/// assert_eq!(summary.min(), Some(2.0));
+/// assert_eq!(summary.max(), Some(12.0));
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+pub struct MeanMinMax<T: Float> {
+    count: usize,
+    mean: T,
+    min: T,
+    max: T,
+}
+
+impl<T: Float> MeanMinMax<T> {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: T::zero(),
+            min: T::infinity(),
+            max: T::neg_infinity(),
+        }
+    }
+    /// Number of accepted results
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    pub fn mean(&self) -> Option<T> {
+        self.if_non_empty(self.mean)
+    }
+    pub fn min(&self) -> Option<T> {
+        self.if_non_empty(self.min)
+    }
+    pub fn max(&self) -> Option<T> {
+        self.if_non_empty(self.max)
+    }
+    fn if_non_empty(&self, x: T) -> Option<T> {
+        if self.count > 0 {
+            Some(x)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Float> Default for MeanMinMax<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> EvalSink<T> for MeanMinMax<T> {
+    fn accept(&mut self, _row_idx: usize, value: T) {
+        self.count += 1;
+        // incremental update of the mean to avoid large intermediate sums
+        self.mean = self.mean + (value - self.mean) / T::from(self.count).unwrap();
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+}
+
+#[cfg(test)]
+use crate::{prelude::*, util::assert_float_eq_f64, OwnedFlatEx};
+
+#[test]
+fn test_eval_into() -> ExResult<()> {
+    fn test(text: &str, rows: &[Vec<f64>]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let reference = rows
+            .iter()
+            .map(|r| flatex.eval(r))
+            .collect::<ExResult<Vec<_>>>()?;
+
+        let mut collected = vec![];
+        flatex.eval_into(rows.iter().map(|r| r.as_slice()), &mut collected)?;
+        assert_eq!(collected, reference);
+
+        let owned_flatex = OwnedFlatEx::from_flatex(flatex);
+        let mut summary = MeanMinMax::new();
+        owned_flatex.eval_into(rows.iter().map(|r| r.as_slice()), &mut summary)?;
+        assert_eq!(summary.count(), reference.len());
+        let mean = reference.iter().sum::<f64>() / reference.len() as f64;
+        assert_float_eq_f64(summary.mean().unwrap(), mean);
+        let min = reference.iter().copied().fold(f64::INFINITY, f64::min);
+        assert_float_eq_f64(summary.min().unwrap(), min);
+        let max = reference.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        assert_float_eq_f64(summary.max().unwrap(), max);
+        Ok(())
+    }
+    test("x*2+y", &[vec![1.0, 2.0], vec![-3.0, 0.5], vec![4.0, -2.0]])?;
+    test(
+        "sin(x)",
+        &(0..100).map(|i| vec![i as f64 * 0.1]).collect::<Vec<_>>(),
+    )?;
+    test("3", &[vec![], vec![]])?;
+
+    let flatex = FlatEx::<f64>::from_str("x")?;
+    let mut summary = MeanMinMax::new();
+    flatex.eval_into(std::iter::empty(), &mut summary)?;
+    assert_eq!(summary.count(), 0);
+    assert_eq!(summary.mean(), None);
+    Ok(())
+}
+
+#[test]
+fn test_eval_into_errors() -> ExResult<()> {
+    struct Skipper {
+        values: Vec<(usize, f64)>,
+        rejected: Vec<usize>,
+    }
+    impl EvalSink<f64> for Skipper {
+        fn accept(&mut self, row_idx: usize, value: f64) {
+            self.values.push((row_idx, value));
+        }
+        fn reject(&mut self, row_idx: usize, _error: ExError) -> ExResult<()> {
+            self.rejected.push(row_idx);
+            Ok(())
+        }
+    }
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    let rows: [&[f64]; 3] = [&[1.0, 2.0], &[1.0], &[3.0, 4.0]];
+
+    let mut collected = vec![];
+    assert!(flatex
+        .eval_into(rows.iter().copied(), &mut collected)
+        .is_err());
+    assert_eq!(collected, vec![3.0]);
+
+    let mut skipper = Skipper {
+        values: vec![],
+        rejected: vec![],
+    };
+    flatex.eval_into(rows.iter().copied(), &mut skipper)?;
+    assert_eq!(skipper.values, vec![(0, 3.0), (2, 7.0)]);
+    assert_eq!(skipper.rejected, vec![1]);
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//! *`feature = "rand"`* - Operators that draw random numbers. The random numbers are drawn from
+//! a random number generator that is passed to the evaluation, see
+//! [`FlatEx::eval_rng`](crate::FlatEx::eval_rng).
+use std::{cell::RefCell, marker::PhantomData};
+
+use num::Float;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    data_type::DataType, expression::MatchLiteral, format_exerr, Eval, ExError, ExResult, FlatEx,
+    FloatOpsFactory, MakeOperators, Operator, OwnedFlatEx,
+};
+
+thread_local! {
+    /// Random number generator of the evaluation that is currently running in this thread.
+    static RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+fn draw<T: Float>(sample: fn(&mut StdRng) -> f64) -> T {
+    RNG.with(|rng| match rng.borrow_mut().as_mut() {
+        Some(rng) => T::from(sample(rng)).unwrap(),
+        None => T::nan(),
+    })
+}
+
+fn rand_uniform<T: Float>(_: T) -> T {
+    draw(|rng| rng.gen::<f64>())
+}
+
+fn rand_normal<T: Float>(_: T) -> T {
+    // Box-Muller transform, 1-u is in (0, 1] such that the logarithm is finite
+    draw(|rng| {
+        let u_1 = 1.0 - rng.gen::<f64>();
+        let u_2 = rng.gen::<f64>();
+        (-2.0 * u_1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u_2).cos()
+    })
+}
+
+/// Makes a generator available to the stochastic operators while `f` is executed. The
+/// operators draw from a generator that is seeded by `rng`.
+fn with_rng<R, F, T>(rng: &mut R, f: F) -> ExResult<T>
+where
+    R: Rng + ?Sized,
+    F: FnOnce() -> ExResult<T>,
+{
+    let seeded = StdRng::from_rng(rng)
+        .map_err(|e| format_exerr!("could not seed random number generator, {}", e))?;
+    let previous = RNG.with(|cur| cur.borrow_mut().replace(seeded));
+    let res = f();
+    RNG.with(|cur| *cur.borrow_mut() = previous);
+    res
+}
+
+/// Factory of the operators of [`FloatOpsFactory`](FloatOpsFactory) extended by stochastic
+/// operators that draw random numbers.
+///
+/// |representation|description|
+/// |--------------|-----------|
+/// |`rand`| uniformly distributed number in `[0, 1)`, called as `rand()` |
+/// |`randn`| standard normally distributed number, called as `randn()` |
+///
+/// Each occurrence of a stochastic operator draws a new number in each evaluation. The numbers
+/// are drawn from a random number generator that is passed to
+/// [`eval_rng`](FlatEx::eval_rng). Seeding the passed generator makes the evaluation
+/// deterministic. Stochastic operators evaluated with [`eval`](Eval::eval) result in NaN.
+/// Expressions with stochastic operators are not compiled during parsing and cannot be
+/// differentiated.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, StochasticOpsFactory};
+/// use rand::{rngs::StdRng, SeedableRng};
+/// let expr = FlatEx::<f64, StochasticOpsFactory<f64>>::from_str("x + rand()")?;
+/// let mut rng = StdRng::seed_from_u64(42);
+/// let x = expr.eval_rng(&[1.0], &mut rng)?;
+/// assert!((1.0..2.0).contains(&x));
+/// // same seed, same result
+/// assert_eq!(expr.eval_rng(&[1.0], &mut StdRng::seed_from_u64(42))?, x);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct StochasticOpsFactory<T: Float> {
+    dummy: PhantomData<T>,
+}
+
+impl<T: Float> MakeOperators<T> for StochasticOpsFactory<T> {
+    /// Returns the default operators extended by `rand` and `randn`.
+    fn make<'a>() -> Vec<Operator<'a, T>> {
+        let mut ops = FloatOpsFactory::make();
+        ops.push(Operator::make_unary("rand", rand_uniform).with_stochastic());
+        ops.push(Operator::make_unary("randn", rand_normal).with_stochastic());
+        ops
+    }
+}
+
+impl<'a, T, OF, LMF> FlatEx<'a, T, OF, LMF>
+where
+    T: DataType,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    /// Evaluates the expression like [`eval`](Eval::eval) where stochastic operators such
+    /// as the ones of [`StochasticOpsFactory`](StochasticOpsFactory) draw from a generator
+    /// seeded by `rng`. Requires the feature `rand`.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as in [`eval`](Eval::eval) are returned. Further, an error is
+    /// returned if `rng` cannot seed a generator.
+    ///
+    pub fn eval_rng<R: Rng + ?Sized>(&self, vars: &[T], rng: &mut R) -> ExResult<T> {
+        with_rng(rng, || self.eval(vars))
+    }
+}
+
+impl<T, OF, LMF> OwnedFlatEx<T, OF, LMF>
+where
+    T: DataType,
+    OF: MakeOperators<T>,
+    LMF: MatchLiteral,
+{
+    /// Evaluates the expression with stochastic operators, see
+    /// [`FlatEx::eval_rng`](FlatEx::eval_rng).
+    pub fn eval_rng<R: Rng + ?Sized>(&self, vars: &[T], rng: &mut R) -> ExResult<T> {
+        with_rng(rng, || self.eval(vars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{Eval, ExResult, Express, FlatEx, OwnedFlatEx, StochasticOpsFactory};
+
+    type FlatExRand<'a> = FlatEx<'a, f64, StochasticOpsFactory<f64>>;
+
+    #[test]
+    fn test_rand() -> ExResult<()> {
+        let expr = FlatExRand::from_str("rand()")?;
+        let mut rng = StdRng::seed_from_u64(0);
+        let draws = (0..1000)
+            .map(|_| expr.eval_rng(&[], &mut rng))
+            .collect::<ExResult<Vec<_>>>()?;
+        assert!(draws.iter().all(|x| (0.0..1.0).contains(x)));
+        let mean = draws.iter().sum::<f64>() / 1000.0;
+        assert!((mean - 0.5).abs() < 0.05);
+        // draws differ between evaluations
+        assert!(draws.windows(2).any(|w| w[0] != w[1]));
+        assert!(expr.eval(&[]).unwrap().is_nan());
+        Ok(())
+    }
+
+    #[test]
+    fn test_randn() -> ExResult<()> {
+        let expr = FlatExRand::from_str("mu + sigma * randn()")?;
+        let mut rng = StdRng::seed_from_u64(1);
+        let n = 10000;
+        let draws = (0..n)
+            .map(|_| expr.eval_rng(&[2.0, 0.5], &mut rng))
+            .collect::<ExResult<Vec<_>>>()?;
+        let mean = draws.iter().sum::<f64>() / n as f64;
+        let var = draws.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n as f64;
+        assert!((mean - 2.0).abs() < 0.05);
+        assert!((var - 0.25).abs() < 0.05);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stochastic() -> ExResult<()> {
+        // two occurrences draw two different numbers
+        let expr = FlatExRand::from_str("rand() - rand()")?;
+        let mut rng = StdRng::seed_from_u64(2);
+        assert!(expr.eval_rng(&[], &mut rng)? != 0.0);
+        // seeding makes the evaluation deterministic
+        let expr = OwnedFlatEx::<f64, StochasticOpsFactory<f64>>::from_str("x*rand()+randn()")?;
+        let res_1 = expr.eval_rng(&[3.0], &mut StdRng::seed_from_u64(3))?;
+        let res_2 = expr.eval_rng(&[3.0], &mut StdRng::seed_from_u64(3))?;
+        assert_eq!(res_1, res_2);
+        // numbers are not combined during parsing
+        let expr = FlatExRand::from_str("2*rand()")?;
+        assert_eq!(expr.n_binops(), 1);
+        // and neither on demand
+        let mut expr = FlatExRand::from_str_wo_compile("2*rand()")?;
+        expr.compile();
+        assert_eq!(expr.n_binops(), 1);
+        assert!(!expr.eval_rng(&[], &mut rng)?.is_nan());
+        let mut expr =
+            OwnedFlatEx::<f64, StochasticOpsFactory<f64>>::from_str_wo_compile("2*rand()")?;
+        expr.compile();
+        assert!(!expr.eval_rng(&[], &mut rng)?.is_nan());
+        // deterministic parts still work as usual
+        let expr = FlatExRand::from_str("sin(x)^2+cos(x)^2")?;
+        assert!((expr.eval(&[0.3])? - 1.0).abs() < 1e-12);
+        // not constant although no variable occurs
+        let expr = FlatExRand::from_str("2*rand()")?;
+        assert!(!expr.is_const());
+        assert!(expr.eval_if_constant().is_none());
+        assert!(!FlatExRand::from_str("rand()")?.is_const());
+        // non-differentiable
+        let expr = FlatExRand::from_str("x*rand()")?;
+        assert!(expr.partial(0).is_err());
+        assert!(FlatExRand::from_str("rand(").is_err());
+        Ok(())
+    }
+}
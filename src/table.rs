@@ -0,0 +1,150 @@
+use smallvec::SmallVec;
+
+use crate::{definitions::N_VARS_ON_STACK, format_exerr, Eval, ExError, ExResult, OwnedFlatEx};
+
+/// Computes a new column of a table from a formula that references the names of other
+/// columns. The variables of the formula are mapped to the columns by name, such that
+/// the columns can be passed in the order of the table's headers instead of the
+/// alphabetical order of the variables that [`eval`](crate::Eval::eval) expects.
+/// Column names with spaces or other special characters need curly brackets in the
+/// formula, e.g., `{unit price}*amount`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::TableFormula;
+///
+/// let formula = TableFormula::new(&["price", "tax", "amount"], "amount*price*(1+tax)")?;
+/// assert!((formula.apply(&[2.0, 0.5, 3.0])? - 9.0).abs() < 1e-12);
+/// let rows = [[1.0, 0.0, 4.0], [2.0, 0.5, 1.0]];
+/// let column = formula.apply_batch(rows.iter().map(|r| &r[..]))?;
+/// assert_eq!(column, vec![4.0, 3.0]);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct TableFormula {
+    expr: OwnedFlatEx<f64>,
+    /// The i-th variable of `expr` is taken from the column with index `col_indices[i]`.
+    col_indices: SmallVec<[usize; N_VARS_ON_STACK]>,
+    n_cols: usize,
+}
+
+impl TableFormula {
+    /// Parses `formula` and maps its variables to the indices of the columns in `headers`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `formula` cannot be parsed or if it references a column that
+    /// is not contained in `headers`.
+    ///
+    pub fn new(headers: &[&str], formula: &str) -> ExResult<TableFormula> {
+        let expr = OwnedFlatEx::<f64>::from_str(formula)?;
+        let col_indices = expr
+            .var_names()
+            .iter()
+            .map(|var_name| {
+                headers
+                    .iter()
+                    .position(|header| header == var_name)
+                    .ok_or_else(|| {
+                        format_exerr!(
+                            "unknown column '{}' in formula '{}', candidates are {}",
+                            var_name,
+                            formula,
+                            headers
+                                .iter()
+                                .map(|h| format!("'{}'", h))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    })
+            })
+            .collect::<ExResult<SmallVec<_>>>()?;
+        Ok(TableFormula {
+            expr,
+            col_indices,
+            n_cols: headers.len(),
+        })
+    }
+
+    fn apply_with_buffer(&self, row: &[f64], vars: &mut Vec<f64>) -> ExResult<f64> {
+        if row.len() != self.n_cols {
+            return Err(format_exerr!(
+                "row has {} values but the table has {} columns",
+                row.len(),
+                self.n_cols
+            ));
+        }
+        vars.clear();
+        vars.extend(self.col_indices.iter().map(|col_idx| row[*col_idx]));
+        self.expr.eval(vars)
+    }
+
+    /// Evaluates the formula for one row of the table.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the length of `row` differs from the number of headers.
+    ///
+    pub fn apply(&self, row: &[f64]) -> ExResult<f64> {
+        let mut vars = Vec::with_capacity(self.col_indices.len());
+        self.apply_with_buffer(row, &mut vars)
+    }
+
+    /// Evaluates the formula for each row and returns the resulting column. The buffer
+    /// for the variables is allocated only once for all rows.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the length of any row differs from the number of headers.
+    ///
+    pub fn apply_batch<'a, I>(&self, rows: I) -> ExResult<Vec<f64>>
+    where
+        I: Iterator<Item = &'a [f64]>,
+    {
+        let mut vars = Vec::with_capacity(self.col_indices.len());
+        rows.map(|row| self.apply_with_buffer(row, &mut vars))
+            .collect()
+    }
+
+    /// Returns the underlying expression. Its variables are in alphabetical order.
+    pub fn expr(&self) -> &OwnedFlatEx<f64> {
+        &self.expr
+    }
+}
+
+#[cfg(test)]
+use crate::util::assert_float_eq_f64;
+
+#[test]
+fn test_table_formula() -> ExResult<()> {
+    let headers = ["z", "b", "not used", "a b", "a"];
+    let formula = TableFormula::new(&headers, "z - b*{a b} / a")?;
+    let rows = [[1.0, 2.0, 100.0, 3.0, 4.0], [0.0, -1.0, f64::NAN, 2.0, 0.5]];
+    assert_float_eq_f64(formula.apply(&rows[0])?, -0.5);
+    assert_float_eq_f64(formula.apply(&rows[1])?, 4.0);
+    let column = formula.apply_batch(rows.iter().map(|r| r.as_slice()))?;
+    assert_eq!(column.len(), 2);
+    assert_float_eq_f64(column[0], -0.5);
+    assert_float_eq_f64(column[1], 4.0);
+    assert_eq!(formula.expr().n_vars(), 4);
+
+    assert!(formula.apply(&[1.0, 2.0]).is_err());
+    assert!(formula
+        .apply_batch([&rows[0][..], &rows[0][..3]].iter().copied())
+        .is_err());
+
+    let formula = TableFormula::new(&headers, "2*PI")?;
+    assert_float_eq_f64(formula.apply(&rows[0])?, 2.0 * std::f64::consts::PI);
+    let formula = TableFormula::new(&[], "2+3")?;
+    assert_float_eq_f64(formula.apply(&[])?, 5.0);
+
+    let err = TableFormula::new(&headers, "a+c").unwrap_err();
+    assert!(err.msg.contains("'c'"));
+    assert!(err.msg.contains("'z', 'b', 'not used', 'a b', 'a'"));
+    assert!(TableFormula::new(&headers, "a+").is_err());
+    Ok(())
+}
@@ -22,6 +22,44 @@ macro_rules! to_type {
     };
 }
 
+macro_rules! as_type {
+    ($name:ident, $T:ty, $variant:ident) => {
+        /// Returns a reference to the contained value without consuming `self` or `None` if
+        #[doc = concat!("the value is not a `Val::", stringify!($variant), "`.")]
+        pub fn $name(&self) -> Option<&$T> {
+            match self {
+                Val::$variant(x) => Some(x),
+                _ => None,
+            }
+        }
+    };
+}
+
+/// *`feature = "value"`* -
+/// The kind of a value of type [`Val`](Val), i.e., its variant without the contained data.
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{Eval, Express, Val, ValKind};
+/// let expr = exmex::parse_val::<i32, f64>("x > 1.0")?;
+/// let res = expr.eval(&[Val::Float(3.4)])?;
+/// assert_eq!(res.kind(), ValKind::Bool);
+/// assert_eq!(res.as_bool(), Some(&true));
+/// assert_eq!(res.as_float(), None);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ValKind {
+    Int,
+    Float,
+    Bool,
+    Error,
+    None,
+}
+
 /// *`feature = "value"`* -
 /// The value type [`Val`](Val) can contain an integer, float, bool, none, or error.
 /// To use the value type, there are the separate parse functions [`parse_val`](`parse_val`) and
@@ -32,7 +70,7 @@ macro_rules! to_type {
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// use exmex::{Express, Val};
+/// use exmex::{Eval, Express, Val};
 /// let expr = exmex::parse_val::<i32, f64>("1.0 if x > y else 73")?;
 /// assert_eq!(expr.eval(&[Val::Float(3.4), Val::Int(3)])?.to_float()?, 1.0);
 /// assert_eq!(expr.eval(&[Val::Int(34), Val::Float(132.0)])?.to_int()?, 73);
@@ -48,7 +86,7 @@ macro_rules! to_type {
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// use exmex::Express;
+/// use exmex::{Eval, Express};
 /// let expr = exmex::parse_val::<i32, f64>("5 else 3")?;
 /// assert_eq!(expr.eval(&[])?.to_int()?, 5);
 /// #
@@ -60,10 +98,10 @@ macro_rules! to_type {
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// use exmex::Express;
+/// use exmex::{Eval, Express};
 /// let expr = exmex::parse_val::<i32, f64>("fact(3.5)")?;
 /// let res = expr.eval(&[])?;
-/// assert!(format!("{:?}", res) == "Error(ExError { msg: \"did not expect Float(3.5)\" })");
+/// assert!(format!("{:?}", res) == "Error(ExError { msg: \"did not expect Float(3.5)\", position: None })");
 /// #
 /// #     Ok(())
 /// # }
@@ -74,10 +112,10 @@ macro_rules! to_type {
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// # use exmex::Express;
+/// # use exmex::{Eval, Express};
 /// # let expr = exmex::parse_val::<i32, f64>("fact(3.5)")?;
 /// # let res = expr.eval(&[])?;
-/// # assert!(format!("{:?}", res) == "Error(ExError { msg: \"did not expect Float(3.5)\" })");
+/// # assert!(format!("{:?}", res) == "Error(ExError { msg: \"did not expect Float(3.5)\", position: None })");
 /// assert!(res.to_int().is_err());
 /// #
 /// #     Ok(())
@@ -107,11 +145,36 @@ where
     to_type!(to_int, I, Int);
     to_type!(to_float, F, Float);
     to_type!(to_bool, bool, Bool);
+    as_type!(as_int, I, Int);
+    as_type!(as_float, F, Float);
+    as_type!(as_bool, bool, Bool);
+
+    /// Returns the kind of the value without consuming `self`.
+    pub fn kind(&self) -> ValKind {
+        match self {
+            Val::Int(_) => ValKind::Int,
+            Val::Float(_) => ValKind::Float,
+            Val::Bool(_) => ValKind::Bool,
+            Val::Error(_) => ValKind::Error,
+            Val::None => ValKind::None,
+        }
+    }
+
+    /// Returns true if the value is a `Val::Error`.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Val::Error(_))
+    }
+
+    /// Returns true if the value is a `Val::None`.
+    pub fn is_none(&self) -> bool {
+        matches!(self, Val::None)
+    }
 }
 
 fn map_parse_err<E: Debug>(e: E) -> ExError {
     ExError {
         msg: format!("{:?}", e),
+        position: None,
     }
 }
 
@@ -136,6 +199,7 @@ where
             Result::Ok(_) => res,
             Result::Err(e) => Err(ExError {
                 msg: format!("could not parse {}, {:?}", s, e),
+                position: None,
             }),
         }
     }
@@ -153,8 +217,8 @@ where
             (Val::Float(x), Val::Float(y)) => x == y,
             (Val::Int(x), Val::Int(y)) => x == y,
             (Val::Bool(x), Val::Bool(y)) => x == y,
-            (Val::Float(x), Val::Int(y)) => *x == F::from(*y).unwrap(),
-            (Val::Int(x), Val::Float(y)) => F::from(*x).unwrap() == *y,
+            (Val::Float(x), Val::Int(y)) => Some(*x) == F::from(*y),
+            (Val::Int(x), Val::Float(y)) => F::from(*x) == Some(*y),
             _ => false,
         }
     }
@@ -171,8 +235,8 @@ where
         match (self, other) {
             (Val::Float(x), Val::Float(y)) => x.partial_cmp(y),
             (Val::Int(x), Val::Int(y)) => x.partial_cmp(y),
-            (Val::Float(x), Val::Int(y)) => x.partial_cmp(&F::from(*y).unwrap()),
-            (Val::Int(x), Val::Float(y)) => F::from(*x).unwrap().partial_cmp(y),
+            (Val::Float(x), Val::Int(y)) => F::from(*y).and_then(|y| x.partial_cmp(&y)),
+            (Val::Int(x), Val::Float(y)) => F::from(*x).and_then(|x| x.partial_cmp(y)),
             _ => None,
         }
     }
@@ -185,7 +249,13 @@ where
 {
     match (a, b) {
         (Val::Float(x), Val::Float(y)) => Val::Float(x.powf(y)),
-        (Val::Float(x), Val::Int(y)) => Val::Float(x.powi(y.to_i32().unwrap())),
+        (Val::Float(x), Val::Int(y)) => match y.to_i32() {
+            Some(exponent) => Val::Float(x.powi(exponent)),
+            None => match F::from(y) {
+                Some(exponent) => Val::Float(x.powf(exponent)),
+                None => Val::Error(format_exerr!("cannot convert {:?} to float", y)),
+            },
+        },
         (Val::Int(x), Val::Int(y)) => match y.to_usize() {
             Some(exponent_) => match num::checked_pow(x, exponent_) {
                 Some(res) => Val::Int(res),
@@ -218,8 +288,14 @@ macro_rules! base_arith {
                         y
                     )),
                 },
-                (Val::Float(x), Val::Int(y)) => Val::Float(x.$name(F::from(y).unwrap())),
-                (Val::Int(x), Val::Float(y)) => Val::Float(F::from(x).unwrap().$name(y)),
+                (Val::Float(x), Val::Int(y)) => match F::from(y) {
+                    Some(y) => Val::Float(x.$name(y)),
+                    None => Val::Error(format_exerr!("cannot convert {:?} to float", y)),
+                },
+                (Val::Int(x), Val::Float(y)) => match F::from(x) {
+                    Some(x) => Val::Float(x.$name(y)),
+                    None => Val::Error(format_exerr!("cannot convert {:?} to float", x)),
+                },
                 _ => Val::Error(ExError::new(
                     format!("can only apply {} to ints or floats", stringify!($name)).as_str(),
                 )),
@@ -252,17 +328,22 @@ macro_rules! single_type_arith {
     };
 }
 
-single_type_arith!(rem, Int, |a, b| if b == I::zero() {
+single_type_arith!(rem, Int, |a: I, b: I| if b == I::zero() {
     Val::Error(ExError::new("% by zero"))
 } else {
-    Val::Int(a % b)
+    if b == -I::one() {
+        // the remainder is 0 but the computation might overflow
+        Val::Int(I::zero())
+    } else {
+        Val::Int(a % b)
+    }
 });
 single_type_arith!(bitwise_or, Int, |a, b| Val::Int(a | b));
 single_type_arith!(bitwise_and, Int, |a, b| Val::Int(a & b));
 single_type_arith!(bitwise_xor, Int, |a, b| Val::Int(a ^ b));
 single_type_arith!(right_shift, Int, |a: I, b: I| -> Val<I, F> {
     match b.to_usize() {
-        Some(bu) if b.to_usize().unwrap() < (a.count_ones() + a.count_zeros()) as usize => {
+        Some(bu) if bu < (a.count_ones() + a.count_zeros()) as usize => {
             Val::Int(a >> bu)
         }
         _ => Val::Error(format_exerr!("cannot shift right {:?} by {:?}", a, b)),
@@ -270,7 +351,7 @@ single_type_arith!(right_shift, Int, |a: I, b: I| -> Val<I, F> {
 });
 single_type_arith!(left_shift, Int, |a: I, b: I| -> Val<I, F> {
     match b.to_usize() {
-        Some(bu) if b.to_usize().unwrap() < (a.count_ones() + a.count_zeros()) as usize => {
+        Some(bu) if bu < (a.count_ones() + a.count_zeros()) as usize => {
             Val::Int(a << bu)
         }
         _ => Val::Error(format_exerr!("cannot shift left {:?} by {:?}", a, b)),
@@ -316,7 +397,6 @@ macro_rules! unary_name {
     }
 }
 
-unary_name!(abs, Float, Int);
 unary_name!(signum, Float, Int);
 unary_name!(sin, Float);
 unary_name!(round, Float);
@@ -360,10 +440,7 @@ unary_op!(
             let res =
                 (1usize..(a_usize_unpacked + 1usize))
                     .map(I::from)
-                    .fold(Some(I::one()), |a, b| match (a, b) {
-                        (Some(a_), Some(b_)) => Some(a_ * b_),
-                        _ => None,
-                    });
+                    .try_fold(I::one(), |a, b| a.checked_mul(&b?));
             match res {
                 Some(i) => Val::Int(i),
                 None => Val::Error(format_exerr!("cannot compute factorial of {:?}", a)),
@@ -373,9 +450,23 @@ unary_op!(
     )
 );
 
+unary_op!(
+    abs,
+    (|a: I| if a == I::min_value() {
+        Val::Error(format_exerr!("overflow in abs({:?})", a))
+    } else {
+        Val::Int(a.abs())
+    }, Int),
+    (|a: F| Val::Float(a.abs()), Float)
+);
+
 unary_op!(
     minus,
-    (|a: I| Val::Int(-a), Int),
+    (|a: I| if a == I::min_value() {
+        Val::Error(format_exerr!("overflow in -{:?}", a))
+    } else {
+        Val::Int(-a)
+    }, Int),
     (|a: F| Val::Float(-a), Float)
 );
 
@@ -390,7 +481,14 @@ macro_rules! cast {
         {
             match v {
                 Val::$variant(x) => Val::$variant(x),
-                Val::$other_variant(x) => Val::$variant($T::from(x).unwrap()),
+                Val::$other_variant(x) => match $T::from(x) {
+                    Some(y) => Val::$variant(y),
+                    None => Val::Error(format_exerr!(
+                        "cannot convert '{:?}' to {}",
+                        x,
+                        stringify!($variant)
+                    )),
+                },
                 Val::Bool(x) => Val::$variant(if x { $T::one() } else { $T::zero() }),
                 _ => Val::Error(format_exerr!("cannot convert '{:?}' to float", v)),
             }
@@ -401,6 +499,18 @@ macro_rules! cast {
 cast!(cast_to_float, Float, Int, F);
 cast!(cast_to_int, Int, Float, I);
 
+/// Converts a constant to the float type, the conversion can fail for custom float types.
+fn float_constant<I, F>(x: f64) -> Val<I, F>
+where
+    I: DataType + PrimInt + Signed,
+    F: DataType + Float,
+{
+    match F::from(x) {
+        Some(x) => Val::Float(x),
+        None => Val::Error(format_exerr!("cannot convert constant {:?} to float", x)),
+    }
+}
+
 /// *`feature = "value"`* - Factory of default operators for the data type [`Val`](Val).
 ///
 /// Operators available in addition to those from [`FloatOpsFactory`](crate::FloatOpsFactory) are:
@@ -449,7 +559,8 @@ where
                     prio: 6,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("pow"),
             Operator::make_bin(
                 "+",
                 BinOp {
@@ -457,7 +568,8 @@ where
                     prio: 3,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("add"),
             Operator::make_bin_unary(
                 "-",
                 BinOp {
@@ -466,7 +578,8 @@ where
                     is_commutative: false,
                 },
                 minus,
-            ),
+            )
+            .with_id("sub"),
             Operator::make_bin(
                 "*",
                 BinOp {
@@ -474,7 +587,8 @@ where
                     prio: 4,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("mul"),
             Operator::make_bin(
                 "/",
                 BinOp {
@@ -487,7 +601,8 @@ where
                     prio: 5,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("div"),
             Operator::make_bin(
                 "%",
                 BinOp {
@@ -495,7 +610,8 @@ where
                     prio: 5,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("rem"),
             Operator::make_bin(
                 "|",
                 BinOp {
@@ -503,7 +619,8 @@ where
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("bitor"),
             Operator::make_bin(
                 "&",
                 BinOp {
@@ -511,7 +628,8 @@ where
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("bitand"),
             Operator::make_bin(
                 "XOR",
                 BinOp {
@@ -519,7 +637,8 @@ where
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("bitxor"),
             Operator::make_bin(
                 ">>",
                 BinOp {
@@ -527,7 +646,8 @@ where
                     prio: 2,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("shr"),
             Operator::make_bin(
                 "<<",
                 BinOp {
@@ -535,7 +655,8 @@ where
                     prio: 2,
                     is_commutative: false,
                 },
-            ),
+            )
+            .with_id("shl"),
             Operator::make_bin(
                 "&&",
                 BinOp {
@@ -543,7 +664,8 @@ where
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("and"),
             Operator::make_bin(
                 "||",
                 BinOp {
@@ -551,7 +673,8 @@ where
                     prio: 2,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("or"),
             Operator::make_bin(
                 "==",
                 BinOp {
@@ -559,7 +682,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("eq"),
             Operator::make_bin(
                 ">=",
                 BinOp {
@@ -567,7 +691,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("ge"),
             Operator::make_bin(
                 ">",
                 BinOp {
@@ -575,7 +700,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("gt"),
             Operator::make_bin(
                 "<=",
                 BinOp {
@@ -583,7 +709,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("le"),
             Operator::make_bin(
                 "<",
                 BinOp {
@@ -591,7 +718,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("lt"),
             Operator::make_bin(
                 "!=",
                 BinOp {
@@ -599,7 +727,8 @@ where
                     prio: 1,
                     is_commutative: true,
                 },
-            ),
+            )
+            .with_id("ne"),
             Operator::make_bin(
                 "if",
                 BinOp {
@@ -648,7 +777,7 @@ where
             Operator::make_unary("sqrt", sqrt),
             Operator::make_unary("cbrt", cbrt),
             Operator::make_unary("round", round),
-            Operator::make_unary("log", ln),
+            Operator::make_unary("log", ln).with_id("ln"),
             Operator::make_unary("log2", log2),
             Operator::make_unary("swap_bytes", swap_bytes),
             Operator::make_unary("to_le", to_le),
@@ -656,9 +785,9 @@ where
             Operator::make_unary("fact", fact),
             Operator::make_unary("to_int", cast_to_int),
             Operator::make_unary("to_float", cast_to_float),
-            Operator::make_constant("PI", Val::Float(F::from(std::f64::consts::PI).unwrap())),
-            Operator::make_constant("π", Val::Float(F::from(std::f64::consts::PI).unwrap())),
-            Operator::make_constant("E", Val::Float(F::from(std::f64::consts::E).unwrap())),
+            Operator::make_constant("PI", float_constant(std::f64::consts::PI)).with_id("pi"),
+            Operator::make_constant("π", float_constant(std::f64::consts::PI)).with_id("pi"),
+            Operator::make_constant("E", float_constant(std::f64::consts::E)).with_id("e"),
         ]
     }
 }
@@ -680,10 +809,12 @@ pub type OwnedFlatExVal<I, F> =
 /// # use std::error::Error;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 /// #
-/// # use exmex::{parse_val, Express, Val};
+/// # use exmex::{parse_val, Eval, Express, Val};
 /// let expr = parse_val::<i32, f64>("x^y")?;
 /// let res = expr.eval(&[Val::Float(2.0), Val::Int(3)])?.to_float()?;
 /// assert!( (res - 8.0).abs() < 1e-12);
+/// let res = expr.eval_named(&[("y", Val::Int(3)), ("x", Val::Float(2.0))])?.to_float()?;
+/// assert!( (res - 8.0).abs() < 1e-12);
 /// #
 /// #     Ok(())
 /// # }
@@ -719,9 +850,16 @@ where
 #[cfg(test)]
 mod tests {
 
+    use std::{fmt::Debug, str::FromStr};
+
+    use num::{Float, PrimInt, Signed};
+
     use crate::{
-        format_exerr, parse_val, util::assert_float_eq_f64, value::Val, ExError, ExResult, Express,
-        FlatExVal, OwnedFlatExVal,
+        data_type::DataType,
+        format_exerr, parse_val,
+        util::{assert_float_eq, assert_float_eq_f64},
+        value::Val,
+        Eval, ExError, ExResult, Express, FlatExVal, OwnedFlatExVal,
     };
 
     #[test]
@@ -736,166 +874,268 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn test_no_vars() -> ExResult<()> {
-        fn test_int(s: &str, reference: i32) -> ExResult<()> {
-            println!("=== testing\n{}", s);
-            let res = parse_val::<i32, f64>(s)?.eval(&[])?.to_int();
-            match res {
-                Ok(i) => {
-                    assert_eq!(reference, i);
-                }
-                Err(e) => {
-                    println!("{:?}", e);
-                    unreachable!();
-                }
+    fn test_int<I, F>(s: &str, reference: i128) -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        println!("=== testing\n{}", s);
+        let res = parse_val::<I, F>(s)?.eval(&[])?.to_int();
+        match res {
+            Ok(i) => {
+                assert_eq!(Some(reference), i.to_i128());
+            }
+            Err(e) => {
+                println!("{:?}", e);
+                unreachable!();
             }
-            Ok(())
-        }
-        fn test_float(s: &str, reference: f64) -> ExResult<()> {
-            println!("=== testing\n{}", s);
-            let expr = FlatExVal::<i32, f64>::from_str(s)?;
-            assert_float_eq_f64(reference, expr.eval(&[])?.to_float()?);
-            let expr = OwnedFlatExVal::<i32, f64>::from_flatex(expr);
-            assert_float_eq_f64(reference, expr.eval(&[])?.to_float()?);
-            let expr = OwnedFlatExVal::<i32, f64>::from_str(s)?;
-            assert_float_eq_f64(reference, expr.eval(&[])?.to_float()?);
-            Ok(())
-        }
-        fn test_bool(s: &str, reference: bool) -> ExResult<()> {
-            println!("=== testing\n{}", s);
-            let expr = FlatExVal::<i32, f64>::from_str(s)?;
-            assert_eq!(reference, expr.eval(&[])?.to_bool()?);
-            let expr = OwnedFlatExVal::<i32, f64>::from_flatex(expr);
-            assert_eq!(reference, expr.eval(&[])?.to_bool()?);
-            let expr = OwnedFlatExVal::<i32, f64>::from_str(s)?;
-            assert_eq!(reference, expr.eval(&[])?.to_bool()?);
-            Ok(())
         }
-        fn test_error(s: &str) -> ExResult<()> {
-            let expr = FlatExVal::<i32, f64>::from_str(s);
-            match expr {
-                Ok(exp) => {
-                    let v = exp.eval(&[])?;
-                    match v {
-                        Val::Error(e) => {
-                            println!("found expected error {:?}", e);
-                            Ok(())
-                        }
-                        _ => Err(format_exerr!("'{}' should fail but didn't", s)),
+        Ok(())
+    }
+    fn test_float<I, F>(s: &str, reference: f64) -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        println!("=== testing\n{}", s);
+        // the tolerance depends on the precision of the float type
+        let tol = F::epsilon().to_f64().unwrap() * 1e4;
+        let to_f64 = |v: Val<I, F>| -> ExResult<f64> { Ok(v.to_float()?.to_f64().unwrap()) };
+        let expr = FlatExVal::<I, F>::from_str(s)?;
+        assert_float_eq(reference, to_f64(expr.eval(&[])?)?, tol, tol, s);
+        let expr = OwnedFlatExVal::<I, F>::from_flatex(expr);
+        assert_float_eq(reference, to_f64(expr.eval(&[])?)?, tol, tol, s);
+        let expr = OwnedFlatExVal::<I, F>::from_str(s)?;
+        assert_float_eq(reference, to_f64(expr.eval(&[])?)?, tol, tol, s);
+        Ok(())
+    }
+    fn test_bool<I, F>(s: &str, reference: bool) -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        println!("=== testing\n{}", s);
+        let expr = FlatExVal::<I, F>::from_str(s)?;
+        assert_eq!(reference, expr.eval(&[])?.to_bool()?);
+        let expr = OwnedFlatExVal::<I, F>::from_flatex(expr);
+        assert_eq!(reference, expr.eval(&[])?.to_bool()?);
+        let expr = OwnedFlatExVal::<I, F>::from_str(s)?;
+        assert_eq!(reference, expr.eval(&[])?.to_bool()?);
+        Ok(())
+    }
+    fn test_error<I, F>(s: &str) -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        let expr = FlatExVal::<I, F>::from_str(s);
+        match expr {
+            Ok(exp) => {
+                let v = exp.eval(&[])?;
+                match v {
+                    Val::Error(e) => {
+                        println!("found expected error {:?}", e);
+                        Ok(())
                     }
+                    _ => Err(format_exerr!("'{}' should fail but didn't", s)),
                 }
-                Err(e) => {
-                    println!("found expected error {:?}", e);
-                    Ok(())
-                }
-            }?;
-            let expr = OwnedFlatExVal::<i32, f64>::from_str(s);
-            match expr {
-                Ok(exp) => {
-                    let v = exp.eval(&[])?;
-                    match v {
-                        Val::Error(e) => {
-                            println!("found expected error {:?}", e);
-                            Ok(())
-                        }
-                        _ => Err(format_exerr!("'{}' should fail but didn't", s)),
+            }
+            Err(e) => {
+                println!("found expected error {:?}", e);
+                Ok(())
+            }
+        }?;
+        let expr = OwnedFlatExVal::<I, F>::from_str(s);
+        match expr {
+            Ok(exp) => {
+                let v = exp.eval(&[])?;
+                match v {
+                    Val::Error(e) => {
+                        println!("found expected error {:?}", e);
+                        Ok(())
                     }
-                }
-                Err(e) => {
-                    println!("found expected error {:?}", e);
-                    Ok(())
+                    _ => Err(format_exerr!("'{}' should fail but didn't", s)),
                 }
             }
-        }
-        fn test_none(s: &str) -> ExResult<()> {
-            let expr = FlatExVal::<i32, f64>::from_str(s)?;
-            match expr.eval(&[])? {
-                Val::None => Ok(()),
-                _ => Err(format_exerr!("'{}' should return none but didn't", s)),
-            }?;
-            let expr = OwnedFlatExVal::<i32, f64>::from_str(s)?;
-            match expr.eval(&[])? {
-                Val::None => Ok(()),
-                _ => Err(format_exerr!("'{}' should return none but didn't", s)),
+            Err(e) => {
+                println!("found expected error {:?}", e);
+                Ok(())
             }
         }
-        test_int("1+2 if 1 > 0 else 2+4", 3)?;
-        test_int("1+2 if 1 < 0 else 2+4", 6)?;
+    }
+    fn test_none<I, F>(s: &str) -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        let expr = FlatExVal::<I, F>::from_str(s)?;
+        match expr.eval(&[])? {
+            Val::None => Ok(()),
+            _ => Err(format_exerr!("'{}' should return none but didn't", s)),
+        }?;
+        let expr = OwnedFlatExVal::<I, F>::from_str(s)?;
+        match expr.eval(&[])? {
+            Val::None => Ok(()),
+            _ => Err(format_exerr!("'{}' should return none but didn't", s)),
+        }
+    }
+
+    /// Cases that do not depend on the width of the types, all integers fit into `i8`.
+    fn test_no_vars_any_width<I, F>() -> ExResult<()>
+    where
+        I: DataType + PrimInt + Signed,
+        F: DataType + Float,
+        <I as FromStr>::Err: Debug,
+        <F as FromStr>::Err: Debug,
+    {
+        test_int::<I, F>("1+2 if 1 > 0 else 2+4", 3)?;
+        test_int::<I, F>("1+2 if 1 < 0 else 2+4", 6)?;
+        test_float::<I, F>("2.0^2", 4.0)?;
+        test_int::<I, F>("2^4", 16)?;
+        test_error::<I, F>("2^-4")?;
+        test_int::<I, F>("2+4", 6)?;
+        test_int::<I, F>("9+4", 13)?;
+        test_int::<I, F>("9+4^2", 25)?;
+        test_int::<I, F>("9/4", 2)?;
+        test_int::<I, F>("9%4", 1)?;
+        test_float::<I, F>("2.5+4.0^2", 18.5)?;
+        test_float::<I, F>("2.5*4.0^2", 2.5 * 4.0 * 4.0)?;
+        test_float::<I, F>("2.5-4.0^-2", 2.5 - 4.0f64.powi(-2))?;
+        test_float::<I, F>("9.0/4.0", 9.0 / 4.0)?;
+        test_float::<I, F>("sin(9.0)", 9.0f64.sin())?;
+        test_float::<I, F>("cos(91.0)", 91.0f64.cos())?;
+        test_float::<I, F>("tan(913.0)", 913.0f64.tan())?;
+        test_float::<I, F>("sin(-π)", 0.0)?;
+        test_float::<I, F>("round(π)", 3.0)?;
+        test_float::<I, F>("cos(π)", -1.0)?;
+        test_float::<I, F>("sin (1 if false else 2.0)", 2.0f64.sin())?;
+        test_float::<I, F>("cbrt(27.0)", 3.0)?;
+        test_int::<I, F>("1 if true else 2.0", 1)?;
+        test_float::<I, F>("(9.0 if true else 2.0)", 9.0)?;
+        test_int::<I, F>("1<<4-2", 4)?;
+        test_int::<I, F>("4>>2", 1)?;
+        test_int::<I, F>("signum(4>>1)", 1)?;
+        test_float::<I, F>("signum(-123.12)", -1.0)?;
+        test_float::<I, F>("abs(-123.12)", 123.12)?;
+        test_int::<I, F>("fact(4)", 2 * 3 * 4)?;
+        test_int::<I, F>("fact(0)", 1)?;
+        test_error::<I, F>("fact(-1)")?;
+        test_bool::<I, F>("1>2", false)?;
+        test_bool::<I, F>("1<2", true)?;
+        test_bool::<I, F>("1.4>=1.4", true)?;
+        test_bool::<I, F>("true==true", true)?;
+        test_bool::<I, F>("false==true", false)?;
+        test_bool::<I, F>("1.5 != 1.5 + 2.0", true)?;
+        test_float::<I, F>("1 + 1.0", 2.0)?;
+        test_bool::<I, F>("1.0 == 1", true)?;
+        test_bool::<I, F>("1 == 1", true)?;
+        test_bool::<I, F>("2 == true", false)?;
+        test_bool::<I, F>("1.5 < 1", false)?;
+        test_bool::<I, F>("true == true", true)?;
+        test_bool::<I, F>("false != true", true)?;
+        test_bool::<I, F>("false != false", false)?;
+        test_bool::<I, F>("1 > 0.5", true)?;
+        test_bool::<I, F>("true == 1", false)?;
+        test_bool::<I, F>("true else 2", true)?;
+        test_int::<I, F>("1 else 2", 1)?;
+        test_error::<I, F>("if true else 2")?;
+        test_none::<I, F>("2 if false")?;
+        test_int::<I, F>("to_int(1)", 1)?;
+        test_int::<I, F>("to_int(3.5)", 3)?;
+        test_float::<I, F>("to_float(2)", 2.0)?;
+        test_float::<I, F>("to_float(3.5)", 3.5)?;
+        test_float::<I, F>("to_float(true)", 1.0)?;
+        test_float::<I, F>("to_float(false)", 0.0)?;
+        test_int::<I, F>("to_int(true)", 1)?;
+        test_int::<I, F>("to_int(false)", 0)?;
+        test_error::<I, F>("to_int(fact(-1))")?;
+        test_error::<I, F>("to_float(5 if false)")?;
+        test_error::<I, F>("0/0")?;
+        test_bool::<I, F>("(5 if false) == (5 if false)", false)?;
+        test_error::<I, F>("0%0")?;
+        test_int::<I, F>("7%(-1)", 0)?;
+        test_int::<I, F>("-7%3", -1)?;
+        test_error::<I, F>("to_int(10.0^100)")?;
+        test_error::<I, F>("to_int(0.0/0.0)")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_vars() -> ExResult<()> {
+        test_no_vars_any_width::<i32, f64>()?;
+        let test_int = test_int::<i32, f64>;
+        let test_error = test_error::<i32, f64>;
         test_error("929<<92")?;
         test_error("929<<32")?;
         test_error("929>>32")?;
         test_int("928<<31", 0)?;
         test_int("929>>31", 0)?;
-        test_float("2.0^2", 4.0)?;
-        test_int("2^4", 16)?;
-        test_error("2^-4")?;
-        test_int("2+4", 6)?;
-        test_int("9+4", 13)?;
-        test_int("9+4^2", 25)?;
-        test_int("9/4", 2)?;
-        test_int("9%4", 1)?;
-        test_float("2.5+4.0^2", 18.5)?;
-        test_float("2.5*4.0^2", 2.5 * 4.0 * 4.0)?;
-        test_float("2.5-4.0^-2", 2.5 - 4.0f64.powi(-2))?;
-        test_float("9.0/4.0", 9.0 / 4.0)?;
-        test_float("sin(9.0)", 9.0f64.sin())?;
-        test_float("cos(91.0)", 91.0f64.cos())?;
-        test_float("tan(913.0)", 913.0f64.tan())?;
-        test_float("sin(-π)", 0.0)?;
-        test_float("round(π)", 3.0)?;
-        test_float("cos(π)", -1.0)?;
-        test_float("sin (1 if false else 2.0)", 2.0f64.sin())?;
-        test_float("cbrt(27.0)", 3.0)?;
-        test_int("1 if true else 2.0", 1)?;
-        test_float("(9.0 if true else 2.0)", 9.0)?;
-        test_int("1<<4-2", 4)?;
-        test_int("4>>2", 1)?;
-        test_int("signum(4>>1)", 1)?;
-        test_float("signum(-123.12)", -1.0)?;
-        test_float("abs(-123.12)", 123.12)?;
-        test_int("fact(4)", 2 * 3 * 4)?;
-        test_int("fact(0)", 1)?;
-        test_error("fact(-1)")?;
-        test_bool("1>2", false)?;
-        test_bool("1<2", true)?;
-        test_bool("1.4>=1.4", true)?;
-        test_bool("true==true", true)?;
-        test_bool("false==true", false)?;
-        test_bool("1.5 != 1.5 + 2.0", true)?;
-        test_float("1 + 1.0", 2.0)?;
-        test_bool("1.0 == 1", true)?;
-        test_bool("1 == 1", true)?;
-        test_bool("2 == true", false)?;
-        test_bool("1.5 < 1", false)?;
-        test_bool("true == true", true)?;
-        test_bool("false != true", true)?;
-        test_bool("false != false", false)?;
-        test_bool("1 > 0.5", true)?;
         test_error("to_float(10000000000000)")?;
-        test_bool("true == 1", false)?;
-        test_bool("true else 2", true)?;
-        test_int("1 else 2", 1)?;
-        test_error("if true else 2")?;
-        test_none("2 if false")?;
-        test_int("to_int(1)", 1)?;
-        test_int("to_int(3.5)", 3)?;
-        test_float("to_float(2)", 2.0)?;
-        test_float("to_float(3.5)", 3.5)?;
-        test_float("to_float(true)", 1.0)?;
-        test_float("to_float(false)", 0.0)?;
-        test_int("to_int(true)", 1)?;
-        test_int("to_int(false)", 0)?;
-        test_error("to_int(fact(-1))")?;
-        test_error("to_float(5 if false)")?;
-        test_error("0/0")?;
-        test_bool("(5 if false) == (5 if false)", false)?;
         test_error("2^40")?;
         test_error("1000000000*1000000000")?;
         test_error("1500000000+1500000000")?;
         test_error("-1500000000-1500000000")?;
-        test_error("0%0")?;
+        test_int("fact(12)", 479001600)?;
+        test_error("fact(13)")?;
+        test_error("-(-2147483647-1)")?;
+        test_error("abs(-2147483647-1)")?;
+        test_int("abs(-2147483647)", 2147483647)?;
+        test_int("(-2147483647-1)%(-1)", 0)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_vars_i128_f32() -> ExResult<()> {
+        test_no_vars_any_width::<i128, f32>()?;
+        let test_int = test_int::<i128, f32>;
+        let test_float = test_float::<i128, f32>;
+        let test_error = test_error::<i128, f32>;
+        test_int("2^100", 1 << 100)?;
+        test_int("1<<126", 1 << 126)?;
+        test_error("1<<128")?;
+        test_int("10000000000000*10000000000000", 100000000000000000000000000)?;
+        test_int("fact(33)", (1..=33).product())?;
+        test_error("fact(34)")?;
+        test_float("to_float(2^100)", 2f64.powi(100))?;
+        test_int("to_int(2.0^100)", 1 << 100)?;
+        test_float("1.0^(2^100)", 1.0)?;
+        test_bool::<i128, f32>("2^100 == 2.0^100", true)?;
+        test_bool::<i128, f32>("2^100 < 2.0^101", true)?;
+        Ok(())
+    }
 
+    #[test]
+    fn test_no_vars_i8_f64() -> ExResult<()> {
+        test_no_vars_any_width::<i8, f64>()?;
+        let test_int = test_int::<i8, f64>;
+        let test_error = test_error::<i8, f64>;
+        test_error("200")?;
+        test_error("100+100")?;
+        test_error("-100-100")?;
+        test_error("20*20")?;
+        test_int("2^6", 64)?;
+        test_error("2^7")?;
+        test_int("1<<6", 64)?;
+        test_error("1<<8")?;
+        test_int("fact(5)", 120)?;
+        test_error("fact(6)")?;
+        test_error("-(-127-1)")?;
+        test_error("abs(-127-1)")?;
+        test_int("abs(-127)", 127)?;
+        test_int("(-127-1)%(-1)", 0)?;
+        test_int("to_int(127.0)", 127)?;
+        test_error("to_int(128.0)")?;
+        test_float::<i8, f64>("1.5*100", 150.0)?;
         Ok(())
     }
 }
@@ -0,0 +1,58 @@
+use smallvec::SmallVec;
+
+use crate::{definitions::N_VARS_ON_STACK, format_exerr, ExError, ExResult, Express};
+
+/// Provides values of variables by their names, e.g., from the fields of a struct. With
+/// the feature `derive`, the trait can be derived for structs with fields of type `f64`
+/// via `#[derive(ExVars)]`. The name of each field is the name of the variable it provides.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// #
+/// use exmex::{prelude::*, VarProvider};
+/// struct Config {
+///     rate: f64,
+///     years: f64,
+/// }
+/// impl VarProvider for Config {
+///     fn var_value(&self, var_name: &str) -> Option<f64> {
+///         match var_name {
+///             "rate" => Some(self.rate),
+///             "years" => Some(self.years),
+///             _ => None,
+///         }
+///     }
+/// }
+/// let config = Config { rate: 0.5, years: 2.0 };
+/// let expr = FlatEx::<f64>::from_str("(1+rate)^years")?;
+/// let vars = config.to_ordered_slice(&expr)?;
+/// assert!((expr.eval(&vars)? - 2.25).abs() < 1e-12);
+/// #
+/// #     Ok(())
+/// # }
+/// ```
+pub trait VarProvider {
+    /// Returns the value of the variable `var_name` or `None` if it is not provided.
+    fn var_value(&self, var_name: &str) -> Option<f64>;
+
+    /// Collects the values of the variables of `expr` in the order expected by
+    /// [`eval`](crate::Eval::eval).
+    ///
+    /// # Errors
+    ///
+    /// If a variable of `expr` is not provided, an [`ExError`](ExError) is returned.
+    ///
+    fn to_ordered_slice<'a, E>(&self, expr: &E) -> ExResult<SmallVec<[f64; N_VARS_ON_STACK]>>
+    where
+        E: Express<'a, f64>,
+    {
+        expr.var_names()
+            .iter()
+            .map(|var_name| {
+                self.var_value(var_name)
+                    .ok_or_else(|| format_exerr!("variable '{}' is not provided", var_name))
+            })
+            .collect()
+    }
+}
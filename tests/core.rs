@@ -1,14 +1,18 @@
 mod utils;
-use std::ops::{BitAnd, BitOr};
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Neg, Sub};
 use std::str::FromStr;
 use std::{iter::once, ops::Range};
 
 use smallvec::{smallvec, SmallVec};
 
 use exmex::{
-    eval_str, parse, ExResult, OwnedFlatEx, {BinOp, FloatOpsFactory, MakeOperators, Operator},
+    eval_str, eval_str_vars, override_prios, parse, CurlyVarNames, EvalBuffer, ExResult,
+    IndeterminateAsNanOpsFactory, OwnedFlatEx, {BinOp, FloatOpsFactory, MakeOperators, Operator},
+};
+use exmex::{
+    literal_matcher_from_pattern, ops_factory, ops_factory_with_prios, prelude::*, ExError,
+    FlatNodeView, MatchLiteral, NumberMatcher, ParseExpress,
 };
-use exmex::{literal_matcher_from_pattern, ops_factory, prelude::*, ExError, MatchLiteral};
 
 use crate::utils::{assert_float_eq, assert_float_eq_f64};
 use rand::{thread_rng, Rng};
@@ -35,10 +39,19 @@ fn test_flatex() -> ExResult<()> {
         println!("testing {}...", sut);
         let flatex = FlatEx::<f64>::from_str(sut)?;
         assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let mut buffer = EvalBuffer::new();
+        for _ in 0..2 {
+            let res = flatex.eval_with_buffer(vars, &mut buffer)?;
+            assert_eq!(res.to_bits(), flatex.eval(vars)?.to_bits());
+        }
         let flatex = OwnedFlatEx::<f64>::from_flatex(flatex);
         assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let flatex = sut.parse::<OwnedFlatEx<f64>>()?;
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
         let flatex = OwnedFlatEx::<f64>::from_str(sut)?;
         assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let res = flatex.eval_with_buffer(vars, &mut buffer)?;
+        assert_eq!(res.to_bits(), flatex.eval(vars)?.to_bits());
         println!("...ok.");
         Ok(())
     }
@@ -155,6 +168,59 @@ fn test_variables_curly_space_names() -> ExResult<()> {
     let expr = FlatEx::<f64>::from_str(sut)?;
     utils::assert_float_eq_f64(expr.eval(&[2.0, 3.0])?, 34.0);
     assert_eq!(expr.unparse()?, sut);
+
+    fn test(
+        sut: &str,
+        curly_var_names: CurlyVarNames,
+        var_names: &[&str],
+        vars: &[f64],
+        reference: f64,
+    ) -> ExResult<()> {
+        let expr = FlatEx::<f64>::from_str_with_curly_var_names(sut, curly_var_names)?;
+        assert_eq!(expr.var_names(), var_names);
+        utils::assert_float_eq_f64(expr.eval(vars)?, reference);
+        assert_eq!(expr.unparse()?, sut);
+        // derived expressions keep the variables
+        let dexpr = expr.clone().partial(0)?;
+        assert_eq!(dexpr.var_names(), var_names);
+        let owned = OwnedFlatEx::<f64>::from_str_with_curly_var_names(sut, curly_var_names)?;
+        assert_eq!(owned.var_names(), var_names);
+        utils::assert_float_eq_f64(owned.eval(vars)?, reference);
+        assert_eq!(owned.unparse()?, sut);
+        let downed = owned.clone().partial(0)?;
+        assert_eq!(downed.var_names(), var_names);
+        utils::assert_float_eq_f64(downed.eval(vars)?, dexpr.eval(vars)?);
+        // reducing memory keeps the variables, too
+        let last = var_names.len() - 1;
+        let mut reduced = expr.clone();
+        reduced.reduce_memory();
+        let dreduced = reduced.partial(last)?;
+        assert_eq!(dreduced.var_names(), var_names);
+        utils::assert_float_eq_f64(dreduced.eval(vars)?, expr.partial(last)?.eval(vars)?);
+        let mut reduced = owned;
+        reduced.reduce_memory();
+        assert_eq!(reduced.partial(last)?.var_names(), var_names);
+        Ok(())
+    }
+    let sut = "{x} + 2*{x }";
+    test(sut, CurlyVarNames::Trim, &["x"], &[1.0], 3.0)?;
+    test(sut, CurlyVarNames::Verbatim, &["x", "x "], &[1.0, 2.0], 5.0)?;
+    assert_eq!(FlatEx::<f64>::from_str(sut)?.var_names(), ["x"]);
+    let sut = "{ y z}*x + { y z }^2 + y";
+    test(
+        sut,
+        CurlyVarNames::Trim,
+        &["x", "y", "y z"],
+        &[2.0, 1.0, 3.0],
+        16.0,
+    )?;
+    test(
+        sut,
+        CurlyVarNames::Verbatim,
+        &[" y z", " y z ", "x", "y"],
+        &[3.0, 4.0, 2.0, 1.0],
+        23.0,
+    )?;
     Ok(())
 }
 #[test]
@@ -222,6 +288,7 @@ fn test_variables_non_ascii() -> ExResult<()> {
             } else {
                 Err(Self::Err {
                     msg: format!("cannot parse {} to `Thumbs`", s),
+                    position: None,
                 })
             }
         }
@@ -371,6 +438,24 @@ fn test_variables() -> ExResult<()> {
     let sut = "trunc(x) + fract(x)";
     let expr = FlatEx::<f64>::from_str(sut)?;
     utils::assert_float_eq_f64(expr.eval(&[23422.52345]).unwrap(), 23422.52345);
+
+    let sut = "atan2(y, x) + atan2(1, -1)*2";
+    let expr = FlatEx::<f64>::from_str(sut)?;
+    utils::assert_float_eq_f64(
+        expr.eval(&[-2.0, 0.5]).unwrap(),
+        0.5f64.atan2(-2.0) + 1f64.atan2(-1.0) * 2.0,
+    );
+    assert_eq!(expr.partial(1)?.eval(&[1.0, 0.0])?, 1.0);
+    let deri = FlatEx::<f64>::from_str("z*atan2(y, x)")?.partial(2)?;
+    assert_eq!(deri.unparse()?, "atan2({y}, {x})");
+
+    let deri = FlatEx::<f64>::from_str("y*(x % 3)")?.partial(1)?;
+    let unparsed = deri.unparse()?;
+    assert_eq!(unparsed, "{x}%3.0");
+    let reparsed = FlatEx::<f64>::from_str(&unparsed)?;
+    for x in [-4.5, -1.0, 0.0, 2.5, 7.0] {
+        assert_eq!(reparsed.eval(&[x])?, deri.eval(&[x, 1.0])?);
+    }
     Ok(())
 }
 
@@ -682,6 +767,21 @@ fn test_eval_str() -> ExResult<()> {
     test("2^log2(2)", 2.0)?;
     test("2^(cos(0)+2)", 8.0)?;
     test("2^cos(0)+2", 4.0)?;
+    test("exp2(3)", 8.0)?;
+    test("exp10(3)", 1000.0)?;
+    test("exp10(-1)+exp2(-1)", 0.6)?;
+    test("exp(exp2(0))", std::f64::consts::E)?;
+    test("recip(4)", 0.25)?;
+    test("recip(-0.5)+1/2", -1.5)?;
+    assert_eq!(eval_str::<f64>("recip(0)")?, f64::INFINITY);
+    assert_eq!(eval_str::<f64>("recip(-0)")?, eval_str::<f64>("1/(-0)")?);
+    test("7.5 % 2", 1.5)?;
+    test("-1 % 3", 2.0)?;
+    test("-7.5 % -2", 0.5)?;
+    test("7.5 % -2", 1.5)?;
+    test("2*5 % 4", 2.0)?;
+    test("-3 % 3", 0.0)?;
+    assert!(eval_str::<f64>("1 % 0")?.is_nan());
     Ok(())
 }
 
@@ -695,6 +795,232 @@ fn test_error_handling() {
     assert!(eval_str::<f64>("2*(5+5))").is_err());
 }
 
+#[test]
+fn test_eval_batch() -> ExResult<()> {
+    fn test<'a, E: Express<'a, f64> + Sync>(expr: &E) -> ExResult<()> {
+        let mut rng = thread_rng();
+        let var_sets = (0..10000)
+            .map(|_| {
+                (0..expr.n_vars())
+                    .map(|_| rng.gen_range(-10.0..10.0))
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        let res = expr.eval_batch(&var_sets)?;
+        assert_eq!(res.len(), var_sets.len());
+        for (vars, r) in var_sets.iter().zip(res.iter()) {
+            assert_float_eq_f64(*r, expr.eval(vars)?);
+        }
+        #[cfg(feature = "rayon")]
+        assert_eq!(expr.eval_batch_par(&var_sets)?, res);
+        Ok(())
+    }
+    test(&FlatEx::<f64>::from_str("sin(x)*y^2-z/3")?)?;
+    test(&OwnedFlatEx::<f64>::from_str("2*x+1")?)?;
+    test(&FlatEx::<f64>::from_str("3.5")?)?;
+
+    let expr = FlatEx::<f64>::from_str("x+y")?;
+    assert!(expr.eval_batch(&[vec![1.0, 2.0], vec![1.0]]).is_err());
+    assert!(expr.eval_batch::<Vec<f64>>(&[])?.is_empty());
+    #[cfg(feature = "rayon")]
+    assert!(expr.eval_batch_par(&[[1.0, 2.0], [3.0, 4.0]]).is_ok());
+    let mut var_sets = vec![vec![1.0, 2.0]; 100];
+    var_sets[57].pop();
+    let err = expr.eval_batch(&var_sets).unwrap_err();
+    assert!(err.msg.contains("row 57"));
+    #[cfg(feature = "rayon")]
+    assert_eq!(expr.eval_batch_par(&var_sets).unwrap_err(), err);
+    Ok(())
+}
+
+#[test]
+fn test_boxed_eval() -> ExResult<()> {
+    fn make(text: &'static str, owned: bool) -> ExResult<Box<dyn Eval<f64> + Send + Sync>> {
+        Ok(if owned {
+            Box::new(OwnedFlatEx::<f64>::from_str(text)?)
+        } else {
+            Box::new(FlatEx::<f64>::from_str(text)?)
+        })
+    }
+    let texts = ["sin(x)*y^2-z/3", "2*{a b}+1", "3.5"];
+    let exprs = texts
+        .iter()
+        .enumerate()
+        .map(|(i, text)| make(text, i % 2 == 0))
+        .collect::<ExResult<Vec<_>>>()?;
+    let vars = [0.5, 2.0, 3.0];
+    let reference = [0.5f64.sin() * 4.0 - 1.0, 2.0 * 0.5 + 1.0, 3.5];
+    for ((expr, text), reference) in exprs.iter().zip(texts).zip(reference) {
+        assert_eq!(expr.unparse()?, text);
+        assert_float_eq_f64(expr.eval(&vars[..expr.n_vars()])?, reference);
+    }
+    assert_eq!(exprs[0].var_names(), ["x", "y", "z"]);
+    assert_eq!(exprs[1].var_names(), ["a b"]);
+    assert!(exprs[2].var_names().is_empty());
+    assert!(exprs[0].eval(&[1.0]).is_err());
+    let handle = std::thread::spawn(move || exprs[1].eval(&[1.0]));
+    assert_float_eq_f64(handle.join().unwrap()?, 3.0);
+    Ok(())
+}
+
+#[test]
+fn test_parse_with_unparsed() -> ExResult<()> {
+    fn test<'a, E: Express<'a, f64>>(text: &'a str) -> ExResult<()> {
+        let (mut expr, unparsed) = E::parse_with_unparsed(text)?;
+        assert_eq!(unparsed, expr.unparse()?);
+        expr.reduce_memory();
+        let expr_reparsed = E::from_str(text)?;
+        assert_eq!(unparsed, expr_reparsed.unparse()?);
+        Ok(())
+    }
+    for text in ["x*y^2-sin({z})", "-(2*x + 3)/y", "1.5"] {
+        test::<FlatEx<f64>>(text)?;
+        test::<OwnedFlatEx<f64>>(text)?;
+        let deriv = FlatEx::<f64>::from_str(text)?.partial(0);
+        if let Ok(deriv) = deriv {
+            let unparsed = deriv.unparse()?;
+            let (_, reunparsed) = FlatEx::<f64>::parse_with_unparsed(&unparsed)?;
+            assert_eq!(unparsed, reunparsed);
+        }
+    }
+    assert!(FlatEx::<f64>::parse_with_unparsed("x+").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_eval_columns() -> ExResult<()> {
+    fn test<'a, E: Express<'a, f64>>(expr: &E) -> ExResult<()> {
+        let mut rng = thread_rng();
+        let n_rows = 100;
+        let columns = (0..expr.n_vars())
+            .map(|_| {
+                (0..n_rows)
+                    .map(|_| rng.gen_range(-10.0..10.0))
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        let column_slices = columns.iter().map(|c| c.as_slice()).collect::<Vec<_>>();
+        let res = expr.eval_columns(&column_slices)?;
+        assert_eq!(res.len(), n_rows);
+        for (row_idx, r) in res.iter().enumerate() {
+            let row = columns.iter().map(|c| c[row_idx]).collect::<Vec<_>>();
+            assert_float_eq_f64(*r, expr.eval(&row)?);
+        }
+        Ok(())
+    }
+    test(&FlatEx::<f64>::from_str("sin(x)*y^2-z/3")?)?;
+    test(&OwnedFlatEx::<f64>::from_str("2*x+1")?)?;
+    test(&FlatEx::<f64>::from_str("if(x > 0, sin(x), -y)")?)?;
+    test(&FlatEx::<f64>::from_str("sin(x+y)^2 + cos(x+y)")?.with_cse()?.0)?;
+
+    let expr = FlatEx::<f64>::from_str("3.5")?;
+    assert_eq!(expr.eval_columns(&[])?, vec![3.5]);
+
+    let expr = FlatEx::<f64>::from_str("x+y+z")?;
+    let err = expr
+        .eval_columns(&[&[1.0, 2.0], &[1.0, 2.0], &[1.0]])
+        .unwrap_err();
+    assert!(err.msg.contains("column of variable 'z' has 1 elements"));
+    assert!(expr.eval_columns(&[&[1.0], &[1.0]]).is_err());
+    assert!(expr.eval_columns(&[&[], &[], &[]])?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_arithmetic_traits() -> ExResult<()> {
+    fn test<'a, E>(text_1: &'a str, text_2: &'a str, vars: &[f64]) -> ExResult<()>
+    where
+        E: Express<'a, f64>
+            + Clone
+            + Add<Output = ExResult<E>>
+            + Sub<Output = ExResult<E>>
+            + Mul<Output = ExResult<E>>
+            + Div<Output = ExResult<E>>
+            + Neg<Output = ExResult<E>>,
+    {
+        println!("testing {} and {}...", text_1, text_2);
+        let expr_1 = E::from_str(text_1)?;
+        let expr_2 = E::from_str(text_2)?;
+        let all_vars = expr_1
+            .var_names()
+            .into_iter()
+            .chain(expr_2.var_names())
+            .map(|name| name.to_string())
+            .collect::<std::collections::BTreeSet<_>>();
+        // values of the variables of each operand
+        let select = |expr: &E| {
+            expr.var_names()
+                .iter()
+                .map(|name| vars[all_vars.iter().position(|n| n == name).unwrap()])
+                .collect::<Vec<_>>()
+        };
+        let val_1 = expr_1.eval(&select(&expr_1))?;
+        let val_2 = expr_2.eval(&select(&expr_2))?;
+
+        let sum = (expr_1.clone() + expr_2.clone())?;
+        assert_eq!(sum.n_vars(), all_vars.len());
+        assert_float_eq_f64(sum.eval(vars)?, val_1 + val_2);
+        let diff = (expr_1.clone() - expr_2.clone())?;
+        assert_float_eq_f64(diff.eval(vars)?, val_1 - val_2);
+        let prod = (expr_1.clone() * expr_2.clone())?;
+        assert_float_eq_f64(prod.eval(vars)?, val_1 * val_2);
+        let quot = (expr_1.clone() / expr_2)?;
+        assert_float_eq_f64(quot.eval(vars)?, val_1 / val_2);
+        let neg = (-expr_1.clone())?;
+        assert_eq!(neg.n_vars(), expr_1.n_vars());
+        assert_float_eq_f64(neg.eval(&select(&expr_1))?, -val_1);
+        println!("...ok.");
+        Ok(())
+    }
+    test::<FlatEx<f64>>("x^2", "2*y", &[3.0, 0.5])?;
+    test::<FlatEx<f64>>("sin(x)-y", "x*z+1", &[0.3, 1.2, -2.0])?;
+    test::<FlatEx<f64>>("x+y", "x-y", &[1.5, 4.0])?;
+    test::<FlatEx<f64>>("b*3", "a", &[1.5, 4.0])?;
+    test::<OwnedFlatEx<f64>>("x^2", "2*y", &[3.0, 0.5])?;
+    test::<OwnedFlatEx<f64>>("-x^3", "3", &[1.5])?;
+
+    let sum = (FlatEx::<f64>::from_str("x")? + FlatEx::<f64>::from_str("y")?)?;
+    assert_eq!(sum.var_names(), vec!["x", "y"]);
+    let prod = (FlatEx::<f64>::from_str("x+1")? * FlatEx::<f64>::from_str("2")?)?;
+    assert_float_eq_f64(prod.eval(&[1.0])?, 4.0);
+    let d_sum = (FlatEx::<f64>::from_str("x^2")? + FlatEx::<f64>::from_str("2*y")?)?;
+    assert_float_eq_f64(d_sum.clone().partial(0)?.eval(&[3.0, 0.5])?, 6.0);
+    assert_float_eq_f64(d_sum.partial(1)?.eval(&[3.0, 0.5])?, 2.0);
+
+    // operator factories without the operator
+    ops_factory!(
+        OnlyMulFactory,
+        f64,
+        Operator::make_bin(
+            "*",
+            BinOp {
+                apply: |a, b| a * b,
+                prio: 2,
+                is_commutative: true,
+            }
+        )
+    );
+    let expr = FlatEx::<f64, OnlyMulFactory>::from_str("x*y")?;
+    assert!((expr.clone() * expr.clone()).is_ok());
+    assert!((expr.clone() + expr.clone()).is_err());
+    assert!((-expr).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vars_in() -> ExResult<()> {
+    assert_eq!(exmex::vars_in::<f64>("sqr(2)")?, vec!["sqr"]);
+    assert_eq!(exmex::vars_in::<f64>("y*sin(x)+{x}")?, vec!["x", "y"]);
+    assert!(exmex::vars_in::<f64>("sqrt(2)+PI")?.is_empty());
+    assert!(exmex::vars_in::<f64>("2 $ x").is_err());
+
+    let err = eval_str::<f64>("sqr(2)").unwrap_err();
+    assert!(err.msg.contains("'sqr' at [0..3]"));
+    let err = eval_str::<f64>("y*x+sin(x)").unwrap_err();
+    assert!(err.msg.contains("'x' at [2..3, 8..9], 'y' at [0..1]"));
+    Ok(())
+}
+
 #[cfg(feature = "serde")]
 #[test]
 fn test_serde_public_interface() -> ExResult<()> {
@@ -728,10 +1054,78 @@ fn test_constants() -> ExResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_indeterminate_pow() -> ExResult<()> {
+    // default follows powf
+    assert_float_eq_f64(eval_str::<f64>("0^0")?, 1.0);
+    assert_float_eq_f64(eval_str::<f64>("1^(1/0)")?, 1.0);
+
+    fn test(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        let expr = FlatEx::<f64, IndeterminateAsNanOpsFactory<f64>>::from_str(text)?;
+        let res = expr.eval(vars)?;
+        if reference.is_nan() {
+            assert!(res.is_nan());
+        } else {
+            assert_float_eq_f64(res, reference);
+        }
+        Ok(())
+    }
+    test("0^0", &[], f64::NAN)?;
+    test("x^y", &[0.0, 0.0], f64::NAN)?;
+    test("(1/0)^0", &[], f64::NAN)?;
+    test("1^(1/0)", &[], f64::NAN)?;
+    test("x^0", &[2.0], 1.0)?;
+    test("0^x", &[2.0], 0.0)?;
+    test("sin(x)^2+cos(x)^2", &[0.3], 1.0)?;
+    test("-PI*E", &[], -std::f64::consts::PI * std::f64::consts::E)?;
+    assert_eq!(
+        IndeterminateAsNanOpsFactory::<f64>::make().len(),
+        FloatOpsFactory::<f64>::make().len()
+    );
+    Ok(())
+}
+
 #[test]
 fn test_fuzz() {
     assert!(eval_str::<f64>("an").is_err());
     assert!(FlatEx::<f64>::from_str("\n").is_err());
+
+    // literal matchers that match the empty string or not at the beginning
+    literal_matcher_from_pattern!(EmptyMatcher, "^[0-9]*");
+    literal_matcher_from_pattern!(UnanchoredMatcher, "[0-9]+");
+    type FlatExEmpty<'a> = FlatEx<'a, f64, FloatOpsFactory<f64>, EmptyMatcher>;
+    type FlatExUnanchored<'a> = FlatEx<'a, f64, FloatOpsFactory<f64>, UnanchoredMatcher>;
+    assert_float_eq_f64(FlatExEmpty::from_str("x+1").unwrap().eval(&[1.0]).unwrap(), 2.0);
+    assert_float_eq_f64(FlatExUnanchored::from_str("x+1").unwrap().eval(&[1.0]).unwrap(), 2.0);
+    assert!(FlatExUnanchored::from_str("ä1").is_err());
+    assert!(FlatExEmpty::from_str("").is_err());
+}
+
+#[test]
+fn test_no_panic() {
+    let tokens = [
+        "x", "y", "{z}", "{", "}", "{ä b}", "2", "0.5", ".", "1.", "PI", "π", "+", "-", "*", "/",
+        "^", "sin", "log2", "(", ")", " ", "ä", "€", "𝕏", "\n", "sinx", "exp10", "[1,2]", "%",
+    ];
+    let mut rng = thread_rng();
+    for _ in 0..20000 {
+        let n_tokens = rng.gen_range(1..10);
+        let text = (0..n_tokens)
+            .map(|_| tokens[rng.gen_range(0..tokens.len())])
+            .collect::<String>();
+        let _ = eval_str::<f64>(&text);
+        if let Ok(flatex) = FlatEx::<f64>::from_str(&text) {
+            let vars = vec![0.5; flatex.n_vars()];
+            let _ = flatex.eval(&vars);
+            let _ = flatex.unparse();
+            for var_idx in 0..flatex.n_vars() {
+                if let Ok(deri) = flatex.clone().partial(var_idx) {
+                    let _ = deri.eval(&vars);
+                }
+            }
+            let _ = OwnedFlatEx::from_flatex(flatex).eval(&vars);
+        };
+    }
 }
 
 #[test]
@@ -791,5 +1185,2398 @@ fn test_partial_finite() -> ExResult<()> {
     test("sqrt(exp(y-x))", -1000.0..0.0)?;
     test("sin(sin(x+z))", -10.0..10.0)?;
     test("asin(sqrt(x+y))", 0.0..0.5)?;
+    test("exp2(x)", -5.0..5.0)?;
+    test("exp10(x*y)", -1.0..1.0)?;
+    test("exp2(sin(x))*exp10(x)", -1.0..1.0)?;
+    test("atan2(y, x)", -10.0..10.0)?;
+    test("atan2(sin(y), x^2+1)*x", -1.0..1.0)?;
+    test("atan2(2, x)", 0.1..10.0)?;
+    test("atan2(x, 2)", -10.0..10.0)?;
+    test("sqdiff(sin(x), y)", -10.0..10.0)?;
+    test("recip(x)", 0.5..10.0)?;
+    test("recip(x)", -10.0..-0.5)?;
+    test("recip(sin(x)+y^2)", 1.0..2.0)?;
+    // ranges without discontinuities of the remainder
+    test("x % 3", 0.1..2.9)?;
+    test("(x*x) % 3", -1.6..-0.1)?;
+    test("7 % x", 2.5..3.4)?;
+    test("sqdiff(2, x)*z", -10.0..10.0)?;
+    test("absdiff(x, y^2+1)*z", -0.5..0.5)?;
+    test("absdiff(exp(x), 0.5)", 0.0..10.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_absdiff_sqdiff() -> ExResult<()> {
+    let expr = FlatEx::<f64>::from_str("absdiff(x, y)")?;
+    assert_float_eq_f64(expr.eval(&[1.5, 4.0])?, 2.5);
+    assert_float_eq_f64(expr.eval(&[4.0, 1.5])?, 2.5);
+    let dx = expr.clone().partial(0)?;
+    assert_float_eq_f64(dx.eval(&[1.5, 4.0])?, -1.0);
+    assert_float_eq_f64(dx.eval(&[4.0, 1.5])?, 1.0);
+    // subgradient at the kink
+    assert_float_eq_f64(dx.eval(&[2.0, 2.0])?, 0.0);
+    let dy = expr.partial(1)?;
+    assert_float_eq_f64(dy.eval(&[1.5, 4.0])?, 1.0);
+    assert_float_eq_f64(dy.eval(&[2.0, 2.0])?, 0.0);
+
+    let expr = FlatEx::<f64>::from_str("sqdiff(x, 3)")?;
+    assert_float_eq_f64(expr.eval(&[1.0])?, 4.0);
+    let dx = expr.partial(0)?;
+    assert_float_eq_f64(dx.eval(&[1.0])?, -4.0);
+    assert_float_eq_f64(dx.eval(&[3.0])?, 0.0);
+
+    assert_float_eq_f64(eval_str::<f64>("sign(-2)+sign(0)+sign(3)")?, 0.0);
+    assert_float_eq_f64(eval_str::<f64>("signum(0)")?, 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_comparisons() -> ExResult<()> {
+    fn test(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test("x < 1", &[0.5], 1.0)?;
+    test("x < 1", &[1.0], 0.0)?;
+    test("x <= 1", &[1.0], 1.0)?;
+    test("x > 1", &[1.0], 0.0)?;
+    test("x >= 1", &[1.0], 1.0)?;
+    test("x == 2", &[2.0], 1.0)?;
+    test("x != 2", &[2.0], 0.0)?;
+    test("x != x", &[f64::NAN], 1.0)?;
+    // arithmetic operators bind stronger than comparisons
+    test("x + 1 > 2", &[1.5], 1.0)?;
+    test("x + 1 > 2", &[0.5], 0.0)?;
+    test("2 < x * 3 - 1", &[1.5], 1.0)?;
+    test("x^2 == 4", &[-2.0], 1.0)?;
+    test("(x > 0) * x", &[-1.5], 0.0)?;
+    test("(x > 0) * x", &[1.5], 1.5)?;
+    test("-x < 0", &[1.0], 1.0)?;
+    // chained comparisons are evaluated from left to right
+    test("1 < 2 < 3", &[], 1.0)?;
+    test("3 > 2 > 1", &[], 0.0)?;
+    test("x < y == 1", &[1.0, 2.0], 1.0)?;
+    test("x < (y == 1)", &[1.0, 2.0], 0.0)?;
+    // comparisons bind stronger than && and && binds stronger than ||
+    test("x > 0 && y > 0", &[1.0, 2.0], 1.0)?;
+    test("x > 0 && y > 0", &[1.0, -2.0], 0.0)?;
+    test("x && y", &[-0.5, 3.0], 1.0)?;
+    test("x || y", &[0.0, 0.0], 0.0)?;
+    test("x || y", &[0.0, -3.0], 1.0)?;
+    test("1 || 0 && 0", &[], 1.0)?;
+    test("(1 || 0) && 0", &[], 0.0)?;
+    test("x < 0 || x > 1 && y", &[-1.0, 0.0], 1.0)?;
+    test("sin(x) >= 0", &[3.0], 1.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_select() -> ExResult<()> {
+    fn test(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test("if(x, sin(x), 0)", &[1.0], 1f64.sin())?;
+    test("if(x, sin(x), 0)", &[0.0], 0.0)?;
+    test("if(x > 1, 2*x, -x) + 1", &[3.0], 7.0)?;
+    test("if(x > 1, 2*x, -x) + 1", &[-3.0], 4.0)?;
+    test("if(1, 2, 3)", &[], 2.0)?;
+    test("if(0, 2, 3)", &[], 3.0)?;
+    // the branch that is not selected does not influence the result
+    test("if(x, 1/x, 0)", &[0.0], 0.0)?;
+    test("if(x == 0, 0, log(x))", &[0.0], 0.0)?;
+    test("if(x == 0, log(x), 5)", &[-2.0], 5.0)?;
+    // NaN in the selected branch is propagated
+    assert!(FlatEx::<f64>::from_str("if(x, log(x), 5)")?
+        .eval(&[-1.0])?
+        .is_nan());
+    assert!(FlatEx::<f64>::from_str("if(x, 5, sqrt(x - 1))")?
+        .eval(&[0.0])?
+        .is_nan());
+    // nested selections and other function-style operators in the arguments
+    test("if(x < 0, -1, if(x > 0, 1, 0))", &[-2.0], -1.0)?;
+    test("if(x < 0, -1, if(x > 0, 1, 0))", &[2.0], 1.0)?;
+    test("if(x < 0, -1, if(x > 0, 1, 0))", &[0.0], 0.0)?;
+    test(
+        "if(atan2(x, 1) > 0, atan2(1, x), y)",
+        &[1.0, 4.0],
+        1f64.atan2(1.0),
+    )?;
+    test("if(atan2(x, 1) > 0, atan2(1, x), y)", &[-1.0, 4.0], 4.0)?;
+    test("2*if(x, y, z)^2", &[1.0, 3.0, 4.0], 18.0)?;
+    assert!(FlatEx::<f64>::from_str("if(x, 1)").is_err());
+    assert!(FlatEx::<f64>::from_str("if(x, 1, 2, 3)").is_err());
+    assert!(FlatEx::<f64>::from_str("if(x, , 2)").is_err());
+    assert!(FlatEx::<f64>::from_str("if x").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_select_round_trips() -> ExResult<()> {
+    fn test_reparse(expr: &FlatEx<f64>, vars: &[f64]) -> ExResult<()> {
+        let unparsed = expr.unparse()?;
+        println!("reparsing {}...", unparsed);
+        let reparsed = FlatEx::<f64>::from_str(&unparsed)?;
+        assert_float_eq_f64(reparsed.eval(vars)?, expr.eval(vars)?);
+        Ok(())
+    }
+    let expr = FlatEx::<f64>::from_str("if(x > 1, 2*x, y) + 1")?;
+    assert_eq!(expr.unparse()?, "if(x > 1, 2*x, y) + 1");
+    test_reparse(&expr, &[3.0, 4.0])?;
+    test_reparse(&expr, &[0.0, 4.0])?;
+
+    // bind a variable in a branch and in the condition
+    let bound = expr.clone().bind(1, 5.0)?;
+    assert_eq!(bound.var_names(), ["x"]);
+    assert_float_eq_f64(bound.eval(&[3.0])?, 7.0);
+    assert_float_eq_f64(bound.eval(&[0.0])?, 6.0);
+    test_reparse(&bound, &[0.0])?;
+    let bound = expr.clone().bind(0, 0.0)?;
+    assert_eq!(bound.var_names(), ["y"]);
+    assert_float_eq_f64(bound.eval(&[4.0])?, 5.0);
+    test_reparse(&bound, &[4.0])?;
+
+    // partial evaluation and substitution re-parse the unparsed expression
+    let bindings = [("y", 2.0)].iter().cloned().collect();
+    let partial_evaluated = expr.clone().partial_eval(&bindings)?;
+    assert_float_eq_f64(partial_evaluated.eval(&[0.0])?, 3.0);
+    test_reparse(&partial_evaluated, &[0.0])?;
+    let substituted = expr.clone().substitute(1, FlatEx::from_str("x^2")?)?;
+    assert_float_eq_f64(substituted.eval(&[-2.0])?, 5.0);
+    test_reparse(&substituted, &[-2.0])?;
+    let simplified = FlatEx::<f64>::from_str("if(x, 0*y + x, 1*y)")?.simplify()?;
+    assert_float_eq_f64(simplified.eval(&[2.0, 3.0])?, 2.0);
+    assert_float_eq_f64(simplified.eval(&[0.0, 3.0])?, 3.0);
+
+    // minify
+    let (minified, names) = expr.minify()?;
+    let names = names.iter().map(|n| n.as_str()).collect::<Vec<_>>();
+    let parsed = FlatEx::<f64>::parse_minified(&minified, &names)?;
+    assert_eq!(parsed.var_names(), expr.var_names());
+    assert_float_eq_f64(parsed.eval(&[3.0, 4.0])?, 7.0);
+    assert_float_eq_f64(parsed.eval(&[0.0, 4.0])?, 5.0);
+
+    // d if(c, a, b) = if(c, a', b')
+    let deri = FlatEx::<f64>::from_str("if(x > 1, x^2, 3*x*y)")?.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[2.0, 5.0])?, 4.0);
+    assert_float_eq_f64(deri.eval(&[0.0, 5.0])?, 15.0);
+    test_reparse(&deri, &[2.0, 5.0])?;
+    test_reparse(&deri, &[0.0, 5.0])?;
+    let owned_deri = OwnedFlatEx::<f64>::from_str("2*if(x, y, z)^2")?.partial(1)?;
+    assert_float_eq_f64(owned_deri.eval(&[1.0, 3.0, 4.0])?, 12.0);
+    assert_float_eq_f64(owned_deri.eval(&[0.0, 3.0, 4.0])?, 0.0);
+
+    // rendering
+    assert_eq!(
+        expr.unparse_latex()?,
+        "\\operatorname{if}(x \\operatorname{>} 1, 2 \\cdot x, y)+1"
+    );
+    let dot = expr.to_dot()?;
+    assert!(dot.contains("label=\"if\""));
+    Ok(())
+}
+
+#[test]
+fn test_wrap_range() -> ExResult<()> {
+    fn test(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(text)?;
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference);
+        Ok(())
+    }
+    test("wrap_range(x, 0, 360)", &[370.0], 10.0)?;
+    test("wrap_range(x, 0, 360)", &[-10.0], 350.0)?;
+    test("wrap_range(x, 0, 360)", &[360.0], 0.0)?;
+    test("wrap_range(x, -1, 1)", &[1.5], -0.5)?;
+    test("wrap_range(x, -1, 1)", &[-3.0], -1.0)?;
+    test("wrap_range(x, 1, -1)", &[1.5], -0.5)?;
+    test("2*wrap_range(x, lo, lo + 1)", &[0.0, 2.25], 0.5)?;
+    // the upper bound is excluded also if rounding would hit it
+    let wrapped = FlatEx::<f64>::from_str("wrap_range(x, 0, 1)")?.eval(&[-1e-20])?;
+    assert!((0.0..1.0).contains(&wrapped));
+    // zero width
+    assert!(FlatEx::<f64>::from_str("wrap_range(x, 2, 2)")?
+        .eval(&[1.0])?
+        .is_nan());
+
+    // the variables are sorted, i.e., hi is the first variable and x the second
+    let expr = FlatEx::<f64>::from_str("wrap_range(x^2, 0, hi)")?;
+    let unparsed = expr.unparse()?;
+    let reparsed = FlatEx::<f64>::from_str(&unparsed)?;
+    assert_float_eq_f64(reparsed.eval(&[3.0, 2.0])?, 1.0);
+    let bound = expr.clone().bind(0, 3.0)?;
+    assert_float_eq_f64(bound.eval(&[2.0])?, 1.0);
+
+    // the derivative with respect to x is the derivative of x^2 almost everywhere
+    let d_x = expr.clone().partial(1)?;
+    assert_float_eq_f64(d_x.eval(&[3.0, 2.0])?, 4.0);
+    assert_float_eq_f64(d_x.eval(&[3.0, 3.0])?, 6.0);
+    // shifting the upper bound moves the result by the number of wrapped periods
+    let d_hi = expr.partial(0)?;
+    assert_float_eq_f64(d_hi.eval(&[3.0, 2.0])?, -1.0);
+    assert_float_eq_f64(d_hi.eval(&[4.0, 3.0])?, -2.0);
+    assert_float_eq_f64(d_hi.eval(&[4.0, 1.0])?, 0.0);
+    Ok(())
+}
+
+#[test]
+fn test_as_polynomial() -> ExResult<()> {
+    fn test(text: &str, var_idx: usize, reference: &[f64]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.as_polynomial(var_idx)?, reference);
+        let owned_flatex = OwnedFlatEx::from_flatex(flatex.clone());
+        assert_eq!(owned_flatex.as_polynomial(var_idx)?, reference);
+        let deri = flatex.partial(var_idx)?;
+        let mut reference_deri = reference
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| i as f64 * c)
+            .collect::<Vec<_>>();
+        if reference_deri.is_empty() {
+            reference_deri.push(0.0);
+        }
+        assert_eq!(deri.as_polynomial(var_idx)?, reference_deri);
+        Ok(())
+    }
+    test("3*x^2 + 2*x + 1", 0, &[1.0, 2.0, 3.0])?;
+    test("(2*x - 1)^2", 0, &[1.0, -4.0, 4.0])?;
+    test("x/4 + 0.5", 0, &[0.5, 0.25])?;
+    test("PI*x", 0, &[0.0, std::f64::consts::PI])?;
+
+    let flatex = FlatEx::<f64>::from_str("sin(x) + x^2")?;
+    assert!(flatex.as_polynomial(0).is_err());
+    assert!(flatex.as_polynomial(1).is_err());
+    let mut flatex = FlatEx::<f64>::from_str("x*y")?.partial(0)?;
+    flatex.reduce_memory();
+    assert!(flatex.as_polynomial(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_consistent_with_parse() {
+    let tokens = [
+        "x", "y", "{z}", "2", "0.5", "PI", "+", "-", "*", "/", "^", "sin", "cos", "(", ")", " ",
+    ];
+    let mut rng = thread_rng();
+    for _ in 0..20000 {
+        let n_tokens = rng.gen_range(1..12);
+        let text = (0..n_tokens)
+            .map(|_| tokens[rng.gen_range(0..tokens.len())])
+            .collect::<String>();
+        let checked = exmex::check::<f64, FloatOpsFactory<f64>, NumberMatcher>(&text);
+        let parsed = FlatEx::<f64>::from_str(&text);
+        match (&checked, &parsed) {
+            (Ok(report), Ok(flatex)) => {
+                assert_eq!(report.var_names.len(), flatex.n_vars());
+                assert!(OwnedFlatEx::<f64>::from_str(&text).is_ok());
+            }
+            (Err(errors), Err(e)) => assert_eq!(&errors[0], e),
+            _ => panic!(
+                "check and parse disagree on '{}', {:?} vs {:?}",
+                text, checked, parsed
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_operator_ids() -> ExResult<()> {
+    // ids are part of the public interface, changing them is a breaking change
+    let ids = FloatOpsFactory::<f64>::make()
+        .iter()
+        .map(|op| (op.repr(), op.id()))
+        .collect::<Vec<_>>();
+    let reference = [
+        ("^", "pow"),
+        ("*", "mul"),
+        ("/", "div"),
+        ("+", "add"),
+        ("-", "sub"),
+        ("abs", "abs"),
+        ("signum", "signum"),
+        ("sin", "sin"),
+        ("cos", "cos"),
+        ("tan", "tan"),
+        ("asin", "asin"),
+        ("acos", "acos"),
+        ("atan", "atan"),
+        ("sinh", "sinh"),
+        ("cosh", "cosh"),
+        ("tanh", "tanh"),
+        ("floor", "floor"),
+        ("round", "round"),
+        ("ceil", "ceil"),
+        ("trunc", "trunc"),
+        ("fract", "fract"),
+        ("exp", "exp"),
+        ("exp2", "exp2"),
+        ("exp10", "exp10"),
+        ("sqrt", "sqrt"),
+        ("cbrt", "cbrt"),
+        ("log", "ln"),
+        ("log2", "log2"),
+        ("PI", "pi"),
+        ("π", "pi"),
+        ("E", "e"),
+        ("atan2", "atan2"),
+        ("round_to", "round_to"),
+        ("round_even", "round_even"),
+        ("absdiff", "absdiff"),
+        ("sqdiff", "sqdiff"),
+        ("sign", "sign"),
+        ("<", "lt"),
+        (">", "gt"),
+        ("<=", "le"),
+        (">=", "ge"),
+        ("==", "eq"),
+        ("!=", "ne"),
+        ("&&", "and"),
+        ("||", "or"),
+        ("if", "if"),
+        ("wrap_range", "wrap_range"),
+        ("recip", "recip"),
+        ("%", "rem"),
+    ];
+    assert_eq!(ids, reference);
+
+    fn test(text: &str, reference: &[&str]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.used_operators()?, reference);
+        let mut owned = OwnedFlatEx::from_flatex(flatex.clone());
+        assert_eq!(owned.used_operators()?, reference);
+        owned.reduce_memory();
+        assert_eq!(owned.used_operators()?, reference);
+        let mut flatex = flatex;
+        flatex.reduce_memory();
+        assert_eq!(flatex.used_operators()?, reference);
+        Ok(())
+    }
+    test("x", &[])?;
+    test("PI*2", &[])?;
+    test("x^2*y", &["mul", "pow"])?;
+    test("-log(x)+π*x", &["add", "ln", "mul", "sub"])?;
+    test("sin(cos(x)/(1-y))", &["cos", "div", "sin", "sub"])?;
+
+    let deri = FlatEx::<f64>::from_str("x^3")?.partial(0)?;
+    let mut ops = deri.used_operators()?;
+    ops.retain(|op| op == "mul" || op == "pow");
+    assert_eq!(ops, vec!["mul", "pow"]);
+    let mut deri_reduced = deri;
+    deri_reduced.reduce_memory();
+    assert!(deri_reduced.used_operators().is_err());
+
+    ops_factory!(
+        IdOpsFactory,
+        f64,
+        Operator::make_bin(
+            "**",
+            BinOp {
+                apply: |a: f64, b| a.powf(b),
+                prio: 2,
+                is_commutative: false,
+            }
+        )
+        .with_id("pow"),
+        Operator::make_unary("ln", |a: f64| a.ln())
+    );
+    let ops = IdOpsFactory::make();
+    assert_eq!(ops[0].id(), "pow");
+    assert_eq!(ops[1].id(), "ln");
+    let flatex = FlatEx::<f64, IdOpsFactory>::from_str("ln(x)**2")?;
+    assert_eq!(flatex.used_operators()?, vec!["ln", "pow"]);
+    Ok(())
+}
+
+#[test]
+fn test_truth_table() -> ExResult<()> {
+    ops_factory!(
+        BooleanOpsFactory,
+        bool,
+        Operator::make_bin(
+            "&&",
+            BinOp {
+                apply: |a, b| a && b,
+                prio: 1,
+                is_commutative: true,
+            }
+        ),
+        Operator::make_bin(
+            "||",
+            BinOp {
+                apply: |a, b| a || b,
+                prio: 0,
+                is_commutative: true,
+            }
+        ),
+        Operator::make_unary("!", |a| !a)
+    );
+    literal_matcher_from_pattern!(BooleanMatcher, "^(true|false)");
+    type FlatExBool<'a> = FlatEx<'a, bool, BooleanOpsFactory, BooleanMatcher>;
+
+    let expr = FlatExBool::from_str("(a || b) && !(a && b)")?;
+    let table = expr.truth_table()?;
+    assert_eq!(
+        table,
+        vec![
+            (vec![false, false], false),
+            (vec![false, true], true),
+            (vec![true, false], true),
+            (vec![true, true], false),
+        ]
+    );
+    let owned = OwnedFlatEx::from_flatex(expr);
+    assert_eq!(owned.truth_table()?, table);
+
+    // De Morgan
+    let table = FlatExBool::from_str("!(x || y || z) || (!x && !y && !z)")?.truth_table()?;
+    assert_eq!(table.len(), 8);
+    assert!(table[0].1);
+    assert!(table.iter().skip(1).all(|(_, res)| !res));
+    assert_eq!(table[4].0, vec![true, false, false]);
+
+    assert_eq!(
+        FlatExBool::from_str("true && !false")?.truth_table()?,
+        vec![(vec![], true)]
+    );
+
+    let many_vars = (0..21).map(|i| format!("x{}", i)).collect::<Vec<_>>();
+    let text = many_vars.join("||");
+    assert!(FlatExBool::from_str(&text)?.truth_table().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_function_style_ops() -> ExResult<()> {
+    ops_factory!(
+        FnStyleOpsFactory,
+        f64,
+        Operator::make_bin(
+            "atan2",
+            BinOp {
+                apply: |y: f64, x| y.atan2(x),
+                prio: 0,
+                is_commutative: false,
+            }
+        )
+        .with_function_style(),
+        Operator::make_bin_unary(
+            "log",
+            BinOp {
+                apply: |a: f64, b| a.log(b),
+                prio: 0,
+                is_commutative: false,
+            },
+            |a| a.ln()
+        )
+        .with_function_style(),
+        Operator::make_bin(
+            "*",
+            BinOp {
+                apply: |a, b| a * b,
+                prio: 2,
+                is_commutative: true,
+            }
+        ),
+        Operator::make_bin_unary(
+            "-",
+            BinOp {
+                apply: |a, b| a - b,
+                prio: 1,
+                is_commutative: false,
+            },
+            |a: f64| -a
+        ),
+        Operator::make_bin_unary(
+            "+",
+            BinOp {
+                apply: |a, b| a + b,
+                prio: 0,
+                is_commutative: true,
+            },
+            |a| a
+        )
+    );
+    fn test(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        let flatex = FlatEx::<f64, FnStyleOpsFactory>::from_str(text)?;
+        assert_eq!(flatex.unparse()?, text);
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64, FnStyleOpsFactory>::from_str(text)?;
+        assert_eq!(owned_flatex.unparse()?, text);
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference);
+        Ok(())
+    }
+    test("atan2(y,x)", &[1.5, 0.5], 0.5f64.atan2(1.5))?;
+    test("atan2(y, x)", &[-1.0, 2.0], 2f64.atan2(-1.0))?;
+    test(
+        "2*atan2(y, x)+1",
+        &[1.5, 0.5],
+        2.0 * 0.5f64.atan2(1.5) + 1.0,
+    )?;
+    test("-atan2(y, x)*2", &[1.5, 0.5], -(0.5f64.atan2(1.5)) * 2.0)?;
+    test(
+        "atan2(atan2(a, b), (b-a)*c)",
+        &[0.5, 1.5, -1.0],
+        0.5f64.atan2(1.5).atan2(-1.0),
+    )?;
+    test("atan2(1, -1)", &[], 1f64.atan2(-1.0))?;
+    test("log(x)", &[2.0], 2f64.ln())?;
+    test("log(x, 2)", &[8.0], 3.0)?;
+    test(
+        "log(log(x), 2)*log(x, 10)",
+        &[100.0],
+        100f64.ln().log2() * 2.0,
+    )?;
+    test(
+        "log(x-1, atan2(1, 1)*4)",
+        &[3.0],
+        2f64.log(4.0 * 1f64.atan2(1.0)),
+    )?;
+
+    fn test_err(text: &str) {
+        assert!(FlatEx::<f64, FnStyleOpsFactory>::from_str(text).is_err());
+        assert!(OwnedFlatEx::<f64, FnStyleOpsFactory>::from_str(text).is_err());
+    }
+    test_err("atan2(y)");
+    test_err("atan2(y, x, z)");
+    test_err("atan2(, x)");
+    test_err("atan2(y, )");
+    test_err("atan2 y");
+    test_err("y atan2 x");
+    test_err("y, x");
+    test_err("log((x, 2))");
+    test_err("log(x, 2),");
+    test_err("atan2(y, x");
+
+    // variables can start with the name of a function-style operator
+    let flatex = FlatEx::<f64, FnStyleOpsFactory>::from_str("atan2x*logy")?;
+    assert_eq!(flatex.n_vars(), 2);
+    assert_float_eq_f64(flatex.eval(&[2.0, 3.0])?, 6.0);
+
+    let flatex = FlatEx::<f64>::from_str("x, y");
+    assert!(flatex.is_err());
+
+    let flatex = FlatEx::<f64, FnStyleOpsFactory>::from_str("atan2(y, x)")?;
+    assert_float_eq_f64(flatex.partial(0)?.eval(&[1.0, 1.0])?, -0.5);
+
+    // there is no derivative of binary log in the default partial derivative operators
+    let flatex = FlatEx::<f64, FnStyleOpsFactory>::from_str("log(x, 2)")?;
+    assert!(flatex.partial(0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_gradient() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let gradient = flatex.clone().gradient()?;
+        assert_eq!(gradient.len(), flatex.n_vars());
+        for (var_idx, deri) in gradient.iter().enumerate() {
+            let reference = flatex.clone().partial(var_idx)?;
+            assert_float_eq_f64(deri.eval(vars)?, reference.eval(vars)?);
+        }
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        let gradient = owned_flatex.clone().gradient()?;
+        assert_eq!(gradient.len(), owned_flatex.n_vars());
+        for (var_idx, deri) in gradient.iter().enumerate() {
+            let reference = owned_flatex.clone().partial(var_idx)?;
+            assert_float_eq_f64(deri.eval(vars)?, reference.eval(vars)?);
+        }
+        Ok(())
+    }
+    test("x^2 + y^2", &[3.0, -4.0])?;
+    test("sin(x)*exp(y)+z", &[0.5, 1.5, -2.0])?;
+    test("2", &[])?;
+
+    let gradient = FlatEx::<f64>::from_str("x^2 + y^2")?.gradient()?;
+    let gradient_values = gradient
+        .iter()
+        .map(|deri| deri.eval(&[3.0, -4.0]))
+        .collect::<ExResult<Vec<_>>>()?;
+    assert_eq!(gradient_values.len(), 2);
+    assert_float_eq_f64(gradient_values[0], 6.0);
+    assert_float_eq_f64(gradient_values[1], -8.0);
+
+    let hessian_row = gradient[0].clone().gradient()?;
+    assert_float_eq_f64(hessian_row[0].eval(&[3.0, -4.0])?, 2.0);
+    assert_float_eq_f64(hessian_row[1].eval(&[3.0, -4.0])?, 0.0);
+
+    // derivatives have no text and cannot be re-created after reducing memory
+    let mut deri = gradient[0].clone();
+    deri.reduce_memory();
+    let partial_err = deri.clone().partial(0).unwrap_err();
+    let gradient_err = deri.gradient().unwrap_err();
+    assert_eq!(partial_err.msg, gradient_err.msg);
+    assert!(gradient_err.msg.contains("reduce_memory"));
+    let mut owned_deri = OwnedFlatEx::from_flatex(gradient[1].clone());
+    owned_deri.reduce_memory();
+    let partial_err = owned_deri.clone().partial(0).unwrap_err();
+    let gradient_err = owned_deri.gradient().unwrap_err();
+    assert_eq!(partial_err.msg, gradient_err.msg);
+    assert!(gradient_err.msg.contains("reduce_memory"));
+
+    // with a text, the deep expression is re-created
+    let mut flatex = FlatEx::<f64>::from_str("x*y")?;
+    flatex.reduce_memory();
+    let gradient = flatex.gradient()?;
+    assert_float_eq_f64(gradient[0].eval(&[2.0, 5.0])?, 5.0);
+    assert_float_eq_f64(gradient[1].eval(&[2.0, 5.0])?, 2.0);
+    Ok(())
+}
+
+#[test]
+fn test_sensitivity() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64], reference: &[f64]) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let sensitivity = FlatEx::<f64>::from_str(sut)?.sensitivity(vars)?;
+        let owned_sensitivity = OwnedFlatEx::<f64>::from_str(sut)?.sensitivity(vars)?;
+        assert_eq!(sensitivity.len(), reference.len());
+        for (i, r) in reference.iter().enumerate() {
+            assert_float_eq_f64(sensitivity[i], *r);
+            assert_float_eq_f64(owned_sensitivity[i], *r);
+        }
+        Ok(())
+    }
+    test("x^2*y", &[3.0, -4.0], &[2.0, 1.0])?;
+    test("x+y", &[1.0, 3.0], &[0.25, 0.75])?;
+    test("exp(x)", &[2.0], &[2.0])?;
+    test("x*y^3/z", &[1.5, 2.0, 0.5], &[1.0, 3.0, -1.0])?;
+    test("5", &[], &[])?;
+    assert!(FlatEx::<f64>::from_str("x-y")?
+        .sensitivity(&[1.0, 1.0])
+        .is_err());
+    assert!(FlatEx::<f64>::from_str("x-y")?.sensitivity(&[1.0]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_hessian() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let hessian = flatex.clone().hessian()?;
+        let owned_hessian = OwnedFlatEx::<f64>::from_str(sut)?.hessian()?;
+        assert_eq!(hessian.len(), flatex.n_vars());
+        assert_eq!(owned_hessian.len(), flatex.n_vars());
+        for i in 0..flatex.n_vars() {
+            assert_eq!(hessian[i].len(), flatex.n_vars());
+            for j in 0..flatex.n_vars() {
+                let reference = flatex.clone().partial(i)?.partial(j)?.eval(vars)?;
+                assert_float_eq_f64(hessian[i][j].eval(vars)?, reference);
+                assert_float_eq_f64(owned_hessian[i][j].eval(vars)?, reference);
+            }
+        }
+        Ok(())
+    }
+    test("x^2*y", &[3.0, -4.0])?;
+    test("sin(x)*exp(y)+z*x", &[0.5, 1.5, -2.0])?;
+    test("2", &[])?;
+
+    let hessian = parse::<f64>("x^2 + x*y + y^2")?.hessian()?;
+    for (row, reference) in hessian.iter().zip([[2.0, 1.0], [1.0, 2.0]]) {
+        for (h, r) in row.iter().zip(reference) {
+            assert_float_eq_f64(h.eval(&[0.3, -7.0])?, r);
+        }
+    }
+
+    let hessian = FlatEx::<f64>::from_str("x^2*y")?.hessian()?;
+    assert_eq!(hessian[0][1].unparse()?, "{x}*2.0");
+    assert_eq!(hessian[1][0].unparse()?, "{x}*2.0");
+
+    let mut flatex = FlatEx::<f64>::from_str("x^2*y")?.partial(0)?;
+    flatex.reduce_memory();
+    let partial_err = flatex.clone().partial(0).unwrap_err();
+    assert_eq!(flatex.hessian().unwrap_err().msg, partial_err.msg);
+    Ok(())
+}
+
+#[test]
+fn test_partial_n() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str("sin(x)")?;
+    let d4 = flatex.clone().partial_n(0, 4)?;
+    let owned_d4 = OwnedFlatEx::<f64>::from_str("sin(x)")?.partial_n(0, 4)?;
+    for i in 0..100 {
+        let x = -10.0 + 0.2 * i as f64;
+        assert_float_eq_f64(d4.eval(&[x])?, x.sin());
+        assert_float_eq_f64(owned_d4.eval(&[x])?, x.sin());
+    }
+
+    fn test(sut: &str, var_idx: usize, order: u32, vars: &[f64]) -> ExResult<()> {
+        println!("testing d^{}/d{}^{} of {}...", order, var_idx, order, sut);
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let reference = (0..order).try_fold(flatex.clone(), |d, _| d.partial(var_idx))?;
+        let d = flatex.partial_n(var_idx, order)?;
+        assert_eq!(d.n_vars(), reference.n_vars());
+        assert_float_eq_f64(d.eval(vars)?, reference.eval(vars)?);
+        Ok(())
+    }
+    test("x^5*y", 0, 3, &[1.5, -2.0])?;
+    test("x^5*y", 1, 2, &[1.5, -2.0])?;
+    test("exp(2*x)*cos(y)", 1, 3, &[0.3, 0.7])?;
+    test("log(x)", 0, 1, &[2.0])?;
+
+    let flatex = FlatEx::<f64>::from_str("x^2+y")?;
+    assert_eq!(flatex.clone().partial_n(1, 0)?, flatex);
+    assert!(flatex.clone().partial_n(2, 0).is_err());
+    assert!(flatex.partial_n(2, 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_parse_batch() -> ExResult<()> {
+    let is_invalid = |i: usize| i % 7 == 3 || i % 11 == 5;
+    let texts = (0..2000)
+        .map(|i| {
+            if is_invalid(i) {
+                format!("x*{}+", i)
+            } else {
+                format!("x*{}+sin(y)", i)
+            }
+        })
+        .collect::<Vec<_>>();
+    let texts = texts.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+    let exprs = exmex::parse_batch::<f64>(&texts);
+    assert_eq!(exprs.len(), texts.len());
+    for (i, expr) in exprs.iter().enumerate() {
+        match expr {
+            Ok(expr) => {
+                assert!(!is_invalid(i));
+                assert_eq!(expr.unparse()?, texts[i]);
+                assert_float_eq_f64(expr.eval(&[1.0, 0.0])?, i as f64);
+            }
+            Err(e) => {
+                assert!(is_invalid(i));
+                assert_eq!(e, &FlatEx::<f64>::from_str(texts[i]).unwrap_err());
+            }
+        }
+    }
+    assert!(exmex::parse_batch::<f64>(&[]).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_const() -> ExResult<()> {
+    fn test(sut: &str, reference: Option<f64>) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        assert_eq!(flatex.is_const(), reference.is_some());
+        assert_eq!(owned_flatex.is_const(), reference.is_some());
+        match reference {
+            Some(reference) => {
+                assert_float_eq_f64(flatex.const_value()?, reference);
+                assert_float_eq_f64(owned_flatex.const_value()?, reference);
+                assert_float_eq_f64(flatex.eval(&[])?, reference);
+            }
+            None => {
+                assert!(flatex.const_value().is_err());
+                assert!(owned_flatex.const_value().is_err());
+            }
+        }
+        Ok(())
+    }
+    test("2.0", Some(2.0))?;
+    test("sin(PI/2)*3+1", Some(4.0))?;
+    test("x", None)?;
+    test("x*0", None)?;
+    test("2*y+x", None)?;
+
+    // derivatives simplified to a number are constant although they keep the variables
+    let d = FlatEx::<f64>::from_str("3*x+y")?.partial(0)?;
+    assert_eq!(d.n_vars(), 2);
+    assert!(d.is_const());
+    assert_float_eq_f64(d.const_value()?, 3.0);
+    assert_float_eq_f64(d.eval(&[0.0, 0.0])?, 3.0);
+    let d = OwnedFlatEx::<f64>::from_str("x^3")?.partial_n(0, 3)?;
+    assert!(d.is_const());
+    assert_float_eq_f64(d.const_value()?, 6.0);
+    let d = FlatEx::<f64>::from_str("x^3*y")?.partial(0)?;
+    assert!(!d.is_const());
+    let err = d.const_value().unwrap_err();
+    assert!(err.msg.contains("[\"x\", \"y\"]"));
+    let d = FlatEx::<f64>::from_str("x^3+y")?.partial(1)?;
+    assert!(d.is_const());
+    // uncompiled expressions without variables are constant, too
+    let flatex = FlatEx::<f64>::from_str_wo_compile("1+2*3")?;
+    assert!(flatex.is_const());
+    assert_float_eq_f64(flatex.const_value()?, 7.0);
+    // unless unary operators are left to apply
+    let mut flatex = FlatEx::<f64>::from_str_wo_compile("-sin(0)")?;
+    assert!(!flatex.is_const());
+    flatex.compile();
+    assert!(flatex.is_const());
+    Ok(())
+}
+
+#[test]
+fn test_is_constant() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str("2.5")?;
+    assert!(flatex.is_constant());
+    assert_float_eq_f64(flatex.eval_if_constant().unwrap()?, 2.5);
+    let owned_flatex = OwnedFlatEx::<f64>::from_str("sin(PI/2)*3+1")?;
+    assert!(owned_flatex.is_constant());
+    assert_float_eq_f64(owned_flatex.eval_if_constant().unwrap()?, 4.0);
+    let flatex = FlatEx::<f64>::from_str("x+1")?;
+    assert!(!flatex.is_constant());
+    assert!(flatex.eval_if_constant().is_none());
+    // derivatives simplified to a number are constant
+    let d = FlatEx::<f64>::from_str("3*x")?.partial(0)?;
+    assert!(d.is_const());
+    assert!(d.is_constant());
+    assert_float_eq_f64(d.eval_if_constant().unwrap()?, 3.0);
+    // both predicates agree
+    for text in ["1", "x", "if(1, 2, 3)", "if(x, 2, 3)", "x*0"] {
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.is_constant(), flatex.is_const());
+        assert_eq!(flatex.eval_if_constant().is_some(), flatex.is_const());
+    }
+    let flatex = FlatEx::<f64>::from_str_wo_compile("1+2*3")?;
+    assert!(flatex.is_constant());
+    assert_float_eq_f64(flatex.eval_if_constant().unwrap()?, 7.0);
+    Ok(())
+}
+
+#[test]
+fn test_directional() -> ExResult<()> {
+    fn test(sut: &str, direction: &[f64], vars: &[f64]) -> ExResult<()> {
+        println!("testing {} along {:?}...", sut, direction);
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let h = 1e-6;
+        let shifted = |sign: f64| {
+            vars.iter()
+                .zip(direction.iter())
+                .map(|(v, d)| v + sign * h * d)
+                .collect::<Vec<_>>()
+        };
+        let reference = (flatex.eval(&shifted(1.0))? - flatex.eval(&shifted(-1.0))?) / (2.0 * h);
+        let dir_deriv = flatex.directional(direction)?;
+        assert_float_eq(dir_deriv.eval(vars)?, reference, 1e-6, 1e-6, sut);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        let dir_deriv = owned_flatex.directional(direction)?;
+        assert_float_eq(dir_deriv.eval(vars)?, reference, 1e-6, 1e-6, sut);
+        Ok(())
+    }
+    let mut rng = thread_rng();
+    for _ in 0..10 {
+        let direction = (0..3)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect::<Vec<f64>>();
+        let vars = (0..3)
+            .map(|_| rng.gen_range(-3.0..3.0))
+            .collect::<Vec<f64>>();
+        test("z*sin(x)+cos(y)^2", &direction, &vars)?;
+    }
+    test("z*sin(x)+cos(y)^2", &[0.0, 1.0, 0.0], &[0.3, 0.4, 0.5])?;
+    test("x^2+y", &[0.5, 2.0], &[1.5, 3.0])?;
+
+    // zero components drop their terms
+    let flatex = FlatEx::<f64>::from_str("z*sin(x)+cos(y)^2")?;
+    let dir_deriv = flatex.clone().directional(&[0.0, 0.0, 2.0])?;
+    assert_eq!(dir_deriv.unparse()?, "2.0*sin({x})");
+    let dir_deriv = flatex.clone().directional(&[0.0, 0.0, 0.0])?;
+    assert_eq!(dir_deriv.unparse()?, "0.0");
+    assert_eq!(dir_deriv.n_vars(), 3);
+    assert!(flatex.directional(&[1.0, 1.0]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_partial_eval() -> ExResult<()> {
+    fn test(sut: &str, bindings: &[(&str, f64)], vars: &[f64], reference: f64) -> ExResult<()> {
+        let bindings = bindings
+            .iter()
+            .copied()
+            .collect::<std::collections::HashMap<_, _>>();
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let n_vars = flatex.n_vars();
+        let specialized = flatex.partial_eval(&bindings)?;
+        assert_eq!(specialized.n_vars(), n_vars - bindings.len());
+        assert_float_eq_f64(specialized.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        let specialized = owned_flatex.partial_eval(&bindings)?;
+        assert_eq!(specialized.n_vars(), n_vars - bindings.len());
+        assert_float_eq_f64(specialized.eval(vars)?, reference);
+        Ok(())
+    }
+    test("a*x + b", &[("a", 2.0), ("b", 3.0)], &[1.5], 6.0)?;
+    test("a*x + b", &[("x", 2.0)], &[1.5, 3.0], 6.0)?;
+    test("a*x + b", &[], &[2.0, 3.0, 1.5], 6.0)?;
+    test("a*x + b", &[("a", 2.0), ("b", 3.0), ("x", 0.5)], &[], 4.0)?;
+    test(
+        "sin(a)*x^b + cos(-{c d}*y)",
+        &[("a", 0.5), ("b", 2.0), ("c d", 3.0)],
+        &[1.5, 0.1],
+        0.5f64.sin() * 1.5f64.powi(2) + (-0.3f64).cos(),
+    )?;
+
+    let bindings = [("a", 2.0), ("b", 3.0)]
+        .iter()
+        .copied()
+        .collect::<std::collections::HashMap<_, _>>();
+    let specialized = FlatEx::<f64>::from_str("a*x + b")?.partial_eval(&bindings)?;
+    assert_eq!(specialized.unparse()?, "2.0*{x}+3.0");
+    let specialized = FlatEx::<f64>::from_str("(a+b)*x")?.partial_eval(&bindings)?;
+    assert_eq!(specialized.unparse()?, "5.0*{x}");
+    let deri = specialized.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[7.0])?, 5.0);
+
+    let mut flatex = FlatEx::<f64>::from_str("a*x + c")?;
+    assert!(flatex.clone().partial_eval(&bindings).is_err());
+    flatex.reduce_memory();
+    let bindings = [("a", 2.0)]
+        .iter()
+        .copied()
+        .collect::<std::collections::HashMap<_, _>>();
+    let specialized = flatex.partial_eval(&bindings)?;
+    assert_float_eq_f64(specialized.eval(&[1.0, 3.0])?, 7.0);
+    Ok(())
+}
+
+#[test]
+fn test_substitute() -> ExResult<()> {
+    fn test(
+        sut: &str,
+        var_idx: usize,
+        replacement: &str,
+        var_names: &[&str],
+        vars: &[f64],
+        reference: f64,
+    ) -> ExResult<()> {
+        println!(
+            "testing {} with {} for var {}...",
+            sut, replacement, var_idx
+        );
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let replacement_flatex = FlatEx::<f64>::from_str(replacement)?;
+        // the substituted variable might re-appear in the replacement
+        let remaining = flatex
+            .var_names()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| *i != var_idx)
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        let n_overlap = replacement_flatex
+            .var_names()
+            .iter()
+            .filter(|name| remaining.contains(name))
+            .count();
+        let n_vars = flatex.n_vars() - 1 + replacement_flatex.n_vars() - n_overlap;
+        let substituted = flatex.substitute(var_idx, replacement_flatex)?;
+        assert_eq!(substituted.var_names(), var_names);
+        assert_eq!(substituted.n_vars(), n_vars);
+        assert_float_eq_f64(substituted.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        let substituted =
+            owned_flatex.substitute(var_idx, OwnedFlatEx::<f64>::from_str(replacement)?)?;
+        assert_eq!(substituted.var_names(), var_names);
+        assert_float_eq_f64(substituted.eval(vars)?, reference);
+        println!("...ok.");
+        Ok(())
+    }
+    test(
+        "x + y",
+        1,
+        "sin(z)",
+        &["x", "z"],
+        &[1.0, 0.5],
+        1.0 + 0.5f64.sin(),
+    )?;
+    test("x + y", 0, "2*y", &["y"], &[1.5], 4.5)?;
+    test("x*y", 0, "x+1", &["x", "y"], &[2.0, 3.0], 9.0)?;
+    test("x^2", 0, "a+1", &["a"], &[2.0], 9.0)?;
+    test(
+        "sin(x)^2 + x*z",
+        0,
+        "a-b",
+        &["a", "b", "z"],
+        &[1.0, 0.25, 2.0],
+        0.75f64.sin().powi(2) + 1.5,
+    )?;
+    // unary operators have a higher priority than binary ones
+    test("-x^2", 0, "3", &[], &[], 9.0)?;
+    test(
+        "exp(x)/(1+x)",
+        0,
+        "log(y)",
+        &["y"],
+        &[2.0],
+        2.0 / (1.0 + 2f64.ln()),
+    )?;
+
+    let substituted =
+        FlatEx::<f64>::from_str("x + y")?.substitute(1, FlatEx::<f64>::from_str("sin(z)")?)?;
+    let reference = FlatEx::<f64>::from_str("x + sin(z)")?;
+    for vars in [[0.0, 0.0], [1.0, -2.0], [-0.5, 3.0]] {
+        assert_float_eq_f64(substituted.eval(&vars)?, reference.eval(&vars)?);
+    }
+    let deri = substituted.partial(1)?;
+    assert_float_eq_f64(deri.eval(&[1.0, 0.5])?, 0.5f64.cos());
+
+    let flatex = FlatEx::<f64>::from_str("x + y")?;
+    assert!(flatex.substitute(2, FlatEx::<f64>::from_str("z")?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_compose() -> ExResult<()> {
+    let f = FlatEx::<f64>::from_str("sin(u)")?;
+    let g = FlatEx::<f64>::from_str("x+y")?;
+    let f_of_g = f.compose(g)?;
+    assert_eq!(f_of_g.unparse()?, "sin({x}+{y})");
+    assert_eq!(f_of_g.var_names(), ["x", "y"]);
+    assert_float_eq_f64(f_of_g.eval(&[0.5, 0.25])?, 0.75f64.sin());
+
+    let f = OwnedFlatEx::<f64>::from_str("u^2+u")?;
+    let g = OwnedFlatEx::<f64>::from_str("z*u")?;
+    let f_of_g = f.compose(g)?;
+    assert_eq!(f_of_g.var_names(), ["u", "z"]);
+    assert_float_eq_f64(f_of_g.eval(&[2.0, 1.5])?, 12.0);
+
+    let f = FlatEx::<f64>::from_str("x*y")?;
+    assert!(f.compose(FlatEx::<f64>::from_str("z")?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_eval_named() -> ExResult<()> {
+    fn test(sut: &str, vars: &[(&str, f64)], reference: f64) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_float_eq_f64(flatex.eval_named(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        assert_float_eq_f64(owned_flatex.eval_named(vars)?, reference);
+        let mut reduced = owned_flatex;
+        reduced.reduce_memory();
+        assert_float_eq_f64(reduced.eval_named(vars)?, reference);
+        Ok(())
+    }
+    test("a*x + b", &[("x", 1.5), ("a", 2.0), ("b", 3.0)], 6.0)?;
+    test("a*x + b", &[("b", 3.0), ("x", 1.5), ("a", 2.0)], 6.0)?;
+    test("2*x+1", &[("x", 1.5)], 4.0)?;
+    test("sin(1.5)", &[], 1.5f64.sin())?;
+    test(
+        "{x y}*2 + {β}^2 + z",
+        &[("z", 1.0), ("β", 3.0), ("x y", 0.5)],
+        11.0,
+    )?;
+
+    let flatex = FlatEx::<f64>::from_str("{x y}*2 + z")?;
+    let vars = [("x y", 0.5), ("z", 1.0)];
+    let deri = flatex.clone().partial(1)?;
+    assert_float_eq_f64(deri.eval_named(&vars)?, 1.0);
+    let unparsed = flatex.unparse()?;
+    let reparsed = FlatEx::<f64>::from_str(unparsed.as_str())?;
+    assert_float_eq_f64(reparsed.eval_named(&vars)?, flatex.eval_named(&vars)?);
+    assert_float_eq_f64(flatex.eval_named(&vars)?, flatex.eval(&[0.5, 1.0])?);
+
+    let err = flatex.eval_named(&[("x", 0.5), ("z", 1.0)]).unwrap_err();
+    assert!(err.msg.contains("'x'"));
+    assert!(err.msg.contains("\"x y\""));
+    let err = flatex.eval_named(&[("z", 1.0)]).unwrap_err();
+    assert!(err.msg.contains("'x y'"));
+    Ok(())
+}
+
+#[test]
+fn test_var_names() -> ExResult<()> {
+    fn test(sut: &str, reference: &[&str]) -> ExResult<()> {
+        let mut flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_eq!(flatex.var_names(), reference);
+        let mut owned_flatex = OwnedFlatEx::from_flatex(flatex.clone());
+        assert_eq!(owned_flatex.var_names(), reference);
+        for (i, name) in reference.iter().enumerate() {
+            assert_eq!(flatex.var_idx(name), Some(i));
+            assert_eq!(owned_flatex.var_idx(name), Some(i));
+        }
+        assert_eq!(flatex.var_idx("not a var"), None);
+        if !reference.is_empty() {
+            assert_eq!(flatex.clone().partial(0)?.var_names(), reference);
+        }
+        flatex.reduce_memory();
+        assert_eq!(flatex.var_names(), reference);
+        owned_flatex.reduce_memory();
+        assert_eq!(owned_flatex.var_names(), reference);
+        Ok(())
+    }
+    test("1+2", &[])?;
+    test("z + x + y", &["x", "y", "z"])?;
+    test("{x y}*x + sin({β})", &["x", "x y", "β"])?;
+    test("αβ*γ^2 - αβ", &["αβ", "γ"])?;
+    test("{Δt}*{5 + c}", &["5 + c", "Δt"])?;
+    Ok(())
+}
+
+#[test]
+fn test_eval_compensated() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_eq!(flatex.eval_compensated(vars)?, reference);
+        let flatex = OwnedFlatEx::from_flatex(flatex);
+        assert_eq!(flatex.eval_compensated(vars)?, reference);
+        Ok(())
+    }
+    // the naive evaluation loses the small summands
+    let vars = [1e16, 1.0, -1e16, 1.0];
+    assert_eq!(exmex::parse::<f64>("a+b+c+d")?.eval(&vars)?, 1.0);
+    test("a+b+c+d", &vars, 2.0)?;
+    test("(a+b+c+d)*2", &vars, 4.0)?;
+    test("sqrt(a+b+c+d+2)", &vars, 2.0)?;
+    test("a+(b+c)+d", &vars, 2.0)?;
+
+    let n = 1000;
+    let sut = vec!["x"; n].join("+") + "+y";
+    let flatex = FlatEx::<f64>::from_str(&sut)?;
+    let naive = flatex.eval(&[0.1, -100.0])?;
+    let compensated = flatex.eval_compensated(&[0.1, -100.0])?;
+    assert!(compensated.abs() < naive.abs());
+    assert!(compensated.abs() < 1e-12);
+
+    // results without additions coincide with the naive evaluation
+    let vars = [0.3, 1.7];
+    for sut in ["x*y-sin(x)/y", "x^y", "-x*y", "2*x-y"] {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_eq!(flatex.eval_compensated(&vars)?, flatex.eval(&vars)?);
+    }
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert!(flatex.eval_compensated(&[1.0]).is_err());
+    assert_eq!(
+        flatex.eval_compensated(&[f64::INFINITY, 1.0])?,
+        f64::INFINITY
+    );
+
+    // additions are recognized by the id of the operator and not by its representation
+    ops_factory!(
+        PlusIsMaxFactory,
+        f64,
+        Operator::make_bin(
+            "+",
+            BinOp {
+                apply: f64::max,
+                prio: 0,
+                is_commutative: true,
+            }
+        ),
+        Operator::make_bin(
+            "⊕",
+            BinOp {
+                apply: |a, b| a + b,
+                prio: 0,
+                is_commutative: true,
+            }
+        )
+        .with_id("add")
+    );
+    let vars = [1e16, 1.0, -1e16, 1.0];
+    let max = FlatEx::<f64, PlusIsMaxFactory>::from_str("a+b+c+d")?;
+    assert_eq!(max.eval_compensated(&vars)?, 1e16);
+    let sum = FlatEx::<f64, PlusIsMaxFactory>::from_str("a⊕b⊕c⊕d")?;
+    assert_eq!(sum.eval(&vars)?, 1.0);
+    assert_eq!(sum.eval_compensated(&vars)?, 2.0);
+    let bytecode = max.dump_bytecode();
+    let loaded = FlatEx::<f64, PlusIsMaxFactory>::load_bytecode(&bytecode)?;
+    assert_eq!(loaded.eval_compensated(&vars)?, 1e16);
+    let bytecode = sum.dump_bytecode();
+    let loaded = FlatEx::<f64, PlusIsMaxFactory>::load_bytecode(&bytecode)?;
+    assert_eq!(loaded.eval_compensated(&vars)?, 2.0);
+    let renamed = max.remap_vars(&[("a", "e")])?;
+    assert_eq!(renamed.eval_compensated(&[1.0, -1e16, 1.0, 1e16])?, 1e16);
+    let renamed = sum.remap_vars(&[("a", "e")])?;
+    assert_eq!(renamed.eval_compensated(&[1.0, -1e16, 1.0, 1e16])?, 2.0);
+    Ok(())
+}
+
+#[test]
+fn test_eval_checked() -> ExResult<()> {
+    fn test_err(sut: &str, vars: &[f64], msg_part: &str) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let err = flatex.eval_checked(vars).unwrap_err();
+        assert!(err.msg.contains(msg_part), "{}", err.msg);
+        let flatex = OwnedFlatEx::from_flatex(flatex);
+        let err = flatex.eval_checked(vars).unwrap_err();
+        assert!(err.msg.contains(msg_part), "{}", err.msg);
+        Ok(())
+    }
+    fn test_ok(sut: &str, vars: &[f64]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_eq!(flatex.eval_checked(vars)?, flatex.eval(vars)?);
+        let flatex = OwnedFlatEx::from_flatex(flatex);
+        assert_eq!(flatex.eval_checked(vars)?, flatex.eval(vars)?);
+        Ok(())
+    }
+    // the plain evaluation keeps floating point semantics
+    assert!(FlatEx::<f64>::from_str("0/0")?.eval(&[])?.is_nan());
+    test_err("0/0", &[], "operator '/' in '(0.0/0.0)'")?;
+    test_err("1+0/0", &[], "operator '/'")?;
+    test_err("x/y", &[0.0, 0.0], "operator '/' in '({x}/{y})'")?;
+    test_err("2*x/y+1", &[1.0, 0.0], "results in inf")?;
+    test_err("sin(log(x))", &[0.0], "operator 'log' in 'log({x})'")?;
+    test_err("sqrt(x-1)", &[0.0], "operator 'sqrt' in 'sqrt(({x}-1.0))'")?;
+    test_err("atan2(x, 0/0)", &[1.0], "operator '/'")?;
+    test_err("x+1", &[f64::NAN], "variable 'x'")?;
+    test_err("x+y", &[1.0], "passed slice has 1 elements")?;
+    test_ok("x/y", &[1.0, 2.0])?;
+    test_ok("sin(log(x))*y^2", &[0.5, 3.0])?;
+    test_ok("atan2(x, y)", &[0.0, 0.0])?;
+    Ok(())
+}
+
+#[test]
+fn test_bytecode() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64]) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let bytecode = flatex.dump_bytecode();
+        println!("{}\n{}", sut, bytecode);
+        assert!(!bytecode.contains('?'));
+        let loaded = FlatEx::<f64>::load_bytecode(&bytecode)?;
+        let reference = flatex.eval(vars)?;
+        let res = loaded.eval(vars)?;
+        assert!(res == reference || (res.is_nan() && reference.is_nan()));
+        assert_eq!(loaded.var_names(), flatex.var_names());
+        assert_eq!(loaded.dump_bytecode(), bytecode);
+        let owned = OwnedFlatEx::from_flatex(flatex);
+        assert_eq!(owned.dump_bytecode(), bytecode);
+        let loaded = OwnedFlatEx::<f64>::load_bytecode(&bytecode)?;
+        let res = loaded.eval(vars)?;
+        assert!(res == reference || (res.is_nan() && reference.is_nan()));
+        Ok(())
+    }
+    test("2.5", &[])?;
+    test("-x", &[1.5])?;
+    test("x+y*z", &[1.5, 2.0, -3.0])?;
+    test("(x+y)*z", &[1.5, 2.0, -3.0])?;
+    test("-sin(cos(x))^2/-(y-z)", &[1.5, 2.0, -3.0])?;
+    test("x^y^z", &[1.5, 2.0, 0.5])?;
+    test("atan2(y, x)*abs(-x)", &[1.5, -2.0])?;
+    test("if(x>y, x, y)+1", &[1.5, -2.0])?;
+    test("if(x>y, x, y)+1", &[-1.5, -2.0])?;
+    test("x%3+{var name}", &[7.5, 1.0])?;
+    test("log(x)", &[-1.0])?;
+
+    let bytecode = "var x\npush var 0 | sin\npush num 2.0\napply 0 *\n";
+    let loaded = FlatEx::<f64>::load_bytecode(bytecode)?;
+    assert_eq!(loaded.eval(&[0.5])?, 0.5f64.sin() * 2.0);
+    assert!(loaded.unparse().is_err());
+    assert!(FlatEx::<f64>::load_bytecode("var x\npush var 0 | foo\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("var x\npush var 1\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("push num 1.0\npush num 2.0\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("push num 1.0\napply 0 +\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("push num a\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("pop\n").is_err());
+    assert!(FlatEx::<f64>::load_bytecode("").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_remap_vars() -> ExResult<()> {
+    fn test(sut: &str, renames: &[(&str, &str)], vars: &[f64], reference: f64) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let owned = OwnedFlatEx::from_flatex(flatex.clone());
+        let remapped = flatex.remap_vars(renames)?;
+        assert_float_eq_f64(remapped.eval(vars)?, reference);
+        let remapped_owned = owned.remap_vars(renames)?;
+        assert_float_eq_f64(remapped_owned.eval(vars)?, reference);
+        assert_eq!(remapped.var_names(), remapped_owned.var_names());
+        assert_eq!(remapped.unparse()?, remapped_owned.unparse()?);
+        let unparsed = remapped.unparse()?;
+        let reparsed = FlatEx::<f64>::from_str(&unparsed)?;
+        assert_float_eq_f64(reparsed.eval(vars)?, reference);
+        Ok(())
+    }
+    test("x-2*y", &[("x", "a")], &[1.0, 4.0], -7.0)?;
+    test("x-2*y", &[("y", "a")], &[4.0, 1.0], -7.0)?;
+    // renaming is simultaneous, hence variables can be swapped
+    test("x-2*y", &[("x", "y"), ("y", "x")], &[4.0, 1.0], -7.0)?;
+    test(
+        "sin(x)^y+z",
+        &[("x", "b"), ("z", "c")],
+        &[0.5, 3.0, 2.0],
+        0.5f64.sin().powi(2) + 3.0,
+    )?;
+    test("x*x-{x y}", &[("x y", "w")], &[3.0, 2.0], 1.0)?;
+    test("x*x-y", &[], &[3.0, 2.0], 7.0)?;
+
+    let flatex = FlatEx::<f64>::from_str("x-2*y")?;
+    assert!(flatex.clone().remap_vars(&[("z", "a")]).is_err());
+    assert!(flatex.clone().remap_vars(&[("x", "y")]).is_err());
+    assert!(flatex
+        .clone()
+        .remap_vars(&[("x", "a"), ("x", "b")])
+        .is_err());
+    assert!(flatex
+        .clone()
+        .remap_vars(&[("x", "a"), ("y", "a")])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_override_prios() -> ExResult<()> {
+    ops_factory_with_prios!(WeakPowOpsFactory, f64, FloatOpsFactory<f64>, ("^", 1));
+    ops_factory_with_prios!(
+        StrongAddOpsFactory,
+        f64,
+        FloatOpsFactory<f64>,
+        ("+", 5),
+        ("-", 5)
+    );
+    let expr = FlatEx::<f64, WeakPowOpsFactory>::from_str("2*x^2")?;
+    assert_float_eq_f64(expr.eval(&[3.0])?, 36.0);
+    // derived expressions are created with the same operator factory
+    let dexpr = expr.clone().partial(0)?;
+    assert_float_eq_f64(dexpr.eval(&[3.0])?, 24.0);
+    let owned = OwnedFlatEx::from_flatex(expr);
+    assert_float_eq_f64(owned.eval(&[3.0])?, 36.0);
+    assert_float_eq_f64(owned.partial(0)?.eval(&[3.0])?, 24.0);
+    let unparsed = dexpr.unparse()?;
+    let reparsed = FlatEx::<f64, WeakPowOpsFactory>::from_str(&unparsed)?;
+    assert_float_eq_f64(reparsed.eval(&[3.0])?, 24.0);
+
+    let expr = FlatEx::<f64, StrongAddOpsFactory>::from_str("2*x+1-y")?;
+    assert_float_eq_f64(expr.eval(&[3.0, 2.0])?, 4.0);
+    // unary operators still bind stronger than binary ones
+    let expr = FlatEx::<f64, StrongAddOpsFactory>::from_str("-x*2")?;
+    assert_float_eq_f64(expr.eval(&[3.0])?, -6.0);
+
+    let ops = override_prios(FloatOpsFactory::<f64>::make(), &[("^", 1), ("*", 0)])?;
+    let pow = ops.iter().find(|op| op.repr() == "^").unwrap();
+    assert_eq!(pow.bin()?.prio, 1);
+    let mul = ops.iter().find(|op| op.repr() == "*").unwrap();
+    assert_eq!(mul.bin()?.prio, 0);
+    assert!(mul.bin()?.is_commutative);
+    let mul = mul.clone().with_commutativity(false);
+    assert!(!mul.bin()?.is_commutative);
+    assert_eq!(ops.len(), FloatOpsFactory::<f64>::make().len());
+    assert!(override_prios(FloatOpsFactory::<f64>::make(), &[("sin", 1)]).is_err());
+    assert!(override_prios(FloatOpsFactory::<f64>::make(), &[("^^", 1)]).is_err());
+    let sin = Operator::make_unary("sin", |a: f64| a.sin()).with_prio(3);
+    assert!(sin.bin().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_compile_on_demand() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64], n_binops_compiled: usize) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let reference = FlatEx::<f64>::from_str(sut)?;
+        let mut flatex = FlatEx::<f64>::from_str_wo_compile(sut)?;
+        let mut owned_flatex = OwnedFlatEx::<f64>::from_str_wo_compile(sut)?;
+        assert_eq!(flatex.unparse()?, sut);
+        assert_eq!(owned_flatex.unparse()?, sut);
+        assert_float_eq_f64(flatex.eval(vars)?, reference.eval(vars)?);
+        flatex.compile();
+        owned_flatex.compile();
+        assert_eq!(flatex.n_binops(), n_binops_compiled);
+        assert_eq!(owned_flatex.n_binops(), n_binops_compiled);
+        assert_eq!(reference.n_binops(), n_binops_compiled);
+        assert_eq!(flatex.unparse()?, sut);
+        assert_float_eq_f64(flatex.eval(vars)?, reference.eval(vars)?);
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference.eval(vars)?);
+        Ok(())
+    }
+    test("1*sin(2-0.1)", &[], 0)?;
+    test("1*sin(2-0.1)*x", &[0.5], 1)?;
+    test("2*3+x*(4-1)", &[2.0], 2)?;
+    Ok(())
+}
+
+#[test]
+fn test_var_names_order() -> ExResult<()> {
+    let mut flatex = FlatEx::<f64>::from_str("z + x + y")?;
+    assert_eq!(flatex.var_names(), ["x", "y", "z"]);
+    flatex.reduce_memory();
+    assert_eq!(flatex.var_names(), ["x", "y", "z"]);
+    let mut owned_flatex = OwnedFlatEx::<f64>::from_str("z + x + y")?;
+    assert_eq!(owned_flatex.var_names(), ["x", "y", "z"]);
+    owned_flatex.reduce_memory();
+    assert_eq!(owned_flatex.var_names(), ["x", "y", "z"]);
+    assert_float_eq_f64(owned_flatex.eval(&[1.0, 2.0, 4.0])?, 7.0);
+    Ok(())
+}
+
+#[test]
+fn test_eval_with_defaults() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str("x*scale+offset")?
+        .with_defaults(&[("offset", 1.0), ("unknown", 5.0)])?
+        .with_defaults(&[("scale", 2.0), ("offset", 0.5)])?;
+    assert_eq!(
+        flatex.defaults(),
+        [("offset".to_string(), 0.5), ("scale".to_string(), 2.0)]
+    );
+    assert_float_eq_f64(flatex.eval_with_defaults(&[("x", 3.0)])?, 6.5);
+    assert_float_eq_f64(
+        flatex.eval_with_defaults(&[("x", 3.0), ("scale", 1.0), ("offset", 0.0)])?,
+        3.0,
+    );
+    let err = flatex.eval_with_defaults(&[("scale", 3.0)]).unwrap_err();
+    assert!(err.msg.contains("'x'"));
+    assert!(flatex
+        .eval_with_defaults(&[("x", 3.0), ("y", 1.0)])
+        .is_err());
+    assert!(flatex
+        .clone()
+        .with_defaults(&[("x", 1.0), ("x", 2.0)])
+        .is_err());
+
+    let owned_flatex = flatex.clone().into_owned();
+    assert_float_eq_f64(owned_flatex.eval_with_defaults(&[("x", 3.0)])?, 6.5);
+    let owned_flatex = owned_flatex.with_defaults(&[("x", 1.0)])?;
+    assert_float_eq_f64(owned_flatex.eval_with_defaults(&[])?, 2.5);
+    assert_float_eq_f64(owned_flatex.expr().eval(&[1.0, 0.5, 2.0])?, 2.0);
+
+    // defaults are not part of the wrapped expression
+    let flatex = flatex.into_expr();
+    assert_eq!(flatex, FlatEx::<f64>::from_str("x*scale+offset")?);
+    let owned_flatex = OwnedFlatEx::from_flatex(flatex).with_defaults(&[("scale", 3.0)])?;
+    assert_float_eq_f64(
+        owned_flatex.eval_with_defaults(&[("x", 1.0), ("offset", 1.0)])?,
+        4.0,
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unary_chain() -> ExResult<()> {
+    ops_factory!(
+        AffineOpsFactory,
+        f64,
+        Operator::make_bin(
+            "+",
+            BinOp {
+                apply: |a, b| a + b,
+                prio: 0,
+                is_commutative: true
+            }
+        ),
+        Operator::make_unary("inc", |a| a + 1.0),
+        Operator::make_unary("dbl", |a| a * 2.0)
+    );
+    fn test(sut: &str, vars: &[f64], chain: &[&str], reference: f64) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let flatex = FlatEx::<f64, AffineOpsFactory>::from_str(sut)?;
+        assert_eq!(flatex.unary_chain(0)?, chain);
+        assert_float_eq_f64(flatex.eval(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::<f64, AffineOpsFactory>::from_str(sut)?;
+        assert_eq!(owned_flatex.unary_chain(0)?, chain);
+        assert_float_eq_f64(owned_flatex.eval(vars)?, reference);
+        Ok(())
+    }
+    // dbl is applied first, then inc
+    test("inc(dbl(x))", &[3.0], &["dbl", "inc"], 7.0)?;
+    test("inc dbl x", &[3.0], &["dbl", "inc"], 7.0)?;
+    test("dbl(inc(x))", &[3.0], &["inc", "dbl"], 8.0)?;
+    test("dbl inc x", &[3.0], &["inc", "dbl"], 8.0)?;
+    test(
+        "dbl(inc(dbl(x)))+y",
+        &[3.0, 1.0],
+        &["dbl", "inc", "dbl"],
+        15.0,
+    )?;
+    test("x+y", &[3.0, 1.0], &[], 4.0)?;
+
+    let flatex = FlatEx::<f64>::from_str("--x+-abs(y)")?;
+    assert_eq!(flatex.unary_chain(0)?, vec!["-", "-"]);
+    assert_eq!(flatex.unary_chain(1)?, vec!["abs", "-"]);
+    assert_float_eq_f64(flatex.eval(&[2.0, -3.0])?, -1.0);
+    assert!(flatex.unary_chain(2).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_into_fn() -> ExResult<()> {
+    fn integrate<F: Fn(f64) -> ExResult<f64>>(f: F, a: f64, b: f64, n: usize) -> ExResult<f64> {
+        let h = (b - a) / n as f64;
+        (0..n).try_fold(0.0, |sum, i| Ok(sum + f(a + (i as f64 + 0.5) * h)? * h))
+    }
+    fn sum_on_grid<F: Fn(&[f64]) -> ExResult<f64>>(f: F) -> ExResult<f64> {
+        let mut sum = 0.0;
+        for x in [0.0, 1.0, 2.0] {
+            for y in [0.0, 1.0, 2.0] {
+                sum += f(&[x, y])?;
+            }
+        }
+        Ok(sum)
+    }
+    let f = FlatEx::<f64>::from_str("3*x^2")?.into_fn1()?;
+    assert_float_eq::<f64>(integrate(f, 0.0, 1.0, 1000)?, 1.0, 1e-6, 0.0, "");
+    let f = OwnedFlatEx::<f64>::from_str("3*x^2")?.into_fn1()?;
+    assert_float_eq::<f64>(integrate(&f, 0.0, 1.0, 1000)?, 1.0, 1e-6, 0.0, "");
+
+    let f = FlatEx::<f64>::from_str("x*y+1")?.into_fn();
+    assert_float_eq_f64(sum_on_grid(&f)?, 18.0);
+    assert!(f(&[1.0]).is_err());
+    let f = OwnedFlatEx::<f64>::from_str("x*y+1")?.into_fn();
+    assert_float_eq_f64(sum_on_grid(f)?, 18.0);
+
+    // the closures are 'static and Send
+    let text = String::from("sin(x)");
+    let f = FlatEx::<f64>::from_str(&text)?.into_fn1()?;
+    drop(text);
+    let handle = std::thread::spawn(move || f(0.0));
+    assert_float_eq_f64(handle.join().unwrap()?, 0.0);
+
+    assert!(FlatEx::<f64>::from_str("x*y")?.into_fn1().is_err());
+    assert!(FlatEx::<f64>::from_str("2")?.into_fn1().is_err());
+    assert!(OwnedFlatEx::<f64>::from_str("x*y")?.into_fn1().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_eval_map() -> ExResult<()> {
+    fn test(sut: &str, vars: &[(&str, f64)], reference: f64) -> ExResult<()> {
+        let vars = vars
+            .iter()
+            .copied()
+            .collect::<std::collections::HashMap<_, _>>();
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_float_eq_f64(flatex.eval_map(&vars)?, reference);
+        let mut owned_flatex = OwnedFlatEx::from_flatex(flatex);
+        assert_float_eq_f64(owned_flatex.eval_map(&vars)?, reference);
+        owned_flatex.reduce_memory();
+        assert_float_eq_f64(owned_flatex.eval_map(&vars)?, reference);
+        Ok(())
+    }
+    let vars = [("alpha", 2.0), ("beta", 0.5), ("gamma", 3.0)];
+    test("alpha*beta + gamma", &vars, 4.0)?;
+    test("gamma^alpha - beta", &vars, 8.5)?;
+    test("sin(beta)", &vars, 0.5f64.sin())?;
+    test("2*3", &vars, 6.0)?;
+    test("{x y}/z", &[("z", 2.0), ("x y", 5.0)], 2.5)?;
+
+    let flatex = FlatEx::<f64>::from_str("alpha*beta + delta")?;
+    let vars = vars
+        .iter()
+        .copied()
+        .collect::<std::collections::HashMap<_, _>>();
+    let err = flatex.eval_map(&vars).unwrap_err();
+    assert!(err.msg.contains("'delta'"));
+    Ok(())
+}
+
+#[test]
+fn test_eval_relaxed() -> ExResult<()> {
+    fn test(sut: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        assert_float_eq_f64(flatex.eval_relaxed(vars)?, reference);
+        let owned_flatex = OwnedFlatEx::from_flatex(flatex);
+        assert_float_eq_f64(owned_flatex.eval_relaxed(vars)?, reference);
+        Ok(())
+    }
+    test("x*y", &[2.0, 3.0], 6.0)?;
+    test("x*y", &[2.0, 3.0, 4.0, 5.0], 6.0)?;
+    test("2*x+1", &[2.0, 3.0], 5.0)?;
+    test("PI", &[2.0], std::f64::consts::PI)?;
+
+    fn test_err(sut: &str, vars: &[f64], relaxed: bool) -> ExResult<()> {
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let owned_flatex = OwnedFlatEx::from_flatex(flatex.clone());
+        let (res, owned_res) = if relaxed {
+            (flatex.eval_relaxed(vars), owned_flatex.eval_relaxed(vars))
+        } else {
+            (flatex.eval(vars), owned_flatex.eval(vars))
+        };
+        for err in [res.unwrap_err(), owned_res.unwrap_err()] {
+            assert!(err.msg.contains(&flatex.n_vars().to_string()));
+            assert!(err.msg.contains(&vars.len().to_string()));
+        }
+        Ok(())
+    }
+    test_err("x*y*z", &[2.0, 3.0], true)?;
+    test_err("x*y*z", &[], true)?;
+    test_err("x*y*z", &[2.0, 3.0], false)?;
+    test_err("x*y*z", &[2.0, 3.0, 4.0, 5.0], false)?;
+    test_err("2*x+1", &[], false)?;
+    test_err("2*x+1", &[1.0, 2.0], false)?;
+    Ok(())
+}
+
+#[test]
+fn test_rounding() -> ExResult<()> {
+    fn test(sut: &str, x: f64, reference: f64) -> ExResult<()> {
+        // constant folding and evaluation with variables coincide exactly
+        let with_var = FlatEx::<f64>::from_str(sut)?.eval(&[x])?;
+        let const_sut = sut.replace('x', &format!("({:?})", x));
+        let folded = exmex::eval_str::<f64>(&const_sut)?;
+        assert_eq!(with_var, reference, "{} with x={}", sut, x);
+        assert_eq!(folded, reference, "{}", const_sut);
+        Ok(())
+    }
+    for (x, away_from_zero, to_even) in [
+        (0.5, 1.0, 0.0),
+        (1.5, 2.0, 2.0),
+        (2.5, 3.0, 2.0),
+        (-0.5, -1.0, -0.0),
+        (-2.5, -3.0, -2.0),
+        (2.4, 2.0, 2.0),
+        (2.6, 3.0, 3.0),
+    ] {
+        test("round(x)", x, away_from_zero)?;
+        test("round_even(x)", x, to_even)?;
+    }
+    // 2.675 is represented as 2.67499999999999982236431605997495353221893310546875
+    test("round_to(x, 2)", 2.675, 2.67)?;
+    test("round_to(x, 2)", 0.125, 0.12)?;
+    test("round_to(x, 2)", 1.005, 1.0)?;
+    test("round_to(x, 2)", -2.675, -2.67)?;
+    test("round_to(x, 2)", 0.375, 0.38)?;
+    test("round_to(x, 0)", 2.5, 2.0)?;
+    test("round_to(x, 1.9)", 0.25, 0.2)?;
+    test("round_to(x, -2)", 1250.0, 1200.0)?;
+    test("round_to(x, -2)", 1350.0, 1400.0)?;
+    test("round_to(x, 15)", 0.1, 0.1)?;
+    assert!(exmex::eval_str::<f64>("round_to(1.5, 16)")?.is_nan());
+    assert!(exmex::eval_str::<f64>("round_to(1.5, -16)")?.is_nan());
+    assert!(FlatEx::<f64>::from_str("round_to(1.5)").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_to_dot() -> ExResult<()> {
+    let sut = "2*{x y}^2+cos(z)";
+    let mut flatex = FlatEx::<f64>::from_str(sut)?;
+    let dot = flatex.to_dot()?;
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+    for label in ["2.0", "x y", "^", "*", "+", "cos", "z"] {
+        assert!(dot.contains(&format!("[label=\"{}\"]", label)));
+    }
+    // 8 nodes, each binary operator has 2 edges, each unary operator has 1 edge
+    assert_eq!(dot.matches("[label=").count(), 8);
+    assert_eq!(dot.matches("->").count(), 7);
+    let mut owned_flatex = OwnedFlatEx::from_flatex(flatex.clone());
+    assert_eq!(owned_flatex.to_dot()?, dot);
+    flatex.reduce_memory();
+    assert_eq!(flatex.to_dot()?, dot);
+    owned_flatex.reduce_memory();
+    assert_eq!(owned_flatex.to_dot()?, dot);
+    Ok(())
+}
+
+#[test]
+fn test_unparse_latex() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str("sqrt(x)*2/y")?;
+    // multiplication has a lower priority than division
+    let reference = r"\sqrt{x} \cdot \frac{2}{y}";
+    assert_eq!(flatex.unparse_latex()?, reference);
+    let owned_flatex = OwnedFlatEx::from_flatex(flatex.clone());
+    assert_eq!(owned_flatex.unparse_latex()?, reference);
+
+    // without text, the deep expression is needed
+    let mut deri = flatex.partial(0)?;
+    assert!(deri.unparse_latex().is_ok());
+    deri.reduce_memory();
+    assert!(deri.unparse().is_err());
+    assert!(deri.unparse_latex().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_error_positions() {
+    fn test(text: &str, position: Option<usize>) {
+        println!("testing {}...", text);
+        let err = FlatEx::<f64>::from_str(text).unwrap_err();
+        assert_eq!(err.position, position);
+        let err_owned = OwnedFlatEx::<f64>::from_str(text).unwrap_err();
+        assert_eq!(err_owned, err);
+        println!("...ok.");
+    }
+    test("z+/Q", Some(2));
+    test("2 $ x", Some(2));
+    test("α+β*/2", Some(4));
+    test("{x+2", Some(0));
+    test("sin(x)+", Some(6));
+    test("*x", Some(0));
+    test("(x))+1", Some(3));
+    test("x + atan2(y)", Some(4));
+    test("(x", None);
+    test("", None);
+
+    let err = FlatEx::<f64>::from_str("z+/Q").unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        format!("parse error at char 2: {}", err.msg)
+    );
+    let err = FlatEx::<f64>::from_str("x").unwrap().eval(&[]).unwrap_err();
+    assert_eq!(format!("{}", err), err.msg);
+}
+
+#[test]
+fn test_from_bytes() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_bytes("x*y+π".as_bytes())?;
+    assert_float_eq_f64(flatex.eval(&[2.0, 3.0])?, 6.0 + std::f64::consts::PI);
+    let owned_flatex = OwnedFlatEx::<f64>::from_bytes(b"x*y")?;
+    assert_float_eq_f64(owned_flatex.eval(&[2.0, 3.0])?, 6.0);
+
+    // byte order mark
+    let flatex = FlatEx::<f64>::from_bytes(b"\xEF\xBB\xBFsqrt(x)")?;
+    assert_eq!(flatex.unparse()?, "sqrt(x)");
+    assert_float_eq_f64(flatex.eval(&[4.0])?, 2.0);
+
+    // invalid byte in the middle
+    let err = FlatEx::<f64>::from_bytes(b"x+\xC0y").unwrap_err();
+    assert_eq!(err.position, Some(2));
+    assert!(err.msg.contains("byte 2"));
+    let err = OwnedFlatEx::<f64>::from_bytes(b"\xEF\xBB\xBFx+\xC0y").unwrap_err();
+    assert_eq!(err.position, Some(2));
+    assert!(err.msg.contains("byte 5"));
+    let err = FlatEx::<f64>::from_bytes(b"\xCE\xB1+\xC0y").unwrap_err();
+    assert_eq!(err.position, Some(2));
+    assert!(err.msg.contains("byte 3"));
+
+    // parse errors refer to the decoded text
+    let err = FlatEx::<f64>::from_bytes(b"\xEF\xBB\xBFz+/Q").unwrap_err();
+    assert_eq!(err.position, Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_total_order() -> ExResult<()> {
+    use std::collections::{BTreeMap, BTreeSet};
+    let texts = ["x+0/0", "(x)+0/0", "x+1", "y+0/0", "0/0", "-(0/0)", "x+0/0"];
+    let set = texts
+        .iter()
+        .map(|t| FlatEx::<f64>::from_str(t))
+        .collect::<ExResult<BTreeSet<_>>>()?;
+    assert_eq!(set.len(), 5);
+    let nan_expr = FlatEx::<f64>::from_str("x+0/0")?;
+    assert_eq!(nan_expr, nan_expr.clone());
+    assert!(set.contains(&nan_expr));
+    assert!(!set.contains(&FlatEx::<f64>::from_str("z+0/0")?));
+
+    let mut map = BTreeMap::new();
+    for (i, t) in texts.iter().enumerate() {
+        map.insert(OwnedFlatEx::<f64>::from_str(t)?, i);
+    }
+    assert_eq!(map.len(), 5);
+    assert_eq!(map[&OwnedFlatEx::<f64>::from_str("x+0/0")?], 6);
+    assert_eq!(map[&OwnedFlatEx::<f64>::from_str("0/0")?], 4);
+
+    // the text is not considered
+    let mut flatex = FlatEx::<f64>::from_str("2*x")?;
+    let reference = flatex.clone();
+    flatex.reduce_memory();
+    assert_eq!(flatex, reference);
+    assert_ne!(FlatEx::<f64>::from_str("x*2")?, reference);
+    Ok(())
+}
+
+#[test]
+fn test_hash() -> ExResult<()> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{Hash, Hasher};
+    fn hash<H: Hash>(x: &H) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+    let flatex_1 = FlatEx::<f64>::from_str("sin(x)*2+y")?;
+    let flatex_2 = FlatEx::<f64>::from_str("sin(x) * 2 + y")?;
+    assert_eq!(hash(&flatex_1), hash(&flatex_2));
+    let owned_flatex = OwnedFlatEx::from_flatex(flatex_1.clone());
+    assert_eq!(hash(&flatex_1), hash(&owned_flatex));
+    let mut reduced = flatex_1.clone();
+    reduced.reduce_memory();
+    assert_eq!(hash(&flatex_1), hash(&reduced));
+    for different in ["sin(x)*2+z", "cos(x)*2+y", "sin(x)*3+y", "sin(x)*2-y"] {
+        let different = FlatEx::<f64>::from_str(different)?;
+        assert_ne!(hash(&flatex_1), hash(&different));
+    }
+
+    let texts = ["x+0/0", "(x)+0/0", "x+1", "y+0/0", "0/0", "2*3+x", "6+x"];
+    let set = texts
+        .iter()
+        .map(|t| FlatEx::<f64>::from_str(t))
+        .collect::<ExResult<HashSet<_>>>()?;
+    assert_eq!(set.len(), 5);
+    let mut map = HashMap::new();
+    for (i, t) in texts.iter().enumerate() {
+        map.insert(OwnedFlatEx::<f64>::from_str(t)?, i);
+    }
+    assert_eq!(map.len(), 5);
+    assert_eq!(map[&OwnedFlatEx::<f64>::from_str("6+x")?], 6);
+
+    // operators are told apart by their representations also if their functions coincide
+    ops_factory!(
+        IdentityOpsFactory,
+        f64,
+        Operator::make_unary("id", |a| a),
+        Operator::make_unary("same", |a| a)
+    );
+    let id = FlatEx::<f64, IdentityOpsFactory>::from_str("id(x)")?;
+    let same = FlatEx::<f64, IdentityOpsFactory>::from_str("same(x)")?;
+    assert!(id != same);
+    assert_ne!(hash(&id), hash(&same));
+    assert!(id == FlatEx::<f64, IdentityOpsFactory>::from_str("id((x))")?);
+    Ok(())
+}
+
+#[test]
+fn test_equivalent() -> ExResult<()> {
+    fn test(a: &str, b: &str, is_equivalent: bool) -> ExResult<()> {
+        println!("testing {} and {}...", a, b);
+        let flatex_a = FlatEx::<f64>::from_str(a)?;
+        let flatex_b = FlatEx::<f64>::from_str(b)?;
+        assert_eq!(flatex_a.equivalent(&flatex_b), is_equivalent);
+        let owned_a = OwnedFlatEx::from_flatex(flatex_a);
+        let owned_b = OwnedFlatEx::from_flatex(flatex_b);
+        assert_eq!(owned_a.equivalent(&owned_b), is_equivalent);
+        Ok(())
+    }
+    test(" x+1", "x + 1", true)?;
+    test("(x)+1", "x+1", true)?;
+    test("x+1", "1+x", true)?;
+    test("sin(x)*y", "y*sin(x)", true)?;
+    test("2*3+x", "x+6", true)?;
+    test("x-1", "1-x", false)?;
+    test("x+1", "y+1", false)?;
+    test("sin(x)", "cos(x)", false)?;
+    test("-x", "0-x", false)?;
+    test("x^3", "x^(1+2)", true)?;
+
+    // operators are told apart by their representations also if their functions coincide
+    ops_factory!(
+        IdentityOpsFactory,
+        f64,
+        Operator::make_unary("id", |a| a),
+        Operator::make_unary("same", |a| a)
+    );
+    let id = FlatEx::<f64, IdentityOpsFactory>::from_str("id(x)")?;
+    assert!(!id.equivalent(&FlatEx::<f64, IdentityOpsFactory>::from_str("same(x)")?));
+    assert!(id.equivalent(&FlatEx::<f64, IdentityOpsFactory>::from_str("id((x))")?));
+    Ok(())
+}
+
+#[test]
+fn test_eval_many() -> ExResult<()> {
+    fn test(text: &str) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let owned_flatex = OwnedFlatEx::<f64>::from_str(text)?;
+        let mut rng = thread_rng();
+        let rows = (0..20)
+            .map(|_| {
+                (0..flatex.n_vars())
+                    .map(|_| rng.gen_range(-10.0..10.0))
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        let row_refs = rows.iter().map(|r| r.as_slice()).collect::<Vec<_>>();
+        let res = flatex.eval_many(&row_refs)?;
+        let res_owned = owned_flatex.eval_many(&row_refs)?;
+        assert_eq!(res.len(), rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            let reference = flatex.eval(row)?;
+            assert_float_eq_f64(res[i], reference);
+            assert_float_eq_f64(res_owned[i], reference);
+        }
+        println!("...ok.");
+        Ok(())
+    }
+    test("x*2.0+y")?;
+    test("1.8*c+32")?;
+    test("sin(x)*y-z/(1+x^2)")?;
+    test("2^3")?;
+    test("-(x+y)*-(z-x)")?;
+
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert_eq!(flatex.eval_many(&[])?, Vec::<f64>::new());
+    let err = flatex
+        .eval_many(&[&[1.0, 2.0], &[1.0], &[1.0, 2.0]])
+        .unwrap_err();
+    assert!(err.msg.contains("row 1 has 1 elements"));
+    let affine = FlatEx::<f64>::from_str("2*x")?;
+    assert!(affine.eval_many(&[&[1.0, 2.0]]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_eval_with_buffer() -> ExResult<()> {
+    // the same buffer is used for expressions of different sizes
+    let mut buffer = EvalBuffer::new();
+    let texts = [
+        "x*0.02*sin(-(3.0*(2.0*sin(x-1.0/(sin(y*5.0)+(5.0-1.0/z))))))",
+        "x",
+        "1.8*x+32",
+        "sin(x)+sin(y)+sin(z)",
+        "x*0.2*5.0/4.0+x*2.0*4.0*1.0*1.0*1.0*1.0*1.0*1.0*1.0+7.0*sin(y)-z/sin(3.0/2.0/(1.0-x*4.0*1.0*1.0*1.0*1.0))",
+        "2^3",
+    ];
+    for text in texts {
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let vars = [0.3, -1.2, 2.5];
+        let vars = &vars[..flatex.n_vars()];
+        let res = flatex.eval_with_buffer(vars, &mut buffer)?;
+        assert_eq!(res.to_bits(), flatex.eval(vars)?.to_bits());
+    }
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert!(flatex.eval_with_buffer(&[1.0], &mut buffer).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_from_number_variable() -> ExResult<()> {
+    let two = FlatEx::<f64>::from_number(2.0);
+    assert_eq!(two.n_vars(), 0);
+    assert_float_eq_f64(two.eval(&[])?, 2.0);
+    assert_eq!(two.unparse()?, "2.0");
+    let x = FlatEx::<f64>::from_variable("x");
+    assert_eq!(x.var_names(), ["x"]);
+    assert_float_eq_f64(x.eval(&[1.5])?, 1.5);
+    assert!(x.eval(&[]).is_err());
+    let y = FlatEx::<f64>::from_variable("y");
+    let expr = ((two * x)? + y)?;
+    assert_eq!(expr.var_names(), ["x", "y"]);
+    assert_float_eq_f64(expr.eval(&[1.5, 0.5])?, 3.5);
+    let deri = expr.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[1.5, 0.5])?, 2.0);
+
+    assert_float_eq_f64(FlatEx::<f64>::zero().eval(&[])?, 0.0);
+    assert_float_eq_f64(FlatEx::<f32>::one().eval(&[])? as f64, 1.0);
+
+    let x = OwnedFlatEx::<f64>::from_variable("x");
+    let expr = (OwnedFlatEx::one() - x)?;
+    assert_eq!(expr.var_names(), ["x"]);
+    assert_float_eq_f64(expr.eval(&[0.25])?, 0.75);
+    assert_float_eq_f64(OwnedFlatEx::<f64>::zero().eval(&[])?, 0.0);
+    assert_float_eq_f64(OwnedFlatEx::<f64>::from_number(-3.0).eval(&[])?, -3.0);
+    Ok(())
+}
+
+#[test]
+fn test_metrics() -> ExResult<()> {
+    fn test(text: &str, n_binops: usize, n_unary_ops: usize, depth: usize) -> ExResult<()> {
+        println!("testing {}...", text);
+        let mut flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.n_binops(), n_binops);
+        assert_eq!(flatex.n_unary_ops(), n_unary_ops);
+        assert_eq!(flatex.depth()?, depth);
+        let mut owned = OwnedFlatEx::from_flatex(flatex.clone());
+        flatex.reduce_memory();
+        assert_eq!(flatex.depth()?, depth);
+        owned.reduce_memory();
+        assert_eq!(owned.n_binops(), n_binops);
+        assert_eq!(owned.n_unary_ops(), n_unary_ops);
+        assert_eq!(owned.depth()?, depth);
+        println!("...ok.");
+        Ok(())
+    }
+    test("sin(cos(x+1))*2", 2, 2, 3)?;
+    test("x", 0, 0, 1)?;
+    test("((x))", 0, 0, 1)?;
+    test("-x", 0, 1, 1)?;
+    test("2*x+1", 2, 0, 1)?;
+    test("sin(x)+1", 1, 1, 2)?;
+    test("2*(x+y)", 2, 0, 2)?;
+    test("exp(x*(y+1))/(z^2+1)", 5, 1, 3)?;
+    // numbers are combined during parsing
+    test("2*3*x", 1, 0, 1)?;
+
+    // depth survives reduce_memory also without text
+    let mut deri = FlatEx::<f64>::from_str("sin(cos(x))")?.partial(0)?;
+    let depth = deri.depth()?;
+    deri.reduce_memory();
+    assert!(deri.unparse().is_err());
+    assert_eq!(deri.depth()?, depth);
+    let mut deri = OwnedFlatEx::from_flatex(deri);
+    deri.reduce_memory();
+    assert_eq!(deri.depth()?, depth);
+    Ok(())
+}
+
+#[test]
+fn test_cse() -> ExResult<()> {
+    fn test(sut: &str, n_vars: usize, n_eliminated_ref: usize) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let reference = FlatEx::<f64>::from_str(sut)?;
+        let (flatex, n_eliminated) = FlatEx::<f64>::from_str(sut)?.with_cse()?;
+        let (owned_flatex, n_eliminated_owned) = OwnedFlatEx::<f64>::from_str(sut)?.with_cse()?;
+        assert_eq!(n_eliminated, n_eliminated_ref);
+        assert_eq!(n_eliminated_owned, n_eliminated_ref);
+        assert_eq!(flatex.unparse()?, reference.unparse()?);
+        let mut rng = thread_rng();
+        let rows = (0..5)
+            .map(|_| {
+                (0..n_vars)
+                    .map(|_| rng.gen_range(-2.0..2.0))
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        for vars in &rows {
+            let expected = reference.eval(vars)?;
+            assert_float_eq_f64(flatex.eval(vars)?, expected);
+            assert_float_eq_f64(owned_flatex.eval(vars)?, expected);
+        }
+        let var_rows = rows.iter().map(|row| row.as_slice()).collect::<Vec<_>>();
+        for (res, expected) in flatex
+            .eval_many(&var_rows)?
+            .iter()
+            .zip(reference.eval_many(&var_rows)?)
+        {
+            assert_float_eq_f64(*res, expected);
+        }
+        assert!(flatex.eval(&[0.5; 5][..n_vars + 1]).is_err());
+        Ok(())
+    }
+    test("sin(x+y)^2+cos(x+y)^2+tan(x+y)", 2, 2)?;
+    test("exp(x*y)+exp(x*y)*2", 2, 1)?;
+    test("sin(x)^2+cos(x)^2", 1, 0)?;
+    test("(x+y)*(x+y)-sin(x+y)", 2, 2)?;
+    test("sin(cos(x*y))+sin(cos(x*y))*x+cos(x*y)", 2, 2)?;
+    Ok(())
+}
+
+#[test]
+fn test_simplify() -> ExResult<()> {
+    fn test(text: &str, simplified_ref: &str) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let simplified = flatex.clone().simplify()?;
+        assert_eq!(simplified.unparse()?, simplified_ref);
+        assert_eq!(simplified.var_names(), flatex.var_names());
+        let owned_simplified = OwnedFlatEx::<f64>::from_str(text)?.simplify()?;
+        assert_eq!(owned_simplified.unparse()?, simplified_ref);
+        let mut rng = thread_rng();
+        for _ in 0..5 {
+            let vars = (0..flatex.n_vars())
+                .map(|_| rng.gen_range(0.5..2.0))
+                .collect::<Vec<f64>>();
+            let reference = flatex.eval(&vars)?;
+            assert_float_eq_f64(simplified.eval(&vars)?, reference);
+            assert_float_eq_f64(owned_simplified.eval(&vars)?, reference);
+        }
+        Ok(())
+    }
+    test("x*1+0*y+z^1-0", "{x}+{z}")?;
+    test("1.0*x", "{x}")?;
+    test("0.0+sin(x)", "sin({x})")?;
+    test("x^0+y", "1.0+{y}")?;
+    test("0^x*y+1^y", "1.0")?;
+    test("log(exp(x*y))", "{x}*{y}")?;
+    test("exp(log(x))^1", "{x}")?;
+    test("sin(log(exp(x)))", "sin({x})")?;
+    test("-log(exp(x))", "-({x})")?;
+    test("x-(y+0)*1", "{x}-{y}")?;
+    test("x-(y+z)*1", "{x}-({y}+{z})")?;
+    test("x*(y+z)", "{x}*({y}+{z})")?;
+    test("a+b*c-d/e", "{a}+{b}*{c}-{d}/{e}")?;
+    test("(x+y)*1+z*(y*1)", "{x}+{y}+{z}*{y}")?;
+    test("sin(x)*1*cos(y)", "sin({x})*cos({y})")?;
+    Ok(())
+}
+
+#[test]
+fn test_operator_reprs() -> ExResult<()> {
+    fn test(text: &str, reference: &[&str]) -> ExResult<()> {
+        println!("testing {}...", text);
+        let mut flatex = FlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.operator_reprs(), reference);
+        let mut owned = OwnedFlatEx::from_flatex(flatex.clone());
+        owned.reduce_memory();
+        assert_eq!(owned.operator_reprs(), reference);
+        flatex.reduce_memory();
+        assert_eq!(flatex.operator_reprs(), reference);
+        for repr in reference {
+            assert!(flatex.uses_operator(repr));
+        }
+        assert!(!flatex.uses_operator("exp"));
+        Ok(())
+    }
+    test("x", &[])?;
+    test("PI*2", &[])?;
+    test("sin(x)+y^2", &["sin", "+", "^"])?;
+    test("-log(x)+π*x", &["log", "-", "+", "*"])?;
+    test("sin(cos(x)/(1-y))", &["cos", "/", "sin", "-"])?;
+    test("x-y*2-x", &["-", "*"])?;
+    test("sin(x/y)", &["/", "sin"])?;
+
+    let mut deri = FlatEx::<f64>::from_str("sin(x)")?.partial(0)?;
+    deri.reduce_memory();
+    assert_eq!(deri.operator_reprs(), ["cos"]);
+    Ok(())
+}
+
+#[test]
+fn test_parse_owned() -> ExResult<()> {
+    fn make_derivative<E: ParseExpress<f64>>(text: &str) -> ExResult<E::Owned> {
+        let expr = E::parse_owned(text)?;
+        expr.partial(0)
+    }
+    fn test<E: ParseExpress<f64>>(text: String, vars: &[f64], reference: f64) -> ExResult<()> {
+        println!("testing {}...", text);
+        let expr = E::parse_owned(&text)?;
+        let deri = make_derivative::<E>(&text)?;
+        drop(text);
+        assert_float_eq_f64(expr.eval(vars)?, reference);
+        assert_eq!(deri.n_vars(), vars.len());
+        Ok(())
+    }
+    test::<FlatEx<f64>>("x^2+y".to_string(), &[2.0, 1.0], 5.0)?;
+    test::<OwnedFlatEx<f64>>("x^2+y".to_string(), &[2.0, 1.0], 5.0)?;
+    test::<FlatEx<f64>>("sin(PI*x)".to_string(), &[0.5], 1.0)?;
+    test::<OwnedFlatEx<f64>>("sin(PI*x)".to_string(), &[0.5], 1.0)?;
+    assert!(<FlatEx<f64> as ParseExpress<f64>>::parse_owned("x+").is_err());
+    assert!(<OwnedFlatEx<f64> as ParseExpress<f64>>::parse_owned("x+").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_eval_str_vars() -> ExResult<()> {
+    fn test(sut: &str, vars: &[(&str, f64)], reference: f64) -> ExResult<()> {
+        println!(" === testing {}", sut);
+        assert_float_eq_f64(eval_str_vars(sut, vars)?, reference);
+        Ok(())
+    }
+    test("x*y", &[("x", 2.0), ("y", 3.0)], 6.0)?;
+    test("x*y", &[("y", 3.0), ("x", 2.0)], 6.0)?;
+    test("2*PI*r", &[("r", 0.5)], std::f64::consts::PI)?;
+    test("sin(1.5)", &[], 1.5f64.sin())?;
+    test("{x y}^2+z", &[("z", 1.0), ("x y", 2.0)], 5.0)?;
+
+    let err = eval_str_vars::<f64>("PI*x", &[("x", 1.0), ("y", 2.0)]).unwrap_err();
+    assert!(err.msg.contains("'y'"));
+    assert!(!err.msg.contains("PI"));
+    let err = eval_str_vars::<f64>("PI*x*y", &[("x", 1.0)]).unwrap_err();
+    assert!(err.msg.contains("'y'"));
+    assert!(!err.msg.contains("PI"));
+    let err = eval_str_vars::<f64>("PI*x", &[("PI", 1.0), ("x", 2.0)]).unwrap_err();
+    assert!(err.msg.contains("'PI'"));
+    assert!(eval_str_vars::<f64>("x+", &[("x", 1.0)]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_partial_by_name() -> ExResult<()> {
+    fn test(sut: &str, var_name: &str, var_idx: usize, vars: &[f64]) -> ExResult<()> {
+        println!("testing d/d{} of {}...", var_name, sut);
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let reference = flatex.clone().partial(var_idx)?;
+        let d = flatex.partial_by_name(var_name)?;
+        assert_eq!(d.unparse()?, reference.unparse()?);
+        assert_float_eq_f64(d.eval(vars)?, reference.eval(vars)?);
+        let owned_d = OwnedFlatEx::<f64>::from_str(sut)?.partial_by_name(var_name)?;
+        assert_float_eq_f64(owned_d.eval(vars)?, reference.eval(vars)?);
+        Ok(())
+    }
+    test("y*x^2", "x", 0, &[2.0, 3.0])?;
+    test("y*x^2", "y", 1, &[2.0, 3.0])?;
+    test("{β}*sin({x y})", "x y", 0, &[0.5, 1.5])?;
+    test("{β}*sin({x y})", "β", 1, &[0.5, 1.5])?;
+
+    let err = FlatEx::<f64>::from_str("y*x^2+PI")?
+        .partial_by_name("z")
+        .unwrap_err();
+    assert!(err.msg.contains("'z'"));
+    assert!(FlatEx::<f64>::from_str("y*x^2+PI")?
+        .partial_by_name("PI")
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_depends_on_var() -> ExResult<()> {
+    fn test(sut: &str, vars: &[&str], non_vars: &[&str]) -> ExResult<()> {
+        println!("testing {}...", sut);
+        let flatex = FlatEx::<f64>::from_str(sut)?;
+        let mut owned_flatex = OwnedFlatEx::<f64>::from_str(sut)?;
+        owned_flatex.reduce_memory();
+        assert_eq!(flatex.occurring_vars(), vars);
+        assert_eq!(owned_flatex.occurring_vars(), vars);
+        for var in vars {
+            assert!(flatex.depends_on_var(var));
+            assert!(owned_flatex.depends_on_var(var));
+            let curly = format!("{{{}}}", var);
+            assert!(flatex.depends_on_var(&curly));
+            assert!(owned_flatex.depends_on_var(&curly));
+        }
+        for non_var in non_vars {
+            assert!(!flatex.depends_on_var(non_var));
+            assert!(!owned_flatex.depends_on_var(non_var));
+        }
+        Ok(())
+    }
+    test("x*y+PI", &["x", "y"], &["PI", "z", "{PI}", "{x"])?;
+    test("2*{χ}+sin({x y})", &["x y", "χ"], &["x", "y", "{χ", "sin"])?;
+    test("3.5", &[], &["x", ""])?;
+
+    // variables that vanished are kept but do not occur anymore
+    let d = FlatEx::<f64>::from_str("3*x+y^2")?.partial(0)?;
+    assert!(!d.depends_on_var("x"));
+    assert!(!d.depends_on_var("y"));
+    assert!(d.occurring_vars().is_empty());
+    let d = OwnedFlatEx::<f64>::from_str("x*y+z")?.partial(0)?;
+    assert_eq!(d.var_names(), ["x", "y", "z"]);
+    assert_eq!(d.occurring_vars(), ["y"]);
+    assert!(d.depends_on_var("y"));
+    assert!(!d.depends_on_var("x"));
+    let simplified = FlatEx::<f64>::from_str("x*1+0*y+z^1")?.simplify()?;
+    assert_eq!(simplified.occurring_vars(), ["x", "z"]);
+    let flatex = FlatEx::<f64>::from_str("if(z > 0, 2, y)")?;
+    assert_eq!(flatex.occurring_vars(), ["y", "z"]);
+    assert!(flatex.clone().bind(1, 1.0)?.occurring_vars().is_empty());
+    assert_eq!(flatex.bind(1, -1.0)?.occurring_vars(), ["y"]);
+    Ok(())
+}
+
+#[test]
+fn test_flat_views() -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str("sin(-x)+cos(abs(y))*2")?;
+    let mut owned = OwnedFlatEx::from_flatex(flatex.clone());
+    owned.reduce_memory();
+    let reference_nodes = vec![
+        FlatNodeView::Var {
+            var_idx: 0,
+            unary_reprs: vec!["-", "sin"],
+        },
+        FlatNodeView::Var {
+            var_idx: 1,
+            unary_reprs: vec!["abs", "cos"],
+        },
+        FlatNodeView::Num {
+            value: 2.0,
+            unary_reprs: vec![],
+        },
+    ];
+    assert_eq!(flatex.nodes().collect::<Vec<_>>(), reference_nodes);
+    assert_eq!(owned.nodes().collect::<Vec<_>>(), reference_nodes);
+    let ops = flatex.ops().collect::<Vec<_>>();
+    assert_eq!(ops, owned.ops().collect::<Vec<_>>());
+    assert_eq!(
+        ops.iter()
+            .map(|op| (op.bin_op_idx, op.repr, op.is_commutative))
+            .collect::<Vec<_>>(),
+        [(1, "*", true), (0, "+", true)]
+    );
+    assert!(ops[0].prio > ops[1].prio);
+
+    let flatex = FlatEx::<f64>::from_str("x-exp(y^2)")?;
+    let ops = flatex.ops().collect::<Vec<_>>();
+    assert_eq!(ops.len(), 2);
+    assert_eq!(
+        (ops[0].repr, ops[0].unary_reprs.clone()),
+        ("^", vec!["exp"])
+    );
+    assert_eq!((ops[1].repr, ops[1].is_commutative), ("-", false));
+
+    let flatex = FlatEx::<f64>::from_str("2*3")?;
+    assert_eq!(flatex.ops().count(), 0);
+    assert_eq!(
+        flatex.nodes().collect::<Vec<_>>(),
+        [FlatNodeView::Num {
+            value: 6.0,
+            unary_reprs: vec![]
+        }]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_int_pow() -> ExResult<()> {
+    fn test(text: &str, exponent: i32) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let owned = OwnedFlatEx::<f64>::from_str(text)?;
+        for x in [-3.0, -2.0, -1.5, -0.1, 0.0, 0.7, 2.0, 1e3] {
+            let reference = f64::powi(x, exponent);
+            assert!(flatex.eval(&[x])? == reference || reference.is_nan());
+            assert!(owned.eval(&[x])? == reference || reference.is_nan());
+        }
+        Ok(())
+    }
+    test("x^3", 3)?;
+    test("x^2", 2)?;
+    test("x^0", 0)?;
+    test("x^-1", -1)?;
+    test("x^(-2)", -2)?;
+    test("x^16", 16)?;
+    test("x^-16", -16)?;
+    test("x^(1+2)", 3)?;
+    test("x^3.0", 3)?;
+
+    // negative bases
+    assert_float_eq_f64(eval_str("(-2)^3")?, -8.0);
+    assert_float_eq_f64(eval_str("-2^3")?, -8.0);
+    let flatex = FlatEx::<f64>::from_str("(x-3)^3*2")?;
+    assert_float_eq_f64(flatex.eval(&[1.0])?, -16.0);
+    let flatex = FlatEx::<f64>::from_str("sin(x)^3")?;
+    assert_float_eq_f64(flatex.eval(&[-1.0])?, (-1.0f64).sin().powi(3));
+
+    // non-integer exponents and exponents out of range still use powf
+    assert!(FlatEx::<f64>::from_str("x^2.5")?.eval(&[-2.0])?.is_nan());
+    assert!(FlatEx::<f64>::from_str("x^(1/3)")?.eval(&[-8.0])?.is_nan());
+    assert_float_eq_f64(
+        FlatEx::<f64>::from_str("x^17")?.eval(&[-1.5])?,
+        (-1.5f64).powf(17.0),
+    );
+    assert_float_eq_f64(FlatEx::<f64>::from_str("x^y")?.eval(&[-2.0, 3.0])?, -8.0);
+    assert_float_eq_f64(FlatEx::<f64>::from_str("3^x")?.eval(&[2.0])?, 9.0);
+
+    // the specialization is applied during compilation and does not change the operator
+    // for comparisons
+    let mut uncompiled = FlatEx::<f64>::from_str_wo_compile("x^3")?;
+    assert_eq!(uncompiled, FlatEx::<f64>::from_str("x^3")?);
+    uncompiled.compile();
+    assert_eq!(uncompiled, FlatEx::<f64>::from_str("x^3")?);
+    assert_float_eq_f64(uncompiled.eval(&[-2.0])?, -8.0);
+
+    // the operator is still recognized as power
+    let flatex = FlatEx::<f64>::from_str("x^3+x^2")?;
+    assert_eq!(flatex.operator_reprs(), ["^", "+"]);
+    let bytecode = flatex.dump_bytecode();
+    let loaded = OwnedFlatEx::<f64>::load_bytecode(&bytecode)?;
+    assert_eq!(loaded, OwnedFlatEx::from_flatex(flatex.clone()));
+    assert_float_eq_f64(flatex.clone().partial(0)?.eval(&[2.0])?, 16.0);
+    assert!(FlatEx::<f64>::from_str("x^-1")?
+        .eval_checked(&[0.0])
+        .unwrap_err()
+        .msg
+        .contains('^'));
+
+    // indeterminate forms of other factories are not affected
+    let flatex = FlatEx::<f64, IndeterminateAsNanOpsFactory<f64>>::from_str("x^0")?;
+    assert!(flatex.eval(&[0.0])?.is_nan());
+    Ok(())
+}
+
+#[test]
+fn test_is_linear() -> ExResult<()> {
+    fn test(text: &str, linear: &[bool], is_affine: bool) -> ExResult<()> {
+        println!("testing {}...", text);
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let owned = OwnedFlatEx::<f64>::from_str(text)?;
+        assert_eq!(flatex.n_vars(), linear.len());
+        for (var_idx, is_linear) in linear.iter().enumerate() {
+            assert_eq!(flatex.is_linear(var_idx), *is_linear);
+            assert_eq!(owned.is_linear(var_idx), *is_linear);
+        }
+        assert_eq!(flatex.is_affine(), is_affine);
+        assert_eq!(owned.is_affine(), is_affine);
+        Ok(())
+    }
+    test("2*x + 3*y", &[true, true], true)?;
+    test("x^2", &[false], false)?;
+    test("x^2 + y", &[false, true], false)?;
+    // linear in each variable separately but not affine
+    test("x*y", &[true, true], false)?;
+    test("2*x*y + z", &[true, true, true], false)?;
+    test("(x+1)/4 - 2*z", &[true, true], true)?;
+    test("sin(x) + y", &[false, true], false)?;
+    test("x/y", &[true, false], false)?;
+    test("exp(2*x)", &[false], false)?;
+    test("3.5", &[], true)?;
+
+    let flatex = FlatEx::<f64>::from_str("x+y")?;
+    assert!(!flatex.is_linear(2));
     Ok(())
 }
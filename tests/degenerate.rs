@@ -0,0 +1,125 @@
+mod utils;
+use exmex::{prelude::*, ExResult, OwnedFlatEx};
+use utils::{assert_float_eq_f32, assert_float_eq_f64};
+
+/// Checks expressions that consist of a single node without any binary operators, i.e., a
+/// single variable, a single literal, a single constant, or a constant that is fully folded
+/// during compilation.
+fn test_single_node(text: &str, vars: &[f64], reference: f64) -> ExResult<()> {
+    let flatex = FlatEx::<f64>::from_str(text)?;
+    assert_eq!(flatex.n_vars(), vars.len());
+    assert_float_eq_f64(flatex.eval(vars)?, reference);
+    assert_eq!(flatex.unparse()?, text);
+    assert_eq!(format!("{}", flatex), text);
+
+    let mut flatex_reduced = flatex.clone();
+    flatex_reduced.reduce_memory();
+    assert_float_eq_f64(flatex_reduced.eval(vars)?, reference);
+    assert_eq!(flatex_reduced.unparse()?, text);
+
+    let owned = OwnedFlatEx::from_flatex(flatex.clone());
+    assert_float_eq_f64(owned.eval(vars)?, reference);
+    assert_eq!(format!("{}", owned), text);
+    let mut owned = OwnedFlatEx::<f64>::from_str(text)?;
+    owned.reduce_memory();
+    assert_float_eq_f64(owned.eval(vars)?, reference);
+    assert_eq!(owned.unparse()?, text);
+
+    #[cfg(feature = "serde")]
+    {
+        let serialized = serde_json::to_string(&flatex).unwrap();
+        let deserialized = serde_json::from_str::<FlatEx<f64>>(serialized.as_str()).unwrap();
+        assert_eq!(format!("{}", deserialized), text);
+        assert_float_eq_f64(deserialized.eval(vars)?, reference);
+        let serialized = serde_json::to_string(&owned).unwrap();
+        let deserialized = serde_json::from_str::<OwnedFlatEx<f64>>(serialized.as_str()).unwrap();
+        assert_eq!(format!("{}", deserialized), text);
+        assert_float_eq_f64(deserialized.eval(vars)?, reference);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_single_var() -> ExResult<()> {
+    fn test(text: &str) -> ExResult<()> {
+        test_single_node(text, &[2.5], 2.5)?;
+
+        let flatex = FlatEx::<f64>::from_str(text)?;
+        let deri = flatex.clone().partial(0)?;
+        assert_eq!(deri.n_vars(), 1);
+        assert_float_eq_f64(deri.eval(&[2.5])?, 1.0);
+        assert_eq!(deri.unparse()?, "1.0");
+        let mut deri_2 = deri.partial(0)?;
+        assert_float_eq_f64(deri_2.eval(&[2.5])?, 0.0);
+        assert_eq!(format!("{}", deri_2), "0.0");
+        deri_2.reduce_memory();
+        assert_float_eq_f64(deri_2.eval(&[2.5])?, 0.0);
+        assert!(deri_2.unparse().is_err());
+        assert!(deri_2.partial(0).is_err());
+        assert!(flatex.clone().partial(1).is_err());
+
+        let owned = OwnedFlatEx::from_flatex(flatex);
+        let deri = owned.partial(0)?;
+        assert_float_eq_f64(deri.eval(&[2.5])?, 1.0);
+        assert_eq!(deri.unparse()?, "1.0");
+        let deri = OwnedFlatEx::from_flatex(FlatEx::<f64>::from_str(text)?.partial(0)?);
+        assert_eq!(format!("{}", deri), "1.0");
+        Ok(())
+    }
+    test("x")?;
+    test("{x}")?;
+    test("{a var}")?;
+    test("(x)")?;
+
+    // derivatives of negated variables are folded to constants
+    for text in ["-x", "-(x)", "--x", "-(-(x))"] {
+        let flatex = FlatEx::<f32>::from_str(text)?;
+        let deri = flatex.clone().partial(0)?;
+        let sign = flatex.eval(&[1.0])?;
+        assert_float_eq_f32(deri.eval(&[2.5])?, sign);
+        assert_eq!(deri.unparse()?, format!("{:?}", sign));
+    }
+
+    assert!(FlatEx::<f64>::from_str("{x").is_err());
+    assert!(FlatEx::<f64>::from_str("2*{x").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_single_literal() -> ExResult<()> {
+    fn test(text: &str, reference: f64) -> ExResult<()> {
+        test_single_node(text, &[], reference)?;
+        assert!(FlatEx::<f64>::from_str(text)?.partial(0).is_err());
+        assert!(OwnedFlatEx::<f64>::from_str(text)?.partial(0).is_err());
+        Ok(())
+    }
+    test("3", 3.0)?;
+    test("0.25", 0.25)?;
+    test("PI", std::f64::consts::PI)?;
+    test("E", std::f64::consts::E)?;
+    test("-3", -3.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_folded_constant() -> ExResult<()> {
+    fn test(text: &str, reference: f64) -> ExResult<()> {
+        test_single_node(text, &[], reference)?;
+        assert!(FlatEx::<f64>::from_str(text)?.partial(0).is_err());
+        Ok(())
+    }
+    test("2*3+1", 7.0)?;
+    test("sin(PI/2)", 1.0)?;
+    test("((((2))))", 2.0)?;
+    test("-(-(2^3))", 8.0)?;
+
+    // variables with derivatives that are folded to constants
+    let flatex = FlatEx::<f64>::from_str("2*x*3+1")?;
+    let deri = flatex.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[7.0])?, 6.0);
+    assert_eq!(deri.unparse()?, "6.0");
+    let deri = FlatEx::<f64>::from_str("x*y")?.partial(0)?;
+    assert_float_eq_f64(deri.eval(&[7.0, 2.0])?, 2.0);
+    assert_eq!(deri.unparse()?, "{y}");
+    Ok(())
+}
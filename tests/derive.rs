@@ -0,0 +1,48 @@
+#[cfg(feature = "derive")]
+use {
+    exmex::{prelude::*, ExResult, ExVars, OwnedFlatEx, VarProvider},
+    utils::assert_float_eq_f64,
+};
+#[cfg(feature = "derive")]
+#[allow(dead_code)]
+mod utils;
+
+#[cfg(feature = "derive")]
+#[derive(ExVars)]
+struct Config {
+    rate: f64,
+    years: f64,
+    r#type: f64,
+}
+
+#[test]
+#[cfg(feature = "derive")]
+fn test_derive() -> ExResult<()> {
+    let config = Config {
+        rate: 0.5,
+        years: 2.0,
+        r#type: -1.0,
+    };
+    assert_eq!(config.var_value("rate"), Some(0.5));
+    assert_eq!(config.var_value("type"), Some(-1.0));
+    assert_eq!(config.var_value("age"), None);
+
+    let expr = FlatEx::<f64>::from_str("(1+rate)^years*type")?;
+    let vars = config.to_ordered_slice(&expr)?;
+    assert_eq!(vars.as_slice(), [0.5, -1.0, 2.0]);
+    assert_float_eq_f64(expr.eval(&vars)?, -2.25);
+    let expr = OwnedFlatEx::<f64>::from_str("years")?;
+    assert_eq!(config.to_ordered_slice(&expr)?.as_slice(), [2.0]);
+
+    let expr = FlatEx::<f64>::from_str("rate*age")?;
+    let err = config.to_ordered_slice(&expr).unwrap_err();
+    assert!(err.msg.contains("'age'"));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "derive")]
+fn test_derive_compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,67 @@
+#[cfg(feature = "library")]
+use {
+    exmex::{library, Eval, ExResult, Express},
+    utils::assert_float_eq_f64,
+};
+#[cfg(feature = "library")]
+#[allow(dead_code)]
+mod utils;
+
+#[test]
+#[cfg(feature = "library")]
+fn test_logistic_softplus() -> ExResult<()> {
+    let logistic = library::logistic();
+    let softplus = library::softplus();
+    assert_eq!(logistic.var_names(), vec!["x"]);
+    assert_eq!(softplus.var_names(), vec!["x"]);
+    let d_logistic = logistic.clone().partial(0)?;
+    let d_softplus = softplus.clone().partial(0)?;
+    for x in [-5.0f64, -0.7, 0.0, 1.3, 8.0] {
+        let s = 1.0 / (1.0 + (-x).exp());
+        assert_float_eq_f64(logistic.eval(&[x])?, s);
+        assert_float_eq_f64(d_logistic.eval(&[x])?, s * (1.0 - s));
+        assert_float_eq_f64(softplus.eval(&[x])?, (1.0 + x.exp()).ln());
+        assert_float_eq_f64(d_softplus.eval(&[x])?, s);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "library")]
+fn test_normal_pdf() -> ExResult<()> {
+    let pdf = library::normal_pdf();
+    assert_eq!(pdf.var_names(), vec!["mu", "sigma", "x"]);
+    let d_x = pdf.clone().partial(2)?;
+    let d_mu = pdf.clone().partial(0)?;
+    for (mu, sigma, x) in [(0.0, 1.0, 0.0), (1.5, 0.5, 1.0), (-2.0, 3.0, 4.0)] {
+        let z: f64 = (x - mu) / sigma;
+        let reference = (-0.5 * z * z).exp() / (sigma * (2.0 * std::f64::consts::PI).sqrt());
+        assert_float_eq_f64(pdf.eval(&[mu, sigma, x])?, reference);
+        assert_float_eq_f64(d_x.eval(&[mu, sigma, x])?, -z / sigma * reference);
+        assert_float_eq_f64(d_mu.eval(&[mu, sigma, x])?, z / sigma * reference);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "library")]
+fn test_haversine() -> ExResult<()> {
+    use std::f64::consts::{FRAC_PI_2, PI};
+    let haversine = library::haversine();
+    assert_eq!(
+        haversine.var_names(),
+        vec!["lat1", "lat2", "lon1", "lon2", "r"]
+    );
+    // quarter of the equator
+    assert_float_eq_f64(haversine.eval(&[0.0, 0.0, 0.0, FRAC_PI_2, 2.0])?, PI);
+    // pole to pole
+    assert_float_eq_f64(haversine.eval(&[FRAC_PI_2, -FRAC_PI_2, 0.3, 1.2, 1.0])?, PI);
+    // the distance is linear in the radius
+    let d_r = haversine.clone().partial(4)?;
+    let vars = [0.2, 0.9, -0.4, 1.1, 6371.0];
+    assert_float_eq_f64(d_r.eval(&vars)?, haversine.eval(&vars)? / 6371.0);
+    // along a meridian, the distance is the difference of the latitudes
+    let d_lat2 = haversine.partial(1)?;
+    assert_float_eq_f64(d_lat2.eval(&[0.2, 0.9, 0.5, 0.5, 3.0])?, 3.0);
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use exmex::ExVars;
+
+#[derive(ExVars)]
+enum Config {
+    Rate(f64),
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use exmex::ExVars;
+
+#[derive(ExVars)]
+struct Config {
+    rate: f32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use exmex::ExVars;
+
+#[derive(ExVars)]
+struct Config {
+    rate: f64,
+    years: u32,
+}
+
+fn main() {}
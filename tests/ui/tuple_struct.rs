@@ -0,0 +1,6 @@
+use exmex::ExVars;
+
+#[derive(ExVars)]
+struct Config(f64, f64);
+
+fn main() {}
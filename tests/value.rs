@@ -1,6 +1,6 @@
 #[cfg(feature = "value")]
 use {
-    exmex::{ExResult, Express, Val},
+    exmex::{Eval, ExResult, Express, Val},
     utils::assert_float_eq_f64,
 };
 #[cfg(feature = "value")]
@@ -36,6 +36,10 @@ fn test_vars() -> ExResult<()> {
         6,
     );
     
+    let expr = "x+5.3".parse::<exmex::OwnedFlatExVal<i32, f64>>()?;
+    utils::assert_float_eq_f64(expr.eval(&[Val::Float(3.4)])?.to_float()?, 8.7);
+    assert!("x+".parse::<exmex::OwnedFlatExVal<i32, f64>>().is_err());
+
     let expr = exmex::parse_val::<i32, f64>("z if false else 2")?;
     println!("{:#?}", expr);
     assert_eq!(
@@ -47,6 +51,33 @@ fn test_vars() -> ExResult<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(feature = "value")]
+fn test_eval_named() -> ExResult<()> {
+    let text = "-(x1 if x0 else x2)+5.3";
+    let positional = [Val::Bool(true), Val::Float(3.4), Val::Int(3)];
+    let named = [
+        ("x2", Val::Int(3)),
+        ("x0", Val::Bool(true)),
+        ("x1", Val::Float(3.4)),
+    ];
+    let expr = exmex::parse_val::<i32, f64>(text)?;
+    let res = expr.eval(&positional)?.to_float()?;
+    assert_float_eq_f64(res, 1.9);
+    assert_float_eq_f64(expr.eval_named(&named)?.to_float()?, res);
+    let expr = exmex::parse_val_owned::<i32, f64>(text)?;
+    assert_float_eq_f64(expr.eval(&positional)?.to_float()?, res);
+    assert_float_eq_f64(expr.eval_named(&named)?.to_float()?, res);
+
+    let err = expr
+        .eval_named(&[("x0", Val::Bool(true)), ("x1", Val::Float(3.4))])
+        .unwrap_err();
+    assert!(err.msg.contains("missing value of variable 'x2'"));
+    let err = expr.eval_named(&[("x3", Val::Int(1))]).unwrap_err();
+    assert!(err.msg.contains("unknown variable 'x3'"));
+    Ok(())
+}
+
 #[test]
 #[cfg(feature = "value")]
 fn test_readme() -> ExResult<()> {
@@ -99,3 +130,86 @@ fn test_serde_public() -> ExResult<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "value")]
+fn test_operator_ids() -> ExResult<()> {
+    use exmex::{FlatExVal, MakeOperators, ValOpsFactory};
+
+    // ids are part of the public interface, changing them is a breaking change
+    let ids = ValOpsFactory::<i32, f64>::make()
+        .iter()
+        .map(|op| (op.repr(), op.id()))
+        .filter(|(repr, id)| repr != id)
+        .collect::<Vec<_>>();
+    let reference = [
+        ("^", "pow"),
+        ("+", "add"),
+        ("-", "sub"),
+        ("*", "mul"),
+        ("/", "div"),
+        ("%", "rem"),
+        ("|", "bitor"),
+        ("&", "bitand"),
+        ("XOR", "bitxor"),
+        (">>", "shr"),
+        ("<<", "shl"),
+        ("&&", "and"),
+        ("||", "or"),
+        ("==", "eq"),
+        (">=", "ge"),
+        (">", "gt"),
+        ("<=", "le"),
+        ("<", "lt"),
+        ("!=", "ne"),
+        ("log", "ln"),
+        ("PI", "pi"),
+        ("π", "pi"),
+        ("E", "e"),
+    ];
+    assert_eq!(ids, reference);
+
+    let expr = FlatExVal::<i32, f64>::from_str("x % 2 if y >= 1 else -x")?;
+    assert_eq!(expr.used_operators()?, vec!["else", "ge", "if", "rem", "sub"]);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "value")]
+fn test_kind() -> ExResult<()> {
+    use exmex::{format_exerr, ExError, ValKind};
+    let values: [Val; 5] = [
+        Val::Int(3),
+        Val::Float(2.5),
+        Val::Bool(true),
+        Val::Error(format_exerr!("some error {}", 1)),
+        Val::None,
+    ];
+    let kinds = values.iter().map(|v| v.kind()).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ValKind::Int,
+            ValKind::Float,
+            ValKind::Bool,
+            ValKind::Error,
+            ValKind::None
+        ]
+    );
+    assert_eq!(values[0].as_int(), Some(&3));
+    assert_eq!(values[1].as_float(), Some(&2.5));
+    assert_eq!(values[2].as_bool(), Some(&true));
+    for (i, v) in values.iter().enumerate() {
+        assert_eq!(v.as_int().is_some(), i == 0);
+        assert_eq!(v.as_float().is_some(), i == 1);
+        assert_eq!(v.as_bool().is_some(), i == 2);
+        assert_eq!(v.is_error(), i == 3);
+        assert_eq!(v.is_none(), i == 4);
+    }
+    let res = exmex::parse_val::<i32, f64>("fact(3.5)")?.eval(&[])?;
+    assert!(res.is_error());
+    assert_eq!(res.kind(), ValKind::Error);
+    // the value is not consumed
+    assert!(res.to_int().is_err());
+    Ok(())
+}